@@ -56,10 +56,16 @@ pub struct ApiKey {
     pub owner: String,
 }
 
+/// Marks a request authenticated with the admin token
+#[derive(Debug, Clone, Copy)]
+pub struct Admin;
+
 /// Authentication state
 pub struct AuthState {
     jwt_secret: String,
     api_keys: dashmap::DashMap<String, ApiKey>,
+    /// Token for `Authorization: Admin <token>`, admin routes are closed without one
+    admin_token: Option<String>,
 }
 
 impl AuthState {
@@ -79,9 +85,25 @@ impl AuthState {
         Self {
             jwt_secret,
             api_keys,
+            admin_token: None,
         }
     }
 
+    /// Accept `token` for the admin routes
+    pub fn with_admin_token(mut self, token: Option<String>) -> Self {
+        self.admin_token = token.filter(|t| !t.is_empty());
+        self
+    }
+
+    /// Validate admin token
+    pub fn validate_admin_token(&self, token: &str) -> bool {
+        self.admin_token.as_ref().is_some_and(|expected| {
+            // Compare every byte, so the time taken does not reveal the matching prefix
+            expected.len() == token.len()
+                && expected.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+        })
+    }
+
     /// Validate API key
     pub fn validate_api_key(&self, key: &str) -> Option<RateLimitTier> {
         self.api_keys
@@ -137,6 +159,7 @@ pub async fn auth_middleware(
         .get(header::AUTHORIZATION)
         .and_then(|h| h.to_str().ok());
 
+    let mut admin = false;
     let tier = if let Some(auth) = auth_header {
         if auth.starts_with("Bearer ") {
             // JWT token
@@ -164,6 +187,14 @@ pub async fn auth_middleware(
                     return Err(StatusCode::UNAUTHORIZED);
                 }
             }
+        } else if let Some(token) = auth.strip_prefix("Admin ") {
+            if !state.validate_admin_token(token) {
+                warn!("Invalid admin token");
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            debug!("Authenticated as admin");
+            admin = true;
+            RateLimitTier::Premium
         } else {
             RateLimitTier::Anonymous
         }
@@ -173,6 +204,9 @@ pub async fn auth_middleware(
 
     // Add tier to request extensions for rate limiting
     request.extensions_mut().insert(tier);
+    if admin {
+        request.extensions_mut().insert(Admin);
+    }
 
     Ok(next.run(request).await)
 }
@@ -3,7 +3,7 @@ use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use std::sync::Arc;
 
-use cherenkov_db::{RadiationDatabase, AggregationLevel};
+use cherenkov_db::{RadiationDatabase, AggregationLevel, AnomalyQuery, TimeRangeQuery};
 use cherenkov_plume::dispersion::{GaussianPlumeModel, WeatherConditions, StabilityClass};
 use cherenkov_plume::ReleaseParameters;
 
@@ -68,29 +68,22 @@ impl QueryRoot {
     ) -> Result<Vec<Reading>> {
         let db = ctx.data::<Arc<RadiationDatabase>>()?;
         
-        let ids: Vec<String> = sensor_ids.iter()
-            .map(|id| id.to_string())
-            .collect();
+        let ids = sensor_ids.iter()
+            .map(|id| Uuid::parse_str(id))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| async_graphql::Error::new(format!("Invalid sensor ID: {}", e)))?;
         
-        let agg = match aggregation.as_deref() {
-            Some("1m") => AggregationLevel::OneMinute,
-            Some("5m") => AggregationLevel::FiveMinutes,
-            Some("1h") => AggregationLevel::OneHour,
-            Some("1d") => AggregationLevel::OneDay,
-            _ => AggregationLevel::Raw,
-        };
+        let query = TimeRangeQuery::new(ids, from.timestamp(), to.timestamp())
+            .with_aggregation(aggregation.as_deref().map_or(AggregationLevel::Raw, AggregationLevel::parse));
         
-        let points = db.query_range(
-            &ids,
-            from.timestamp(),
-            to.timestamp(),
-            agg,
-        ).await.map_err(|e| async_graphql::Error::new(format!("Query error: {}", e)))?;
+        let points = db.query_series(&query).await
+            .map_err(|e| async_graphql::Error::new(format!("Query error: {}", e)))?
+            .items;
         
         let readings: Vec<Reading> = points.into_iter()
             .map(|p| Reading {
                 id: ID::from(Uuid::new_v4().to_string()),
-                sensor_id: ID::from("unknown"),
+                sensor_id: ID::from(p.sensor_id.map_or_else(|| "aggregate".to_string(), |id| id.to_string())),
                 timestamp: p.timestamp,
                 dose_rate: p.value,
                 unit: "microsieverts_per_hour".to_string(),
@@ -109,19 +102,17 @@ impl QueryRoot {
     ) -> Result<Vec<Anomaly>> {
         let db = ctx.data::<Arc<RadiationDatabase>>()?;
         
-        let records = db.get_anomalies(
-            since.timestamp(),
-            limit.unwrap_or(100) as usize
-        ).await.map_err(|e| async_graphql::Error::new(format!("Database error: {}", e)))?;
+        let mut query = AnomalyQuery::since(since.timestamp())
+            .limit(limit.unwrap_or(100).max(0) as usize);
+        if let Some(severity) = severity {
+            query = query.with_severity(severity);
+        }
+        
+        let records = db.query_anomalies(&query).await
+            .map_err(|e| async_graphql::Error::new(format!("Database error: {}", e)))?
+            .items;
         
         let anomalies: Vec<Anomaly> = records.into_iter()
-            .filter(|r| {
-                // Filter by severity if provided
-                match &severity {
-                    Some(sevs) => sevs.contains(&r.severity),
-                    None => true,
-                }
-            })
             .map(|r| Anomaly {
                 id: ID::from(r.anomaly_id),
                 sensor_id: ID::from(r.sensor_id.to_string()),
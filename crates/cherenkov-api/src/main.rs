@@ -1,5 +1,6 @@
 use axum::{
     routing::get,
+    Extension,
     Router,
     middleware,
 };
@@ -18,7 +19,7 @@ mod rate_limit;
 use auth::AuthState;
 use websocket::{create_websocket_state, create_websocket_router};
 use graphql::schema::build_schema;
-use cherenkov_db::{RadiationDatabase, DatabaseConfig, scylla::ScyllaConfig};
+use cherenkov_db::{RadiationDatabase, BackupConfig, DatabaseConfig, ExportJobs, StorageBackends};
 use cherenkov_observability::init_observability;
 use cherenkov_core::{EventBus, CherenkovEvent};
 use cherenkov_ml::ModelRegistry;
@@ -32,20 +33,40 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Cherenkov API Server v{}", env!("CARGO_PKG_VERSION"));
     
     // Initialize database
-    let scylla_config = ScyllaConfig::default();
+    // Backends come from STORAGE_PROFILE and friends, see `StorageBackends::from_env`
     let db = Arc::new(
-        RadiationDatabase::new(
-            scylla_config,
-            "./data/cherenkov_warm.db",
-            "redis://127.0.0.1:6379",
-            DatabaseConfig::default(),
-        ).await?
+        RadiationDatabase::open(StorageBackends::from_env(), DatabaseConfig {
+            // The ingest daemon archives the WAL, so this process must not
+            // checkpoint it either; the config also serves the admin backup routes
+            backup: BackupConfig::from_env(),
+            ..DatabaseConfig::default()
+        }).await?
     );
     
+    // Export jobs write their files under EXPORT_DIR and are kept for a day
+    let export_dir = std::env::var("EXPORT_DIR").unwrap_or_else(|_| {
+        let data_dir = std::env::var("DATA_DIR").unwrap_or_else(|_| "./data".to_string());
+        format!("{}/exports", data_dir)
+    });
+    let exports = Arc::new(ExportJobs::new(db.clone(), export_dir));
+    let exports_pruner = exports.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            let pruned = exports_pruner.prune(chrono::Duration::hours(24)).await;
+            if pruned > 0 {
+                info!("Removed {} expired exports", pruned);
+            }
+        }
+    });
+    
     // Initialize authentication
     let jwt_secret = std::env::var("JWT_SECRET")
         .unwrap_or_else(|_| "cherenkov-dev-secret-change-in-production".to_string());
-    let auth_state = Arc::new(AuthState::new(jwt_secret));
+    let auth_state = Arc::new(
+        AuthState::new(jwt_secret).with_admin_token(std::env::var("ADMIN_TOKEN").ok())
+    );
     
     // Initialize ModelRegistry
     let device = Device::cuda_if_available(0).unwrap_or(Device::Cpu);
@@ -92,6 +113,7 @@ async fn main() -> anyhow::Result<()> {
             auth::auth_middleware,
         ))
         .layer(rate_limit::create_rate_limit_layer())
+        .layer(Extension(exports))
         .layer(CompressionLayer::new())
         .layer(CorsLayer::permissive())
         
@@ -1,114 +1,212 @@
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    routing::get,
-    Json, Router,
+    http::{header, HeaderMap, HeaderValue},
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio_util::io::ReaderStream;
 use tracing::{info, debug, error};
 use uuid::Uuid;
 
-use cherenkov_db::{RadiationDatabase, AggregationLevel};
-use crate::auth::AuthState;
+use cherenkov_db::backup;
+use cherenkov_db::{
+    RadiationDatabase, AggregationLevel, AnomalyCountsView, AnomalyQuery, BackupManifest, Cursor, DatabaseError,
+    EventQuery, EventType, ExportFormat, ExportJob, ExportJobs, Incident, NewSensor, QualityFlag, ReadingLineage,
+    RestoreReport, Sensor, SensorUpdate, SortOrder, SpatialQuery, StoredEvent, TimeRangeQuery, TrackSummary,
+    VerifyReport, WarmBackup,
+};
+use crate::auth::{Admin, AuthState};
 use crate::websocket::WebSocketState;
 
 /// REST API router - uses same state type as main app
+///
+/// The export routes expect an `Extension<Arc<ExportJobs>>` layer.
 pub fn create_router() -> Router<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)> {
     Router::new()
-        .route("/sensors", get(list_sensors))
-        .route("/sensors/:id", get(get_sensor))
+        .route("/sensors", get(list_sensors).post(create_sensor))
+        .route("/sensors/:id", get(get_sensor).put(update_sensor))
+        .route("/sensors/:id/decommission", post(decommission_sensor))
         .route("/sensors/:id/readings", get(get_sensor_readings))
+        .route("/sensors/:id/readings/:timestamp/provenance", get(get_reading_provenance))
+        .route("/readings", get(search_readings))
         .route("/sensors/nearby", get(get_nearby_sensors))
+        .route("/sensors/:id/tracks", get(list_sensor_tracks))
+        .route("/tracks/:id", get(get_track))
         .route("/status", get(get_global_status))
         .route("/anomalies", get(list_anomalies))
+        .route("/anomalies/counts", get(get_anomaly_counts))
+        .route("/events", get(list_events))
+        .route("/incidents", get(list_open_incidents))
+        .route("/alerts/timeline", get(get_alert_timeline))
         .route("/alerts/:id/acknowledge", get(acknowledge_alert))
+        .route("/exports", post(start_export))
+        .route("/exports/:id", get(get_export))
+        .route("/exports/:id/download", get(download_export))
+        .route("/admin/backups", get(list_backups).post(take_snapshot))
+        .route("/admin/backups/verify", post(verify_backups))
+        .route("/admin/backups/restore", post(restore_backup))
 }
 
-/// List all sensors
+/// List registered sensors
 async fn list_sensors(
-    State((_, _db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
-) -> Json<Vec<SensorResponse>> {
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Query(params): Query<SensorsQuery>,
+) -> Result<Json<Vec<Sensor>>, StatusCode> {
     debug!("Listing all sensors");
     
-    // Query from database using public API
-    // For now return empty list - would need to add list_sensors to RadiationDatabase
-    let sensors: Vec<SensorResponse> = vec![];
-    
-    Json(sensors)
+    match db.list_registered_sensors(params.include_decommissioned.unwrap_or(false)).await {
+        Ok(sensors) => Ok(Json(sensors)),
+        Err(e) => {
+            error!("Failed to list sensors: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
 /// Get sensor by ID
 async fn get_sensor(
     State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
     Path(id): Path<String>,
-) -> Result<Json<SensorResponse>, StatusCode> {
+) -> Result<Json<Sensor>, StatusCode> {
     debug!("Getting sensor: {}", id);
     
-    // Try to get latest reading for this sensor using public API
-    let latest = match db.get_sensor_latest(&id).await {
-        Ok(Some(r)) => r,
-        _ => {
-            return Err(StatusCode::NOT_FOUND);
+    let sensor_id = Uuid::parse_str(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    match db.get_sensor(&sensor_id).await {
+        Ok(Some(sensor)) => Ok(Json(sensor)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get sensor: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
-    };
+    }
+}
+
+/// Register a sensor
+async fn create_sensor(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Json(sensor): Json<NewSensor>,
+) -> Result<(StatusCode, Json<Sensor>), StatusCode> {
+    info!("Registering sensor from source {}", sensor.source);
     
-    let response = SensorResponse {
-        id: id.clone(),
-        name: format!("Sensor {}", id),
-        latitude: latest.latitude,
-        longitude: latest.longitude,
-        status: "active".to_string(),
-        last_reading: DateTime::from_timestamp(latest.timestamp, 0),
-    };
+    match db.create_sensor(&sensor).await {
+        Ok(Some(sensor)) => Ok((StatusCode::CREATED, Json(sensor))),
+        Ok(None) => Err(StatusCode::CONFLICT),
+        Err(e) => {
+            error!("Failed to register sensor: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Update sensor metadata or maintenance state
+async fn update_sensor(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Path(id): Path<String>,
+    Json(update): Json<SensorUpdate>,
+) -> Result<Json<Sensor>, StatusCode> {
+    info!("Updating sensor: {}", id);
     
-    Ok(Json(response))
+    let sensor_id = Uuid::parse_str(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    match db.update_sensor(&sensor_id, &update).await {
+        Ok(Some(sensor)) => Ok(Json(sensor)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(DatabaseError::Query(e)) => {
+            debug!("Rejected sensor update: {}", e);
+            Err(StatusCode::BAD_REQUEST)
+        }
+        Err(e) => {
+            error!("Failed to update sensor: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Decommission a sensor, keeping its history
+async fn decommission_sensor(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Path(id): Path<String>,
+) -> Result<Json<Sensor>, StatusCode> {
+    info!("Decommissioning sensor: {}", id);
+    
+    let sensor_id = Uuid::parse_str(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    match db.decommission_sensor(&sensor_id).await {
+        Ok(Some(sensor)) => Ok(Json(sensor)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to decommission sensor: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
 /// Get sensor readings with time range
+///
+/// When `limit` cuts the range short, the `X-Next-Cursor` header holds the
+/// cursor of the next page.
 async fn get_sensor_readings(
     State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
     Path(id): Path<String>,
     Query(params): Query<ReadingsQuery>,
-) -> Result<Json<Vec<ReadingResponse>>, StatusCode> {
+) -> Result<(HeaderMap, Json<Vec<ReadingResponse>>), StatusCode> {
     debug!("Getting readings for sensor: {}", id);
     
     let sensor_id = Uuid::parse_str(&id)
         .map_err(|_| StatusCode::BAD_REQUEST)?;
     
-    let from = params.from.timestamp();
-    let to = params.to.timestamp();
+    let query = params.to_query(vec![sensor_id])?;
     
-    let aggregation = params.aggregation
-        .map(|a| match a.as_str() {
-            "1m" => AggregationLevel::OneMinute,
-            "5m" => AggregationLevel::FiveMinutes,
-            "1h" => AggregationLevel::OneHour,
-            "1d" => AggregationLevel::OneDay,
-            _ => AggregationLevel::Raw,
-        })
-        .unwrap_or(AggregationLevel::Raw);
+    read_series(&db, &query).await
+}
+
+/// Search readings across sensors, area, time, quality and source
+async fn search_readings(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Query(params): Query<ReadingsQuery>,
+) -> Result<(HeaderMap, Json<Vec<ReadingResponse>>), StatusCode> {
+    debug!("Searching readings: {:?}", params);
     
-    let readings = match db.query_range(
-        &[sensor_id.to_string()],
-        from,
-        to,
-        aggregation,
-    ).await {
-        Ok(points) => points.into_iter().map(|p| ReadingResponse {
+    let sensor_ids = parse_sensor_ids(params.sensors.as_deref())?;
+    let query = params.to_query(sensor_ids)?;
+    
+    read_series(&db, &query).await
+}
+
+async fn read_series(
+    db: &RadiationDatabase,
+    query: &TimeRangeQuery,
+) -> Result<(HeaderMap, Json<Vec<ReadingResponse>>), StatusCode> {
+    let page = db.query_series(query).await.map_err(|e| {
+        error!("Failed to query readings: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    
+    // Aggregated points merge sensors, so they only name one when one was asked for
+    let only_sensor = match query.sensor_ids.as_slice() {
+        [sensor_id] => Some(*sensor_id),
+        _ => None,
+    };
+    let readings = page.items.into_iter()
+        .map(|p| ReadingResponse {
             id: Uuid::new_v4().to_string(),
-            sensor_id: id.clone(),
+            sensor_id: p.sensor_id.or(only_sensor).map(|id| id.to_string()).unwrap_or_default(),
             timestamp: p.timestamp,
             dose_rate: p.value,
             unit: "microsieverts_per_hour".to_string(),
-        }).collect(),
-        Err(e) => {
-            error!("Failed to query readings: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
+        })
+        .collect();
     
-    Ok(Json(readings))
+    Ok((next_cursor_header(page.next_cursor)?, Json(readings)))
 }
 
 /// Get nearby sensors
@@ -155,6 +253,75 @@ async fn get_nearby_sensors(
     Json(sensors)
 }
 
+/// List survey tracks recorded by a mobile sensor
+async fn list_sensor_tracks(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Path(id): Path<String>,
+    Query(params): Query<TracksQuery>,
+) -> Result<Json<Vec<TrackSummary>>, StatusCode> {
+    debug!("Listing tracks for sensor: {}", id);
+    
+    let sensor_id = Uuid::parse_str(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    let to = params.to.unwrap_or_else(Utc::now);
+    let from = params.from.unwrap_or(to - chrono::Duration::days(7));
+    
+    match db.list_tracks(Some(&sensor_id), from.timestamp(), to.timestamp(), params.limit.unwrap_or(100)).await {
+        Ok(tracks) => Ok(Json(tracks)),
+        Err(e) => {
+            error!("Failed to list tracks: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get a track as a GeoJSON FeatureCollection of dose-colored LineString segments
+/// Fetch, raw value, unit conversion and QC decision behind one reading
+///
+/// `timestamp` is the reading's time, RFC 3339 or Unix seconds.
+async fn get_reading_provenance(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Path((id, timestamp)): Path<(String, String)>,
+) -> Result<Json<ReadingLineage>, StatusCode> {
+    let sensor_id = Uuid::parse_str(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let timestamp = match timestamp.parse::<i64>() {
+        Ok(secs) => secs,
+        Err(_) => DateTime::parse_from_rfc3339(&timestamp)
+            .map_err(|_| StatusCode::BAD_REQUEST)?
+            .timestamp(),
+    };
+    
+    match db.reading_lineage(&sensor_id, timestamp).await {
+        Ok(Some(lineage)) => Ok(Json(lineage)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get provenance of {} at {}: {}", sensor_id, timestamp, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn get_track(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    debug!("Getting track: {}", id);
+    
+    let track_id = Uuid::parse_str(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    match db.get_track(&track_id).await {
+        Ok(Some(track)) => Ok(Json(track.to_geojson())),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get track: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 /// Get global status (DEFCON indicator)
 async fn get_global_status() -> Json<GlobalStatusResponse> {
     // TODO: Calculate actual DEFCON level based on anomaly data
@@ -167,14 +334,238 @@ async fn get_global_status() -> Json<GlobalStatusResponse> {
     })
 }
 
-/// List anomalies
+/// List anomalies, newest first, paged through the `X-Next-Cursor` header
 async fn list_anomalies(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
     Query(params): Query<AnomaliesQuery>,
-) -> Json<Vec<AnomalyResponse>> {
+) -> Result<(HeaderMap, Json<Vec<AnomalyResponse>>), StatusCode> {
     debug!("Listing anomalies with severity: {:?}", params.severity);
     
-    // TODO: Query from database
-    Json(vec![])
+    let since = params.since.unwrap_or_else(|| Utc::now() - chrono::Duration::hours(24));
+    let mut query = AnomalyQuery::since(since.timestamp());
+    
+    let severity = split_list(params.severity.as_deref());
+    if !severity.is_empty() {
+        query = query.with_severity(severity);
+    }
+    let sensor_ids = parse_sensor_ids(params.sensors.as_deref())?;
+    if !sensor_ids.is_empty() {
+        query = query.for_sensors(sensor_ids);
+    }
+    if let Some(limit) = params.limit {
+        query = query.limit(usize::try_from(limit).map_err(|_| StatusCode::BAD_REQUEST)?);
+    }
+    if let Some(order) = &params.order {
+        query = query.order_by(SortOrder::parse(order).ok_or(StatusCode::BAD_REQUEST)?);
+    }
+    if let Some(cursor) = &params.cursor {
+        query = query.after(Cursor::decode(cursor).map_err(|_| StatusCode::BAD_REQUEST)?);
+    }
+    
+    let page = db.query_anomalies(&query).await.map_err(|e| {
+        error!("Failed to list anomalies: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    
+    let anomalies = page.items.into_iter()
+        .map(|r| AnomalyResponse {
+            id: r.anomaly_id,
+            sensor_id: r.sensor_id.to_string(),
+            severity: r.severity,
+            z_score: r.z_score,
+            detected_at: DateTime::from_timestamp(r.detected_at, 0).unwrap_or_else(Utc::now),
+        })
+        .collect();
+    
+    Ok((next_cursor_header(page.next_cursor)?, Json(anomalies)))
+}
+
+/// Audit trail: logged domain events, oldest first unless ordered otherwise
+async fn list_events(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Query(params): Query<EventsQuery>,
+) -> Result<(HeaderMap, Json<Vec<StoredEvent>>), StatusCode> {
+    debug!("Listing events of types {:?} for {:?}", params.types, params.aggregates);
+    
+    let event_types = split_list(params.types.as_deref())
+        .iter()
+        .map(|t| EventType::parse(t).ok_or(StatusCode::BAD_REQUEST))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut query = EventQuery::all()
+        .for_aggregates(parse_sensor_ids(params.aggregates.as_deref())?)
+        .of_types(event_types)
+        .between(params.from.map(|t| t.timestamp()), params.to.map(|t| t.timestamp()));
+    if let Some(limit) = params.limit {
+        query = query.limit(usize::try_from(limit).map_err(|_| StatusCode::BAD_REQUEST)?);
+    }
+    if let Some(order) = &params.order {
+        query = query.order_by(SortOrder::parse(order).ok_or(StatusCode::BAD_REQUEST)?);
+    }
+    if let Some(cursor) = &params.cursor {
+        query = query.after(cursor.parse().map_err(|_| StatusCode::BAD_REQUEST)?);
+    }
+    
+    let page = db.query_events(&query).await.map_err(|e| {
+        error!("Failed to list events: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    
+    Ok((next_cursor_header(page.next_cursor)?, Json(page.items)))
+}
+
+/// Incidents created and not yet resolved
+async fn list_open_incidents(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+) -> Result<Json<Vec<Incident>>, StatusCode> {
+    db.open_incidents().await.map(Json).map_err(|e| {
+        error!("Failed to list incidents: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Alerts and incident changes, newest first
+async fn get_alert_timeline(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    Query(params): Query<TimelineQuery>,
+) -> Result<Json<Vec<StoredEvent>>, StatusCode> {
+    let limit = usize::try_from(params.limit.unwrap_or(100)).map_err(|_| StatusCode::BAD_REQUEST)?;
+    db.alert_timeline(limit).await.map(Json).map_err(|e| {
+        error!("Failed to read alert timeline: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Anomalies detected per sensor, by severity
+async fn get_anomaly_counts(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+) -> Result<Json<AnomalyCountsView>, StatusCode> {
+    db.anomaly_counts().await.map(Json).map_err(|e| {
+        error!("Failed to read anomaly counts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Start exporting readings; poll the returned job until it completes
+async fn start_export(
+    Extension(exports): Extension<Arc<ExportJobs>>,
+    Json(request): Json<ExportRequest>,
+) -> Result<(StatusCode, Json<ExportJob>), StatusCode> {
+    let format = ExportFormat::parse(&request.format).ok_or(StatusCode::BAD_REQUEST)?;
+    info!("Starting {:?} export from {} to {}", format, request.readings.from, request.readings.to);
+    
+    let sensor_ids = parse_sensor_ids(request.readings.sensors.as_deref())?;
+    let query = request.readings.to_query(sensor_ids)?;
+    
+    match exports.start(query, format) {
+        Ok(job) => Ok((StatusCode::ACCEPTED, Json(job))),
+        Err(e) => {
+            error!("Failed to start export: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Export job status and progress
+async fn get_export(
+    Extension(exports): Extension<Arc<ExportJobs>>,
+    Path(id): Path<String>,
+) -> Result<Json<ExportJob>, StatusCode> {
+    let export_id = Uuid::parse_str(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    exports.get(&export_id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Stream a completed export's file
+async fn download_export(
+    Extension(exports): Extension<Arc<ExportJobs>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let export_id = Uuid::parse_str(&id)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    let job = exports.get(&export_id).ok_or(StatusCode::NOT_FOUND)?;
+    let path = exports.file(&export_id).ok_or(StatusCode::CONFLICT)?;
+    
+    let file = tokio::fs::File::open(&path).await.map_err(|e| {
+        error!("Failed to open export {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    
+    let disposition = format!("attachment; filename=\"cherenkov-{}.{}\"", id, job.format.extension());
+    let headers = [
+        (header::CONTENT_TYPE, job.format.content_type().to_string()),
+        (header::CONTENT_DISPOSITION, disposition),
+    ];
+    Ok((headers, Body::from_stream(ReaderStream::new(file))))
+}
+
+/// Warm tier snapshots, oldest first
+async fn list_backups(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    admin: Option<Extension<Admin>>,
+) -> Result<Json<Vec<BackupManifest>>, StatusCode> {
+    let warm_backup = admin_backups(&db, admin)?;
+    backup::list_snapshots(&warm_backup.config().dir).map(Json).map_err(|e| {
+        error!("Failed to list backups: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Snapshot the warm tier now
+async fn take_snapshot(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    admin: Option<Extension<Admin>>,
+) -> Result<(StatusCode, Json<BackupManifest>), StatusCode> {
+    let mut warm_backup = admin_backups(&db, admin)?;
+    match warm_backup.snapshot().await {
+        Ok(manifest) => Ok((StatusCode::CREATED, Json(manifest))),
+        Err(e) => {
+            error!("Failed to take snapshot: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Check snapshots against their manifests and archived WAL frames against their checksums
+async fn verify_backups(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    admin: Option<Extension<Admin>>,
+) -> Result<Json<VerifyReport>, StatusCode> {
+    let warm_backup = admin_backups(&db, admin)?;
+    backup::verify(&warm_backup.config().dir).await.map(Json).map_err(|e| {
+        error!("Failed to verify backups: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Restore the warm tier as of `at` into a new file under the backup
+/// directory, for an operator to check and move into place
+async fn restore_backup(
+    State((_, db, _)): State<(Arc<WebSocketState>, Arc<RadiationDatabase>, Arc<AuthState>)>,
+    admin: Option<Extension<Admin>>,
+    Json(request): Json<RestoreRequest>,
+) -> Result<(StatusCode, Json<RestoreReport>), StatusCode> {
+    let warm_backup = admin_backups(&db, admin)?;
+    let dir = &warm_backup.config().dir;
+    let target = dir.join("restores").join(format!("{}.db", Utc::now().timestamp_millis()));
+    info!("Restoring warm tier as of {:?} to {}", request.at, target.display());
+    
+    match backup::restore(dir, &target, request.at).await {
+        Ok(report) => Ok((StatusCode::CREATED, Json(report))),
+        Err(e) => {
+            error!("Failed to restore backup: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Backups of the warm tier, for admin requests when they are configured
+fn admin_backups(db: &RadiationDatabase, admin: Option<Extension<Admin>>) -> Result<WarmBackup, StatusCode> {
+    if admin.is_none() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    db.warm_backup().ok_or(StatusCode::SERVICE_UNAVAILABLE)
 }
 
 /// Acknowledge alert
@@ -194,6 +585,29 @@ async fn acknowledge_alert(
 
 use axum::http::StatusCode;
 
+/// Comma-separated query parameter values, blanks dropped
+fn split_list(value: Option<&str>) -> Vec<String> {
+    value
+        .map(|v| v.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn parse_sensor_ids(value: Option<&str>) -> Result<Vec<Uuid>, StatusCode> {
+    split_list(value)
+        .iter()
+        .map(|id| Uuid::parse_str(id).map_err(|_| StatusCode::BAD_REQUEST))
+        .collect()
+}
+
+fn next_cursor_header(next_cursor: Option<String>) -> Result<HeaderMap, StatusCode> {
+    let mut headers = HeaderMap::new();
+    if let Some(cursor) = next_cursor {
+        let value = HeaderValue::from_str(&cursor).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        headers.insert("x-next-cursor", value);
+    }
+    Ok(headers)
+}
+
 // Request/Response types
 
 #[derive(Debug, Deserialize)]
@@ -202,6 +616,65 @@ pub struct ReadingsQuery {
     pub from: DateTime<Utc>,
     pub to: DateTime<Utc>,
     pub aggregation: Option<String>,
+    /// Comma-separated sensor IDs, all sensors when absent; ignored under `/sensors/:id`
+    pub sensors: Option<String>,
+    pub min_lat: Option<f64>,
+    pub max_lat: Option<f64>,
+    pub min_lon: Option<f64>,
+    pub max_lon: Option<f64>,
+    /// Comma-separated quality flags, e.g. `valid,suspect`
+    pub quality: Option<String>,
+    /// Comma-separated data sources
+    pub source: Option<String>,
+    /// `asc` (default) or `desc`
+    pub order: Option<String>,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+impl ReadingsQuery {
+    fn to_query(&self, sensor_ids: Vec<Uuid>) -> Result<TimeRangeQuery, StatusCode> {
+        let mut query = TimeRangeQuery::new(sensor_ids, self.from.timestamp(), self.to.timestamp());
+        if let Some(aggregation) = &self.aggregation {
+            query = query.with_aggregation(AggregationLevel::parse(aggregation));
+        }
+        match (self.min_lat, self.max_lat, self.min_lon, self.max_lon) {
+            (Some(min_lat), Some(max_lat), Some(min_lon), Some(max_lon)) => {
+                query = query.within(SpatialQuery {
+                    active_only: false,
+                    ..SpatialQuery::bounding_box(min_lat, max_lat, min_lon, max_lon)
+                });
+            }
+            (None, None, None, None) => {}
+            _ => return Err(StatusCode::BAD_REQUEST),
+        }
+        let quality = split_list(self.quality.as_deref())
+            .iter()
+            .map(|flag| QualityFlag::parse(flag))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        query = query
+            .with_quality(quality)
+            .with_sources(split_list(self.source.as_deref()));
+        if let Some(order) = &self.order {
+            query = query.order_by(SortOrder::parse(order).ok_or(StatusCode::BAD_REQUEST)?);
+        }
+        if let Some(limit) = self.limit {
+            query = query.limit(limit);
+        }
+        if let Some(cursor) = &self.cursor {
+            query = query.after(Cursor::decode(cursor).map_err(|_| StatusCode::BAD_REQUEST)?);
+        }
+        Ok(query)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportRequest {
+    /// `csv`, `geojson`, `parquet` or `netcdf`
+    pub format: String,
+    #[serde(flatten)]
+    pub readings: ReadingsQuery,
 }
 
 #[derive(Debug, Deserialize)]
@@ -212,12 +685,53 @@ pub struct NearbyQuery {
     pub radius_km: f64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SensorsQuery {
+    pub include_decommissioned: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TracksQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 pub struct AnomaliesQuery {
+    /// Comma-separated severities
     pub severity: Option<String>,
     pub since: Option<DateTime<Utc>>,
     pub limit: Option<i32>,
+    /// Comma-separated sensor IDs
+    pub sensors: Option<String>,
+    pub order: Option<String>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Comma-separated aggregate IDs, e.g. sensors or incidents
+    pub aggregates: Option<String>,
+    /// Comma-separated event types, e.g. `AnomalyDetected,SensorOffline`
+    pub types: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i32>,
+    pub order: Option<String>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimelineQuery {
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreRequest {
+    /// Restore as of this time, or as late as the archive goes
+    pub at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize)]
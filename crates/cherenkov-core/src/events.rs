@@ -47,6 +47,9 @@ pub struct NormalizedReading {
     pub uncertainty: f64,
     pub source: String,
     pub quality_flag: QualityFlag,
+    /// Survey track for readings from mobile sensors
+    #[serde(default)]
+    pub track_id: Option<Uuid>,
 }
 
 /// Quality classification for readings
@@ -0,0 +1,10 @@
+-- QC reasons recorded alongside each reading's quality flag
+
+ALTER TABLE radiation_readings_warm ADD COLUMN qc_reasons TEXT NOT NULL DEFAULT '[]';
+
+-- Index for filtering by quality flag
+CREATE INDEX IF NOT EXISTS idx_readings_quality 
+ON radiation_readings_warm(quality_flag, timestamp);
+
+INSERT OR IGNORE INTO schema_migrations (version, description) 
+VALUES (2, 'QC reasons on warm readings');
@@ -0,0 +1,31 @@
+-- Mobile survey tracks (bGeigie drives, drone flights)
+
+CREATE TABLE IF NOT EXISTS tracks (
+    track_id TEXT PRIMARY KEY,
+    sensor_id TEXT NOT NULL,
+    source TEXT NOT NULL,
+    started_at DATETIME NOT NULL,
+    ended_at DATETIME NOT NULL,
+    point_count INTEGER NOT NULL DEFAULT 0,
+    max_dose_rate REAL NOT NULL DEFAULT 0
+);
+
+-- Index for listing a sensor's tracks
+CREATE INDEX IF NOT EXISTS idx_tracks_sensor 
+ON tracks(sensor_id, started_at);
+
+-- Ordered geo-path of each track, stored independently of reading tiers
+CREATE TABLE IF NOT EXISTS track_points (
+    track_id TEXT NOT NULL,
+    timestamp DATETIME NOT NULL,
+    latitude REAL NOT NULL,
+    longitude REAL NOT NULL,
+    altitude_m REAL,
+    dose_rate REAL NOT NULL,
+    
+    PRIMARY KEY (track_id, timestamp),
+    FOREIGN KEY (track_id) REFERENCES tracks(track_id)
+) WITHOUT ROWID;
+
+INSERT OR IGNORE INTO schema_migrations (version, description) 
+VALUES (3, 'Mobile survey tracks');
@@ -0,0 +1,30 @@
+-- Sensor registry: operator metadata and lifecycle on the sensors table
+
+ALTER TABLE sensors ADD COLUMN detector_model TEXT;
+ALTER TABLE sensors ADD COLUMN calibration_factor REAL NOT NULL DEFAULT 1.0;
+ALTER TABLE sensors ADD COLUMN height_m REAL;
+ALTER TABLE sensors ADD COLUMN placement TEXT; -- 'indoor' or 'outdoor'
+ALTER TABLE sensors ADD COLUMN owner TEXT;
+ALTER TABLE sensors ADD COLUMN commissioned_at DATETIME;
+ALTER TABLE sensors ADD COLUMN decommissioned_at DATETIME;
+ALTER TABLE sensors ADD COLUMN status_changed_at DATETIME;
+
+-- Statuses are now online/degraded/offline/maintenance/decommissioned
+UPDATE sensors SET status = 'online' WHERE status = 'active';
+
+-- Register sensors that so far only existed implicitly in readings
+INSERT OR IGNORE INTO sensors (sensor_id, source, status, first_seen, last_reading, latitude, longitude)
+SELECT r.sensor_id, r.source, 'online', first.min_ts, r.timestamp, r.latitude, r.longitude
+FROM radiation_readings_warm r
+INNER JOIN (
+    SELECT sensor_id, MIN(timestamp) AS min_ts, MAX(timestamp) AS max_ts
+    FROM radiation_readings_warm
+    GROUP BY sensor_id
+) first ON r.sensor_id = first.sensor_id AND r.timestamp = first.max_ts;
+
+-- Index for liveness sweeps
+CREATE INDEX IF NOT EXISTS idx_sensors_last_reading
+ON sensors(status, last_reading);
+
+INSERT OR IGNORE INTO schema_migrations (version, description)
+VALUES (4, 'Sensor registry metadata and lifecycle');
@@ -0,0 +1,23 @@
+-- Continuous aggregates per sensor and geo cell, maintained on write
+
+CREATE TABLE IF NOT EXISTS rollups (
+    level TEXT NOT NULL,            -- '1m', '1h' or '1d'
+    scope TEXT NOT NULL,            -- 'sensor' or 'cell'
+    scope_id TEXT NOT NULL,         -- sensor UUID or geohash cell
+    bucket_start INTEGER NOT NULL,  -- unix seconds
+    count INTEGER NOT NULL,
+    sum REAL NOT NULL,
+    min REAL NOT NULL,
+    max REAL NOT NULL,
+    histogram TEXT NOT NULL,        -- JSON log-bin counts, for percentiles
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (level, scope, scope_id, bucket_start)
+) WITHOUT ROWID;
+
+-- Superseded by the rollups, never written to
+DROP TRIGGER IF EXISTS cleanup_expired_cache;
+DROP TABLE IF EXISTS aggregation_cache;
+
+INSERT OR IGNORE INTO schema_migrations (version, description)
+VALUES (5, 'Rollups per sensor and geo cell');
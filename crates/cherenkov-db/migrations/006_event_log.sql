@@ -0,0 +1,20 @@
+-- Event log: an append order for replaying domain events, and
+-- checkpoints of the read models projected from it
+
+ALTER TABLE domain_events ADD COLUMN sequence INTEGER;
+
+-- Existing events keep the order they were inserted in
+UPDATE domain_events SET sequence = rowid;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_domain_events_sequence
+ON domain_events(sequence);
+
+CREATE TABLE IF NOT EXISTS projection_checkpoints (
+    name TEXT PRIMARY KEY,
+    sequence INTEGER NOT NULL,
+    state TEXT NOT NULL, -- JSON
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO schema_migrations (version, description)
+VALUES (6, 'Event log sequence and projection checkpoints');
@@ -0,0 +1,35 @@
+-- Reading provenance: the fetch, raw value, unit conversion and QC
+-- decision behind each stored dose rate
+
+CREATE TABLE IF NOT EXISTS source_fetches (
+    id INTEGER PRIMARY KEY,
+    fetch_id TEXT NOT NULL UNIQUE,
+    source TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    completed_at INTEGER NOT NULL,
+    readings INTEGER NOT NULL
+);
+
+-- Conversion and QC settings, shared by every reading that used them
+CREATE TABLE IF NOT EXISTS provenance_profiles (
+    id INTEGER PRIMARY KEY,
+    raw_unit TEXT NOT NULL,
+    conversion_factor REAL NOT NULL,
+    normalizer_version TEXT NOT NULL,
+    qc_thresholds TEXT NOT NULL DEFAULT '',
+    UNIQUE (raw_unit, conversion_factor, normalizer_version, qc_thresholds)
+);
+
+CREATE TABLE IF NOT EXISTS reading_provenance (
+    sensor_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    fetch INTEGER REFERENCES source_fetches(id),
+    profile INTEGER NOT NULL REFERENCES provenance_profiles(id),
+    raw_value REAL NOT NULL,
+    qc_flag TEXT NOT NULL,
+    qc_reasons TEXT, -- JSON, NULL when no check fired
+    PRIMARY KEY (sensor_id, timestamp)
+) WITHOUT ROWID;
+
+INSERT OR IGNORE INTO schema_migrations (version, description)
+VALUES (7, 'Reading provenance');
@@ -0,0 +1,14 @@
+-- Hourly weather at the points sources report it for, used as context for
+-- dose rate baselines (rain washes radon progeny out of the air)
+
+CREATE TABLE IF NOT EXISTS weather_observations (
+    latitude REAL NOT NULL,
+    longitude REAL NOT NULL,
+    timestamp INTEGER NOT NULL, -- start of the hour
+    source TEXT NOT NULL,
+    precipitation_mm REAL NOT NULL,
+    PRIMARY KEY (timestamp, latitude, longitude)
+) WITHOUT ROWID;
+
+INSERT OR IGNORE INTO schema_migrations (version, description)
+VALUES (8, 'Weather observations');
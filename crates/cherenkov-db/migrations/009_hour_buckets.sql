@@ -0,0 +1,19 @@
+-- Readings are partitioned by the hour of their timestamp. Some sources used
+-- to store day buckets, which hour bucket scans and tiering never found.
+
+-- A reading stored under both buckets keeps the hourly row
+DELETE FROM radiation_readings_warm
+WHERE bucket != CAST(strftime('%s', timestamp) AS INTEGER) / 3600
+AND EXISTS (
+    SELECT 1 FROM radiation_readings_warm AS hourly
+    WHERE hourly.sensor_id = radiation_readings_warm.sensor_id
+    AND hourly.timestamp = radiation_readings_warm.timestamp
+    AND hourly.bucket = CAST(strftime('%s', radiation_readings_warm.timestamp) AS INTEGER) / 3600
+);
+
+UPDATE radiation_readings_warm
+SET bucket = CAST(strftime('%s', timestamp) AS INTEGER) / 3600
+WHERE bucket != CAST(strftime('%s', timestamp) AS INTEGER) / 3600;
+
+INSERT OR IGNORE INTO schema_migrations (version, description)
+VALUES (9, 'Hour buckets for every reading');
@@ -0,0 +1,12 @@
+-- Liveness is judged against each sensor's own delivery cadence, learned
+-- from when its readings arrive rather than when they were taken
+
+ALTER TABLE sensors ADD COLUMN last_delivery DATETIME;
+ALTER TABLE sensors ADD COLUMN cadence_secs REAL;
+ALTER TABLE sensors ADD COLUMN cadence_intervals INTEGER NOT NULL DEFAULT 0;
+
+-- Until they deliver again, sensors are taken to have last delivered their newest reading
+UPDATE sensors SET last_delivery = last_reading WHERE last_reading IS NOT NULL;
+
+INSERT OR IGNORE INTO schema_migrations (version, description)
+VALUES (10, 'Learned sensor delivery cadence');
@@ -0,0 +1,868 @@
+//! Online backups of the warm tier, with point-in-time restore
+//!
+//! A snapshot is a page-for-page copy of the warm database taken with the
+//! SQLite online backup API, so readers and writers carry on while it runs.
+//! Between snapshots, committed WAL frames are archived as they are written:
+//! the archiver blocks writers for a moment, copies the frames appended since
+//! its last run and checkpoints them itself. With automatic checkpoints off,
+//! no frame reaches the database file before it is archived, so the archive
+//! holds every change since the snapshot.
+//!
+//! Frames are archived per WAL generation (one run of the WAL file between
+//! restarts, identified by its salt). Restoring copies the newest snapshot
+//! taken at or before the target time and replays the archived frames of its
+//! generation and the ones after it, up to the target or the first gap, by
+//! checkpointing them into the copy. Replay is exact because snapshots keep the source's page
+//! numbers.
+//!
+//! ```text
+//! <dir>/snapshots/<taken_at>.db, <taken_at>.json
+//! <dir>/wal/<created_at>-<salt>/header
+//! <dir>/wal/<created_at>-<salt>/<offset>-<archived_at>.frames
+//! ```
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{ConnectOptions, Connection, Row, SqliteConnection};
+use std::collections::BTreeMap;
+use std::ffi::{CStr, OsString};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use crate::sqlite::SqliteStorage;
+
+const WAL_HEADER_LEN: usize = 32;
+const FRAME_HEADER_LEN: usize = 24;
+/// WAL magic numbers; the last bit selects big-endian checksums
+const WAL_MAGIC_LE: u32 = 0x377f_0682;
+const WAL_MAGIC_BE: u32 = 0x377f_0683;
+
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    /// Snapshots and archived WAL are kept under here
+    pub dir: PathBuf,
+    /// Time between WAL archive runs, `None` takes snapshots only
+    pub archive_interval: Option<Duration>,
+    pub snapshot_interval: Duration,
+    /// Snapshots kept; older ones and the WAL only they need are pruned
+    pub keep_snapshots: usize,
+}
+
+impl BackupConfig {
+    /// Archive the WAL every minute and snapshot daily, keeping a week
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            archive_interval: Some(Duration::from_secs(60)),
+            snapshot_interval: Duration::from_secs(86_400),
+            keep_snapshots: 7,
+        }
+    }
+
+    /// From `BACKUP_DIR`, `BACKUP_WAL_ARCHIVE_SECS` (0 disables archiving),
+    /// `BACKUP_SNAPSHOT_INTERVAL_SECS` and `BACKUP_KEEP_SNAPSHOTS`; `None`
+    /// when `BACKUP_DIR` is unset
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let secs = |name: &str| var(name).and_then(|v| v.parse::<u64>().ok());
+
+        let mut config = Self::new(var("BACKUP_DIR")?);
+        if let Some(archive) = secs("BACKUP_WAL_ARCHIVE_SECS") {
+            config.archive_interval = (archive > 0).then(|| Duration::from_secs(archive));
+        }
+        if let Some(snapshot) = secs("BACKUP_SNAPSHOT_INTERVAL_SECS").filter(|s| *s > 0) {
+            config.snapshot_interval = Duration::from_secs(snapshot);
+        }
+        if let Some(keep) = secs("BACKUP_KEEP_SNAPSHOTS").filter(|k| *k > 0) {
+            config.keep_snapshots = keep as usize;
+        }
+        Some(config)
+    }
+
+    /// Whether the WAL is archived, which leaves checkpoints to the archiver
+    pub fn archives_wal(&self) -> bool {
+        self.archive_interval.is_some()
+    }
+}
+
+/// What a snapshot holds, written next to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// Milliseconds since the epoch, also the snapshot's file name
+    pub taken_at: i64,
+    pub size_bytes: u64,
+    pub sha256: String,
+    /// WAL generation current when the snapshot was taken, archived up to
+    /// `wal_offset`; `None` without WAL archiving
+    pub wal_generation: Option<String>,
+    pub wal_offset: u64,
+    pub tables: BTreeMap<String, TableChecksum>,
+}
+
+/// Row count and order-independent checksum of a table's rows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableChecksum {
+    pub rows: u64,
+    pub checksum: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveReport {
+    pub generation: Option<String>,
+    pub archived_bytes: u64,
+    /// Whether the checkpoint after archiving drained the WAL
+    pub checkpointed: bool,
+    /// Snapshot taken because the WAL restarted without being archived
+    pub snapshot: Option<BackupManifest>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub path: PathBuf,
+    /// Snapshot restored from
+    pub snapshot: i64,
+    pub segments_applied: usize,
+    /// Archive time of the last change restored, in milliseconds
+    pub restored_to: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    pub snapshots: Vec<BackupCheck>,
+    pub generations: Vec<BackupCheck>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupCheck {
+    pub name: String,
+    pub problems: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.snapshots.iter().chain(&self.generations).all(|c| c.problems.is_empty())
+    }
+}
+
+/// Snapshots and WAL archiving of one warm database
+pub struct WarmBackup {
+    storage: Arc<SqliteStorage>,
+    database_path: PathBuf,
+    config: BackupConfig,
+    /// WAL generation archived last, `None` until the first snapshot
+    chain: Option<Chain>,
+}
+
+struct Chain {
+    salt: String,
+    /// The WAL header's first salt, which goes up by one on every restart
+    sequence: u32,
+    /// Whether the last checkpoint moved every frame into the database,
+    /// which is what allows the next writer to restart the WAL
+    drained: bool,
+}
+
+/// Archived extent of a WAL generation
+struct WalPosition {
+    generation: String,
+    salt: String,
+    sequence: u32,
+    end: u64,
+    archived_bytes: u64,
+}
+
+impl WarmBackup {
+    pub fn new(storage: Arc<SqliteStorage>, config: BackupConfig) -> anyhow::Result<Self> {
+        let database_path = storage
+            .path()
+            .ok_or_else(|| anyhow::anyhow!("An in-memory database cannot be backed up"))?
+            .to_path_buf();
+        Ok(Self { storage, database_path, config, chain: None })
+    }
+
+    pub fn config(&self) -> &BackupConfig {
+        &self.config
+    }
+
+    /// Snapshot the database while it stays online
+    ///
+    /// Writers are blocked only while pending WAL frames are archived and a
+    /// read of the same state is pinned; the copy itself reads that state.
+    pub async fn snapshot(&mut self) -> anyhow::Result<BackupManifest> {
+        let dir = self.config.dir.join("snapshots");
+        std::fs::create_dir_all(&dir)?;
+        let taken_at = Utc::now().timestamp_millis();
+        let partial = dir.join(format!("{}.db.partial", taken_at));
+
+        let mut writer = self.storage.pool().acquire().await?;
+        let mut source = self.storage.pool().acquire().await?;
+
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *writer).await?;
+        let pinned = async {
+            let position = match self.config.archives_wal() {
+                true => self.archive_frames(taken_at)?,
+                false => None,
+            };
+            sqlx::query("BEGIN").execute(&mut *source).await?;
+            sqlx::query("SELECT COUNT(*) FROM sqlite_master").fetch_one(&mut *source).await?;
+            Ok::<_, anyhow::Error>(position)
+        }
+        .await;
+        sqlx::query("ROLLBACK").execute(&mut *writer).await?;
+
+        let copied = match &pinned {
+            Ok(_) => copy_database(&mut source, &partial).await,
+            Err(_) => Ok(()),
+        };
+        // Ends the pinned read, if it began
+        let _ = sqlx::query("ROLLBACK").execute(&mut *source).await;
+        let position = pinned?;
+        if let Err(e) = copied {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+
+        let manifest = BackupManifest {
+            taken_at,
+            size_bytes: std::fs::metadata(&partial)?.len(),
+            sha256: file_sha256(&partial)?,
+            wal_generation: position.as_ref().map(|p| p.generation.clone()),
+            wal_offset: position.as_ref().map_or(0, |p| p.end),
+            tables: table_checksums(&mut open_immutable(&partial).await?).await?,
+        };
+        std::fs::rename(&partial, dir.join(format!("{}.db", taken_at)))?;
+        std::fs::write(dir.join(format!("{}.json", taken_at)), serde_json::to_vec_pretty(&manifest)?)?;
+
+        self.chain = position.map(|p| Chain { salt: p.salt, sequence: p.sequence, drained: false });
+        info!("Snapshot of {} taken: {} bytes", self.database_path.display(), manifest.size_bytes);
+        Ok(manifest)
+    }
+
+    /// Archive the WAL frames committed since the last run, then checkpoint them
+    ///
+    /// If the WAL restarted without the archiver draining it first, frames
+    /// may be missing from the archive, so a new snapshot starts a new chain.
+    pub async fn archive_wal(&mut self) -> anyhow::Result<ArchiveReport> {
+        let now = Utc::now().timestamp_millis();
+        let mut writer = self.storage.pool().acquire().await?;
+        let mut checkpointer = self.storage.pool().acquire().await?;
+
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *writer).await?;
+        let archived = async {
+            if let Some(header) = read_wal(&self.database_path)?.map(|(header, _)| header) {
+                // Only a restart straight after our own checkpoint keeps the chain
+                let broken = self.chain.as_ref().is_some_and(|c| {
+                    c.salt != header.salt_hex() && !(c.drained && header.sequence() == c.sequence.wrapping_add(1))
+                });
+                if broken {
+                    return Ok(None);
+                }
+            }
+            let position = self.archive_frames(now)?;
+
+            // Writers are blocked, so every frame the checkpoint moves is archived
+            let row = sqlx::query("PRAGMA wal_checkpoint(PASSIVE)").fetch_one(&mut *checkpointer).await?;
+            let (frames, checkpointed): (i64, i64) = (row.get(1), row.get(2));
+            Ok::<_, anyhow::Error>(Some((position, frames >= 0 && frames == checkpointed)))
+        }
+        .await;
+        sqlx::query("ROLLBACK").execute(&mut *writer).await?;
+
+        let Some((position, drained)) = archived? else {
+            warn!("WAL restarted before it was archived, starting a new chain from a snapshot");
+            let snapshot = self.snapshot().await?;
+            self.prune_logged();
+            return Ok(ArchiveReport {
+                generation: snapshot.wal_generation.clone(),
+                snapshot: Some(snapshot),
+                ..ArchiveReport::default()
+            });
+        };
+
+        let report = ArchiveReport {
+            generation: position.as_ref().map(|p| p.generation.clone()),
+            archived_bytes: position.as_ref().map_or(0, |p| p.archived_bytes),
+            checkpointed: drained,
+            snapshot: None,
+        };
+        if let Some(position) = position {
+            self.chain = Some(Chain { salt: position.salt, sequence: position.sequence, drained });
+        }
+        Ok(report)
+    }
+
+    /// Delete all but the newest `keep_snapshots` snapshots, and the WAL
+    /// generations only the deleted ones could be restored with
+    pub fn prune(&self) -> anyhow::Result<usize> {
+        let snapshots = list_snapshots(&self.config.dir)?;
+        let keep = self.config.keep_snapshots.max(1);
+        let Some(oldest_kept) = snapshots.len().checked_sub(keep).map_or(snapshots.first(), |i| snapshots.get(i)).cloned() else {
+            return Ok(0);
+        };
+
+        let mut pruned = 0;
+        for manifest in snapshots.iter().filter(|m| m.taken_at < oldest_kept.taken_at) {
+            let dir = self.config.dir.join("snapshots");
+            std::fs::remove_file(dir.join(format!("{}.db", manifest.taken_at)))?;
+            std::fs::remove_file(dir.join(format!("{}.json", manifest.taken_at)))?;
+            pruned += 1;
+        }
+
+        let first_needed = oldest_kept
+            .wal_generation
+            .unwrap_or_else(|| format!("{:013}", oldest_kept.taken_at));
+        for (name, dir) in generation_dirs(&self.config.dir)? {
+            if name < first_needed {
+                std::fs::remove_dir_all(dir)?;
+            }
+        }
+        Ok(pruned)
+    }
+
+    fn prune_logged(&self) {
+        match self.prune() {
+            Ok(0) => {}
+            Ok(pruned) => info!("Pruned {} old snapshots", pruned),
+            Err(e) => warn!("Failed to prune backups: {}", e),
+        }
+    }
+
+    /// Snapshot, then archive the WAL and take further snapshots on schedule
+    /// until `shutdown` is cancelled
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        // Every archive chain starts from a snapshot
+        match self.snapshot().await {
+            Ok(_) => self.prune_logged(),
+            Err(e) => error!("Initial snapshot failed: {}", e),
+        }
+
+        let snapshot_interval = self.config.snapshot_interval;
+        let mut archive = tokio::time::interval(self.config.archive_interval.unwrap_or(snapshot_interval));
+        let mut snapshots = tokio::time::interval_at(tokio::time::Instant::now() + snapshot_interval, snapshot_interval);
+
+        loop {
+            tokio::select! {
+                _ = archive.tick(), if self.config.archives_wal() => match self.archive_wal().await {
+                    Ok(report) if report.archived_bytes > 0 => debug!("Archived WAL: {:?}", report),
+                    Ok(_) => {}
+                    Err(e) => error!("WAL archiving failed: {}", e),
+                },
+                _ = snapshots.tick() => {
+                    match self.snapshot().await {
+                        Ok(_) => self.prune_logged(),
+                        Err(e) => error!("Snapshot failed: {}", e),
+                    }
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        // Archive what was written since the last run, before closing the
+        // pool checkpoints it
+        if self.config.archives_wal() {
+            if let Err(e) = self.archive_wal().await {
+                error!("Final WAL archive failed: {}", e);
+            }
+        }
+        info!("Warm tier backups stopped");
+    }
+
+    /// Copy the current generation's committed frames that are not archived
+    /// yet; the caller holds the write lock
+    fn archive_frames(&self, now: i64) -> anyhow::Result<Option<WalPosition>> {
+        let Some((header, wal)) = read_wal(&self.database_path)? else {
+            return Ok(None);
+        };
+
+        let salt = header.salt_hex();
+        let generation_dir = match generation_dirs(&self.config.dir)?.into_iter().find(|(name, _)| name.ends_with(&salt)) {
+            Some((_, dir)) => dir,
+            None => {
+                let dir = self.config.dir.join("wal").join(format!("{:013}-{}", now, salt));
+                std::fs::create_dir_all(&dir)?;
+                write_atomic(&dir.join("header"), &header.bytes)?;
+                dir
+            }
+        };
+        let generation = generation_dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+
+        let archived = segments(&generation_dir)?.last().map_or(WAL_HEADER_LEN as u64, |s| s.offset + s.len);
+        let start = archived as usize;
+        let frame_len = header.frame_len();
+        if start > wal.len() || (start - WAL_HEADER_LEN) % frame_len != 0 {
+            anyhow::bail!("Archive of WAL generation {} does not match the WAL file", generation);
+        }
+
+        // Continue the checksum chain from the last archived frame
+        let checksum = match start {
+            WAL_HEADER_LEN => header.checksum(),
+            _ => {
+                let frame = &wal[start - frame_len..start];
+                (be_u32(&frame[16..20]), be_u32(&frame[20..24]))
+            }
+        };
+        let (committed, _) = committed_frames(&header, &wal[start..], checksum);
+        if committed > 0 {
+            let segment = generation_dir.join(format!("{:012}-{:013}.frames", archived, now));
+            write_atomic(&segment, &wal[start..start + committed])?;
+        }
+
+        Ok(Some(WalPosition {
+            generation,
+            salt,
+            sequence: header.sequence(),
+            end: archived + committed as u64,
+            archived_bytes: committed as u64,
+        }))
+    }
+}
+
+/// Snapshots in `dir`, oldest first
+pub fn list_snapshots(dir: &Path) -> anyhow::Result<Vec<BackupManifest>> {
+    let dir = dir.join("snapshots");
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut manifests = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            manifests.push(serde_json::from_slice::<BackupManifest>(&std::fs::read(&path)?)?);
+        }
+    }
+    manifests.sort_by_key(|m| m.taken_at);
+    Ok(manifests)
+}
+
+/// Restore the backups in `dir` to a new database at `target`, as of `at`
+/// or as late as the archive goes
+///
+/// `target` must not exist; stop the services and move the restored file
+/// into place once it is checked.
+pub async fn restore(dir: &Path, target: &Path, at: Option<DateTime<Utc>>) -> anyhow::Result<RestoreReport> {
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+    let until = at.map_or(i64::MAX, |at| at.timestamp_millis());
+    let snapshot = list_snapshots(dir)?
+        .into_iter()
+        .rev()
+        .find(|m| m.taken_at <= until)
+        .ok_or_else(|| anyhow::anyhow!("No snapshot taken at or before the restore time"))?;
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(dir.join("snapshots").join(format!("{}.db", snapshot.taken_at)), target)?;
+
+    let mut report = RestoreReport {
+        path: target.to_path_buf(),
+        snapshot: snapshot.taken_at,
+        segments_applied: 0,
+        restored_to: snapshot.taken_at,
+    };
+
+    // Without a WAL at the time, every later generation follows the snapshot
+    let first = snapshot.wal_generation.clone().unwrap_or_else(|| format!("{:013}", snapshot.taken_at));
+    let mut previous: Option<u32> = None;
+    for (name, generation_dir) in generation_dirs(dir)?.into_iter().filter(|(name, _)| *name >= first) {
+        let mut wal = std::fs::read(generation_dir.join("header"))?;
+        let sequence = WalHeader::parse(&wal).map(|h| h.sequence());
+        if previous.is_some_and(|p| sequence != Some(p.wrapping_add(1))) {
+            warn!("WAL generation {} does not follow the one before it, restoring up to it", name);
+            break;
+        }
+        previous = sequence;
+
+        let mut applied = 0;
+        let mut complete = true;
+        for segment in segments(&generation_dir)?.into_iter().filter(|s| s.archived_at <= until) {
+            if segment.offset != wal.len() as u64 {
+                warn!("WAL generation {} has a gap at offset {}, restoring up to it", name, wal.len());
+                complete = false;
+                break;
+            }
+            wal.extend(std::fs::read(&segment.path)?);
+            report.restored_to = report.restored_to.max(segment.archived_at);
+            applied += 1;
+        }
+
+        if applied > 0 {
+            apply_wal(target, &wal).await?;
+            report.segments_applied += applied;
+        }
+        if !complete {
+            break;
+        }
+    }
+
+    let mut restored = SqliteConnectOptions::new().filename(target).connect().await?;
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check").fetch_one(&mut restored).await?;
+    restored.close().await?;
+    if integrity != "ok" {
+        anyhow::bail!("Restored database fails its integrity check: {}", integrity);
+    }
+
+    info!(
+        "Restored {} from snapshot {} and {} WAL segments",
+        target.display(),
+        snapshot.taken_at,
+        report.segments_applied
+    );
+    Ok(report)
+}
+
+/// Check every snapshot against its manifest and every archived WAL
+/// generation's frames
+pub async fn verify(dir: &Path) -> anyhow::Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+
+    for manifest in list_snapshots(dir)? {
+        let path = dir.join("snapshots").join(format!("{}.db", manifest.taken_at));
+        report.snapshots.push(BackupCheck {
+            name: manifest.taken_at.to_string(),
+            problems: verify_snapshot(&path, &manifest).await?,
+        });
+    }
+
+    for (name, generation_dir) in generation_dirs(dir)? {
+        report.generations.push(BackupCheck { name, problems: verify_generation(&generation_dir)? });
+    }
+
+    Ok(report)
+}
+
+async fn verify_snapshot(path: &Path, manifest: &BackupManifest) -> anyhow::Result<Vec<String>> {
+    let mut problems = Vec::new();
+    if !path.exists() {
+        problems.push("snapshot file is missing".to_string());
+        return Ok(problems);
+    }
+    if file_sha256(path)? != manifest.sha256 {
+        problems.push("file checksum does not match the manifest".to_string());
+    }
+
+    let mut conn = open_immutable(path).await?;
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check").fetch_one(&mut conn).await?;
+    if integrity != "ok" {
+        problems.push(format!("integrity check failed: {}", integrity));
+        return Ok(problems);
+    }
+
+    let tables = table_checksums(&mut conn).await?;
+    for (table, expected) in &manifest.tables {
+        match tables.get(table) {
+            None => problems.push(format!("table {} is missing", table)),
+            Some(actual) if actual.rows != expected.rows => {
+                problems.push(format!("table {} has {} rows, expected {}", table, actual.rows, expected.rows))
+            }
+            Some(actual) if actual.checksum != expected.checksum => {
+                problems.push(format!("table {} checksum does not match", table))
+            }
+            Some(_) => {}
+        }
+    }
+    for table in tables.keys().filter(|t| !manifest.tables.contains_key(*t)) {
+        problems.push(format!("table {} is not in the manifest", table));
+    }
+    Ok(problems)
+}
+
+fn verify_generation(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let Some(header) = WalHeader::parse(&std::fs::read(dir.join("header"))?) else {
+        return Ok(vec!["WAL header is invalid".to_string()]);
+    };
+
+    let mut problems = Vec::new();
+    let (mut offset, mut checksum) = (WAL_HEADER_LEN as u64, header.checksum());
+    for segment in segments(dir)? {
+        if segment.offset != offset {
+            problems.push(format!("frames missing between offsets {} and {}", offset, segment.offset));
+            break;
+        }
+        let frames = std::fs::read(&segment.path)?;
+        let (committed, next) = committed_frames(&header, &frames, checksum);
+        if committed != frames.len() {
+            problems.push(format!("segment at offset {} has invalid frames", segment.offset));
+            break;
+        }
+        offset += frames.len() as u64;
+        checksum = next;
+    }
+    Ok(problems)
+}
+
+/// Page-for-page copy of the database `source` has open, in the state its
+/// current read transaction sees
+async fn copy_database(source: &mut SqliteConnection, target: &Path) -> anyhow::Result<()> {
+    let mut copy = SqliteConnectOptions::new()
+        .filename(target)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Delete)
+        .connect()
+        .await?;
+
+    let main = CStr::from_bytes_with_nul(b"main\0")?;
+    let (step, finish) = {
+        let mut source = source.lock_handle().await?;
+        let mut copy = copy.lock_handle().await?;
+        // SAFETY: both handles are locked for the duration, and the backup
+        // object is finished before they are released
+        unsafe {
+            let backup = libsqlite3_sys::sqlite3_backup_init(
+                copy.as_raw_handle().as_ptr(),
+                main.as_ptr(),
+                source.as_raw_handle().as_ptr(),
+                main.as_ptr(),
+            );
+            if backup.is_null() {
+                let message = CStr::from_ptr(libsqlite3_sys::sqlite3_errmsg(copy.as_raw_handle().as_ptr()));
+                anyhow::bail!("Backup failed to start: {}", message.to_string_lossy());
+            }
+            let step = libsqlite3_sys::sqlite3_backup_step(backup, -1);
+            (step, libsqlite3_sys::sqlite3_backup_finish(backup))
+        }
+    };
+    if step != libsqlite3_sys::SQLITE_DONE || finish != libsqlite3_sys::SQLITE_OK {
+        anyhow::bail!("Backup failed: {}", error_string(if step != libsqlite3_sys::SQLITE_DONE { step } else { finish }));
+    }
+
+    // Archived WAL is replayed onto snapshots, so they are WAL databases too
+    sqlx::query("PRAGMA journal_mode = WAL").execute(&mut copy).await?;
+    copy.close().await?;
+    Ok(())
+}
+
+/// Replay a generation's WAL into `database` and checkpoint it
+async fn apply_wal(database: &Path, wal: &[u8]) -> anyhow::Result<()> {
+    // A stale index would hide the frames; SQLite rebuilds it from the WAL
+    let _ = std::fs::remove_file(sidecar(database, "-shm"));
+    std::fs::write(sidecar(database, "-wal"), wal)?;
+
+    let mut conn = SqliteConnectOptions::new().filename(database).connect().await?;
+    // TRUNCATE reports no frames once it empties the WAL, so count them first
+    let row = sqlx::query("PRAGMA wal_checkpoint(FULL)").fetch_one(&mut conn).await?;
+    let (busy, frames, checkpointed): (i64, i64, i64) = (row.get(0), row.get(1), row.get(2));
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut conn).await?;
+    conn.close().await?;
+
+    let expected = (wal.len() - WAL_HEADER_LEN) as i64 / WalHeader::parse(wal).map_or(1, |h| h.frame_len() as i64);
+    if busy != 0 || frames != checkpointed || checkpointed < expected {
+        anyhow::bail!("Replayed {} of {} archived WAL frames", checkpointed.max(0), expected);
+    }
+    Ok(())
+}
+
+async fn open_immutable(path: &Path) -> anyhow::Result<SqliteConnection> {
+    Ok(SqliteConnectOptions::new().filename(path).immutable(true).connect().await?)
+}
+
+/// Row count and checksum of every table
+///
+/// A row hashes as its `quote()`d columns, and a table's checksum is the sum
+/// of its rows' hashes, so it does not depend on the order rows are stored in.
+async fn table_checksums(conn: &mut SqliteConnection) -> anyhow::Result<BTreeMap<String, TableChecksum>> {
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut checksums = BTreeMap::new();
+    for table in tables {
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+            .bind(&table)
+            .fetch_all(&mut *conn)
+            .await?;
+        let row = columns
+            .iter()
+            .map(|c| format!("quote({})", quote_identifier(c)))
+            .collect::<Vec<_>>()
+            .join(" || '|' || ");
+        let sql = format!("SELECT {} FROM {}", row, quote_identifier(&table));
+
+        let (mut rows, mut sum) = (0u64, 0u64);
+        let mut stream = sqlx::query_scalar::<_, String>(&sql).fetch(&mut *conn);
+        while let Some(row) = stream.try_next().await? {
+            let digest = Sha256::digest(row.as_bytes());
+            let mut prefix = [0u8; 8];
+            prefix.copy_from_slice(&digest[..8]);
+            sum = sum.wrapping_add(u64::from_be_bytes(prefix));
+            rows += 1;
+        }
+        checksums.insert(table, TableChecksum { rows, checksum: format!("{:016x}", sum) });
+    }
+    Ok(checksums)
+}
+
+struct WalHeader {
+    bytes: Vec<u8>,
+    page_size: usize,
+    big_endian: bool,
+}
+
+impl WalHeader {
+    /// Header at the start of `wal`, if it is a valid one
+    fn parse(wal: &[u8]) -> Option<Self> {
+        let bytes = wal.get(..WAL_HEADER_LEN)?;
+        let big_endian = match be_u32(&bytes[0..4]) {
+            WAL_MAGIC_BE => true,
+            WAL_MAGIC_LE => false,
+            _ => return None,
+        };
+        let page_size = be_u32(&bytes[8..12]) as usize;
+        if !(512..=65_536).contains(&page_size) || !page_size.is_power_of_two() {
+            return None;
+        }
+        let header = Self { bytes: bytes.to_vec(), page_size, big_endian };
+        (wal_checksum(big_endian, &bytes[..24], (0, 0)) == header.checksum()).then_some(header)
+    }
+
+    fn salt_hex(&self) -> String {
+        self.bytes[16..24].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// First salt, which SQLite increments every time the WAL restarts
+    fn sequence(&self) -> u32 {
+        be_u32(&self.bytes[16..20])
+    }
+
+    fn frame_len(&self) -> usize {
+        FRAME_HEADER_LEN + self.page_size
+    }
+
+    fn checksum(&self) -> (u32, u32) {
+        (be_u32(&self.bytes[24..28]), be_u32(&self.bytes[28..32]))
+    }
+}
+
+/// Length of the valid frames at the start of `frames` that end in a commit,
+/// continuing the checksum chain from `checksum`, and the chain's checksum there
+fn committed_frames(header: &WalHeader, frames: &[u8], checksum: (u32, u32)) -> (usize, (u32, u32)) {
+    let frame_len = header.frame_len();
+    let (mut committed, mut committed_checksum, mut running) = (0, checksum, checksum);
+
+    for (i, frame) in frames.chunks_exact(frame_len).enumerate() {
+        // Frames left over from an earlier generation carry its salt
+        if frame[8..16] != header.bytes[16..24] {
+            break;
+        }
+        running = wal_checksum(header.big_endian, &frame[FRAME_HEADER_LEN..], wal_checksum(header.big_endian, &frame[..8], running));
+        if running != (be_u32(&frame[16..20]), be_u32(&frame[20..24])) {
+            break;
+        }
+        if be_u32(&frame[4..8]) != 0 {
+            committed = (i + 1) * frame_len;
+            committed_checksum = running;
+        }
+    }
+    (committed, committed_checksum)
+}
+
+/// SQLite's WAL checksum over `data`, continuing from `(s0, s1)`
+fn wal_checksum(big_endian: bool, data: &[u8], (mut s0, mut s1): (u32, u32)) -> (u32, u32) {
+    let word = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    };
+    for pair in data.chunks_exact(8) {
+        s0 = s0.wrapping_add(word(&pair[..4])).wrapping_add(s1);
+        s1 = s1.wrapping_add(word(&pair[4..])).wrapping_add(s0);
+    }
+    (s0, s1)
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// The database's WAL file and its header, unless there is no valid one
+fn read_wal(database: &Path) -> anyhow::Result<Option<(WalHeader, Vec<u8>)>> {
+    let wal = match std::fs::read(sidecar(database, "-wal")) {
+        Ok(wal) => wal,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(WalHeader::parse(&wal).map(|header| (header, wal)))
+}
+
+struct Segment {
+    offset: u64,
+    len: u64,
+    archived_at: i64,
+    path: PathBuf,
+}
+
+/// Archived WAL generations, oldest first
+fn generation_dirs(dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let dir = dir.join("wal");
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut generations = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            generations.push((entry.file_name().to_string_lossy().into_owned(), entry.path()));
+        }
+    }
+    generations.sort();
+    Ok(generations)
+}
+
+/// A generation's archived segments, in WAL order
+fn segments(generation_dir: &Path) -> anyhow::Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    for entry in std::fs::read_dir(generation_dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |e| e != "frames") {
+            continue;
+        }
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let Some((offset, archived_at)) = stem.split_once('-') else {
+            continue;
+        };
+        if let (Ok(offset), Ok(archived_at)) = (offset.parse(), archived_at.parse()) {
+            segments.push(Segment { offset, len: std::fs::metadata(&path)?.len(), archived_at, path });
+        }
+    }
+    segments.sort_by_key(|s| s.offset);
+    Ok(segments)
+}
+
+fn sidecar(database: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(database.as_os_str());
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Write to a temporary file first, so a crash never leaves a torn file
+fn write_atomic(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let partial = sidecar(path, ".partial");
+    std::fs::write(&partial, contents)?;
+    std::fs::File::open(&partial)?.sync_all()?;
+    std::fs::rename(partial, path)?;
+    Ok(())
+}
+
+fn file_sha256(path: &Path) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn error_string(code: i32) -> String {
+    // SAFETY: sqlite3_errstr returns a static string for any code
+    unsafe { CStr::from_ptr(libsqlite3_sys::sqlite3_errstr(code)).to_string_lossy().into_owned() }
+}
@@ -82,23 +82,8 @@ impl RedisCache {
 
     #[instrument(skip(self))]
     pub async fn invalidate_sensor(&self, sensor_id: &Uuid) -> anyhow::Result<()> {
-        let pattern = format!("sensor:{}:*", sensor_id);
         let mut conn = self.connection.write().await;
-        
-        // Find and delete all keys matching the sensor pattern
-        let keys: Vec<String> = redis::cmd("KEYS")
-            .arg(&pattern)
-            .query_async(&mut *conn)
-            .await?;
-        
-        if !keys.is_empty() {
-            redis::cmd("DEL")
-                .arg(&keys)
-                .query_async::<_, ()>(&mut *conn)
-                .await?;
-        }
-        
-        Ok(())
+        delete_matching(&mut conn, &format!("sensor:{}:*", sensor_id)).await
     }
 
     #[instrument(skip(self, reading))]
@@ -315,6 +300,104 @@ impl RedisCache {
     }
 }
 
+#[async_trait::async_trait]
+impl crate::store::Cache for RedisCache {
+    async fn get_raw(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let mut conn = self.connection.write().await;
+
+        let value: Option<String> = redis::cmd("GET")
+            .arg(key)
+            .query_async(&mut *conn)
+            .await?;
+
+        Ok(value)
+    }
+
+    async fn set_raw(&self, key: &str, value: String, ttl_seconds: u64) -> anyhow::Result<()> {
+        let mut conn = self.connection.write().await;
+
+        redis::cmd("SETEX")
+            .arg(key)
+            .arg(ttl_seconds)
+            .arg(value)
+            .query_async::<_, ()>(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        RedisCache::delete(self, key).await
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> anyhow::Result<()> {
+        let mut conn = self.connection.write().await;
+        delete_matching(&mut conn, &format!("{}*", prefix)).await
+    }
+
+    async fn get_many(&self, keys: &[String]) -> anyhow::Result<Vec<Option<String>>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.connection.write().await;
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(keys)
+            .query_async(&mut *conn)
+            .await?;
+
+        Ok(values)
+    }
+
+    async fn set_many(&self, entries: &[(String, String)], ttl_seconds: u64) -> anyhow::Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut pipe = redis::pipe();
+        for (key, value) in entries {
+            pipe.cmd("SETEX").arg(key).arg(ttl_seconds).arg(value).ignore();
+        }
+
+        let mut conn = self.connection.write().await;
+        pipe.query_async::<_, ()>(&mut *conn).await?;
+
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        RedisCache::health_check(self).await
+    }
+}
+
+/// Delete keys matching `pattern`, walking the keyspace with `SCAN` in
+/// small steps so Redis keeps serving other clients meanwhile
+async fn delete_matching(conn: &mut MultiplexedConnection, pattern: &str) -> anyhow::Result<()> {
+    let mut cursor: u64 = 0;
+    loop {
+        let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(pattern)
+            .arg("COUNT")
+            .arg(500)
+            .query_async(conn)
+            .await?;
+
+        if !keys.is_empty() {
+            redis::cmd("UNLINK")
+                .arg(&keys)
+                .query_async::<_, ()>(conn)
+                .await?;
+        }
+
+        if next == 0 {
+            return Ok(());
+        }
+        cursor = next;
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CacheStats {
     pub used_memory_bytes: u64,
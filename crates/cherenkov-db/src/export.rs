@@ -0,0 +1,631 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::netcdf::{self, AttrValue, NcType};
+use crate::query::{SortOrder, SpatialQuery, Tier, TimeRangeQuery};
+use crate::storage::{CompressionType, ParquetWriter};
+use crate::{AggregationLevel, DatabaseError, QcCheck, QualityFlag, RadiationDatabase, RadiationReading};
+
+/// Window read from the database at a time, bounding an export's memory use
+const EXPORT_CHUNK_SECS: i64 = 6 * 3600;
+const PARQUET_ROW_GROUP_SIZE: usize = 65_536;
+/// Key of the provenance JSON in Parquet footers and NetCDF global attributes
+const PROVENANCE_KEY: &str = "cherenkov_provenance";
+
+/// QC checks in the bit order of the NetCDF `qc_checks` mask
+const QC_CHECKS: [(QcCheck, &str); 8] = [
+    (QcCheck::Range, "range"),
+    (QcCheck::Spike, "spike"),
+    (QcCheck::Step, "step"),
+    (QcCheck::Flatline, "flatline"),
+    (QcCheck::RateOfChange, "rate_of_change"),
+    (QcCheck::FutureTimestamp, "future_timestamp"),
+    (QcCheck::LocationJump, "location_jump"),
+    (QcCheck::DuplicateStation, "duplicate_station"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    GeoJson,
+    Parquet,
+    NetCdf,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "geojson" => Some(ExportFormat::GeoJson),
+            "parquet" => Some(ExportFormat::Parquet),
+            "netcdf" | "nc" => Some(ExportFormat::NetCdf),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::NetCdf => "nc",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::GeoJson => "application/geo+json",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+            ExportFormat::NetCdf => "application/x-netcdf",
+        }
+    }
+}
+
+/// What an export contains and where it came from
+///
+/// Embedded in GeoJSON, Parquet and NetCDF files; every format also carries
+/// each reading's source, track and QC reasons.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportProvenance {
+    pub generator: String,
+    pub generated_at: DateTime<Utc>,
+    pub from_timestamp: i64,
+    pub to_timestamp: i64,
+    pub sensor_ids: Vec<Uuid>,
+    pub area: Option<SpatialQuery>,
+    pub quality_flags: Vec<QualityFlag>,
+    pub sources: Vec<String>,
+    /// Tiers the readings were read from
+    pub tiers: Vec<Tier>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub format: ExportFormat,
+    pub readings: u64,
+    pub bytes: u64,
+    pub provenance: ExportProvenance,
+}
+
+/// Write every raw reading matching `query` to `path`
+///
+/// Readings are read a few hours at a time, oldest first, and handed to the
+/// format's writer on the blocking pool. `progress` gets the running count
+/// after each chunk.
+#[instrument(skip(db, progress))]
+pub async fn export_readings(
+    db: &RadiationDatabase,
+    query: &TimeRangeQuery,
+    format: ExportFormat,
+    path: &Path,
+    mut progress: impl FnMut(u64),
+) -> Result<ExportSummary, DatabaseError> {
+    let query = TimeRangeQuery {
+        aggregation: AggregationLevel::Raw,
+        order: SortOrder::Ascending,
+        limit: None,
+        cursor: None,
+        ..query.clone()
+    };
+    let provenance = ExportProvenance {
+        generator: concat!("cherenkov-db ", env!("CARGO_PKG_VERSION")).to_string(),
+        generated_at: Utc::now(),
+        from_timestamp: query.from_timestamp,
+        to_timestamp: query.to_timestamp,
+        sensor_ids: query.sensor_ids.clone(),
+        area: query.area.clone(),
+        quality_flags: query.quality_flags.clone(),
+        sources: query.sources.clone(),
+        tiers: db.plan(&query).steps.iter().map(|s| s.tier).collect(),
+    };
+
+    let mut sink = open_sink(format, path, &provenance).map_err(|e| DatabaseError::Storage(e.to_string()))?;
+    let mut written = 0u64;
+    let mut start = query.from_timestamp;
+
+    while start <= query.to_timestamp {
+        let end = start.saturating_add(EXPORT_CHUNK_SECS - 1).min(query.to_timestamp);
+        let chunk = TimeRangeQuery {
+            from_timestamp: start,
+            to_timestamp: end,
+            ..query.clone()
+        };
+        let readings = db.query_readings(&chunk).await?.items;
+
+        if !readings.is_empty() {
+            written += readings.len() as u64;
+            sink = tokio::task::spawn_blocking(move || {
+                sink.write(&readings)?;
+                Ok::<_, anyhow::Error>(sink)
+            })
+            .await
+            .map_err(|e| DatabaseError::Storage(e.to_string()))?
+            .map_err(|e| DatabaseError::Storage(e.to_string()))?;
+            progress(written);
+        }
+
+        if end == i64::MAX {
+            break;
+        }
+        start = end + 1;
+    }
+
+    tokio::task::spawn_blocking(move || sink.finish())
+        .await
+        .map_err(|e| DatabaseError::Storage(e.to_string()))?
+        .map_err(|e| DatabaseError::Storage(e.to_string()))?;
+
+    let bytes = tokio::fs::metadata(path).await
+        .map_err(|e| DatabaseError::Storage(e.to_string()))?
+        .len();
+
+    Ok(ExportSummary { format, readings: written, bytes, provenance })
+}
+
+/// A file being written, one batch of readings at a time
+trait ExportSink: Send {
+    fn write(&mut self, readings: &[RadiationReading]) -> anyhow::Result<()>;
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+fn open_sink(format: ExportFormat, path: &Path, provenance: &ExportProvenance) -> anyhow::Result<Box<dyn ExportSink>> {
+    let provenance_json = serde_json::to_string(provenance)?;
+    Ok(match format {
+        ExportFormat::Csv => Box::new(CsvSink::create(path)?),
+        ExportFormat::GeoJson => Box::new(GeoJsonSink::create(path, &provenance_json)?),
+        ExportFormat::Parquet => Box::new(ParquetSink(ParquetWriter::create(
+            path,
+            CompressionType::Zstd,
+            PARQUET_ROW_GROUP_SIZE,
+            vec![(PROVENANCE_KEY.to_string(), provenance_json)],
+        )?)),
+        ExportFormat::NetCdf => Box::new(NetCdfSink::create(path, provenance, provenance_json)?),
+    })
+}
+
+fn rfc3339(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0).map(|t| t.to_rfc3339()).unwrap_or_default()
+}
+
+struct CsvSink {
+    writer: csv::Writer<BufWriter<File>>,
+}
+
+impl CsvSink {
+    const HEADER: [&'static str; 13] = [
+        "sensor_id",
+        "timestamp",
+        "time",
+        "latitude",
+        "longitude",
+        "altitude_m",
+        "dose_rate_usv_h",
+        "uncertainty_usv_h",
+        "quality_flag",
+        "qc_reasons",
+        "source",
+        "track_id",
+        "cell_id",
+    ];
+
+    fn create(path: &Path) -> anyhow::Result<Self> {
+        let mut writer = csv::Writer::from_writer(BufWriter::new(File::create(path)?));
+        writer.write_record(Self::HEADER)?;
+        Ok(Self { writer })
+    }
+}
+
+impl ExportSink for CsvSink {
+    fn write(&mut self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        for r in readings {
+            self.writer.write_record([
+                r.sensor_id.to_string(),
+                r.timestamp.to_string(),
+                rfc3339(r.timestamp),
+                r.latitude.to_string(),
+                r.longitude.to_string(),
+                r.altitude_m.map(|a| a.to_string()).unwrap_or_default(),
+                r.dose_rate_microsieverts.to_string(),
+                r.uncertainty.to_string(),
+                r.quality_flag.as_str().to_string(),
+                serde_json::to_string(&r.qc_reasons)?,
+                r.source.clone(),
+                r.track_id.map(|t| t.to_string()).unwrap_or_default(),
+                r.cell_id.clone(),
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// FeatureCollection of points, written feature by feature
+struct GeoJsonSink {
+    writer: BufWriter<File>,
+    first: bool,
+}
+
+impl GeoJsonSink {
+    fn create(path: &Path, provenance_json: &str) -> anyhow::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, r#"{{"type":"FeatureCollection","metadata":{},"features":["#, provenance_json)?;
+        Ok(Self { writer, first: true })
+    }
+}
+
+impl ExportSink for GeoJsonSink {
+    fn write(&mut self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        for r in readings {
+            let mut coordinates = vec![r.longitude, r.latitude];
+            coordinates.extend(r.altitude_m);
+            let feature = serde_json::json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": coordinates },
+                "properties": {
+                    "sensor_id": r.sensor_id,
+                    "time": rfc3339(r.timestamp),
+                    "dose_rate_usv_h": r.dose_rate_microsieverts,
+                    "uncertainty_usv_h": r.uncertainty,
+                    "quality_flag": r.quality_flag.as_str(),
+                    "qc_reasons": r.qc_reasons,
+                    "source": r.source,
+                    "track_id": r.track_id,
+                    "cell_id": r.cell_id,
+                },
+            });
+
+            if !self.first {
+                self.writer.write_all(b",")?;
+            }
+            self.first = false;
+            serde_json::to_writer(&mut self.writer, &feature)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.writer.write_all(b"]}")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+struct ParquetSink(ParquetWriter);
+
+impl ExportSink for ParquetSink {
+    fn write(&mut self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        self.0.write(readings)
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        self.0.close()
+    }
+}
+
+struct Station {
+    sensor_id: Uuid,
+    latitude: f64,
+    longitude: f64,
+    source: String,
+}
+
+/// CF `timeSeries` of observations in an indexed ragged array
+///
+/// The number of sensors is only known at the end, so observations are
+/// spooled to a side file as finished NetCDF records and copied in behind
+/// the header once the station table is complete. A station's position is
+/// the last one it reported.
+struct NetCdfSink {
+    path: PathBuf,
+    spool_path: PathBuf,
+    spool: BufWriter<File>,
+    stations: Vec<Station>,
+    station_index: HashMap<Uuid, usize>,
+    records: u64,
+    title: String,
+    provenance_json: String,
+}
+
+impl NetCdfSink {
+    fn create(path: &Path, provenance: &ExportProvenance, provenance_json: String) -> anyhow::Result<Self> {
+        let mut spool_path = path.as_os_str().to_owned();
+        spool_path.push(".records");
+        let spool_path = PathBuf::from(spool_path);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            spool: BufWriter::new(File::create(&spool_path)?),
+            spool_path,
+            stations: Vec::new(),
+            station_index: HashMap::new(),
+            records: 0,
+            title: format!(
+                "Cherenkov dose rate readings, {} to {}",
+                rfc3339(provenance.from_timestamp),
+                rfc3339(provenance.to_timestamp)
+            ),
+            provenance_json,
+        })
+    }
+
+    fn schema(&self, source_len: usize) -> netcdf::Schema {
+        let mut schema = netcdf::Schema::default();
+        let station = schema.dimension("station", Some(self.stations.len().max(1)));
+        let id_len = schema.dimension("id_strlen", Some(36));
+        let source_strlen = schema.dimension("source_strlen", Some(source_len));
+        let obs = schema.dimension("obs", None);
+
+        let text = |s: &str| AttrValue::Text(s.to_string());
+        schema.attribute("Conventions", text("CF-1.8"));
+        schema.attribute("featureType", text("timeSeries"));
+        schema.attribute("title", text(&self.title));
+        schema.attribute("source", text("Cherenkov radiation monitoring network"));
+        schema.attribute("history", text(&format!("{} exported by cherenkov-db", Utc::now().to_rfc3339())));
+        schema.attribute(PROVENANCE_KEY, text(&self.provenance_json));
+
+        let var = schema.variable("station_id", NcType::Char, &[station, id_len]);
+        schema.variable_attribute(var, "cf_role", text("timeseries_id"));
+        schema.variable_attribute(var, "long_name", text("sensor identifier"));
+        let var = schema.variable("lat", NcType::Double, &[station]);
+        schema.variable_attribute(var, "standard_name", text("latitude"));
+        schema.variable_attribute(var, "units", text("degrees_north"));
+        let var = schema.variable("lon", NcType::Double, &[station]);
+        schema.variable_attribute(var, "standard_name", text("longitude"));
+        schema.variable_attribute(var, "units", text("degrees_east"));
+        let var = schema.variable("station_source", NcType::Char, &[station, source_strlen]);
+        schema.variable_attribute(var, "long_name", text("data source of the sensor"));
+
+        let var = schema.variable("time", NcType::Double, &[obs]);
+        schema.variable_attribute(var, "standard_name", text("time"));
+        schema.variable_attribute(var, "units", text("seconds since 1970-01-01 00:00:00 UTC"));
+        schema.variable_attribute(var, "calendar", text("standard"));
+        schema.variable_attribute(var, "axis", text("T"));
+        let var = schema.variable("dose_rate", NcType::Double, &[obs]);
+        schema.variable_attribute(var, "long_name", text("ambient dose equivalent rate"));
+        schema.variable_attribute(var, "units", text("uSv h-1"));
+        schema.variable_attribute(var, "coordinates", text("time lat lon station_id"));
+        schema.variable_attribute(var, "ancillary_variables", text("uncertainty quality_flag qc_checks"));
+        let var = schema.variable("uncertainty", NcType::Float, &[obs]);
+        schema.variable_attribute(var, "long_name", text("dose rate uncertainty"));
+        schema.variable_attribute(var, "units", text("uSv h-1"));
+        let var = schema.variable("quality_flag", NcType::Byte, &[obs]);
+        schema.variable_attribute(var, "long_name", text("quality control flag"));
+        schema.variable_attribute(var, "flag_values", AttrValue::Byte(vec![0, 1, 2]));
+        schema.variable_attribute(var, "flag_meanings", text("valid suspect invalid"));
+        let var = schema.variable("qc_checks", NcType::Int, &[obs]);
+        schema.variable_attribute(var, "long_name", text("quality control checks that fired"));
+        let masks: Vec<i32> = (0..QC_CHECKS.len()).map(|bit| 1 << bit).collect();
+        let meanings: Vec<&str> = QC_CHECKS.iter().map(|(_, name)| *name).collect();
+        schema.variable_attribute(var, "flag_masks", AttrValue::Int(masks));
+        schema.variable_attribute(var, "flag_meanings", text(&meanings.join(" ")));
+        let var = schema.variable("station_index", NcType::Int, &[obs]);
+        schema.variable_attribute(var, "long_name", text("index of the station this observation belongs to"));
+        schema.variable_attribute(var, "instance_dimension", text("station"));
+
+        schema
+    }
+}
+
+impl ExportSink for NetCdfSink {
+    fn write(&mut self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        for r in readings {
+            let index = match self.station_index.get(&r.sensor_id) {
+                Some(index) => {
+                    let station = &mut self.stations[*index];
+                    station.latitude = r.latitude;
+                    station.longitude = r.longitude;
+                    *index
+                }
+                None => {
+                    self.stations.push(Station {
+                        sensor_id: r.sensor_id,
+                        latitude: r.latitude,
+                        longitude: r.longitude,
+                        source: r.source.clone(),
+                    });
+                    self.station_index.insert(r.sensor_id, self.stations.len() - 1);
+                    self.stations.len() - 1
+                }
+            };
+
+            let quality: i8 = match r.quality_flag {
+                QualityFlag::Valid => 0,
+                QualityFlag::Suspect => 1,
+                QualityFlag::Invalid => 2,
+            };
+            let checks = r.qc_reasons.iter().fold(0i32, |mask, reason| {
+                let bit = QC_CHECKS.iter().position(|(check, _)| *check == reason.check).unwrap_or(0);
+                mask | (1 << bit)
+            });
+
+            // Record variables in declaration order, each padded to four bytes
+            self.spool.write_all(&(r.timestamp as f64).to_be_bytes())?;
+            self.spool.write_all(&r.dose_rate_microsieverts.to_be_bytes())?;
+            self.spool.write_all(&r.uncertainty.to_be_bytes())?;
+            self.spool.write_all(&[quality as u8, 0, 0, 0])?;
+            self.spool.write_all(&checks.to_be_bytes())?;
+            self.spool.write_all(&(index as i32).to_be_bytes())?;
+            self.records += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.spool.flush()?;
+
+        // NetCDF has no empty fixed dimensions, so an empty export keeps one blank station
+        if self.stations.is_empty() {
+            self.stations.push(Station {
+                sensor_id: Uuid::nil(),
+                latitude: f64::NAN,
+                longitude: f64::NAN,
+                source: String::new(),
+            });
+        }
+
+        let source_len = self.stations.iter().map(|s| s.source.len()).max().unwrap_or(0).max(1);
+        let schema = self.schema(source_len);
+
+        let fixed_text = |value: &str, width: usize| {
+            let mut bytes = value.as_bytes().to_vec();
+            bytes.resize(width, 0);
+            bytes
+        };
+        let mut station_ids = Vec::new();
+        let mut latitudes = Vec::new();
+        let mut longitudes = Vec::new();
+        let mut sources = Vec::new();
+        for station in &self.stations {
+            station_ids.extend(fixed_text(&station.sensor_id.to_string(), 36));
+            latitudes.extend(station.latitude.to_be_bytes());
+            longitudes.extend(station.longitude.to_be_bytes());
+            sources.extend(fixed_text(&station.source, source_len));
+        }
+
+        let mut out = BufWriter::new(File::create(&self.path)?);
+        let mut records = BufReader::new(File::open(&self.spool_path)?);
+        schema.write(&mut out, self.records, &[station_ids, latitudes, longitudes, sources], &mut records)?;
+        out.flush()?;
+
+        std::fs::remove_file(&self.spool_path)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportJob {
+    pub id: Uuid,
+    pub format: ExportFormat,
+    pub status: ExportStatus,
+    pub readings_written: u64,
+    pub bytes: Option<u64>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub provenance: Option<ExportProvenance>,
+}
+
+/// Exports running in the background, each written to its own file in `dir`
+pub struct ExportJobs {
+    db: Arc<RadiationDatabase>,
+    dir: PathBuf,
+    jobs: Arc<RwLock<HashMap<Uuid, ExportJob>>>,
+}
+
+impl ExportJobs {
+    pub fn new(db: Arc<RadiationDatabase>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            db,
+            dir: dir.into(),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Start exporting `query` and return the job to poll
+    pub fn start(&self, query: TimeRangeQuery, format: ExportFormat) -> Result<ExportJob, DatabaseError> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| DatabaseError::Storage(e.to_string()))?;
+
+        let job = ExportJob {
+            id: Uuid::new_v4(),
+            format,
+            status: ExportStatus::Running,
+            readings_written: 0,
+            bytes: None,
+            created_at: Utc::now(),
+            finished_at: None,
+            error: None,
+            provenance: None,
+        };
+        self.jobs.write().unwrap_or_else(|e| e.into_inner()).insert(job.id, job.clone());
+
+        let (db, jobs, id) = (self.db.clone(), self.jobs.clone(), job.id);
+        let path = self.path(id, format);
+        tokio::spawn(async move {
+            let update = |f: &dyn Fn(&mut ExportJob)| {
+                if let Some(job) = jobs.write().unwrap_or_else(|e| e.into_inner()).get_mut(&id) {
+                    f(job);
+                }
+            };
+
+            let result = export_readings(&db, &query, format, &path, |n| update(&|job| job.readings_written = n)).await;
+            match result {
+                Ok(summary) => {
+                    info!("Export {} finished: {} readings, {} bytes", id, summary.readings, summary.bytes);
+                    update(&|job| {
+                        job.status = ExportStatus::Completed;
+                        job.readings_written = summary.readings;
+                        job.bytes = Some(summary.bytes);
+                        job.finished_at = Some(Utc::now());
+                        job.provenance = Some(summary.provenance.clone());
+                    });
+                }
+                Err(e) => {
+                    error!("Export {} failed: {}", id, e);
+                    let _ = tokio::fs::remove_file(&path).await;
+                    update(&|job| {
+                        job.status = ExportStatus::Failed;
+                        job.finished_at = Some(Utc::now());
+                        job.error = Some(e.to_string());
+                    });
+                }
+            }
+        });
+
+        Ok(job)
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<ExportJob> {
+        self.jobs.read().unwrap_or_else(|e| e.into_inner()).get(id).cloned()
+    }
+
+    /// File of a completed export
+    pub fn file(&self, id: &Uuid) -> Option<PathBuf> {
+        self.get(id)
+            .filter(|job| job.status == ExportStatus::Completed)
+            .map(|job| self.path(job.id, job.format))
+    }
+
+    /// Forget finished jobs older than `max_age` and delete their files
+    pub async fn prune(&self, max_age: Duration) -> usize {
+        let cutoff = Utc::now() - max_age;
+        let expired: Vec<ExportJob> = {
+            let mut jobs = self.jobs.write().unwrap_or_else(|e| e.into_inner());
+            let ids: Vec<Uuid> = jobs
+                .values()
+                .filter(|job| job.finished_at.is_some_and(|at| at < cutoff))
+                .map(|job| job.id)
+                .collect();
+            ids.iter().filter_map(|id| jobs.remove(id)).collect()
+        };
+
+        for job in &expired {
+            let _ = tokio::fs::remove_file(self.path(job.id, job.format)).await;
+        }
+        expired.len()
+    }
+
+    fn path(&self, id: Uuid, format: ExportFormat) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, format.extension()))
+    }
+}
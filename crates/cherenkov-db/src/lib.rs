@@ -4,13 +4,47 @@ pub mod query;
 pub mod sqlite;
 pub mod cache;
 pub mod storage;
+pub mod track;
+pub mod lineage;
+pub mod weather;
+pub mod registry;
+pub mod tiering;
+pub mod rollup;
+pub mod store;
+pub mod memory;
+pub mod spatial;
+pub mod query_cache;
+pub mod projection;
+pub mod backup;
+pub mod export;
+mod netcdf;
 
 pub use sqlite::{SensorInfo, AnomalyRecord, SensorRecord};
+pub use track::{Track, TrackPoint, TrackSummary};
+pub use lineage::{QcDecision, ReadingLineage, ReadingProvenance, SourceFetch};
+pub use weather::WeatherObservation;
+pub use storage::{ColdStorage, ColdStorageConfig, CompressionType};
+pub use rollup::{RollupLevel, RollupScope, RollupStats};
+pub use schema::{Migration, Replication};
+pub use store::{Cache, CacheTier, EventStore, HotTier, ReadingStore, StorageBackends};
+pub use memory::{MemoryCache, MemoryStore};
+pub use query::{Access, AnomalyQuery, Cursor, EventQuery, Page, PlanStep, QueryPlan, SortOrder, SpatialQuery, Tier, TimeRangeQuery};
+pub use query_cache::QueryCache;
+pub use backup::{ArchiveReport, BackupConfig, BackupManifest, RestoreReport, VerifyReport, WarmBackup};
+pub use projection::{
+    AlertTimelineView, AnomalyCount, AnomalyCountsView, Checkpoint, Incident, OpenIncidentsView, Projection,
+    Projections, SensorStatus, SensorStatusView, StoredEvent,
+};
+pub use spatial::{NearbySensor, SensorIndex, SensorLocation};
+pub use export::{ExportFormat, ExportJob, ExportJobs, ExportProvenance, ExportStatus, ExportSummary};
+pub use tiering::{TierMigrator, TieringConfig, TieringCursor, TieringReport};
+pub use registry::{LivenessThresholds, NewSensor, Placement, Sensor, SensorState, SensorUpdate, StatusTransition};
 
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc, Duration};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use tracing::{info, warn, instrument};
 use thiserror::Error;
@@ -20,6 +54,9 @@ use scylla::ScyllaStorage;
 use sqlite::SqliteStorage;
 use cache::RedisCache;
 
+/// Width of the time buckets readings are partitioned by in every tier, seconds
+pub const BUCKET_SECS: i64 = 3600;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RadiationReading {
     pub sensor_id: Uuid,
@@ -32,15 +69,84 @@ pub struct RadiationReading {
     pub quality_flag: QualityFlag,
     pub source: String,
     pub cell_id: String,
+    /// Survey track this reading belongs to, `None` for stationary sensors
+    #[serde(default)]
+    pub track_id: Option<Uuid>,
+    /// Altitude above sea level in metres, reported by mobile sensors
+    #[serde(default)]
+    pub altitude_m: Option<f64>,
+    /// Machine-readable reasons behind `quality_flag`, empty when no QC rule fired
+    #[serde(default)]
+    pub qc_reasons: Vec<QcReason>,
+    /// Raw value, conversion and fetch behind the dose rate, stored separately
+    /// from the reading; `None` for sources that do not report it
+    #[serde(default)]
+    pub provenance: Option<ReadingProvenance>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl RadiationReading {
+    /// Partition bucket of a reading taken at `timestamp`
+    pub fn bucket_for(timestamp: i64) -> i64 {
+        timestamp.div_euclid(BUCKET_SECS)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QualityFlag {
     Valid,
     Suspect,
     Invalid,
 }
 
+impl QualityFlag {
+    /// Parse a flag name, ignoring case
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "valid" => Some(QualityFlag::Valid),
+            "suspect" => Some(QualityFlag::Suspect),
+            "invalid" => Some(QualityFlag::Invalid),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QualityFlag::Valid => "valid",
+            QualityFlag::Suspect => "suspect",
+            QualityFlag::Invalid => "invalid",
+        }
+    }
+}
+
+/// Automated quality-control check that can flag a reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QcCheck {
+    Range,
+    Spike,
+    Step,
+    Flatline,
+    RateOfChange,
+    FutureTimestamp,
+    LocationJump,
+    DuplicateStation,
+}
+
+/// Outcome of a single QC check that fired for a reading
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QcReason {
+    pub check: QcCheck,
+    /// Flag this check alone would assign
+    pub flag: QualityFlag,
+    /// Observed value that tripped the check
+    pub value: f64,
+    /// Configured limit it was compared against
+    pub limit: f64,
+    /// Extra context, e.g. the canonical station of an alias
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DomainEvent {
     pub event_id: String,
@@ -50,13 +156,45 @@ pub struct DomainEvent {
     pub timestamp: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EventType {
     AnomalyDetected,
     AlertTriggered,
     IncidentCreated,
+    IncidentResolved,
     SensorOffline,
     SensorOnline,
+    SensorDegraded,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 7] = [
+        EventType::AnomalyDetected,
+        EventType::AlertTriggered,
+        EventType::IncidentCreated,
+        EventType::IncidentResolved,
+        EventType::SensorOffline,
+        EventType::SensorOnline,
+        EventType::SensorDegraded,
+    ];
+
+    /// Name stored in the event log
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::AnomalyDetected => "AnomalyDetected",
+            EventType::AlertTriggered => "AlertTriggered",
+            EventType::IncidentCreated => "IncidentCreated",
+            EventType::IncidentResolved => "IncidentResolved",
+            EventType::SensorOffline => "SensorOffline",
+            EventType::SensorOnline => "SensorOnline",
+            EventType::SensorDegraded => "SensorDegraded",
+        }
+    }
+
+    /// Parse a stored name, ignoring case
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str().eq_ignore_ascii_case(value))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,10 +205,18 @@ pub struct TimeSeriesPoint {
     pub min: f64,
     pub max: f64,
     pub avg: f64,
+    /// 95th percentile, available for points served from rollups
+    #[serde(default)]
+    pub p95: Option<f64>,
+    /// Sensor of a raw reading, `None` for points merged across sensors
+    #[serde(default)]
+    pub sensor_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum AggregationLevel {
+    /// Pick a level from the requested span, see `AggregationLevel::for_span`
+    Auto,
     Raw,
     OneMinute,
     FiveMinutes,
@@ -117,9 +263,17 @@ pub enum DatabaseError {
 
 /// Unified storage abstraction with hot/warm/cold tiering
 pub struct RadiationDatabase {
-    hot: Arc<ScyllaStorage>,
+    hot: Arc<dyn ReadingStore>,
     warm: Arc<SqliteStorage>,
-    cache: Arc<RedisCache>,
+    events: Arc<dyn EventStore>,
+    cache: Arc<dyn Cache>,
+    /// Query results over `cache`, invalidated by sensor and area tags
+    query_cache: QueryCache,
+    cold: Arc<ColdStorage>,
+    /// Latest location of every sensor seen, across all tiers
+    sensor_index: SensorIndex,
+    /// Read models over the event log, caught up on each read
+    projections: tokio::sync::Mutex<Projections>,
     config: DatabaseConfig,
 }
 
@@ -127,8 +281,19 @@ pub struct RadiationDatabase {
 pub struct DatabaseConfig {
     pub hot_retention_days: i64,
     pub warm_retention_days: i64,
+    /// Age after which cold archives are deleted, `None` keeps them forever
+    pub cold_retention_days: Option<i64>,
     pub enable_cold_archive: bool,
     pub max_retry_attempts: u32,
+    /// Parquet archive settings, used when `enable_cold_archive` is set
+    pub cold_storage: ColdStorageConfig,
+    /// Query results kept in process in front of Redis, 0 disables
+    pub local_cache_entries: usize,
+    /// How long results stay in process; other processes' writes are seen
+    /// regardless, since lookups always check tag versions in Redis
+    pub local_cache_ttl_secs: u64,
+    /// Warm tier snapshots and WAL archiving, `None` disables them
+    pub backup: Option<BackupConfig>,
 }
 
 impl Default for DatabaseConfig {
@@ -136,76 +301,258 @@ impl Default for DatabaseConfig {
         Self {
             hot_retention_days: 7,
             warm_retention_days: 30,
+            cold_retention_days: None,
             enable_cold_archive: false,
             max_retry_attempts: 3,
+            cold_storage: ColdStorageConfig::default(),
+            local_cache_entries: 10_000,
+            local_cache_ttl_secs: 30,
+            backup: None,
         }
     }
 }
 
 impl RadiationDatabase {
+    /// Connect to ScyllaDB, SQLite and Redis
     pub async fn new(
         scylla_config: scylla::ScyllaConfig,
         sqlite_path: &str,
         redis_url: &str,
         config: DatabaseConfig,
     ) -> Result<Self, DatabaseError> {
-        let hot = Arc::new(
-            ScyllaStorage::new(scylla_config)
-                .await
-                .map_err(|e| DatabaseError::Scylla(e.to_string()))?
-        );
-
-        let warm = Arc::new(
-            SqliteStorage::new(sqlite_path)
-                .await
-                .map_err(|e| DatabaseError::Sqlite(e.to_string()))?
-        );
-
-        let cache = Arc::new(
-            RedisCache::new(redis_url)
-                .await
-                .map_err(|e| DatabaseError::Redis(e.to_string()))?
-        );
-
-        info!("RadiationDatabase initialized with hot/warm/cold tiers");
-
-        Ok(Self {
+        Self::open(StorageBackends::cluster(scylla_config, sqlite_path, redis_url), config).await
+    }
+
+    /// Open each tier on the backend chosen in `backends`
+    pub async fn open(backends: StorageBackends, config: DatabaseConfig) -> Result<Self, DatabaseError> {
+        let hot: Arc<dyn ReadingStore> = match &backends.hot {
+            HotTier::Scylla(scylla_config) => Arc::new(
+                ScyllaStorage::new(scylla_config.clone())
+                    .await
+                    .map_err(|e| DatabaseError::Scylla(e.to_string()))?
+            ),
+            HotTier::Sqlite(path) => {
+                let storage = SqliteStorage::new(path)
+                    .await
+                    .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+                storage.apply_schema()
+                    .await
+                    .map_err(|e| DatabaseError::Migration(e.to_string()))?;
+                Arc::new(storage)
+            }
+            HotTier::Memory => Arc::new(MemoryStore::new()),
+        };
+
+        // An archived WAL is checkpointed only by the archiver
+        let archived = config.backup.as_ref().is_some_and(BackupConfig::archives_wal);
+        let warm = match archived {
+            true => SqliteStorage::with_wal_archiving(&backends.warm_path).await,
+            false => SqliteStorage::new(&backends.warm_path).await,
+        };
+        let warm = Arc::new(warm.map_err(|e| DatabaseError::Sqlite(e.to_string()))?);
+
+        let (cache, query_cache): (Arc<dyn Cache>, _) = match &backends.cache {
+            CacheTier::Redis(redis_url) => {
+                let cache: Arc<dyn Cache> = Arc::new(
+                    RedisCache::new(redis_url)
+                        .await
+                        .map_err(|e| DatabaseError::Redis(e.to_string()))?
+                );
+                let query_cache = QueryCache::new(cache.clone())
+                    .with_local(config.local_cache_entries, config.local_cache_ttl_secs);
+                (cache, query_cache)
+            }
+            CacheTier::Memory => {
+                let cache: Arc<dyn Cache> = Arc::new(MemoryCache::default());
+                (cache.clone(), QueryCache::new(cache))
+            }
+        };
+
+        let cold = Arc::new(if config.enable_cold_archive {
+            ColdStorage::new(config.cold_storage.clone())
+                .map_err(|e| DatabaseError::Storage(e.to_string()))?
+        } else {
+            ColdStorage::disabled()
+        });
+
+        info!("RadiationDatabase initialized with hot/warm/cold tiers: {:?}", backends);
+
+        let db = Self {
             hot,
+            events: warm.clone(),
             warm,
             cache,
+            query_cache,
+            cold,
+            sensor_index: SensorIndex::new(),
+            projections: tokio::sync::Mutex::new(Projections::default()),
             config,
-        })
+        };
+
+        // A fresh warm tier has no tables until migrations run, which reload the index
+        if let Err(e) = db.reload_sensor_index().await {
+            warn!("Sensor index starts empty: {}", e);
+        }
+
+        Ok(db)
     }
 
     /// Write with automatic tier routing based on timestamp
     #[instrument(skip(self, reading))]
     pub async fn write_reading(&self, reading: &RadiationReading) -> Result<(), DatabaseError> {
-        let reading_time = DateTime::from_timestamp(reading.timestamp, 0)
-            .ok_or_else(|| DatabaseError::Query("Invalid timestamp".to_string()))?;
+        self.write_readings(std::slice::from_ref(reading)).await
+    }
 
+    /// Write a batch, routing each reading by its age
+    ///
+    /// Readings old enough for the cold tier are archived together, one file
+    /// per partition, and are durable once this returns like the rest.
+    #[instrument(skip(self, readings), fields(count = readings.len()))]
+    pub async fn write_readings(&self, readings: &[RadiationReading]) -> Result<(), DatabaseError> {
         let now = Utc::now();
-        let age = now.signed_duration_since(reading_time);
-
-        // Route to appropriate tier
-        if age <= Duration::days(self.config.hot_retention_days) {
-            // Hot tier: ScyllaDB for real-time queries
-            self.write_to_hot(reading).await?;
-        } else if age <= Duration::days(self.config.warm_retention_days) {
-            // Warm tier: SQLite for analytical queries
-            self.write_to_warm(reading).await?;
-        } else if self.config.enable_cold_archive {
-            // Cold tier: Object storage for historical archive
-            // TODO: Implement cold storage tier
-            warn!("Cold storage not yet implemented, dropping reading");
+        let mut archive: Vec<RadiationReading> = Vec::new();
+
+        for reading in readings {
+            // Tiers find readings by the bucket of their timestamp, whatever the source put there
+            let reading = RadiationReading {
+                bucket: RadiationReading::bucket_for(reading.timestamp),
+                ..reading.clone()
+            };
+            let reading_time = DateTime::from_timestamp(reading.timestamp, 0)
+                .ok_or_else(|| DatabaseError::Query("Invalid timestamp".to_string()))?;
+            let age = now.signed_duration_since(reading_time);
+
+            // Version of the reading this write replaces, so the rollups can be corrected rather than double counted
+            let previous = if age <= Duration::days(self.config.hot_retention_days) {
+                // Hot tier: ScyllaDB for real-time queries
+                let previous = self.hot.query_by_time_range(reading.sensor_id, reading.timestamp, reading.timestamp).await
+                    .map_err(|e| DatabaseError::Scylla(e.to_string()))?
+                    .into_iter()
+                    .next();
+                self.write_to_hot(&reading).await?;
+                previous
+            } else if age <= Duration::days(self.config.warm_retention_days) {
+                // Warm tier: SQLite for analytical queries
+                let previous = self.warm.stored_reading(&reading).await
+                    .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+                self.write_to_warm(&reading).await?;
+                previous
+            } else if self.config.enable_cold_archive {
+                // Cold tier: Parquet archive, written once the batch is sorted
+                archive.push(reading);
+                continue;
+            } else {
+                warn!("Reading older than warm retention and cold archive disabled, dropping it");
+                self.index_reading(&reading, None, false).await?;
+                continue;
+            };
+
+            self.index_reading(&reading, previous, true).await?;
+        }
+
+        if !archive.is_empty() {
+            self.write_to_cold(archive).await?;
         }
 
-        // Invalidate cache for this sensor
-        self.cache.invalidate_sensor(&reading.sensor_id).await
-            .map_err(|e| DatabaseError::Redis(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn write_to_cold(&self, mut archive: Vec<RadiationReading>) -> Result<(), DatabaseError> {
+        // The last delivery of a reading within the batch wins
+        let mut seen = HashSet::new();
+        archive.reverse();
+        archive.retain(|r| seen.insert((r.sensor_id, r.timestamp)));
+        archive.reverse();
+
+        let sensors: HashSet<String> = archive.iter().map(|r| r.sensor_id.to_string()).collect();
+        let sensors: Vec<String> = sensors.into_iter().collect();
+        let from = archive.iter().map(|r| r.timestamp).min().unwrap_or_default();
+        let to = archive.iter().map(|r| r.timestamp).max().unwrap_or_default();
+        let mut previous: HashMap<(Uuid, i64), RadiationReading> = self.cold.query_range(&sensors, timestamp(from)?, timestamp(to)?).await
+            .map_err(|e| DatabaseError::Storage(e.to_string()))?
+            .into_iter()
+            .filter(|r| seen.contains(&(r.sensor_id, r.timestamp)))
+            .map(|r| ((r.sensor_id, r.timestamp), r))
+            .collect();
 
+        self.cold.archive_readings(&archive).await
+            .map_err(|e| DatabaseError::Storage(e.to_string()))?;
+
+        for reading in &archive {
+            self.index_reading(reading, previous.remove(&(reading.sensor_id, reading.timestamp)), true).await?;
+        }
         Ok(())
     }
 
+    /// Record what the warm tier keeps about a reading, whichever tier holds it
+    ///
+    /// `previous` is the version the write replaced; `stored` is false for a
+    /// reading no tier kept.
+    async fn index_reading(
+        &self,
+        reading: &RadiationReading,
+        previous: Option<RadiationReading>,
+        stored: bool,
+    ) -> Result<(), DatabaseError> {
+        // Rollups cover every tier and live in the warm tier
+        let rollups = match previous {
+            _ if !stored => Ok(()),
+            None => self.warm.apply_rollups(std::slice::from_ref(reading)).await,
+            Some(old)
+                if old.dose_rate_microsieverts == reading.dose_rate_microsieverts
+                    && old.latitude == reading.latitude
+                    && old.longitude == reading.longitude =>
+            {
+                Ok(())
+            }
+            Some(old) => self.warm.correct_rollups(reading, &old).await,
+        };
+        rollups.map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+
+        // Sensor registry lives in the warm tier regardless of reading age
+        self.warm.touch_sensor(reading).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+
+        // Track geometry lives in the warm tier regardless of reading age
+        if reading.track_id.is_some() {
+            self.warm.record_track_point(reading).await
+                .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+        }
+
+        // So does provenance, which outlives the tier the reading is in
+        if let Some(provenance) = reading.provenance.as_ref().filter(|_| stored) {
+            self.warm.store_provenance(reading, provenance).await
+                .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+        }
+
+        if stored {
+            self.sensor_index.upsert(SensorLocation {
+                sensor_id: reading.sensor_id,
+                latitude: reading.latitude,
+                longitude: reading.longitude,
+                timestamp: reading.timestamp,
+            });
+        }
+
+        // Cached results covering this sensor or its area are now stale
+        self.query_cache.invalidate(&query_cache::reading_tags(reading)).await?;
+
+        Ok(())
+    }
+
+    /// Background job that moves readings between tiers as they age
+    pub fn tier_migrator(&self, config: TieringConfig) -> TierMigrator {
+        TierMigrator::new(self.warm.clone(), self.cold.clone(), &self.config, config)
+            .with_hot(self.hot.clone())
+    }
+
+    /// Snapshots and WAL archiving of the warm tier; `None` unless backups
+    /// are configured and the warm tier is a file
+    pub fn warm_backup(&self) -> Option<WarmBackup> {
+        let config = self.config.backup.clone()?;
+        WarmBackup::new(self.warm.clone(), config).ok()
+    }
+
     async fn write_to_hot(&self, reading: &RadiationReading) -> Result<(), DatabaseError> {
         let operation = || async {
             self.hot.write_reading(reading).await
@@ -232,105 +579,179 @@ impl RadiationDatabase {
             .map_err(|e| DatabaseError::Sqlite(format!("Failed after retries: {}", e)))
     }
 
-    /// Time-range query with aggregation across all tiers
-    #[instrument(skip(self, sensor_ids))]
-    pub async fn query_range(
-        &self,
-        sensor_ids: &[String],
-        start_ts: i64,
-        end_ts: i64,
-        aggregation: AggregationLevel,
-    ) -> Result<Vec<TimeSeriesPoint>, DatabaseError> {
-        let start = DateTime::from_timestamp(start_ts, 0)
-            .unwrap_or_else(|| Utc::now());
-        let end = DateTime::from_timestamp(end_ts, 0)
-            .unwrap_or_else(|| Utc::now());
+    /// Tiers and access paths a query will use
+    pub fn plan(&self, query: &TimeRangeQuery) -> QueryPlan {
+        QueryPlan::for_query(query, &self.config, self.cold.is_enabled(), Utc::now())
+    }
 
-        // Check cache first
-        let cache_key = format!("query_range:{:?}:{:?}:{:?}", sensor_ids, start, end);
-        if let Some(cached) = self.cache.get_query_result(&cache_key).await
-            .map_err(|e| DatabaseError::Redis(e.to_string()))? 
-        {
-            return Ok(cached);
-        }
+    /// Raw readings matching a query, across all tiers
+    ///
+    /// The query's aggregation is ignored; see `query_series` for aggregated points.
+    #[instrument(skip(self))]
+    pub async fn query_readings(&self, query: &TimeRangeQuery) -> Result<Page<RadiationReading>, DatabaseError> {
+        let plan = self.plan(&query.clone().with_aggregation(AggregationLevel::Raw));
+        let readings = self.fetch_readings(&plan, query, true).await?;
 
-        let now = Utc::now();
-        let mut all_points = Vec::new();
-
-        // Query hot tier (last 7 days)
-        let hot_cutoff = now - Duration::days(self.config.hot_retention_days);
-        if end > hot_cutoff {
-            let hot_start = if start > hot_cutoff { start } else { hot_cutoff };
-            let hot_points = self.query_hot_range(sensor_ids, hot_start, end, aggregation).await?;
-            all_points.extend(hot_points);
-        }
+        Ok(Page::paginate(readings, reading_position, query.order, query.cursor.as_ref(), query.limit))
+    }
 
-        // Query warm tier (7-30 days)
-        let warm_cutoff = now - Duration::days(self.config.warm_retention_days);
-        if start < hot_cutoff && end > warm_cutoff {
-            let warm_start = if start > warm_cutoff { start } else { warm_cutoff };
-            let warm_end = if end < hot_cutoff { end } else { hot_cutoff };
-            let warm_points = self.query_warm_range(sensor_ids, warm_start, warm_end, aggregation).await?;
-            all_points.extend(warm_points);
-        }
+    /// Time series for a query at its aggregation level
+    ///
+    /// Raw points carry their sensor. Aggregated points merge every matching
+    /// sensor and come from the rollups when only sensors are filtered on,
+    /// otherwise they are computed from the matching readings.
+    #[instrument(skip(self))]
+    pub async fn query_series(&self, query: &TimeRangeQuery) -> Result<Page<TimeSeriesPoint>, DatabaseError> {
+        let cache_key = format!("series:{:?}", query);
+        let tags = query_cache::query_tags(query);
+        self.query_cache
+            .get_or_load(&cache_key, &tags, 300, || self.load_series(query))
+            .await
+    }
 
-        // Sort and aggregate results
-        all_points.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    async fn load_series(&self, query: &TimeRangeQuery) -> Result<Page<TimeSeriesPoint>, DatabaseError> {
+        let plan = self.plan(query);
+        let points = match plan.aggregation.rollup() {
+            // Raw points page exactly like the readings they come from
+            None => self.fetch_readings(&plan, query, true).await?
+                .iter()
+                .map(raw_point)
+                .collect(),
+            Some((level, bucket_secs)) if plan.steps.iter().any(|s| s.tier == Tier::Rollups) => {
+                let (from, to) = query.window();
+                let ids: Vec<String> = query.sensor_ids.iter().map(|id| id.to_string()).collect();
+                let rows = self.warm.query_rollups(level, RollupScope::Sensor, &ids, timestamp(from)?, timestamp(to)?).await
+                    .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+                rollup::regroup(rows, bucket_secs)
+            }
+            Some((_, bucket_secs)) => {
+                let readings = self.fetch_readings(&plan, query, false).await?;
+                let mut buckets: BTreeMap<i64, RollupStats> = BTreeMap::new();
+                for reading in &readings {
+                    buckets
+                        .entry(reading.timestamp - reading.timestamp.rem_euclid(bucket_secs))
+                        .or_default()
+                        .add(reading.dose_rate_microsieverts);
+                }
+                buckets.into_iter().map(|(start, stats)| stats.to_point(start)).collect()
+            }
+        };
 
-        // Cache result
-        self.cache.set_query_result(&cache_key, &all_points, 300).await
-            .map_err(|e| DatabaseError::Redis(e.to_string()))?;
+        Ok(Page::paginate(points, point_position, query.order, query.cursor.as_ref(), query.limit))
+    }
+
+    /// Anomalies matching a query, newest first unless the query says otherwise
+    #[instrument(skip(self))]
+    pub async fn query_anomalies(&self, query: &AnomalyQuery) -> Result<Page<AnomalyRecord>, DatabaseError> {
+        let mut query = query.clone();
+
+        // Anomalies have no location of their own, so the box selects sensors
+        if let Some(area) = query.area.take() {
+            let in_area: Vec<Uuid> = self.query_bbox(&area).await?
+                .into_iter()
+                .map(|s| s.sensor_id)
+                .filter(|id| query.sensor_ids.is_empty() || query.sensor_ids.contains(id))
+                .collect();
+            if in_area.is_empty() {
+                return Ok(Page { items: Vec::new(), next_cursor: None });
+            }
+            query.sensor_ids = in_area;
+        }
 
-        Ok(all_points)
+        // One extra row tells whether there is a next page
+        let limit = query.limit;
+        let records = self.events.query_anomalies(&query.clone().limit(limit.saturating_add(1))).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+
+        Ok(Page::paginate(records, anomaly_position, query.order, query.cursor.as_ref(), Some(limit)))
     }
 
-    async fn query_hot_range(
+    /// Readings for every step of a plan, filtered and without duplicates
+    ///
+    /// When `paged`, each tier reads in query order from the cursor and stops
+    /// after one more reading than the page holds, so a page costs the same
+    /// wherever it falls in the window; the merged result is then cut to the
+    /// page by `Page::paginate`.
+    async fn fetch_readings(
         &self,
-        sensor_ids: &[String],
-        start: DateTime<Utc>,
-        end: DateTime<Utc>,
-        _aggregation: AggregationLevel,
-    ) -> Result<Vec<TimeSeriesPoint>, DatabaseError> {
-        let mut points = Vec::new();
-
-        for sensor_id in sensor_ids {
-            let uuid = Uuid::parse_str(sensor_id)
-                .map_err(|e| DatabaseError::Query(format!("Invalid UUID: {}", e)))?;
-
-            let readings = self.hot.query_by_time_range(
-                uuid,
-                start.timestamp(),
-                end.timestamp(),
-            ).await.map_err(|e| DatabaseError::Scylla(e.to_string()))?;
-
-            for reading in readings {
-                points.push(TimeSeriesPoint {
-                    timestamp: DateTime::from_timestamp(reading.timestamp, 0)
-                        .unwrap_or_else(|| Utc::now()),
-                    value: reading.dose_rate_microsieverts,
-                    count: 1,
-                    min: reading.dose_rate_microsieverts,
-                    max: reading.dose_rate_microsieverts,
-                    avg: reading.dose_rate_microsieverts,
-                });
-            }
+        plan: &QueryPlan,
+        query: &TimeRangeQuery,
+        paged: bool,
+    ) -> Result<Vec<RadiationReading>, DatabaseError> {
+        let page = StepPage {
+            query,
+            cursor: query.cursor.as_ref().filter(|_| paged),
+            // One extra reading tells whether there is a next page
+            need: query.limit.filter(|_| paged).map_or(usize::MAX, |limit| limit.saturating_add(1)),
+        };
+        let mut seen = HashSet::new();
+        let mut results = Vec::new();
+
+        for step in &plan.steps {
+            let readings = match step.tier {
+                Tier::Hot => read_step(self.hot.as_ref(), step, &page).await
+                    .map_err(|e| DatabaseError::Scylla(e.to_string()))?,
+                Tier::Warm => read_step(self.warm.as_ref(), step, &page).await
+                    .map_err(|e| DatabaseError::Sqlite(e.to_string()))?,
+                Tier::Cold => self.read_cold_step(step, &page).await
+                    .map_err(|e| DatabaseError::Storage(e.to_string()))?,
+                Tier::Rollups => continue,
+            };
+
+            results.extend(readings.into_iter().filter(|r| seen.insert((r.sensor_id, r.timestamp))));
         }
 
-        Ok(points)
+        Ok(results)
+    }
+
+    /// Cold archives are pruned by date, so they are read a growing span at a time
+    async fn read_cold_step(&self, step: &PlanStep, page: &StepPage<'_>) -> anyhow::Result<Vec<RadiationReading>> {
+        let ids: Vec<String> = match &step.access {
+            Access::Sensors(ids) => ids.iter().map(|id| id.to_string()).collect(),
+            _ => Vec::new(),
+        };
+
+        let mut readings = Vec::new();
+        for (from, to) in spans(step.from, step.to, page.query.order) {
+            readings.extend(self.cold.query_range(&ids, timestamp(from)?, timestamp(to)?).await?.into_iter().filter(|r| page.wants(r)));
+            if readings.len() >= page.need {
+                break;
+            }
+        }
+        Ok(readings)
     }
 
-    async fn query_warm_range(
+    /// Aggregated dose rates over a geohash cell, from the per-cell rollups
+    ///
+    /// `cell` is matched at the rollup precision, longer geohashes are truncated.
+    #[instrument(skip(self))]
+    pub async fn query_cell_range(
         &self,
-        sensor_ids: &[String],
-        start: DateTime<Utc>,
-        end: DateTime<Utc>,
+        cell: &str,
+        start_ts: i64,
+        end_ts: i64,
         aggregation: AggregationLevel,
     ) -> Result<Vec<TimeSeriesPoint>, DatabaseError> {
-        self.warm.query_range(sensor_ids, start, end, aggregation).await
-            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+        let start = DateTime::from_timestamp(start_ts, 0)
+            .ok_or_else(|| DatabaseError::Query("Invalid start timestamp".to_string()))?;
+        let end = DateTime::from_timestamp(end_ts, 0)
+            .ok_or_else(|| DatabaseError::Query("Invalid end timestamp".to_string()))?;
+
+        // Raw readings are not kept per cell, so fall back to the finest rollup
+        let (level, bucket_secs) = aggregation.resolve(end - start).rollup()
+            .unwrap_or((RollupLevel::Minute, 60));
+        let cell: String = cell.chars().take(rollup::CELL_PRECISION).collect();
+
+        let rows = self.warm.query_rollups(level, RollupScope::Cell, &[cell], start, end).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+
+        Ok(rollup::regroup(rows, bucket_secs))
     }
 
-    /// Spatial query: sensors within radius
+    /// Spatial query: readings within `radius_km` of `center`
+    ///
+    /// Queries each geohash cell covering the radius in the hot and warm
+    /// tiers, then keeps the readings actually within the radius.
     #[instrument(skip(self))]
     pub async fn query_geo(
         &self,
@@ -338,28 +759,27 @@ impl RadiationDatabase {
         radius_km: f64,
         time_window: TimeRange,
     ) -> Result<Vec<SensorReading>, DatabaseError> {
-        // Use geohash for efficient spatial indexing
-        let coord = geohash::Coord { x: center.longitude, y: center.latitude };
-        let geohash_prefix = geohash::encode(coord, 4)
-            .map_err(|e| DatabaseError::Query(format!("Geohash error: {:?}", e)))?;
-
-        // Query hot tier by location
-        let hot_readings = self.hot.query_by_location(
-            &geohash_prefix,
-            &geohash_prefix,
-            time_window.start.timestamp(),
-            time_window.end.timestamp(),
-        ).await.map_err(|e| DatabaseError::Scylla(e.to_string()))?;
-
-        let mut results: Vec<SensorReading> = hot_readings.into_iter()
+        let cells = spatial::covering_cells(&center, radius_km);
+        let (from, to) = (time_window.start.timestamp(), time_window.end.timestamp());
+
+        let mut readings = Vec::new();
+        for cell in &cells {
+            readings.extend(
+                self.hot.query_by_geohash(cell, from, to).await
+                    .map_err(|e| DatabaseError::Scylla(e.to_string()))?
+            );
+            readings.extend(
+                ReadingStore::query_by_geohash(self.warm.as_ref(), cell, from, to).await
+                    .map_err(|e| DatabaseError::Sqlite(e.to_string()))?
+            );
+        }
+
+        // Readings on a shared cell edge come back from both cells
+        let mut seen = HashSet::new();
+        let results = readings.into_iter()
+            .filter(|r| seen.insert((r.sensor_id, r.timestamp)))
             .filter(|r| {
-                let distance = haversine_distance(
-                    center.latitude,
-                    center.longitude,
-                    r.latitude,
-                    r.longitude,
-                );
-                distance <= radius_km
+                haversine_distance(center.latitude, center.longitude, r.latitude, r.longitude) <= radius_km
             })
             .map(|r| SensorReading {
                 sensor_id: r.sensor_id,
@@ -373,15 +793,49 @@ impl RadiationDatabase {
             })
             .collect();
 
-        // Also query warm tier
-        let warm_readings = self.warm.query_geo(center, radius_km, time_window).await
-            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
-        
-        results.extend(warm_readings);
-
         Ok(results)
     }
 
+    /// The `k` sensors nearest to `center`, by last known location
+    pub fn nearest_sensors(&self, center: &GeoPoint, k: usize) -> Vec<NearbySensor> {
+        self.sensor_index.nearest(center, k)
+    }
+
+    /// Sensors whose last known location lies inside the query's box
+    #[instrument(skip(self))]
+    pub async fn query_bbox(&self, query: &SpatialQuery) -> Result<Vec<SensorLocation>, DatabaseError> {
+        let mut sensors = self.sensor_index.within(query);
+
+        if query.active_only {
+            let retired: HashSet<Uuid> = self.list_registered_sensors(true).await?
+                .into_iter()
+                .filter(|s| s.status == SensorState::Decommissioned)
+                .map(|s| s.sensor_id)
+                .collect();
+            sensors.retain(|s| !retired.contains(&s.sensor_id));
+        }
+
+        Ok(sensors)
+    }
+
+    /// Load the last known location of every registered sensor into the spatial index
+    ///
+    /// The registry follows the newest reading of each sensor in any tier.
+    pub async fn reload_sensor_index(&self) -> Result<usize, DatabaseError> {
+        for sensor in self.list_registered_sensors(true).await? {
+            if let (Some(latitude), Some(longitude)) = (sensor.latitude, sensor.longitude) {
+                self.sensor_index.upsert(SensorLocation {
+                    sensor_id: sensor.sensor_id,
+                    latitude,
+                    longitude,
+                    timestamp: sensor.last_reading.unwrap_or(i64::MIN),
+                });
+            }
+        }
+
+        Ok(self.sensor_index.len())
+    }
+
     /// Get latest reading for a sensor
     pub async fn get_sensor_latest(
         &self,
@@ -390,20 +844,17 @@ impl RadiationDatabase {
         let uuid = Uuid::parse_str(sensor_id)
             .map_err(|e| DatabaseError::Query(format!("Invalid UUID: {}", e)))?;
 
-        // Check cache first
-        if let Some(cached) = self.cache.get_sensor_latest(&uuid).await
-            .map_err(|e| DatabaseError::Redis(e.to_string()))? 
-        {
-            return Ok(Some(cached));
-        }
+        let tags = [query_cache::sensor_tag(&uuid)];
+        self.query_cache
+            .get_or_load(&format!("latest:{}", uuid), &tags, 60, || self.load_sensor_latest(uuid))
+            .await
+    }
 
+    async fn load_sensor_latest(&self, uuid: Uuid) -> Result<Option<RadiationReading>, DatabaseError> {
         // Query hot tier
-        if let Some(reading) = self.hot.get_sensor_latest(uuid).await
+        if let Some(reading) = self.hot.latest_reading(uuid).await
             .map_err(|e| DatabaseError::Scylla(e.to_string()))? 
         {
-            // Cache the result
-            self.cache.set_sensor_latest(&uuid, &reading, 60).await
-                .map_err(|e| DatabaseError::Redis(e.to_string()))?;
             return Ok(Some(reading));
         }
 
@@ -421,17 +872,119 @@ impl RadiationDatabase {
     #[instrument(skip(self, event))]
     pub async fn store_event(&self, event: &DomainEvent) -> Result<(), DatabaseError> {
         // Store events in warm tier (SQLite) for audit trail
-        self.warm.store_event(event).await
+        self.events.store_event(event).await
             .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
         
         info!("Stored domain event: {} ({:?})", event.event_id, event.event_type);
         Ok(())
     }
 
-    /// Run database migrations
+    /// Logged events matching a query, paged by sequence
+    #[instrument(skip(self))]
+    pub async fn query_events(&self, query: &EventQuery) -> Result<Page<StoredEvent>, DatabaseError> {
+        // One extra row tells whether there is a next page
+        let mut events = self.events.query_events(&query.clone().limit(query.limit.saturating_add(1))).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+
+        let next_cursor = if events.len() > query.limit {
+            events.truncate(query.limit);
+            events.last().map(|e| e.sequence.to_string())
+        } else {
+            None
+        };
+        Ok(Page { items: events, next_cursor })
+    }
+
+    /// Latest state of every sensor that reported a status change
+    pub async fn sensor_statuses(&self) -> Result<SensorStatusView, DatabaseError> {
+        Ok(self.caught_up().await?.sensor_status.state.clone())
+    }
+
+    /// Incidents not yet resolved, oldest first
+    pub async fn open_incidents(&self) -> Result<Vec<Incident>, DatabaseError> {
+        let projections = self.caught_up().await?;
+        let mut incidents: Vec<Incident> = projections.open_incidents.state.incidents.values().cloned().collect();
+        incidents.sort_by_key(|i| i.opened_at);
+        Ok(incidents)
+    }
+
+    pub async fn anomaly_counts(&self) -> Result<AnomalyCountsView, DatabaseError> {
+        Ok(self.caught_up().await?.anomaly_counts.state.clone())
+    }
+
+    /// Up to `limit` alerts and incident changes, newest first
+    pub async fn alert_timeline(&self, limit: usize) -> Result<Vec<StoredEvent>, DatabaseError> {
+        let projections = self.caught_up().await?;
+        Ok(projections.alert_timeline.state.entries.iter().rev().take(limit).cloned().collect())
+    }
+
+    /// Replay the whole event log into fresh read models; returns the number
+    /// of events replayed
+    #[instrument(skip(self))]
+    pub async fn rebuild_projections(&self) -> Result<usize, DatabaseError> {
+        self.projections.lock().await.rebuild(self.events.as_ref()).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// Save the read models so the next start resumes where they are
+    pub async fn checkpoint_projections(&self) -> Result<(), DatabaseError> {
+        let mut projections = self.projections.lock().await;
+        projections.catch_up(self.events.as_ref()).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+        projections.checkpoint(self.events.as_ref()).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    async fn caught_up(&self) -> Result<tokio::sync::MutexGuard<'_, Projections>, DatabaseError> {
+        let mut projections = self.projections.lock().await;
+        projections.catch_up(self.events.as_ref()).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+        Ok(projections)
+    }
+
+    /// Migrate every configured backend's schema, before opening them
+    ///
+    /// Covers a ScyllaDB or SQLite hot tier and the SQLite warm tier; memory
+    /// backends have nothing to migrate. A fresh ScyllaDB cluster gets its
+    /// keyspace here, so this must run once before `open`. With `dry_run`
+    /// nothing changes and the reports list what would be applied.
+    pub async fn migrate(backends: &StorageBackends, dry_run: bool) -> Result<Vec<MigrationReport>, DatabaseError> {
+        let mut reports = Vec::new();
+
+        match &backends.hot {
+            HotTier::Scylla(scylla_config) => reports.push(
+                ScyllaStorage::migrate(scylla_config, dry_run)
+                    .await
+                    .map_err(|e| DatabaseError::Migration(e.to_string()))?
+            ),
+            HotTier::Sqlite(path) => reports.push(
+                SqliteStorage::migrate(path, dry_run)
+                    .await
+                    .map_err(|e| DatabaseError::Migration(e.to_string()))?
+            ),
+            HotTier::Memory => {}
+        }
+
+        if backends.warm_path != ":memory:" {
+            reports.push(
+                SqliteStorage::migrate(&backends.warm_path, dry_run)
+                    .await
+                    .map_err(|e| DatabaseError::Migration(e.to_string()))?
+            );
+        }
+
+        for report in &reports {
+            let verb = if report.dry_run { "would apply" } else { "applied" };
+            info!("{}: {} {} migration(s)", report.backend, verb, report.pending.len());
+        }
+        Ok(reports)
+    }
+
+    /// Bring the open warm tier up to date and backfill derived tables
     pub async fn run_migrations(&self) -> Result<(), DatabaseError> {
         self.warm.run_migrations().await
             .map_err(|e| DatabaseError::Migration(e.to_string()))?;
+        self.reload_sensor_index().await?;
         
         info!("Database migrations completed successfully");
         Ok(())
@@ -447,30 +1000,179 @@ impl RadiationDatabase {
         }
     }
 
-    /// Get anomalies since a given timestamp
+    /// Get count of anomalies in last N hours
+    #[instrument(skip(self))]
+    pub async fn get_anomaly_count(&self, hours: i64) -> Result<i64, DatabaseError> {
+        self.events.get_anomaly_count(hours).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// List all sensors with their latest location and timestamp
+    #[instrument(skip(self))]
+    pub async fn list_sensors(&self) -> Result<Vec<SensorRecord>, DatabaseError> {
+        self.warm.list_sensors_with_location().await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// Get a mobile survey track with its geo-path
+    #[instrument(skip(self))]
+    pub async fn get_track(&self, track_id: &Uuid) -> Result<Option<Track>, DatabaseError> {
+        self.warm.get_track(track_id).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// Record a source fetch that readings' provenance refers to
+    pub async fn record_fetch(&self, fetch: &SourceFetch) -> Result<(), DatabaseError> {
+        self.warm.record_fetch(fetch).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// Fetch, raw value, conversion and QC decision behind a stored reading
+    #[instrument(skip(self))]
+    pub async fn reading_lineage(&self, sensor_id: &Uuid, timestamp: i64) -> Result<Option<ReadingLineage>, DatabaseError> {
+        self.warm.reading_lineage(sensor_id, timestamp).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// Store hourly weather observations, which live in the warm tier regardless of age
+    #[instrument(skip(self, observations))]
+    pub async fn store_weather(&self, observations: &[WeatherObservation]) -> Result<(), DatabaseError> {
+        self.warm.store_weather(observations).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// Hourly weather over `[from, to]` at the reporting point nearest to a
+    /// location, if one lies within `radius_km`
+    #[instrument(skip(self))]
+    pub async fn weather_near(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<WeatherObservation>, DatabaseError> {
+        let lat_delta = radius_km / 111.0;
+        let lon_delta = radius_km / (111.0 * latitude.to_radians().cos().abs().max(0.01));
+        let observations = self.warm
+            .weather_in(
+                ((latitude - lat_delta).max(-90.0), (longitude - lon_delta).max(-180.0)),
+                ((latitude + lat_delta).min(90.0), (longitude + lon_delta).min(180.0)),
+                from,
+                to,
+            )
+            .await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+
+        let nearest = observations
+            .iter()
+            .map(|o| ((o.latitude, o.longitude), haversine_distance(latitude, longitude, o.latitude, o.longitude)))
+            .filter(|(_, distance)| *distance <= radius_km)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(point, _)| point);
+
+        Ok(match nearest {
+            Some(point) => observations.into_iter().filter(|o| (o.latitude, o.longitude) == point).collect(),
+            None => Vec::new(),
+        })
+    }
+
+    /// List tracks overlapping a time range, optionally for one sensor
     #[instrument(skip(self))]
-    pub async fn get_anomalies(
+    pub async fn list_tracks(
         &self,
-        since: i64,
+        sensor_id: Option<&Uuid>,
+        from: i64,
+        to: i64,
         limit: usize,
-    ) -> Result<Vec<AnomalyRecord>, DatabaseError> {
-        self.warm.get_anomalies(since, limit).await
+    ) -> Result<Vec<TrackSummary>, DatabaseError> {
+        let start = DateTime::from_timestamp(from, 0)
+            .ok_or_else(|| DatabaseError::Query("Invalid start timestamp".to_string()))?;
+        let end = DateTime::from_timestamp(to, 0)
+            .ok_or_else(|| DatabaseError::Query("Invalid end timestamp".to_string()))?;
+
+        self.warm.list_tracks(sensor_id, start, end, limit).await
             .map_err(|e| DatabaseError::Sqlite(e.to_string()))
     }
 
-    /// Get count of anomalies in last N hours
+    /// Register a sensor, returning `None` if the ID is already taken
+    #[instrument(skip(self, sensor))]
+    pub async fn create_sensor(&self, sensor: &NewSensor) -> Result<Option<Sensor>, DatabaseError> {
+        self.warm.create_sensor(sensor).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// Update a sensor's metadata, returning `None` for unknown sensors
+    ///
+    /// Only maintenance can be entered or left by hand; liveness states are
+    /// swept and decommissioning has its own call.
+    #[instrument(skip(self, update))]
+    pub async fn update_sensor(&self, sensor_id: &Uuid, update: &SensorUpdate) -> Result<Option<Sensor>, DatabaseError> {
+        if let Some(status) = update.status {
+            if !matches!(status, SensorState::Maintenance | SensorState::Online) {
+                return Err(DatabaseError::Query(format!("Status {} cannot be set directly", status.as_str())));
+            }
+
+            let current = self.get_sensor(sensor_id).await?;
+            if current.is_some_and(|s| s.status == SensorState::Decommissioned) {
+                return Err(DatabaseError::Query("Sensor is decommissioned".to_string()));
+            }
+        }
+
+        self.warm.update_sensor(sensor_id, update).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// Decommission a sensor, returning `None` for unknown sensors
     #[instrument(skip(self))]
-    pub async fn get_anomaly_count(&self, hours: i64) -> Result<i64, DatabaseError> {
-        self.warm.get_anomaly_count(hours).await
+    pub async fn decommission_sensor(&self, sensor_id: &Uuid) -> Result<Option<Sensor>, DatabaseError> {
+        self.warm.decommission_sensor(sensor_id, Utc::now()).await
             .map_err(|e| DatabaseError::Sqlite(e.to_string()))
     }
 
-    /// List all sensors with their latest location and timestamp
+    /// Get a registered sensor
     #[instrument(skip(self))]
-    pub async fn list_sensors(&self) -> Result<Vec<SensorRecord>, DatabaseError> {
-        self.warm.list_sensors_with_location().await
+    pub async fn get_sensor(&self, sensor_id: &Uuid) -> Result<Option<Sensor>, DatabaseError> {
+        self.warm.get_sensor(sensor_id).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))
+    }
+
+    /// List registered sensors, optionally including decommissioned ones
+    #[instrument(skip(self))]
+    pub async fn list_registered_sensors(&self, include_decommissioned: bool) -> Result<Vec<Sensor>, DatabaseError> {
+        self.warm.list_registered_sensors(include_decommissioned).await
             .map_err(|e| DatabaseError::Sqlite(e.to_string()))
     }
+
+    /// Apply delivery-gap status transitions and record them in the audit trail
+    #[instrument(skip(self))]
+    pub async fn sweep_sensor_status(&self, thresholds: &LivenessThresholds) -> Result<Vec<StatusTransition>, DatabaseError> {
+        let transitions = self.warm.sweep_sensor_status(Utc::now(), thresholds).await
+            .map_err(|e| DatabaseError::Sqlite(e.to_string()))?;
+
+        for transition in &transitions {
+            let event = DomainEvent {
+                event_id: Uuid::new_v4().to_string(),
+                event_type: match transition.to {
+                    SensorState::Offline => EventType::SensorOffline,
+                    SensorState::Degraded => EventType::SensorDegraded,
+                    _ => EventType::SensorOnline,
+                },
+                aggregate_id: transition.sensor_id,
+                payload: serde_json::json!({
+                    "from": transition.from,
+                    "to": transition.to,
+                }),
+                timestamp: transition.at,
+            };
+
+            if let Err(e) = self.events.store_event(&event).await {
+                warn!("Failed to record status change for {}: {}", transition.sensor_id, e);
+            }
+        }
+
+        Ok(transitions)
+    }
 }
 
 
@@ -487,6 +1189,170 @@ impl DatabaseHealth {
     }
 }
 
+/// Schema state of one backend, and what a migration run applies to it
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    /// Backend and location, e.g. `scylla:cherenkov` or `sqlite:./data/cherenkov_warm.db`
+    pub backend: String,
+    /// Newest migration applied before this run, `None` for a fresh backend
+    pub current_version: Option<i64>,
+    /// Statements preparing the backend for versioned migrations, such as
+    /// creating the keyspace
+    pub setup: Vec<String>,
+    pub pending: Vec<PendingMigration>,
+    /// Whether the run only reported, without applying anything
+    pub dry_run: bool,
+}
+
+impl MigrationReport {
+    pub fn is_up_to_date(&self) -> bool {
+        self.setup.is_empty() && self.pending.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+    pub statements: Vec<String>,
+}
+
+/// What one page of a query needs from each tier
+struct StepPage<'a> {
+    query: &'a TimeRangeQuery,
+    /// Only readings past it are wanted
+    cursor: Option<&'a Cursor>,
+    /// Readings after which a tier stops reading
+    need: usize,
+}
+
+impl StepPage<'_> {
+    fn wants(&self, reading: &RadiationReading) -> bool {
+        self.query.matches(reading)
+            && self.cursor.map_or(true, |cursor| {
+                cursor.is_before(reading.timestamp, &reading.sensor_id.to_string(), self.query.order)
+            })
+    }
+}
+
+/// Readings of one plan step that the page wants, read in query order
+///
+/// A tier stops once it has found `need` readings, or for a sensor once that
+/// sensor has; everything it skipped sorts after what it returned.
+async fn read_step(store: &dyn ReadingStore, step: &PlanStep, page: &StepPage<'_>) -> anyhow::Result<Vec<RadiationReading>> {
+    let order = page.query.order;
+    let mut readings = Vec::new();
+    match &step.access {
+        Access::Sensors(ids) => {
+            for id in ids {
+                let (mut from, mut to) = (step.from, step.to);
+                let mut found = 0;
+                // Filters the store cannot apply may discard part of each batch
+                while found < page.need && from <= to {
+                    let batch = store.query_sensor_page(*id, from, to, order, page.need).await?;
+                    let exhausted = batch.len() < page.need;
+                    match (order, batch.last()) {
+                        (SortOrder::Ascending, Some(last)) => from = last.timestamp + 1,
+                        (SortOrder::Descending, Some(last)) => to = last.timestamp - 1,
+                        (_, None) => break,
+                    }
+                    for reading in batch.into_iter().filter(|r| page.wants(r)) {
+                        readings.push(reading);
+                        found += 1;
+                    }
+                    if exhausted {
+                        break;
+                    }
+                }
+            }
+        }
+        Access::Cells(cells) => {
+            for (from, to) in spans(step.from, step.to, order) {
+                for cell in cells {
+                    readings.extend(store.query_by_geohash(cell, from, to).await?.into_iter().filter(|r| page.wants(r)));
+                }
+                if readings.len() >= page.need {
+                    break;
+                }
+            }
+        }
+        Access::Scan => {
+            let buckets = RadiationReading::bucket_for(step.from)..=RadiationReading::bucket_for(step.to);
+            let buckets: Vec<i64> = match order {
+                SortOrder::Ascending => buckets.collect(),
+                SortOrder::Descending => buckets.rev().collect(),
+            };
+            for bucket in buckets {
+                readings.extend(
+                    store.query_bucket(bucket).await?
+                        .into_iter()
+                        .filter(|r| r.timestamp >= step.from && r.timestamp <= step.to)
+                        .filter(|r| page.wants(r)),
+                );
+                if readings.len() >= page.need {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(readings)
+}
+
+/// Consecutive spans covering `[from, to]` in `order`, starting at one
+/// bucket and doubling, so sparse windows take few reads and dense ones
+/// stop early
+fn spans(from: i64, to: i64, order: SortOrder) -> impl Iterator<Item = (i64, i64)> {
+    let mut width = BUCKET_SECS;
+    let mut next = (from <= to).then_some(match order {
+        SortOrder::Ascending => from,
+        SortOrder::Descending => to,
+    });
+    std::iter::from_fn(move || {
+        let start = next?;
+        let span = match order {
+            SortOrder::Ascending => (start, start.saturating_add(width - 1).min(to)),
+            SortOrder::Descending => (start.saturating_sub(width - 1).max(from), start),
+        };
+        next = match order {
+            SortOrder::Ascending => (span.1 < to).then(|| span.1 + 1),
+            SortOrder::Descending => (span.0 > from).then(|| span.0 - 1),
+        };
+        width = width.saturating_mul(2);
+        Some(span)
+    })
+}
+
+fn reading_position(reading: &RadiationReading) -> (i64, String) {
+    (reading.timestamp, reading.sensor_id.to_string())
+}
+
+fn point_position(point: &TimeSeriesPoint) -> (i64, String) {
+    (point.timestamp.timestamp(), point.sensor_id.map(|id| id.to_string()).unwrap_or_default())
+}
+
+fn anomaly_position(anomaly: &AnomalyRecord) -> (i64, String) {
+    (anomaly.detected_at, anomaly.anomaly_id.clone())
+}
+
+fn raw_point(reading: &RadiationReading) -> TimeSeriesPoint {
+    let value = reading.dose_rate_microsieverts;
+    TimeSeriesPoint {
+        timestamp: DateTime::from_timestamp(reading.timestamp, 0).unwrap_or_else(Utc::now),
+        value,
+        count: 1,
+        min: value,
+        max: value,
+        avg: value,
+        p95: None,
+        sensor_id: Some(reading.sensor_id),
+    }
+}
+
+fn timestamp(secs: i64) -> Result<DateTime<Utc>, DatabaseError> {
+    DateTime::from_timestamp(secs, 0)
+        .ok_or_else(|| DatabaseError::Query(format!("Invalid timestamp {}", secs)))
+}
+
 /// Calculate haversine distance between two points in kilometers
 fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     const R: f64 = 6371.0; // Earth's radius in km
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{QcReason, QualityFlag};
+
+/// How a reading's stored dose rate was derived from what its source reported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingProvenance {
+    /// Fetch the reading arrived in, see [`SourceFetch`]; set by the pipeline
+    pub fetch_id: Option<Uuid>,
+    /// Value and unit exactly as the source reported them
+    pub raw_value: f64,
+    pub raw_unit: String,
+    /// Multiplier from `raw_unit` to µSv/h
+    pub conversion_factor: f64,
+    /// Version of the conversion code that chose the factor
+    pub normalizer_version: String,
+    /// QC thresholds the reading was checked against, e.g. `source:safecast`;
+    /// set by the QC engine
+    pub qc_thresholds: Option<String>,
+}
+
+impl ReadingProvenance {
+    /// A reading converted from `raw_value` in `raw_unit` by multiplying with `factor`
+    pub fn converted(raw_value: f64, raw_unit: impl Into<String>, factor: f64, normalizer_version: &str) -> Self {
+        Self {
+            fetch_id: None,
+            raw_value,
+            raw_unit: raw_unit.into(),
+            conversion_factor: factor,
+            normalizer_version: normalizer_version.to_string(),
+            qc_thresholds: None,
+        }
+    }
+}
+
+/// One fetch from a data source, shared by every reading it returned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceFetch {
+    pub fetch_id: Uuid,
+    pub source: String,
+    pub started_at: i64,
+    pub completed_at: i64,
+    /// Readings the fetch returned, before dedup and QC
+    pub readings: u64,
+}
+
+/// QC outcome recorded when the reading was written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QcDecision {
+    pub flag: QualityFlag,
+    pub thresholds: Option<String>,
+    /// Checks that fired, empty when the reading passed all of them
+    pub reasons: Vec<QcReason>,
+}
+
+/// Full lineage of a stored reading
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingLineage {
+    pub sensor_id: Uuid,
+    pub timestamp: i64,
+    /// `None` if the fetch was not recorded
+    pub fetch: Option<SourceFetch>,
+    pub raw_value: f64,
+    pub raw_unit: String,
+    pub conversion_factor: f64,
+    pub normalizer_version: String,
+    pub qc: QcDecision,
+}
+
+impl ReadingLineage {
+    /// Dose rate in µSv/h the lineage produces
+    pub fn dose_rate_microsieverts(&self) -> f64 {
+        self.raw_value * self.conversion_factor
+    }
+}
@@ -0,0 +1,257 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+use crate::projection::{Checkpoint, StoredEvent};
+use crate::query::{EventQuery, SortOrder};
+use crate::sqlite::AnomalyRecord;
+use crate::store::{Cache, EventStore, ReadingStore};
+use crate::{DomainEvent, EventType, RadiationReading};
+
+/// Readings and events held in process memory
+///
+/// Stands in for the hot tier on a single node or in tests. Nothing survives
+/// a restart, so pair it with the tiering job to move readings to SQLite.
+#[derive(Default)]
+pub struct MemoryStore {
+    /// Keyed by `(sensor_id, timestamp)`, the same identity the other tiers use
+    readings: RwLock<BTreeMap<(Uuid, i64), RadiationReading>>,
+    /// In append order; an event's sequence is its position plus one
+    events: RwLock<Vec<DomainEvent>>,
+    checkpoints: RwLock<HashMap<String, Checkpoint>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn len(&self) -> usize {
+        self.readings.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.readings.read().await.is_empty()
+    }
+}
+
+#[async_trait]
+impl ReadingStore for MemoryStore {
+    async fn write_reading(&self, reading: &RadiationReading) -> anyhow::Result<()> {
+        self.readings
+            .write()
+            .await
+            .insert((reading.sensor_id, reading.timestamp), reading.clone());
+        Ok(())
+    }
+
+    async fn query_by_time_range(&self, sensor_id: Uuid, from: i64, to: i64) -> anyhow::Result<Vec<RadiationReading>> {
+        if from > to {
+            return Ok(vec![]);
+        }
+        let readings = self.readings.read().await;
+        Ok(readings
+            .range((sensor_id, from)..=(sensor_id, to))
+            .map(|(_, r)| r.clone())
+            .collect())
+    }
+
+    async fn query_sensor_page(
+        &self,
+        sensor_id: Uuid,
+        from: i64,
+        to: i64,
+        order: SortOrder,
+        limit: usize,
+    ) -> anyhow::Result<Vec<RadiationReading>> {
+        if from > to {
+            return Ok(vec![]);
+        }
+        let readings = self.readings.read().await;
+        let range = readings.range((sensor_id, from)..=(sensor_id, to)).map(|(_, r)| r.clone());
+        Ok(match order {
+            SortOrder::Ascending => range.take(limit).collect(),
+            SortOrder::Descending => range.rev().take(limit).collect(),
+        })
+    }
+
+    async fn query_by_geohash(&self, geohash: &str, from: i64, to: i64) -> anyhow::Result<Vec<RadiationReading>> {
+        let bbox = geohash::decode_bbox(geohash)
+            .map_err(|e| anyhow::anyhow!("Geohash error: {:?}", e))?;
+        let (min, max) = (bbox.min(), bbox.max());
+
+        let readings = self.readings.read().await;
+        Ok(readings
+            .values()
+            .filter(|r| r.timestamp >= from && r.timestamp <= to)
+            .filter(|r| r.longitude >= min.x && r.longitude <= max.x && r.latitude >= min.y && r.latitude <= max.y)
+            .cloned()
+            .collect())
+    }
+
+    async fn latest_reading(&self, sensor_id: Uuid) -> anyhow::Result<Option<RadiationReading>> {
+        let readings = self.readings.read().await;
+        Ok(readings
+            .range((sensor_id, i64::MIN)..=(sensor_id, i64::MAX))
+            .next_back()
+            .map(|(_, r)| r.clone()))
+    }
+
+    async fn query_bucket(&self, bucket: i64) -> anyhow::Result<Vec<RadiationReading>> {
+        let readings = self.readings.read().await;
+        Ok(readings.values().filter(|r| r.bucket == bucket).cloned().collect())
+    }
+
+    async fn delete_readings(&self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        let mut stored = self.readings.write().await;
+        for reading in readings {
+            stored.remove(&(reading.sensor_id, reading.timestamp));
+        }
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl EventStore for MemoryStore {
+    async fn store_event(&self, event: &DomainEvent) -> anyhow::Result<()> {
+        let mut events = self.events.write().await;
+        if !events.iter().any(|e| e.event_id == event.event_id) {
+            events.push(event.clone());
+        }
+        Ok(())
+    }
+
+    /// Anomalies are read back from `AnomalyDetected` events
+    async fn get_anomalies(&self, since: i64, limit: usize) -> anyhow::Result<Vec<AnomalyRecord>> {
+        let events = self.events.read().await;
+        let mut anomalies: Vec<AnomalyRecord> = events
+            .iter()
+            .filter(|e| matches!(e.event_type, EventType::AnomalyDetected) && e.timestamp >= since)
+            .map(|e| AnomalyRecord {
+                anomaly_id: e.event_id.clone(),
+                sensor_id: e.aggregate_id,
+                severity: e.payload.get("severity")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown")
+                    .to_string(),
+                z_score: e.payload.get("z_score").and_then(|v| v.as_f64()).unwrap_or_default(),
+                detected_at: e.timestamp,
+            })
+            .collect();
+
+        anomalies.sort_by_key(|a| std::cmp::Reverse(a.detected_at));
+        anomalies.truncate(limit);
+        Ok(anomalies)
+    }
+
+    async fn get_anomaly_count(&self, hours: i64) -> anyhow::Result<i64> {
+        let since = (Utc::now() - chrono::Duration::hours(hours)).timestamp();
+        Ok(self.get_anomalies(since, usize::MAX).await?.len() as i64)
+    }
+
+    async fn query_events(&self, query: &EventQuery) -> anyhow::Result<Vec<StoredEvent>> {
+        let events = self.events.read().await;
+        let stored = events.iter().enumerate().map(|(i, event)| StoredEvent {
+            sequence: i as i64 + 1,
+            event: event.clone(),
+        });
+        let matching = stored.filter(|e| query.matches(e));
+        Ok(match query.order {
+            SortOrder::Ascending => matching.take(query.limit).collect(),
+            SortOrder::Descending => {
+                let mut newest: Vec<StoredEvent> = matching.collect();
+                newest.reverse();
+                newest.truncate(query.limit);
+                newest
+            }
+        })
+    }
+
+    async fn load_checkpoint(&self, name: &str) -> anyhow::Result<Option<Checkpoint>> {
+        Ok(self.checkpoints.read().await.get(name).cloned())
+    }
+
+    async fn save_checkpoint(&self, checkpoint: &Checkpoint) -> anyhow::Result<()> {
+        let mut checkpoints = self.checkpoints.write().await;
+        if checkpoints.get(&checkpoint.name).map_or(true, |saved| saved.sequence <= checkpoint.sequence) {
+            checkpoints.insert(checkpoint.name.clone(), checkpoint.clone());
+        }
+        Ok(())
+    }
+}
+
+/// In-process cache with per-key expiry, for deployments without Redis
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+    max_entries: usize,
+}
+
+impl MemoryCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries,
+        }
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(100_000)
+    }
+}
+
+#[async_trait]
+impl Cache for MemoryCache {
+    async fn get_raw(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let mut entries = self.entries.lock().await;
+        match entries.get(key) {
+            Some((_, expires)) if *expires <= Instant::now() => {
+                entries.remove(key);
+                Ok(None)
+            }
+            Some((value, _)) => Ok(Some(value.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_raw(&self, key: &str, value: String, ttl_seconds: u64) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().await;
+
+        if entries.len() >= self.max_entries && !entries.contains_key(key) {
+            entries.retain(|_, (_, expires)| *expires > now);
+
+            // Still full of live entries: drop the one closest to expiring
+            if entries.len() >= self.max_entries {
+                if let Some(oldest) = entries.iter().min_by_key(|(_, (_, e))| *e).map(|(k, _)| k.clone()) {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+
+        entries.insert(key.to_string(), (value, now + Duration::from_secs(ttl_seconds)));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.entries.lock().await.remove(key);
+        Ok(())
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> anyhow::Result<()> {
+        self.entries.lock().await.retain(|key, _| !key.starts_with(prefix));
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        true
+    }
+}
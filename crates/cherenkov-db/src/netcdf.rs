@@ -0,0 +1,239 @@
+//! Writer for the NetCDF classic format, 64-bit offset variant (CDF-2)
+//!
+//! Covers what exports need: fixed-size and record variables of the basic
+//! classic types, with global and per-variable attributes. The layout follows
+//! the NetCDF Classic Format Specification; values are big-endian and every
+//! variable's data is padded to four bytes.
+
+use std::io::{self, Read, Write};
+
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NcType {
+    Byte,
+    Char,
+    Int,
+    Float,
+    Double,
+}
+
+impl NcType {
+    fn code(self) -> u32 {
+        match self {
+            NcType::Byte => 1,
+            NcType::Char => 2,
+            NcType::Int => 4,
+            NcType::Float => 5,
+            NcType::Double => 6,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            NcType::Byte | NcType::Char => 1,
+            NcType::Int | NcType::Float => 4,
+            NcType::Double => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum AttrValue {
+    Text(String),
+    Byte(Vec<i8>),
+    Int(Vec<i32>),
+}
+
+#[derive(Debug)]
+struct Variable {
+    name: String,
+    nc_type: NcType,
+    dims: Vec<usize>,
+    attrs: Vec<(String, AttrValue)>,
+}
+
+/// Dimensions, attributes and variables of a file
+///
+/// A dimension without a length is the record dimension; it must come first
+/// in the dimensions of any variable using it.
+#[derive(Debug, Default)]
+pub(crate) struct Schema {
+    dims: Vec<(String, Option<usize>)>,
+    attrs: Vec<(String, AttrValue)>,
+    vars: Vec<Variable>,
+}
+
+impl Schema {
+    pub(crate) fn dimension(&mut self, name: &str, len: Option<usize>) -> usize {
+        self.dims.push((name.to_string(), len));
+        self.dims.len() - 1
+    }
+
+    pub(crate) fn attribute(&mut self, name: &str, value: AttrValue) {
+        self.attrs.push((name.to_string(), value));
+    }
+
+    pub(crate) fn variable(&mut self, name: &str, nc_type: NcType, dims: &[usize]) -> usize {
+        self.vars.push(Variable {
+            name: name.to_string(),
+            nc_type,
+            dims: dims.to_vec(),
+            attrs: Vec::new(),
+        });
+        self.vars.len() - 1
+    }
+
+    pub(crate) fn variable_attribute(&mut self, var: usize, name: &str, value: AttrValue) {
+        self.vars[var].attrs.push((name.to_string(), value));
+    }
+
+    fn is_record(&self, var: &Variable) -> bool {
+        var.dims.first().is_some_and(|d| self.dims[*d].1.is_none())
+    }
+
+    /// Bytes one variable takes up, per record for record variables
+    fn vsize(&self, var: &Variable) -> usize {
+        let values: usize = var.dims.iter().filter_map(|d| self.dims[*d].1).product();
+        pad4(values * var.nc_type.size())
+    }
+
+    /// Bytes of one record, every record variable's values side by side
+    pub(crate) fn record_size(&self) -> usize {
+        self.vars.iter().filter(|v| self.is_record(v)).map(|v| self.vsize(v)).sum()
+    }
+
+    /// Write the file: header, then `fixed` holding the values of each
+    /// non-record variable in declaration order, then `numrecs` records read
+    /// from `records`
+    pub(crate) fn write(
+        &self,
+        out: &mut impl Write,
+        numrecs: u64,
+        fixed: &[Vec<u8>],
+        records: &mut impl Read,
+    ) -> io::Result<()> {
+        let numrecs = u32::try_from(numrecs)
+            .ok()
+            .filter(|n| *n < i32::MAX as u32)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Too many records for NetCDF"))?;
+
+        let fixed_vars: Vec<&Variable> = self.vars.iter().filter(|v| !self.is_record(v)).collect();
+        if fixed_vars.len() != fixed.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Data missing for a fixed variable"));
+        }
+        for (var, data) in fixed_vars.iter().zip(fixed) {
+            if pad4(data.len()) != self.vsize(var) {
+                let message = format!("Variable {} expects {} bytes, got {}", var.name, self.vsize(var), data.len());
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+        }
+
+        // Offsets are fixed-width, so the header's length does not depend on them
+        let header_len = self.header(numrecs, &vec![0; self.vars.len()]).len() as u64;
+        let fixed_len: u64 = fixed_vars.iter().map(|v| self.vsize(v) as u64).sum();
+        let (mut fixed_offset, mut record_offset) = (header_len, header_len + fixed_len);
+        let begins: Vec<u64> = self
+            .vars
+            .iter()
+            .map(|var| {
+                let offset = if self.is_record(var) { &mut record_offset } else { &mut fixed_offset };
+                let begin = *offset;
+                *offset += self.vsize(var) as u64;
+                begin
+            })
+            .collect();
+
+        out.write_all(&self.header(numrecs, &begins))?;
+        for data in fixed {
+            out.write_all(data)?;
+            out.write_all(&[0; 3][..pad4(data.len()) - data.len()])?;
+        }
+
+        let expected = numrecs as u64 * self.record_size() as u64;
+        let copied = io::copy(&mut records.take(expected), out)?;
+        if copied != expected {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Record data ended early"));
+        }
+        Ok(())
+    }
+
+    fn header(&self, numrecs: u32, begins: &[u64]) -> Vec<u8> {
+        let mut buf = b"CDF\x02".to_vec();
+        put_u32(&mut buf, numrecs);
+
+        if self.dims.is_empty() {
+            buf.extend_from_slice(&[0; 8]);
+        } else {
+            put_u32(&mut buf, NC_DIMENSION);
+            put_u32(&mut buf, self.dims.len() as u32);
+            for (name, len) in &self.dims {
+                put_name(&mut buf, name);
+                put_u32(&mut buf, len.unwrap_or(0) as u32);
+            }
+        }
+
+        put_attrs(&mut buf, &self.attrs);
+
+        if self.vars.is_empty() {
+            buf.extend_from_slice(&[0; 8]);
+        } else {
+            put_u32(&mut buf, NC_VARIABLE);
+            put_u32(&mut buf, self.vars.len() as u32);
+            for (var, begin) in self.vars.iter().zip(begins) {
+                put_name(&mut buf, &var.name);
+                put_u32(&mut buf, var.dims.len() as u32);
+                for dim in &var.dims {
+                    put_u32(&mut buf, *dim as u32);
+                }
+                put_attrs(&mut buf, &var.attrs);
+                put_u32(&mut buf, var.nc_type.code());
+                put_u32(&mut buf, self.vsize(var).min(u32::MAX as usize) as u32);
+                buf.extend_from_slice(&begin.to_be_bytes());
+            }
+        }
+
+        buf
+    }
+}
+
+pub(crate) fn pad4(len: usize) -> usize {
+    len.div_ceil(4) * 4
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(bytes);
+    buf.resize(buf.len() + pad4(bytes.len()) - bytes.len(), 0);
+}
+
+fn put_name(buf: &mut Vec<u8>, name: &str) {
+    put_u32(buf, name.len() as u32);
+    put_bytes(buf, name.as_bytes());
+}
+
+fn put_attrs(buf: &mut Vec<u8>, attrs: &[(String, AttrValue)]) {
+    if attrs.is_empty() {
+        buf.extend_from_slice(&[0; 8]);
+        return;
+    }
+
+    put_u32(buf, NC_ATTRIBUTE);
+    put_u32(buf, attrs.len() as u32);
+    for (name, value) in attrs {
+        put_name(buf, name);
+        let (nc_type, count, bytes): (NcType, usize, Vec<u8>) = match value {
+            AttrValue::Text(text) => (NcType::Char, text.len(), text.as_bytes().to_vec()),
+            AttrValue::Byte(values) => (NcType::Byte, values.len(), values.iter().map(|v| *v as u8).collect()),
+            AttrValue::Int(values) => (NcType::Int, values.len(), values.iter().flat_map(|v| v.to_be_bytes()).collect()),
+        };
+        put_u32(buf, nc_type.code());
+        put_u32(buf, count as u32);
+        put_bytes(buf, &bytes);
+    }
+}
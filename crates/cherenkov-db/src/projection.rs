@@ -0,0 +1,344 @@
+//! Read models projected from the domain event log
+//!
+//! Every `DomainEvent` is appended to the log with a sequence number. A
+//! projection folds events into a read model in sequence order and records
+//! the last sequence it applied, so it can be rebuilt from the first event or
+//! resumed from a saved checkpoint and fed only what was appended since.
+//! Several processes may project the same log; each keeps its own state and
+//! a checkpoint is never replaced by one that is behind it.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::query::{EventQuery, SortOrder};
+use crate::registry::SensorState;
+use crate::store::EventStore;
+use crate::{DomainEvent, EventType};
+
+/// Events read from the log per round trip while catching up
+const REPLAY_BATCH: usize = 1000;
+
+/// Events applied between automatic checkpoints
+const CHECKPOINT_INTERVAL: i64 = 500;
+
+/// Alerts and incident changes the timeline keeps, oldest dropped first
+pub const TIMELINE_CAPACITY: usize = 10_000;
+
+/// A domain event and its position in the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredEvent {
+    /// Append order, starting at 1
+    pub sequence: i64,
+    #[serde(flatten)]
+    pub event: DomainEvent,
+}
+
+/// Saved state of one projection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub name: String,
+    /// Last event applied to `state`
+    pub sequence: i64,
+    pub state: serde_json::Value,
+}
+
+/// A read model built by folding events in log order
+pub trait Projection: Default + Clone + Serialize + DeserializeOwned + Send + Sync {
+    /// Checkpoint name; change it when the state layout changes, so the
+    /// projection is rebuilt rather than resumed from an unreadable state
+    const NAME: &'static str;
+
+    fn apply(&mut self, event: &StoredEvent);
+}
+
+/// A projection's state and the last sequence folded into it
+#[derive(Debug, Default)]
+pub struct Projector<P> {
+    pub state: P,
+    pub sequence: i64,
+    saved_sequence: i64,
+}
+
+impl<P: Projection> Projector<P> {
+    fn feed(&mut self, event: &StoredEvent) {
+        if event.sequence > self.sequence {
+            self.state.apply(event);
+            self.sequence = event.sequence;
+        }
+    }
+
+    /// Start from the saved checkpoint, or from scratch when there is none
+    /// or its state no longer reads
+    async fn resume(&mut self, store: &dyn EventStore) -> anyhow::Result<()> {
+        *self = Self::default();
+        if let Some(checkpoint) = store.load_checkpoint(P::NAME).await? {
+            match serde_json::from_value(checkpoint.state) {
+                Ok(state) => {
+                    self.state = state;
+                    self.sequence = checkpoint.sequence;
+                    self.saved_sequence = checkpoint.sequence;
+                }
+                Err(e) => warn!("Checkpoint of {} unreadable, rebuilding: {}", P::NAME, e),
+            }
+        }
+        Ok(())
+    }
+
+    async fn save(&mut self, store: &dyn EventStore) -> anyhow::Result<()> {
+        if self.sequence == self.saved_sequence {
+            return Ok(());
+        }
+        store
+            .save_checkpoint(&Checkpoint {
+                name: P::NAME.to_string(),
+                sequence: self.sequence,
+                state: serde_json::to_value(&self.state)?,
+            })
+            .await?;
+        self.saved_sequence = self.sequence;
+        Ok(())
+    }
+}
+
+/// Latest reported state of each sensor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SensorStatusView {
+    pub sensors: BTreeMap<Uuid, SensorStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorStatus {
+    pub state: SensorState,
+    /// When the sensor entered `state`
+    pub since: i64,
+}
+
+impl Projection for SensorStatusView {
+    const NAME: &'static str = "sensor_status.v1";
+
+    fn apply(&mut self, stored: &StoredEvent) {
+        let event = &stored.event;
+        let state = match event.event_type {
+            EventType::SensorOnline => SensorState::Online,
+            EventType::SensorDegraded => SensorState::Degraded,
+            EventType::SensorOffline => SensorState::Offline,
+            _ => return,
+        };
+        // The transition payload names the state exactly, e.g. maintenance
+        let state = event
+            .payload
+            .get("to")
+            .and_then(|to| serde_json::from_value(to.clone()).ok())
+            .unwrap_or(state);
+
+        // A late event does not override a newer transition
+        if self.sensors.get(&event.aggregate_id).is_some_and(|s| s.since > event.timestamp) {
+            return;
+        }
+        self.sensors.insert(event.aggregate_id, SensorStatus { state, since: event.timestamp });
+    }
+}
+
+/// Incidents created and not yet resolved, keyed by incident
+///
+/// An incident's events use the incident's id as their aggregate.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpenIncidentsView {
+    pub incidents: BTreeMap<Uuid, Incident>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Incident {
+    pub incident_id: Uuid,
+    pub opened_at: i64,
+    pub severity: Option<String>,
+    pub title: Option<String>,
+    /// Payload of the `IncidentCreated` event
+    pub details: serde_json::Value,
+}
+
+impl Projection for OpenIncidentsView {
+    const NAME: &'static str = "open_incidents.v1";
+
+    fn apply(&mut self, stored: &StoredEvent) {
+        let event = &stored.event;
+        match event.event_type {
+            EventType::IncidentCreated => {
+                let text = |field: &str| event.payload.get(field).and_then(|v| v.as_str()).map(str::to_string);
+                self.incidents.insert(
+                    event.aggregate_id,
+                    Incident {
+                        incident_id: event.aggregate_id,
+                        opened_at: event.timestamp,
+                        severity: text("severity"),
+                        title: text("title"),
+                        details: event.payload.clone(),
+                    },
+                );
+            }
+            EventType::IncidentResolved => {
+                self.incidents.remove(&event.aggregate_id);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Anomalies detected per sensor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnomalyCountsView {
+    pub sensors: BTreeMap<Uuid, AnomalyCount>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnomalyCount {
+    pub total: u64,
+    /// Counts by lowercase severity
+    pub by_severity: BTreeMap<String, u64>,
+    pub last_detected_at: i64,
+}
+
+impl Projection for AnomalyCountsView {
+    const NAME: &'static str = "anomaly_counts.v1";
+
+    fn apply(&mut self, stored: &StoredEvent) {
+        let event = &stored.event;
+        if !matches!(event.event_type, EventType::AnomalyDetected) {
+            return;
+        }
+
+        let severity = event
+            .payload
+            .get("severity")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_ascii_lowercase();
+        let count = self.sensors.entry(event.aggregate_id).or_default();
+        count.total += 1;
+        *count.by_severity.entry(severity).or_default() += 1;
+        count.last_detected_at = count.last_detected_at.max(event.timestamp);
+    }
+}
+
+/// Alerts and incident changes in log order, capped at `TIMELINE_CAPACITY`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertTimelineView {
+    pub entries: VecDeque<StoredEvent>,
+}
+
+impl Projection for AlertTimelineView {
+    const NAME: &'static str = "alert_timeline.v1";
+
+    fn apply(&mut self, stored: &StoredEvent) {
+        if matches!(
+            stored.event.event_type,
+            EventType::AlertTriggered | EventType::IncidentCreated | EventType::IncidentResolved
+        ) {
+            if self.entries.len() == TIMELINE_CAPACITY {
+                self.entries.pop_front();
+            }
+            self.entries.push_back(stored.clone());
+        }
+    }
+}
+
+/// The read models kept by `RadiationDatabase`, projected together
+#[derive(Debug, Default)]
+pub struct Projections {
+    pub sensor_status: Projector<SensorStatusView>,
+    pub open_incidents: Projector<OpenIncidentsView>,
+    pub anomaly_counts: Projector<AnomalyCountsView>,
+    pub alert_timeline: Projector<AlertTimelineView>,
+    /// Whether checkpoints have been loaded
+    resumed: bool,
+}
+
+impl Projections {
+    /// Apply every event appended since the projections last ran, resuming
+    /// from checkpoints first if this is the first run; returns the number
+    /// of events read
+    pub async fn catch_up(&mut self, store: &dyn EventStore) -> anyhow::Result<usize> {
+        if !self.resumed {
+            self.sensor_status.resume(store).await?;
+            self.open_incidents.resume(store).await?;
+            self.anomaly_counts.resume(store).await?;
+            self.alert_timeline.resume(store).await?;
+            self.resumed = true;
+        }
+
+        let mut read = 0;
+        loop {
+            let after = self.sequence();
+            let batch = store
+                .query_events(&EventQuery::all().after(after).order_by(SortOrder::Ascending).limit(REPLAY_BATCH))
+                .await?;
+            for event in &batch {
+                self.sensor_status.feed(event);
+                self.open_incidents.feed(event);
+                self.anomaly_counts.feed(event);
+                self.alert_timeline.feed(event);
+            }
+            read += batch.len();
+            if batch.len() < REPLAY_BATCH {
+                break;
+            }
+        }
+
+        if self.max_sequence() - self.saved_sequence() >= CHECKPOINT_INTERVAL {
+            self.checkpoint(store).await?;
+        }
+        Ok(read)
+    }
+
+    /// Discard the read models and replay the whole log
+    pub async fn rebuild(&mut self, store: &dyn EventStore) -> anyhow::Result<usize> {
+        *self = Self { resumed: true, ..Self::default() };
+        let read = self.catch_up(store).await?;
+        self.checkpoint(store).await?;
+        info!("Rebuilt projections from {} events", read);
+        Ok(read)
+    }
+
+    /// Save each projection that moved since its last checkpoint
+    pub async fn checkpoint(&mut self, store: &dyn EventStore) -> anyhow::Result<()> {
+        self.sensor_status.save(store).await?;
+        self.open_incidents.save(store).await?;
+        self.anomaly_counts.save(store).await?;
+        self.alert_timeline.save(store).await?;
+        Ok(())
+    }
+
+    /// Last event every projection has applied
+    pub fn sequence(&self) -> i64 {
+        self.sequences().into_iter().min().unwrap_or(0)
+    }
+
+    fn max_sequence(&self) -> i64 {
+        self.sequences().into_iter().max().unwrap_or(0)
+    }
+
+    fn saved_sequence(&self) -> i64 {
+        [
+            self.sensor_status.saved_sequence,
+            self.open_incidents.saved_sequence,
+            self.anomaly_counts.saved_sequence,
+            self.alert_timeline.saved_sequence,
+        ]
+        .into_iter()
+        .min()
+        .unwrap_or(0)
+    }
+
+    fn sequences(&self) -> [i64; 4] {
+        [
+            self.sensor_status.sequence,
+            self.open_incidents.sequence,
+            self.anomaly_counts.sequence,
+            self.alert_timeline.sequence,
+        ]
+    }
+}
@@ -1,19 +1,43 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::projection::StoredEvent;
+use crate::sqlite::AnomalyRecord;
+use crate::{spatial, AggregationLevel, DatabaseConfig, DatabaseError, EventType, QualityFlag, RadiationReading};
+
+/// How far behind its schedule the tiering job may be before a tier is no
+/// longer searched for readings that should already have moved on
+const MIGRATION_SLACK_SECS: i64 = 86_400;
+
+/// Readings matching every filter that is set
+///
+/// Empty filter lists match everything. Results are ordered by timestamp,
+/// then sensor, and paged with an opaque cursor.
+#[derive(Debug, Clone)]
 pub struct TimeRangeQuery {
     pub sensor_ids: Vec<Uuid>,
     pub from_timestamp: i64,
     pub to_timestamp: i64,
-    pub aggregation: Aggregation,
+    pub aggregation: AggregationLevel,
+    /// Only readings located inside this box
+    pub area: Option<SpatialQuery>,
+    pub quality_flags: Vec<QualityFlag>,
+    pub sources: Vec<String>,
+    pub order: SortOrder,
+    /// Page size, `None` returns the whole range
+    pub limit: Option<usize>,
+    pub cursor: Option<Cursor>,
 }
 
-pub enum Aggregation {
-    Raw,
-    Minute,
-    Hour,
-    Day,
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpatialQuery {
     pub min_lat: f64,
     pub max_lat: f64,
@@ -22,10 +46,87 @@ pub struct SpatialQuery {
     pub active_only: bool,
 }
 
+#[derive(Debug, Clone)]
 pub struct AnomalyQuery {
     pub severity: Option<Vec<String>>,
     pub since: i64,
     pub limit: usize,
+    pub sensor_ids: Vec<Uuid>,
+    /// Only anomalies of sensors whose last known location is inside this box
+    pub area: Option<SpatialQuery>,
+    pub order: SortOrder,
+    pub cursor: Option<Cursor>,
+}
+
+/// Events from the log matching every filter that is set
+///
+/// Events are ordered and paged by sequence, so a page never skips or
+/// repeats an event appended while paging.
+#[derive(Debug, Clone)]
+pub struct EventQuery {
+    pub aggregate_ids: Vec<Uuid>,
+    pub event_types: Vec<EventType>,
+    /// Event time window, inclusive
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    /// Only events past this sequence in the query's order
+    pub after: Option<i64>,
+    pub order: SortOrder,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub timestamp: i64,
+    /// Tie-breaker between items with the same timestamp
+    pub key: String,
+}
+
+/// One page of results and the cursor of the next, if any
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// Storage tier a plan step reads from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Tier {
+    Hot,
+    Warm,
+    Cold,
+    Rollups,
+}
+
+/// How a plan step finds readings within its tier
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Access {
+    /// Per-sensor partitions
+    Sensors(Vec<Uuid>),
+    /// Geohash cells covering the query's box
+    Cells(Vec<String>),
+    /// Everything in the window, hour by hour
+    Scan,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanStep {
+    pub tier: Tier,
+    pub access: Access,
+    pub from: i64,
+    pub to: i64,
+}
+
+/// Tiers a query fans out to, hottest first
+///
+/// Each tier is only read for the part of the window it can hold. A reading
+/// found in more than one tier, e.g. mid-migration, is taken from the first.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryPlan {
+    /// Level the query resolved to, with `Auto` replaced
+    #[serde(skip)]
+    pub aggregation: AggregationLevel,
+    pub steps: Vec<PlanStep>,
 }
 
 impl TimeRangeQuery {
@@ -34,14 +135,84 @@ impl TimeRangeQuery {
             sensor_ids,
             from_timestamp: from,
             to_timestamp: to,
-            aggregation: Aggregation::Raw,
+            aggregation: AggregationLevel::Raw,
+            area: None,
+            quality_flags: Vec::new(),
+            sources: Vec::new(),
+            order: SortOrder::Ascending,
+            limit: None,
+            cursor: None,
         }
     }
-    
-    pub fn with_aggregation(mut self, agg: Aggregation) -> Self {
+
+    pub fn with_aggregation(mut self, agg: AggregationLevel) -> Self {
         self.aggregation = agg;
         self
     }
+
+    pub fn within(mut self, area: SpatialQuery) -> Self {
+        self.area = Some(area);
+        self
+    }
+
+    pub fn with_quality(mut self, flags: impl IntoIterator<Item = QualityFlag>) -> Self {
+        self.quality_flags = flags.into_iter().collect();
+        self
+    }
+
+    pub fn with_sources(mut self, sources: impl IntoIterator<Item = String>) -> Self {
+        self.sources = sources.into_iter().collect();
+        self
+    }
+
+    pub fn order_by(mut self, order: SortOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Whether a reading passes every filter
+    pub fn matches(&self, reading: &RadiationReading) -> bool {
+        reading.timestamp >= self.from_timestamp
+            && reading.timestamp <= self.to_timestamp
+            && (self.sensor_ids.is_empty() || self.sensor_ids.contains(&reading.sensor_id))
+            && self.area.as_ref().map_or(true, |a| a.contains(reading.latitude, reading.longitude))
+            && (self.quality_flags.is_empty() || self.quality_flags.contains(&reading.quality_flag))
+            && (self.sources.is_empty() || self.sources.contains(&reading.source))
+    }
+
+    /// Window still to be read, narrowed to the far side of the cursor
+    pub fn window(&self) -> (i64, i64) {
+        match (&self.cursor, self.order) {
+            (Some(c), SortOrder::Ascending) => (self.from_timestamp.max(c.timestamp), self.to_timestamp),
+            (Some(c), SortOrder::Descending) => (self.from_timestamp, self.to_timestamp.min(c.timestamp)),
+            (None, _) => (self.from_timestamp, self.to_timestamp),
+        }
+    }
+
+    /// Rollups are kept per sensor, so they can only answer a pure sensor filter
+    fn rollups_apply(&self) -> bool {
+        !self.sensor_ids.is_empty() && self.area.is_none() && self.quality_flags.is_empty() && self.sources.is_empty()
+    }
+}
+
+impl SortOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "asc" | "ascending" => Some(SortOrder::Ascending),
+            "desc" | "descending" => Some(SortOrder::Descending),
+            _ => None,
+        }
+    }
 }
 
 impl SpatialQuery {
@@ -54,4 +225,250 @@ impl SpatialQuery {
             active_only: true,
         }
     }
+
+    /// Whether a point is inside the box; `min_lon > max_lon` wraps across the antimeridian
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        let in_lon = if self.min_lon <= self.max_lon {
+            longitude >= self.min_lon && longitude <= self.max_lon
+        } else {
+            longitude >= self.min_lon || longitude <= self.max_lon
+        };
+        in_lon && latitude >= self.min_lat && latitude <= self.max_lat
+    }
+}
+
+impl AnomalyQuery {
+    /// Newest anomalies first, 100 per page
+    pub fn since(since: i64) -> Self {
+        Self {
+            severity: None,
+            since,
+            limit: 100,
+            sensor_ids: Vec::new(),
+            area: None,
+            order: SortOrder::Descending,
+            cursor: None,
+        }
+    }
+
+    pub fn with_severity(mut self, severity: Vec<String>) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    pub fn for_sensors(mut self, sensor_ids: Vec<Uuid>) -> Self {
+        self.sensor_ids = sensor_ids;
+        self
+    }
+
+    pub fn within(mut self, area: SpatialQuery) -> Self {
+        self.area = Some(area);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn order_by(mut self, order: SortOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Whether an anomaly passes the severity, sensor and time filters
+    pub fn matches(&self, anomaly: &AnomalyRecord) -> bool {
+        anomaly.detected_at >= self.since
+            && self.severity.as_ref().map_or(true, |s| s.contains(&anomaly.severity))
+            && (self.sensor_ids.is_empty() || self.sensor_ids.contains(&anomaly.sensor_id))
+    }
+}
+
+impl EventQuery {
+    /// The whole log, oldest first, 1000 per page
+    pub fn all() -> Self {
+        Self {
+            aggregate_ids: Vec::new(),
+            event_types: Vec::new(),
+            from: None,
+            to: None,
+            after: None,
+            order: SortOrder::Ascending,
+            limit: 1000,
+        }
+    }
+
+    pub fn for_aggregates(mut self, aggregate_ids: Vec<Uuid>) -> Self {
+        self.aggregate_ids = aggregate_ids;
+        self
+    }
+
+    pub fn of_types(mut self, event_types: Vec<EventType>) -> Self {
+        self.event_types = event_types;
+        self
+    }
+
+    pub fn between(mut self, from: Option<i64>, to: Option<i64>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    pub fn after(mut self, sequence: i64) -> Self {
+        self.after = Some(sequence);
+        self
+    }
+
+    pub fn order_by(mut self, order: SortOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Whether an event passes every filter and lies past the cursor
+    pub fn matches(&self, stored: &StoredEvent) -> bool {
+        let event = &stored.event;
+        let past_cursor = self.after.map_or(true, |after| match self.order {
+            SortOrder::Ascending => stored.sequence > after,
+            SortOrder::Descending => stored.sequence < after,
+        });
+        past_cursor
+            && (self.aggregate_ids.is_empty() || self.aggregate_ids.contains(&event.aggregate_id))
+            && (self.event_types.is_empty() || self.event_types.contains(&event.event_type))
+            && self.from.map_or(true, |from| event.timestamp >= from)
+            && self.to.map_or(true, |to| event.timestamp <= to)
+    }
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.timestamp, self.key)
+    }
+
+    pub fn decode(value: &str) -> Result<Self, DatabaseError> {
+        let (timestamp, key) = value
+            .split_once(':')
+            .ok_or_else(|| DatabaseError::Query(format!("Invalid cursor: {}", value)))?;
+        let timestamp = timestamp
+            .parse()
+            .map_err(|_| DatabaseError::Query(format!("Invalid cursor: {}", value)))?;
+        Ok(Self { timestamp, key: key.to_string() })
+    }
+
+    /// Whether an item at `(timestamp, key)` belongs after this cursor in `order`
+    pub fn is_before(&self, timestamp: i64, key: &str, order: SortOrder) -> bool {
+        let position = (timestamp, key).cmp(&(self.timestamp, self.key.as_str()));
+        match order {
+            SortOrder::Ascending => position.is_gt(),
+            SortOrder::Descending => position.is_lt(),
+        }
+    }
+}
+
+impl<T> Page<T> {
+    /// Sort `items` by `position`, skip past `cursor` and cut to `limit`
+    pub fn paginate(
+        items: Vec<T>,
+        position: impl Fn(&T) -> (i64, String),
+        order: SortOrder,
+        cursor: Option<&Cursor>,
+        limit: Option<usize>,
+    ) -> Self {
+        let mut keyed: Vec<((i64, String), T)> = items.into_iter().map(|item| (position(&item), item)).collect();
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        if order == SortOrder::Descending {
+            keyed.reverse();
+        }
+        if let Some(cursor) = cursor {
+            keyed.retain(|((timestamp, key), _)| cursor.is_before(*timestamp, key, order));
+        }
+
+        let mut next_cursor = None;
+        if let Some(limit) = limit {
+            if keyed.len() > limit {
+                keyed.truncate(limit);
+                next_cursor = keyed.last().map(|((timestamp, key), _)| {
+                    Cursor { timestamp: *timestamp, key: key.clone() }.encode()
+                });
+            }
+        }
+
+        Page {
+            items: keyed.into_iter().map(|(_, item)| item).collect(),
+            next_cursor,
+        }
+    }
+}
+
+impl QueryPlan {
+    /// Decide which tiers, and which parts of each, a query has to read
+    pub fn for_query(
+        query: &TimeRangeQuery,
+        config: &DatabaseConfig,
+        cold_enabled: bool,
+        now: DateTime<Utc>,
+    ) -> Self {
+        // Resolved from the full range, so every page of a query uses the same level
+        let span = Duration::seconds((query.to_timestamp - query.from_timestamp).max(0));
+        let aggregation = query.aggregation.resolve(span);
+        let (from, to) = query.window();
+
+        if aggregation.rollup().is_some() && query.rollups_apply() {
+            return Self {
+                aggregation,
+                steps: vec![PlanStep {
+                    tier: Tier::Rollups,
+                    access: Access::Sensors(query.sensor_ids.clone()),
+                    from,
+                    to,
+                }],
+            };
+        }
+
+        let access = if !query.sensor_ids.is_empty() {
+            Access::Sensors(query.sensor_ids.clone())
+        } else if let Some(area) = &query.area {
+            Access::Cells(spatial::box_cells(area))
+        } else {
+            Access::Scan
+        };
+
+        let now = now.timestamp();
+        let hot_cutoff = now - config.hot_retention_days * 86_400;
+        let warm_cutoff = now - config.warm_retention_days * 86_400;
+
+        // Readings only reach warm once older than hot retention, and cold once
+        // older than warm retention; the job moving them may lag behind
+        let windows = [
+            (Tier::Hot, from.max(hot_cutoff - MIGRATION_SLACK_SECS), to),
+            (Tier::Warm, from.max(warm_cutoff - MIGRATION_SLACK_SECS), to.min(hot_cutoff)),
+            (Tier::Cold, from, to.min(warm_cutoff)),
+        ];
+
+        let steps = windows
+            .into_iter()
+            .filter(|(tier, from, to)| from <= to && (*tier != Tier::Cold || cold_enabled))
+            .map(|(tier, from, to)| PlanStep {
+                tier,
+                // Cold archives are pruned by sensor or read whole
+                access: match (&access, tier) {
+                    (Access::Cells(_), Tier::Cold) => Access::Scan,
+                    (access, _) => access.clone(),
+                },
+                from,
+                to,
+            })
+            .collect();
+
+        Self { aggregation, steps }
+    }
 }
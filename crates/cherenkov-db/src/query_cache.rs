@@ -0,0 +1,210 @@
+//! Query results cached by tag, in process memory in front of the shared cache
+//!
+//! Writes never delete cached entries. Every entry is stored under a key
+//! that includes the current version of each tag it depends on: the sensors
+//! a query reads, or the geohash cells its area covers. A write bumps the
+//! versions of its own tags, so later lookups compute different keys and the
+//! superseded entries age out on their TTL. Invalidating a reading is one
+//! pipelined write, however many entries it touches.
+//!
+//! Versions live in the shared cache, so a write in one process is seen by
+//! every other. Results are also kept briefly in a local cache (L1) under the
+//! same versioned keys, which saves fetching and decoding them again, and
+//! concurrent misses on one key are coalesced so only one reaches the tiers.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::memory::MemoryCache;
+use crate::query::TimeRangeQuery;
+use crate::store::Cache;
+use crate::{spatial, DatabaseError, RadiationReading};
+
+/// Geohash precision of cell tags (~40 km cells)
+///
+/// Writes bump their cell at every precision up to this one; areas tag their
+/// covering cells, truncated to it.
+pub const TAG_CELL_PRECISION: usize = 4;
+
+/// Tag of queries that read every sensor
+pub const ALL_READINGS_TAG: &str = "readings";
+
+/// Versions must outlive any entry keyed on them: a lost version reads as
+/// the initial one again and could revive an entry cached under it
+const TAG_TTL_SECS: u64 = 7 * 86_400;
+
+/// Version of a tag that was never invalidated
+const INITIAL_VERSION: &str = "0";
+
+pub fn sensor_tag(sensor_id: &Uuid) -> String {
+    format!("sensor:{}", sensor_id)
+}
+
+/// Tags a new or changed reading invalidates
+pub fn reading_tags(reading: &RadiationReading) -> Vec<String> {
+    let mut tags = vec![sensor_tag(&reading.sensor_id), ALL_READINGS_TAG.to_string()];
+    let coord = geohash::Coord { x: reading.longitude, y: reading.latitude };
+    if let Ok(cell) = geohash::encode(coord, TAG_CELL_PRECISION) {
+        tags.extend((1..=cell.len()).map(|len| format!("cell:{}", &cell[..len])));
+    }
+    tags
+}
+
+/// Tags a query's result depends on
+pub fn query_tags(query: &TimeRangeQuery) -> Vec<String> {
+    if !query.sensor_ids.is_empty() {
+        let sensors: BTreeSet<&Uuid> = query.sensor_ids.iter().collect();
+        return sensors.into_iter().map(sensor_tag).collect();
+    }
+
+    match &query.area {
+        Some(area) => spatial::box_cells(area)
+            .iter()
+            .map(|cell| format!("cell:{}", &cell[..cell.len().min(TAG_CELL_PRECISION)]))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        None => vec![ALL_READINGS_TAG.to_string()],
+    }
+}
+
+/// Cache of query results with tag invalidation
+pub struct QueryCache {
+    shared: Arc<dyn Cache>,
+    /// `None` when the shared cache is itself in process
+    local: Option<MemoryCache>,
+    local_ttl_secs: u64,
+    /// One lock per key being loaded, so concurrent misses wait for the first
+    inflight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl QueryCache {
+    pub fn new(shared: Arc<dyn Cache>) -> Self {
+        Self {
+            shared,
+            local: None,
+            local_ttl_secs: 0,
+            inflight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keep up to `max_entries` results in process for `ttl_secs`
+    pub fn with_local(mut self, max_entries: usize, ttl_secs: u64) -> Self {
+        self.local = (max_entries > 0 && ttl_secs > 0).then(|| MemoryCache::new(max_entries));
+        self.local_ttl_secs = ttl_secs;
+        self
+    }
+
+    /// Cached result for `key`, or `load`'s result, cached for `ttl_seconds`
+    /// until one of `tags` is invalidated
+    pub async fn get_or_load<T, F, Fut>(
+        &self,
+        key: &str,
+        tags: &[String],
+        ttl_seconds: u64,
+        load: F,
+    ) -> Result<T, DatabaseError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, DatabaseError>>,
+    {
+        let key = self.versioned_key(key, tags).await.map_err(cache_error)?;
+        if let Some(hit) = self.lookup(&key).await.map_err(cache_error)? {
+            return Ok(hit);
+        }
+
+        let flight = self
+            .inflight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let turn = flight.lock().await;
+
+        // Whoever loaded while this call waited has left the result behind
+        let result = match self.lookup(&key).await.map_err(cache_error)? {
+            Some(hit) => Ok(hit),
+            None => match load().await {
+                Ok(value) => self.store(&key, &value, ttl_seconds).await.map_err(cache_error).map(|_| value),
+                Err(e) => Err(e),
+            },
+        };
+
+        drop(turn);
+        let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+        if inflight.get(&key).is_some_and(|current| Arc::ptr_eq(current, &flight)) {
+            inflight.remove(&key);
+        }
+
+        result
+    }
+
+    /// Make every entry depending on any of `tags` unreachable
+    pub async fn invalidate(&self, tags: &[String]) -> Result<(), DatabaseError> {
+        let entries: Vec<(String, String)> = tags
+            .iter()
+            .map(|tag| (version_key(tag), Uuid::new_v4().simple().to_string()))
+            .collect();
+        self.shared.set_many(&entries, TAG_TTL_SECS).await.map_err(cache_error)
+    }
+
+    /// Loads coalesced right now, for monitoring
+    pub fn inflight(&self) -> usize {
+        self.inflight.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    async fn versioned_key(&self, key: &str, tags: &[String]) -> anyhow::Result<String> {
+        let version_keys: Vec<String> = tags.iter().map(|tag| version_key(tag)).collect();
+        let versions = self.shared.get_many(&version_keys).await?;
+
+        let mut hasher = DefaultHasher::new();
+        for version in &versions {
+            version.as_deref().unwrap_or(INITIAL_VERSION).hash(&mut hasher);
+        }
+        Ok(format!("query:{}@{:016x}", key, hasher.finish()))
+    }
+
+    async fn lookup<T: DeserializeOwned>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        if let Some(local) = &self.local {
+            if let Some(value) = local.get_raw(key).await? {
+                return Ok(Some(serde_json::from_str(&value)?));
+            }
+        }
+
+        match self.shared.get_raw(key).await? {
+            Some(value) => {
+                let decoded = serde_json::from_str(&value)?;
+                if let Some(local) = &self.local {
+                    local.set_raw(key, value, self.local_ttl_secs).await?;
+                }
+                Ok(Some(decoded))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn store<T: Serialize>(&self, key: &str, value: &T, ttl_seconds: u64) -> anyhow::Result<()> {
+        let value = serde_json::to_string(value)?;
+        if let Some(local) = &self.local {
+            local.set_raw(key, value.clone(), self.local_ttl_secs.min(ttl_seconds)).await?;
+        }
+        self.shared.set_raw(key, value, ttl_seconds).await
+    }
+}
+
+fn version_key(tag: &str) -> String {
+    format!("tag:{}", tag)
+}
+
+fn cache_error(e: anyhow::Error) -> DatabaseError {
+    DatabaseError::Redis(e.to_string())
+}
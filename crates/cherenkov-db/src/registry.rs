@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Lifecycle state of a registered sensor
+///
+/// `Online`, `Degraded` and `Offline` follow the delivery gap automatically;
+/// `Maintenance` and `Decommissioned` are set by operators and never swept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorState {
+    Online,
+    Degraded,
+    Offline,
+    Maintenance,
+    Decommissioned,
+}
+
+impl SensorState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SensorState::Online => "online",
+            SensorState::Degraded => "degraded",
+            SensorState::Offline => "offline",
+            SensorState::Maintenance => "maintenance",
+            SensorState::Decommissioned => "decommissioned",
+        }
+    }
+
+    /// Parse a stored status, treating the legacy `active` default as online
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "online" | "active" => Some(SensorState::Online),
+            "degraded" => Some(SensorState::Degraded),
+            "offline" => Some(SensorState::Offline),
+            "maintenance" => Some(SensorState::Maintenance),
+            "decommissioned" => Some(SensorState::Decommissioned),
+            _ => None,
+        }
+    }
+
+    /// Whether the state is derived from reporting gaps
+    pub fn is_automatic(&self) -> bool {
+        matches!(self, SensorState::Online | SensorState::Degraded | SensorState::Offline)
+    }
+}
+
+/// Where a detector is mounted, which shifts its expected background
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    Indoor,
+    Outdoor,
+}
+
+impl Placement {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Placement::Indoor => "indoor",
+            Placement::Outdoor => "outdoor",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "indoor" => Some(Placement::Indoor),
+            "outdoor" => Some(Placement::Outdoor),
+            _ => None,
+        }
+    }
+}
+
+/// Registered sensor with operator metadata and liveness state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sensor {
+    pub sensor_id: Uuid,
+    pub name: Option<String>,
+    pub source: String,
+    pub detector_model: Option<String>,
+    /// Multiplier from the detector's reported value to µSv/h, applied at
+    /// ingest in place of the source's default; 1.0 keeps the default
+    pub calibration_factor: f64,
+    /// Detector height above ground in metres
+    pub height_m: Option<f64>,
+    pub placement: Option<Placement>,
+    pub owner: Option<String>,
+    pub commissioned_at: Option<i64>,
+    pub decommissioned_at: Option<i64>,
+    pub status: SensorState,
+    pub status_changed_at: Option<i64>,
+    pub last_reading: Option<i64>,
+    /// Learned interval between deliveries of new readings, seconds
+    pub cadence_secs: Option<f64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+/// Request to register a sensor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewSensor {
+    /// Existing ID, e.g. the UUIDv5 a source already derives; generated when absent
+    #[serde(default)]
+    pub sensor_id: Option<Uuid>,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub source: String,
+    #[serde(default)]
+    pub detector_model: Option<String>,
+    #[serde(default)]
+    pub calibration_factor: Option<f64>,
+    #[serde(default)]
+    pub height_m: Option<f64>,
+    #[serde(default)]
+    pub placement: Option<Placement>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub commissioned_at: Option<i64>,
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+}
+
+/// Partial update of a sensor's metadata, unset fields are left unchanged
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SensorUpdate {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub detector_model: Option<String>,
+    #[serde(default)]
+    pub calibration_factor: Option<f64>,
+    #[serde(default)]
+    pub height_m: Option<f64>,
+    #[serde(default)]
+    pub placement: Option<Placement>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub commissioned_at: Option<i64>,
+    /// Only `Maintenance` or `Online` (to leave maintenance) may be set by hand
+    #[serde(default)]
+    pub status: Option<SensorState>,
+}
+
+/// Smoothing factor for the delivery interval EWMA
+pub const CADENCE_ALPHA: f64 = 0.2;
+
+/// Arrivals closer together than this are one delivery, e.g. one polled batch
+pub const MIN_DELIVERY_GAP_SECS: f64 = 60.0;
+
+/// Delivery gaps after which a sensor is considered degraded or offline
+///
+/// Sensors are judged against their own learned cadence: one delivering
+/// every 10 minutes is degraded after 30 silent minutes, an hourly one only
+/// after 3 hours. Until the cadence is learned the fixed gaps apply.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LivenessThresholds {
+    pub degraded_after_secs: i64,
+    pub offline_after_secs: i64,
+    /// Gap, in multiples of the learned cadence, after which a sensor is degraded
+    pub degraded_multiple: f64,
+    /// Gap, in multiples of the learned cadence, after which a sensor is offline
+    pub offline_multiple: f64,
+    /// Floor on the learned cadence so fast reporters are not flagged on jitter
+    pub min_cadence_secs: f64,
+    /// Intervals observed before a sensor's cadence is trusted
+    pub min_intervals: u32,
+}
+
+impl Default for LivenessThresholds {
+    fn default() -> Self {
+        Self {
+            degraded_after_secs: 3600,
+            offline_after_secs: 6 * 3600,
+            degraded_multiple: 3.0,
+            offline_multiple: 6.0,
+            min_cadence_secs: MIN_DELIVERY_GAP_SECS,
+            min_intervals: 3,
+        }
+    }
+}
+
+impl LivenessThresholds {
+    /// State for a sensor that last delivered `gap_secs` ago, if it ever did,
+    /// with the cadence learned over `intervals` deliveries
+    pub fn state_for_gap(&self, gap_secs: Option<i64>, cadence_secs: Option<f64>, intervals: u32) -> SensorState {
+        let Some(gap) = gap_secs else {
+            return SensorState::Offline;
+        };
+        let (degraded_after, offline_after) = match cadence_secs.filter(|_| intervals >= self.min_intervals) {
+            Some(cadence) => {
+                let expected = cadence.max(self.min_cadence_secs);
+                (expected * self.degraded_multiple, expected * self.offline_multiple)
+            }
+            None => (self.degraded_after_secs as f64, self.offline_after_secs as f64),
+        };
+
+        let gap = gap as f64;
+        if gap < degraded_after {
+            SensorState::Online
+        } else if gap < offline_after {
+            SensorState::Degraded
+        } else {
+            SensorState::Offline
+        }
+    }
+}
+
+/// Automatic status change applied by a liveness sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusTransition {
+    pub sensor_id: Uuid,
+    pub from: SensorState,
+    pub to: SensorState,
+    pub at: i64,
+}
@@ -0,0 +1,306 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{AggregationLevel, TimeSeriesPoint};
+
+/// Histogram resolution; adjacent bin edges differ by about 12%
+const BINS_PER_DECADE: f64 = 20.0;
+/// Bin holding zero and negative values, which have no logarithm
+const ZERO_BIN: i32 = i32::MIN;
+/// Geohash precision of the cells readings are rolled up into (~40 km)
+pub const CELL_PRECISION: usize = 4;
+
+/// Resolution of a continuously maintained rollup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollupLevel {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl RollupLevel {
+    pub const ALL: [RollupLevel; 3] = [RollupLevel::Minute, RollupLevel::Hour, RollupLevel::Day];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollupLevel::Minute => "1m",
+            RollupLevel::Hour => "1h",
+            RollupLevel::Day => "1d",
+        }
+    }
+
+    pub fn width_secs(&self) -> i64 {
+        match self {
+            RollupLevel::Minute => 60,
+            RollupLevel::Hour => 3600,
+            RollupLevel::Day => 86_400,
+        }
+    }
+
+    /// Start of the bucket containing `timestamp`
+    pub fn bucket_start(&self, timestamp: i64) -> i64 {
+        timestamp - timestamp.rem_euclid(self.width_secs())
+    }
+}
+
+/// What a rollup row aggregates over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollupScope {
+    Sensor,
+    /// Geohash cell at [`CELL_PRECISION`]
+    Cell,
+}
+
+impl RollupScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollupScope::Sensor => "sensor",
+            RollupScope::Cell => "cell",
+        }
+    }
+}
+
+/// Identifies one rollup row
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RollupKey {
+    pub level: RollupLevel,
+    pub scope: RollupScope,
+    pub scope_id: String,
+    pub bucket_start: i64,
+}
+
+/// Mergeable summary of the dose rates in one bucket
+///
+/// Count, sum, min and max are exact. Percentiles come from a log-binned
+/// histogram, which is what lets a bucket absorb late readings, be merged
+/// into coarser buckets, and have a corrected reading retracted again.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RollupStats {
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Readings per log bin, keyed by `bin_of`
+    pub histogram: BTreeMap<i32, u64>,
+}
+
+impl RollupStats {
+    pub fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.sum += value;
+        *self.histogram.entry(bin_of(value)).or_insert(0) += 1;
+    }
+
+    pub fn merge(&mut self, other: &RollupStats) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        for (bin, n) in &other.histogram {
+            *self.histogram.entry(*bin).or_insert(0) += n;
+        }
+    }
+
+    /// Remove a previously added value, e.g. when a reading is corrected
+    ///
+    /// If it was the extreme, min and max are tightened to the edges of the
+    /// outermost remaining histogram bins, since the exact values are gone.
+    pub fn retract(&mut self, value: f64) {
+        let bin = bin_of(value);
+        let Some(n) = self.histogram.get_mut(&bin) else {
+            return;
+        };
+        *n -= 1;
+        if *n == 0 {
+            self.histogram.remove(&bin);
+        }
+
+        self.count -= 1;
+        self.sum -= value;
+        if self.count == 0 {
+            *self = RollupStats::default();
+            return;
+        }
+
+        if let (Some(lowest), Some(highest)) = (self.histogram.keys().next(), self.histogram.keys().next_back()) {
+            if value <= self.min {
+                self.min = self.min.max(bin_bounds(*lowest).0);
+            }
+            if value >= self.max {
+                self.max = self.max.min(bin_bounds(*highest).1);
+            }
+        }
+    }
+
+    pub fn avg(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    /// Estimated `q` quantile, accurate to about half a bin width
+    pub fn quantile(&self, q: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bin, n) in &self.histogram {
+            seen += n;
+            if seen >= rank {
+                let (low, high) = bin_bounds(*bin);
+                let mid = if *bin == ZERO_BIN { 0.0 } else { (low * high).sqrt() };
+                return mid.clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    pub fn p95(&self) -> f64 {
+        self.quantile(0.95)
+    }
+
+    pub fn to_point(&self, bucket_start: i64) -> TimeSeriesPoint {
+        TimeSeriesPoint {
+            timestamp: DateTime::from_timestamp(bucket_start, 0).unwrap_or_else(Utc::now),
+            value: self.avg(),
+            count: self.count,
+            min: self.min,
+            max: self.max,
+            avg: self.avg(),
+            p95: Some(self.p95()),
+            sensor_id: None,
+        }
+    }
+}
+
+fn bin_of(value: f64) -> i32 {
+    if value <= 0.0 || !value.is_finite() {
+        return ZERO_BIN;
+    }
+    (value.log10() * BINS_PER_DECADE).floor() as i32
+}
+
+fn bin_bounds(bin: i32) -> (f64, f64) {
+    if bin == ZERO_BIN {
+        return (f64::MIN, 0.0);
+    }
+    (
+        10f64.powf(bin as f64 / BINS_PER_DECADE),
+        10f64.powf((bin + 1) as f64 / BINS_PER_DECADE),
+    )
+}
+
+/// Geohash cell a reading is rolled up into
+pub fn cell_of(latitude: f64, longitude: f64) -> Option<String> {
+    geohash::encode(geohash::Coord { x: longitude, y: latitude }, CELL_PRECISION).ok()
+}
+
+/// Rollup keys a reading contributes to, at every level and scope
+pub fn keys_for(sensor_id: &uuid::Uuid, timestamp: i64, latitude: f64, longitude: f64) -> Vec<RollupKey> {
+    let cell = cell_of(latitude, longitude);
+    let mut keys = Vec::with_capacity(RollupLevel::ALL.len() * 2);
+
+    for level in RollupLevel::ALL {
+        let bucket_start = level.bucket_start(timestamp);
+        keys.push(RollupKey {
+            level,
+            scope: RollupScope::Sensor,
+            scope_id: sensor_id.to_string(),
+            bucket_start,
+        });
+        if let Some(cell) = &cell {
+            keys.push(RollupKey {
+                level,
+                scope: RollupScope::Cell,
+                scope_id: cell.clone(),
+                bucket_start,
+            });
+        }
+    }
+
+    keys
+}
+
+/// Combine per-bucket stats into points `bucket_secs` wide, oldest first
+///
+/// Used to serve widths without their own rollup, such as five minutes
+/// from the one-minute rollup.
+pub fn regroup(rows: Vec<(i64, RollupStats)>, bucket_secs: i64) -> Vec<TimeSeriesPoint> {
+    let mut buckets: BTreeMap<i64, RollupStats> = BTreeMap::new();
+    for (start, stats) in rows {
+        buckets
+            .entry(start - start.rem_euclid(bucket_secs))
+            .or_default()
+            .merge(&stats);
+    }
+
+    buckets
+        .into_iter()
+        .map(|(start, stats)| stats.to_point(start))
+        .collect()
+}
+
+impl AggregationLevel {
+    /// Parse an API level name ("1m", "5m", "1h", "1d", "auto"), raw otherwise
+    pub fn parse(value: &str) -> AggregationLevel {
+        match value {
+            "1m" => AggregationLevel::OneMinute,
+            "5m" => AggregationLevel::FiveMinutes,
+            "1h" => AggregationLevel::OneHour,
+            "1d" => AggregationLevel::OneDay,
+            "auto" => AggregationLevel::Auto,
+            _ => AggregationLevel::Raw,
+        }
+    }
+
+    /// Level serving a query of this span with at most a few thousand points per sensor
+    pub fn for_span(span: Duration) -> AggregationLevel {
+        if span <= Duration::hours(6) {
+            AggregationLevel::Raw
+        } else if span <= Duration::days(2) {
+            AggregationLevel::OneMinute
+        } else if span <= Duration::days(90) {
+            AggregationLevel::OneHour
+        } else {
+            AggregationLevel::OneDay
+        }
+    }
+
+    /// Replace `Auto` with the level suited to `span`
+    pub fn resolve(self, span: Duration) -> AggregationLevel {
+        match self {
+            AggregationLevel::Auto => AggregationLevel::for_span(span),
+            level => level,
+        }
+    }
+
+    /// Rollup the level is served from and its point width, `None` for raw
+    pub fn rollup(&self) -> Option<(RollupLevel, i64)> {
+        match self {
+            AggregationLevel::Raw | AggregationLevel::Auto => None,
+            AggregationLevel::OneMinute => Some((RollupLevel::Minute, 60)),
+            AggregationLevel::FiveMinutes => Some((RollupLevel::Minute, 300)),
+            AggregationLevel::OneHour => Some((RollupLevel::Hour, 3600)),
+            AggregationLevel::OneDay => Some((RollupLevel::Day, 86_400)),
+        }
+    }
+}
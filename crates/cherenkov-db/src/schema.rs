@@ -1,11 +1,91 @@
-pub const CREATE_KEYSPACE: &str = "
-    CREATE KEYSPACE IF NOT EXISTS cherenkov
-    WITH REPLICATION = {
-        'class': 'NetworkTopologyStrategy',
-        'replication_factor': 3
-    }
+//! ScyllaDB schema, as versioned migrations
+//!
+//! Each migration is applied once, in version order, and recorded in the
+//! keyspace's `schema_version` table. CQL schema changes are not
+//! transactional, so every statement is idempotent: a migration interrupted
+//! half way is simply applied again on the next run. New schema goes into a
+//! new migration; applied migrations are never edited.
+
+use serde::{Deserialize, Serialize};
+
+/// One step of the schema history
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub statements: &'static [&'static str],
+}
+
+/// Every migration, oldest first
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "readings, events and sensors tables",
+        statements: &[CREATE_READINGS_TABLE, CREATE_EVENTS_TABLE, CREATE_SENSORS_TABLE],
+    },
+    Migration {
+        version: 2,
+        description: "readings by location and by time views",
+        statements: &[CREATE_MATERIALIZED_VIEW_BY_LOCATION, CREATE_MATERIALIZED_VIEW_BY_TIME],
+    },
+];
+
+/// Applied migrations, one row per version
+pub const CREATE_SCHEMA_VERSION_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS schema_version (
+        version BIGINT PRIMARY KEY,
+        description TEXT,
+        applied_at BIGINT
+    )
 ";
 
+/// Replication of the keyspace, fixed when migrations first create it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "class", rename_all = "snake_case")]
+pub enum Replication {
+    /// `SimpleStrategy`, for a single node or a single-rack test cluster
+    Simple { replication_factor: u32 },
+    /// `NetworkTopologyStrategy` with the same factor in every datacenter
+    NetworkTopology { replication_factor: u32 },
+}
+
+impl Default for Replication {
+    fn default() -> Self {
+        Replication::NetworkTopology { replication_factor: 3 }
+    }
+}
+
+impl Replication {
+    /// Strategy by name, `simple` or `network` (`SimpleStrategy` and
+    /// `NetworkTopologyStrategy` are accepted too)
+    pub fn parse(strategy: &str, replication_factor: u32) -> Option<Self> {
+        match strategy.to_ascii_lowercase().as_str() {
+            "simple" | "simplestrategy" => Some(Replication::Simple { replication_factor }),
+            "network" | "networktopology" | "networktopologystrategy" => {
+                Some(Replication::NetworkTopology { replication_factor })
+            }
+            _ => None,
+        }
+    }
+
+    /// Replication map as written in `CREATE KEYSPACE`
+    pub fn to_cql(&self) -> String {
+        let (class, replication_factor) = match self {
+            Replication::Simple { replication_factor } => ("SimpleStrategy", replication_factor),
+            Replication::NetworkTopology { replication_factor } => ("NetworkTopologyStrategy", replication_factor),
+        };
+        format!("{{'class': '{}', 'replication_factor': {}}}", class, replication_factor)
+    }
+}
+
+/// Statement creating `keyspace`, if it does not exist yet
+///
+/// An existing keyspace keeps its replication; change it with
+/// `ALTER KEYSPACE` followed by a repair.
+pub fn create_keyspace(keyspace: &str, replication: &Replication) -> String {
+    format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {}", keyspace, replication.to_cql())
+}
+
 pub const CREATE_READINGS_TABLE: &str = "
     CREATE TABLE IF NOT EXISTS radiation_readings (
         sensor_id UUID,
@@ -18,6 +98,8 @@ pub const CREATE_READINGS_TABLE: &str = "
         quality_flag TEXT,
         source TEXT,
         cell_id TEXT,
+        qc_reasons TEXT,
+        geohash_4 TEXT,
         PRIMARY KEY ((sensor_id, bucket), timestamp)
     ) WITH CLUSTERING ORDER BY (timestamp DESC)
     AND compaction = {'class': 'TimeWindowCompactionStrategy', 'compaction_window_unit': 'HOURS', 'compaction_window_size': 1}
@@ -48,11 +130,13 @@ pub const CREATE_SENSORS_TABLE: &str = "
 pub const CREATE_MATERIALIZED_VIEW_BY_LOCATION: &str = "
     CREATE MATERIALIZED VIEW IF NOT EXISTS readings_by_location AS
     SELECT * FROM radiation_readings
-    PRIMARY KEY ((cell_id), latitude, longitude, timestamp, sensor_id)
+    WHERE geohash_4 IS NOT NULL AND timestamp IS NOT NULL AND sensor_id IS NOT NULL AND bucket IS NOT NULL
+    PRIMARY KEY ((geohash_4), timestamp, sensor_id, bucket)
 ";
 
 pub const CREATE_MATERIALIZED_VIEW_BY_TIME: &str = "
     CREATE MATERIALIZED VIEW IF NOT EXISTS readings_by_time AS
     SELECT * FROM radiation_readings
+    WHERE bucket IS NOT NULL AND timestamp IS NOT NULL AND sensor_id IS NOT NULL
     PRIMARY KEY ((bucket), timestamp, sensor_id)
 ";
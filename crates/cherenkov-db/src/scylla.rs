@@ -1,3 +1,4 @@
+use futures::StreamExt;
 use scylla::{Session, SessionBuilder, ExecutionProfile};
 use scylla::frame::types::Consistency;
 use std::sync::Arc;
@@ -5,12 +6,25 @@ use std::time::Duration;
 use tracing::{info, warn};
 use tokio::sync::Semaphore;
 
+use crate::schema::{self, Replication, MIGRATIONS};
+use crate::{MigrationReport, PendingMigration};
+
+/// Column order expected by `parse_row_to_reading`
+const READING_COLUMNS: &str =
+    "sensor_id, bucket, timestamp, latitude, longitude, dose_rate, uncertainty, quality_flag, source, cell_id, qc_reasons";
+
+/// Geohash precision of the `readings_by_location` partitions (~40 km cells)
+pub const LOCATION_PRECISION: usize = 4;
+
+const GEOHASH_BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
 pub struct ScyllaStorage {
     session: Arc<Session>,
     write_semaphore: Arc<Semaphore>,
     read_semaphore: Arc<Semaphore>,
 }
 
+#[derive(Debug, Clone)]
 pub struct ScyllaConfig {
     pub nodes: Vec<String>,
     pub keyspace: String,
@@ -20,6 +34,8 @@ pub struct ScyllaConfig {
     pub max_concurrent_writes: usize,
     pub max_concurrent_reads: usize,
     pub connection_timeout: Duration,
+    /// Used only when migrations create the keyspace
+    pub replication: Replication,
 }
 
 impl Default for ScyllaConfig {
@@ -33,12 +49,29 @@ impl Default for ScyllaConfig {
             max_concurrent_writes: 1000,
             max_concurrent_reads: 500,
             connection_timeout: Duration::from_secs(5),
+            replication: Replication::default(),
         }
     }
 }
 
 impl ScyllaStorage {
     pub async fn new(config: ScyllaConfig) -> anyhow::Result<Self> {
+        let session = Self::connect(&config).await?;
+
+        session.query(format!("USE {}", config.keyspace), &[]).await.map_err(|e| {
+            anyhow::anyhow!("Keyspace {} is not usable, have migrations run? {}", config.keyspace, e)
+        })?;
+        
+        info!("Connected to ScyllaDB cluster with {} nodes", config.nodes.len());
+        
+        Ok(Self {
+            session: Arc::new(session),
+            write_semaphore: Arc::new(Semaphore::new(config.max_concurrent_writes)),
+            read_semaphore: Arc::new(Semaphore::new(config.max_concurrent_reads)),
+        })
+    }
+
+    async fn connect(config: &ScyllaConfig) -> anyhow::Result<Session> {
         let execution_profile = ExecutionProfile::builder()
             .consistency(config.write_consistency)
             .request_timeout(Some(config.connection_timeout))
@@ -50,15 +83,89 @@ impl ScyllaStorage {
             .build()
             .await?;
 
-        session.query(format!("USE {}", config.keyspace), &[]).await?;
-        
-        info!("Connected to ScyllaDB cluster with {} nodes", config.nodes.len());
-        
-        Ok(Self {
-            session: Arc::new(session),
-            write_semaphore: Arc::new(Semaphore::new(config.max_concurrent_writes)),
-            read_semaphore: Arc::new(Semaphore::new(config.max_concurrent_reads)),
-        })
+        Ok(session)
+    }
+
+    /// Create the keyspace if needed and apply pending schema migrations
+    ///
+    /// With `dry_run` nothing is written; the report lists what would run.
+    pub async fn migrate(config: &ScyllaConfig, dry_run: bool) -> anyhow::Result<MigrationReport> {
+        let session = Self::connect(config).await?;
+        let keyspace = config.keyspace.as_str();
+
+        let keyspace_exists = session
+            .query("SELECT keyspace_name FROM system_schema.keyspaces WHERE keyspace_name = ?", (keyspace,))
+            .await?
+            .rows_typed_or_empty::<(String,)>()
+            .next()
+            .is_some();
+        let table_exists = keyspace_exists
+            && session
+                .query(
+                    "SELECT table_name FROM system_schema.tables WHERE keyspace_name = ? AND table_name = 'schema_version'",
+                    (keyspace,),
+                )
+                .await?
+                .rows_typed_or_empty::<(String,)>()
+                .next()
+                .is_some();
+
+        let mut applied = Vec::new();
+        if table_exists {
+            let result = session.query(format!("SELECT version FROM {}.schema_version", keyspace), &[]).await?;
+            for row in result.rows_typed_or_empty::<(i64,)>() {
+                applied.push(row?.0);
+            }
+        }
+
+        let mut setup = Vec::new();
+        if !keyspace_exists {
+            setup.push(schema::create_keyspace(keyspace, &config.replication));
+        }
+        if !table_exists {
+            setup.push(schema::CREATE_SCHEMA_VERSION_TABLE.trim().to_string());
+        }
+
+        let report = MigrationReport {
+            backend: format!("scylla:{}", keyspace),
+            current_version: applied.iter().copied().max(),
+            setup,
+            pending: MIGRATIONS
+                .iter()
+                .filter(|m| !applied.contains(&m.version))
+                .map(|m| PendingMigration {
+                    version: m.version,
+                    description: m.description.to_string(),
+                    statements: m.statements.iter().map(|s| s.trim().to_string()).collect(),
+                })
+                .collect(),
+            dry_run,
+        };
+        if dry_run {
+            return Ok(report);
+        }
+
+        if let Some(create_keyspace) = report.setup.iter().find(|s| s.starts_with("CREATE KEYSPACE")) {
+            session.query(create_keyspace.as_str(), &[]).await?;
+            info!("Created keyspace {} with replication {}", keyspace, config.replication.to_cql());
+        }
+        session.query(format!("USE {}", keyspace), &[]).await?;
+        session.query(schema::CREATE_SCHEMA_VERSION_TABLE, &[]).await?;
+
+        let record = session
+            .prepare("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+            .await?;
+        for migration in &report.pending {
+            for statement in &migration.statements {
+                session.query(statement.as_str(), &[]).await?;
+            }
+            session
+                .execute(&record, (migration.version, &migration.description, chrono::Utc::now().timestamp()))
+                .await?;
+            info!("Applied ScyllaDB migration {}: {}", migration.version, migration.description);
+        }
+
+        Ok(report)
     }
     
     pub async fn write_reading(&self, reading: &super::RadiationReading) -> anyhow::Result<()> {
@@ -66,8 +173,8 @@ impl ScyllaStorage {
         
         let query = "
             INSERT INTO radiation_readings 
-            (sensor_id, bucket, timestamp, latitude, longitude, dose_rate, uncertainty, quality_flag, source, cell_id)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (sensor_id, bucket, timestamp, latitude, longitude, dose_rate, uncertainty, quality_flag, source, cell_id, qc_reasons, geohash_4)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ";
         
         let prepared = self.session.prepare(query).await?;
@@ -83,6 +190,8 @@ impl ScyllaStorage {
             format!("{:?}", reading.quality_flag),
             &reading.source,
             &reading.cell_id,
+            serde_json::to_string(&reading.qc_reasons)?,
+            location_geohash(reading),
         )).await?;
         
         Ok(())
@@ -97,8 +206,8 @@ impl ScyllaStorage {
         
         let query = "
             INSERT INTO radiation_readings 
-            (sensor_id, bucket, timestamp, latitude, longitude, dose_rate, uncertainty, quality_flag, source, cell_id)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (sensor_id, bucket, timestamp, latitude, longitude, dose_rate, uncertainty, quality_flag, source, cell_id, qc_reasons, geohash_4)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ";
         
         let prepared = self.session.prepare(query).await?;
@@ -109,7 +218,7 @@ impl ScyllaStorage {
             batch.append_statement(prepared.clone());
         }
         
-        let values: Vec<_> = readings.iter().map(|r| (
+        let values = readings.iter().map(|r| Ok((
             r.sensor_id,
             r.bucket,
             r.timestamp,
@@ -120,7 +229,9 @@ impl ScyllaStorage {
             format!("{:?}", r.quality_flag),
             &r.source,
             &r.cell_id,
-        )).collect();
+            serde_json::to_string(&r.qc_reasons)?,
+            location_geohash(r),
+        ))).collect::<anyhow::Result<Vec<_>>>()?;
         
         self.session.batch(&batch, &values).await?;
         
@@ -135,12 +246,12 @@ impl ScyllaStorage {
     ) -> anyhow::Result<Vec<super::RadiationReading>> {
         let _permit = self.read_semaphore.acquire().await?;
         
-        let query = "
-            SELECT * FROM radiation_readings 
+        let query = format!("
+            SELECT {} FROM radiation_readings 
             WHERE sensor_id = ? AND bucket IN ? AND timestamp >= ? AND timestamp <= ?
-        ";
+        ", READING_COLUMNS);
         
-        let buckets: Vec<i64> = ((from / 3600)..=(to / 3600)).collect();
+        let buckets: Vec<i64> = (super::RadiationReading::bucket_for(from)..=super::RadiationReading::bucket_for(to)).collect();
         
         let prepared = self.session.prepare(query).await?;
         let result = self.session.execute(&prepared, (sensor_id, &buckets, from, to)).await?;
@@ -155,22 +266,66 @@ impl ScyllaStorage {
         Ok(readings)
     }
     
+    /// The first `limit` readings of a sensor within `[from, to]`, walking
+    /// its bucket partitions in `order` and stopping once enough are read
+    pub async fn query_sensor_page(
+        &self,
+        sensor_id: uuid::Uuid,
+        from: i64,
+        to: i64,
+        order: crate::query::SortOrder,
+        limit: usize,
+    ) -> anyhow::Result<Vec<super::RadiationReading>> {
+        let _permit = self.read_semaphore.acquire().await?;
+
+        let direction = match order {
+            crate::query::SortOrder::Ascending => "ASC",
+            crate::query::SortOrder::Descending => "DESC",
+        };
+        let query = format!("
+            SELECT {} FROM radiation_readings
+            WHERE sensor_id = ? AND bucket = ? AND timestamp >= ? AND timestamp <= ?
+            ORDER BY timestamp {} LIMIT ?
+        ", READING_COLUMNS, direction);
+        let prepared = self.session.prepare(query).await?;
+
+        let buckets = super::RadiationReading::bucket_for(from)..=super::RadiationReading::bucket_for(to);
+        let buckets: Vec<i64> = match order {
+            crate::query::SortOrder::Ascending => buckets.collect(),
+            crate::query::SortOrder::Descending => buckets.rev().collect(),
+        };
+
+        let mut readings = Vec::new();
+        for bucket in buckets {
+            if readings.len() >= limit {
+                break;
+            }
+            let remaining = (limit - readings.len()).min(i32::MAX as usize) as i32;
+            let result = self.session.execute(&prepared, (sensor_id, bucket, from, to, remaining)).await?;
+            for row in result.rows()? {
+                readings.push(parse_row_to_reading(row)?);
+            }
+        }
+
+        Ok(readings)
+    }
+
+    /// Readings in one `readings_by_location` partition
     pub async fn query_by_location(
         &self,
-        cell_id: &str,
-        geohash_prefix: &str,
+        geohash_4: &str,
         from: i64,
         to: i64,
     ) -> anyhow::Result<Vec<super::RadiationReading>> {
         let _permit = self.read_semaphore.acquire().await?;
         
-        let query = "
-            SELECT * FROM readings_by_location 
-            WHERE cell_id = ? AND geohash_4 = ? AND timestamp >= ? AND timestamp <= ?
-        ";
+        let query = format!("
+            SELECT {} FROM readings_by_location 
+            WHERE geohash_4 = ? AND timestamp >= ? AND timestamp <= ?
+        ", READING_COLUMNS);
         
         let prepared = self.session.prepare(query).await?;
-        let result = self.session.execute(&prepared, (cell_id, geohash_prefix, from, to)).await?;
+        let result = self.session.execute(&prepared, (geohash_4, from, to)).await?;
         
         let mut readings = Vec::new();
         let rows = result.rows()?;
@@ -188,11 +343,11 @@ impl ScyllaStorage {
     ) -> anyhow::Result<Option<super::RadiationReading>> {
         let _permit = self.read_semaphore.acquire().await?;
         
-        let query = "
-            SELECT * FROM radiation_readings 
+        let query = format!("
+            SELECT {} FROM radiation_readings 
             WHERE sensor_id = ? 
             LIMIT 1
-        ";
+        ", READING_COLUMNS);
         
         let prepared = self.session.prepare(query).await?;
         let result = self.session.execute(&prepared, (sensor_id,)).await?;
@@ -204,6 +359,55 @@ impl ScyllaStorage {
         Ok(None)
     }
     
+    /// All readings in one hour bucket, across sensors, paging through the partition
+    pub async fn query_bucket(&self, bucket: i64) -> anyhow::Result<Vec<super::RadiationReading>> {
+        let _permit = self.read_semaphore.acquire().await?;
+
+        let query = format!("
+            SELECT {} FROM readings_by_time
+            WHERE bucket = ?
+        ", READING_COLUMNS);
+
+        let prepared = self.session.prepare(query).await?;
+        let mut rows = self.session.execute_iter(prepared, (bucket,)).await?;
+
+        let mut readings = Vec::new();
+        while let Some(row) = rows.next().await {
+            readings.push(parse_row_to_reading(row?)?);
+        }
+
+        Ok(readings)
+    }
+
+    /// Delete readings by primary key; the materialized views follow
+    pub async fn delete_readings(&self, readings: &[super::RadiationReading]) -> anyhow::Result<()> {
+        let _permit = self.write_semaphore.acquire().await?;
+
+        if readings.is_empty() {
+            return Ok(());
+        }
+
+        let query = "
+            DELETE FROM radiation_readings
+            WHERE sensor_id = ? AND bucket = ? AND timestamp = ?
+        ";
+
+        let prepared = self.session.prepare(query).await?;
+
+        let mut batch = scylla::batch::Batch::new(scylla::batch::BatchType::Unlogged);
+        for _reading in readings {
+            batch.append_statement(prepared.clone());
+        }
+
+        let values: Vec<_> = readings.iter()
+            .map(|r| (r.sensor_id, r.bucket, r.timestamp))
+            .collect();
+
+        self.session.batch(&batch, &values).await?;
+
+        Ok(())
+    }
+
     pub fn get_session(&self) -> Arc<Session> {
         self.session.clone()
     }
@@ -219,6 +423,91 @@ impl ScyllaStorage {
     }
 }
 
+#[async_trait::async_trait]
+impl crate::store::ReadingStore for ScyllaStorage {
+    async fn write_reading(&self, reading: &super::RadiationReading) -> anyhow::Result<()> {
+        ScyllaStorage::write_reading(self, reading).await
+    }
+
+    async fn write_batch(&self, readings: &[super::RadiationReading]) -> anyhow::Result<()> {
+        ScyllaStorage::write_batch(self, readings).await
+    }
+
+    async fn query_by_time_range(&self, sensor_id: uuid::Uuid, from: i64, to: i64) -> anyhow::Result<Vec<super::RadiationReading>> {
+        ScyllaStorage::query_by_time_range(self, sensor_id, from, to).await
+    }
+
+    async fn query_sensor_page(
+        &self,
+        sensor_id: uuid::Uuid,
+        from: i64,
+        to: i64,
+        order: crate::query::SortOrder,
+        limit: usize,
+    ) -> anyhow::Result<Vec<super::RadiationReading>> {
+        ScyllaStorage::query_sensor_page(self, sensor_id, from, to, order, limit).await
+    }
+
+    /// Finer cells read their enclosing partition and filter; coarser cells
+    /// fan out over every partition inside them
+    async fn query_by_geohash(&self, geohash: &str, from: i64, to: i64) -> anyhow::Result<Vec<super::RadiationReading>> {
+        let bbox = geohash::decode_bbox(geohash)
+            .map_err(|e| anyhow::anyhow!("Geohash error: {:?}", e))?;
+        let (min, max) = (bbox.min(), bbox.max());
+
+        let partitions = location_partitions(geohash);
+        let mut results = futures::stream::iter(partitions)
+            .map(|partition| async move { self.query_by_location(&partition, from, to).await })
+            .buffer_unordered(8);
+
+        let mut readings = Vec::new();
+        while let Some(partition) = results.next().await {
+            readings.extend(partition?.into_iter().filter(|r| {
+                r.longitude >= min.x && r.longitude <= max.x && r.latitude >= min.y && r.latitude <= max.y
+            }));
+        }
+        Ok(readings)
+    }
+
+    async fn latest_reading(&self, sensor_id: uuid::Uuid) -> anyhow::Result<Option<super::RadiationReading>> {
+        self.get_sensor_latest(sensor_id).await
+    }
+
+    async fn query_bucket(&self, bucket: i64) -> anyhow::Result<Vec<super::RadiationReading>> {
+        ScyllaStorage::query_bucket(self, bucket).await
+    }
+
+    async fn delete_readings(&self, readings: &[super::RadiationReading]) -> anyhow::Result<()> {
+        ScyllaStorage::delete_readings(self, readings).await
+    }
+
+    async fn health_check(&self) -> bool {
+        ScyllaStorage::health_check(self).await
+    }
+}
+
+/// Partition key of a reading in `readings_by_location`
+fn location_geohash(reading: &super::RadiationReading) -> Option<String> {
+    let coord = geohash::Coord { x: reading.longitude, y: reading.latitude };
+    geohash::encode(coord, LOCATION_PRECISION).ok()
+}
+
+/// `readings_by_location` partitions covering a geohash cell of any precision
+fn location_partitions(geohash: &str) -> Vec<String> {
+    if geohash.len() >= LOCATION_PRECISION {
+        return vec![geohash[..LOCATION_PRECISION].to_string()];
+    }
+
+    let mut partitions = vec![geohash.to_string()];
+    for _ in geohash.len()..LOCATION_PRECISION {
+        partitions = partitions
+            .iter()
+            .flat_map(|prefix| GEOHASH_BASE32.iter().map(move |c| format!("{}{}", prefix, *c as char)))
+            .collect();
+    }
+    partitions
+}
+
 /// Helper function to parse a Scylla row into RadiationReading
 fn parse_row_to_reading(row: scylla::frame::response::result::Row) -> anyhow::Result<super::RadiationReading> {
     use scylla::frame::response::result::CqlValue;
@@ -283,6 +572,11 @@ fn parse_row_to_reading(row: scylla::frame::response::result::Row) -> anyhow::Re
         _ => String::new(),
     };
     
+    let qc_reasons = match columns.get(10) {
+        Some(Some(CqlValue::Text(json))) => serde_json::from_str(json).unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    
     Ok(super::RadiationReading {
         sensor_id,
         bucket,
@@ -294,5 +588,9 @@ fn parse_row_to_reading(row: scylla::frame::response::result::Row) -> anyhow::Re
         quality_flag,
         source,
         cell_id,
+        track_id: None,
+        altitude_m: None,
+        qc_reasons,
+        provenance: None,
     })
 }
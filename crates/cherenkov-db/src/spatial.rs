@@ -0,0 +1,308 @@
+use rstar::{PointDistance, RTree, RTreeObject, AABB};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+use crate::query::SpatialQuery;
+use crate::GeoPoint;
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+const KM_PER_DEGREE: f64 = 111.0;
+/// Finest geohash precision used to cover a radius (~150 m cells)
+pub const MAX_COVER_PRECISION: usize = 7;
+
+/// Height and width in degrees of a geohash cell at `precision`
+pub fn cell_size_deg(precision: usize) -> (f64, f64) {
+    let bits = 5 * precision as i32;
+    let lat_bits = bits / 2;
+    let lon_bits = bits - lat_bits;
+    (180.0 / 2f64.powi(lat_bits), 360.0 / 2f64.powi(lon_bits))
+}
+
+/// Geohash cells that together contain every point within `radius_km` of `center`
+///
+/// Uses the finest precision whose cells are at least as large as the radius,
+/// so the result is the centre cell plus whichever of its neighbours the
+/// radius reaches, across the antimeridian and over the poles included.
+pub fn covering_cells(center: &GeoPoint, radius_km: f64) -> Vec<String> {
+    let radius_km = radius_km.max(0.0);
+    let lat_delta = radius_km / KM_PER_DEGREE;
+    let min_lat = (center.latitude - lat_delta).max(-90.0);
+    let max_lat = (center.latitude + lat_delta).min(90.0);
+
+    // Longitude degrees shrink towards the poles, so size for the poleward edge
+    let poleward = min_lat.abs().max(max_lat.abs());
+    let lon_delta = if poleward >= 90.0 {
+        180.0
+    } else {
+        (radius_km / (KM_PER_DEGREE * poleward.to_radians().cos())).min(180.0)
+    };
+
+    cover_box(min_lat, max_lat, center.longitude - lon_delta, center.longitude + lon_delta)
+}
+
+/// Geohash cells that together contain a bounding box
+pub fn box_cells(area: &SpatialQuery) -> Vec<String> {
+    let east = if area.min_lon <= area.max_lon { area.max_lon } else { area.max_lon + 360.0 };
+    cover_box(area.min_lat.max(-90.0), area.max_lat.min(90.0), area.min_lon, east)
+}
+
+/// Cover a box at the finest precision whose cells span at least half of it,
+/// which keeps the result to a handful of cells; `east` may exceed 180°
+fn cover_box(min_lat: f64, max_lat: f64, west: f64, east: f64) -> Vec<String> {
+    let (half_height, half_width) = ((max_lat - min_lat) / 2.0, (east - west) / 2.0);
+    let precision = (1..=MAX_COVER_PRECISION)
+        .rev()
+        .find(|p| {
+            let (height, width) = cell_size_deg(*p);
+            height >= half_height && width >= half_width
+        })
+        .unwrap_or(1);
+    let (height, width) = cell_size_deg(precision);
+
+    // Steps no larger than a cell, so no cell the box touches is skipped
+    let mut cells = BTreeSet::new();
+    let mut lat = min_lat;
+    loop {
+        let mut lon = west;
+        loop {
+            let coord = geohash::Coord { x: wrap_longitude(lon), y: lat };
+            if let Ok(cell) = geohash::encode(coord, precision) {
+                cells.insert(cell);
+            }
+            if lon >= east {
+                break;
+            }
+            lon = (lon + width).min(east);
+        }
+        if lat >= max_lat {
+            break;
+        }
+        lat = (lat + height).min(max_lat);
+    }
+
+    cells.into_iter().collect()
+}
+
+fn wrap_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Where a sensor was last seen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorLocation {
+    pub sensor_id: Uuid,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Time of the reading the location comes from
+    pub timestamp: i64,
+}
+
+/// A sensor returned by a nearest-neighbour query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearbySensor {
+    pub location: SensorLocation,
+    pub distance_km: f64,
+}
+
+/// Sensor position on the unit sphere, where straight-line distance orders
+/// points the same way great-circle distance does
+#[derive(Debug, Clone, PartialEq)]
+struct IndexedSensor {
+    location: SensorLocation,
+    position: [f64; 3],
+}
+
+impl IndexedSensor {
+    fn new(location: SensorLocation) -> Self {
+        let position = unit_vector(location.latitude, location.longitude);
+        Self { location, position }
+    }
+}
+
+impl RTreeObject for IndexedSensor {
+    type Envelope = AABB<[f64; 3]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_point(self.position)
+    }
+}
+
+impl PointDistance for IndexedSensor {
+    fn distance_2(&self, point: &[f64; 3]) -> f64 {
+        self.position.iter().zip(point).map(|(a, b)| (a - b).powi(2)).sum()
+    }
+}
+
+fn unit_vector(latitude: f64, longitude: f64) -> [f64; 3] {
+    let (lat, lon) = (latitude.to_radians(), longitude.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+/// In-memory R-tree of sensor locations for nearest-k and bounding-box lookups
+#[derive(Default)]
+pub struct SensorIndex {
+    inner: RwLock<IndexState>,
+}
+
+#[derive(Default)]
+struct IndexState {
+    tree: RTree<IndexedSensor>,
+    by_id: HashMap<Uuid, IndexedSensor>,
+}
+
+impl SensorIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bulk-load an index, keeping the newest location per sensor
+    pub fn from_locations(locations: impl IntoIterator<Item = SensorLocation>) -> Self {
+        let mut by_id: HashMap<Uuid, IndexedSensor> = HashMap::new();
+        for location in locations {
+            match by_id.get(&location.sensor_id) {
+                Some(existing) if existing.location.timestamp > location.timestamp => {}
+                _ => {
+                    by_id.insert(location.sensor_id, IndexedSensor::new(location));
+                }
+            }
+        }
+
+        let tree = RTree::bulk_load(by_id.values().cloned().collect());
+        Self {
+            inner: RwLock::new(IndexState { tree, by_id }),
+        }
+    }
+
+    /// Record a sensor's location, ignoring readings older than the one indexed
+    pub fn upsert(&self, location: SensorLocation) {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(existing) = state.by_id.get(&location.sensor_id).cloned() {
+            if existing.location.timestamp > location.timestamp {
+                return;
+            }
+            state.tree.remove(&existing);
+        }
+
+        let sensor = IndexedSensor::new(location);
+        state.by_id.insert(sensor.location.sensor_id, sensor.clone());
+        state.tree.insert(sensor);
+    }
+
+    pub fn remove(&self, sensor_id: &Uuid) {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = state.by_id.remove(sensor_id) {
+            state.tree.remove(&existing);
+        }
+    }
+
+    pub fn get(&self, sensor_id: &Uuid) -> Option<SensorLocation> {
+        let state = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        state.by_id.get(sensor_id).map(|s| s.location.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `k` sensors closest to `center`, nearest first
+    pub fn nearest(&self, center: &GeoPoint, k: usize) -> Vec<NearbySensor> {
+        let point = unit_vector(center.latitude, center.longitude);
+        let state = self.inner.read().unwrap_or_else(|e| e.into_inner());
+
+        state
+            .tree
+            .nearest_neighbor_iter(&point)
+            .take(k)
+            .map(|sensor| {
+                // Chord length on the unit sphere to arc length
+                let chord = sensor.distance_2(&point).sqrt();
+                NearbySensor {
+                    location: sensor.location.clone(),
+                    distance_km: 2.0 * EARTH_RADIUS_KM * (chord / 2.0).min(1.0).asin(),
+                }
+            })
+            .collect()
+    }
+
+    /// Sensors inside a latitude/longitude box
+    ///
+    /// A box with `min_lon > max_lon` wraps across the antimeridian.
+    /// `active_only` is not applied here, since the index does not track
+    /// registry status.
+    pub fn within(&self, query: &SpatialQuery) -> Vec<SensorLocation> {
+        let lon_ranges = if query.min_lon <= query.max_lon {
+            vec![(query.min_lon, query.max_lon)]
+        } else {
+            vec![(query.min_lon, 180.0), (-180.0, query.max_lon)]
+        };
+
+        let state = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        let mut found: Vec<SensorLocation> = Vec::new();
+
+        for (min_lon, max_lon) in lon_ranges {
+            let envelope = box_envelope(query.min_lat, query.max_lat, min_lon, max_lon);
+            found.extend(
+                state
+                    .tree
+                    .locate_in_envelope(&envelope)
+                    .map(|s| &s.location)
+                    .filter(|l| query.contains(l.latitude, l.longitude))
+                    .cloned(),
+            );
+        }
+
+        found.sort_by_key(|l| l.sensor_id);
+        found.dedup_by_key(|l| l.sensor_id);
+        found
+    }
+}
+
+/// Axis-aligned box on the unit sphere containing a latitude/longitude box
+fn box_envelope(min_lat: f64, max_lat: f64, min_lon: f64, max_lon: f64) -> AABB<[f64; 3]> {
+    const SLACK: f64 = 1e-9;
+    let (a, b) = (min_lat.to_radians(), max_lat.to_radians());
+    let (c, d) = (min_lon.to_radians(), max_lon.to_radians());
+
+    let cos_lat = (
+        a.cos().min(b.cos()),
+        if a <= 0.0 && b >= 0.0 { 1.0 } else { a.cos().max(b.cos()) },
+    );
+    let cos_lon = extremes(c, d, f64::cos, &[(0.0, 1.0), (-PI, -1.0), (PI, -1.0)]);
+    let sin_lon = extremes(c, d, f64::sin, &[(FRAC_PI_2, 1.0), (-FRAC_PI_2, -1.0)]);
+
+    let (x_min, x_max) = product(cos_lat, cos_lon);
+    let (y_min, y_max) = product(cos_lat, sin_lon);
+
+    AABB::from_corners(
+        [x_min - SLACK, y_min - SLACK, a.sin() - SLACK],
+        [x_max + SLACK, y_max + SLACK, b.sin() + SLACK],
+    )
+}
+
+/// Range of `f` over `[from, to]`, given the interior points where it peaks
+fn extremes(from: f64, to: f64, f: fn(f64) -> f64, peaks: &[(f64, f64)]) -> (f64, f64) {
+    let (mut low, mut high) = (f(from).min(f(to)), f(from).max(f(to)));
+    for (at, value) in peaks {
+        if *at >= from && *at <= to {
+            low = low.min(*value);
+            high = high.max(*value);
+        }
+    }
+    (low, high)
+}
+
+fn product(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let corners = [a.0 * b.0, a.0 * b.1, a.1 * b.0, a.1 * b.1];
+    (
+        corners.iter().cloned().fold(f64::INFINITY, f64::min),
+        corners.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+    )
+}
@@ -1,10 +1,26 @@
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite, Row, QueryBuilder};
+use sqlx::{sqlite::{SqliteConnectOptions, SqlitePoolOptions}, Pool, Sqlite, Row, QueryBuilder};
 use chrono::{DateTime, Utc, NaiveDateTime};
-use std::path::Path;
-use tracing::{info, error, instrument};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::sync::Mutex;
+use tracing::{info, error, warn, instrument};
 use uuid::Uuid;
 
-use crate::{RadiationReading, QualityFlag, TimeSeriesPoint, AggregationLevel, GeoPoint, SensorReading, TimeRange};
+use crate::{RadiationReading, QualityFlag, TimeSeriesPoint, AggregationLevel, EventType, MigrationReport, PendingMigration};
+use crate::projection::{Checkpoint, StoredEvent};
+use crate::query::{AnomalyQuery, EventQuery, SortOrder};
+use crate::store::{EventStore, ReadingStore};
+use crate::rollup::{self, RollupKey, RollupLevel, RollupScope, RollupStats};
+use crate::track::{Track, TrackPoint, TrackSummary};
+use crate::lineage::{QcDecision, ReadingLineage, ReadingProvenance, SourceFetch};
+use crate::weather::WeatherObservation;
+use crate::registry::{CADENCE_ALPHA, MIN_DELIVERY_GAP_SECS, LivenessThresholds, NewSensor, Placement, Sensor, SensorState, SensorUpdate, StatusTransition};
+
+/// Columns selected for `row_to_sensor`, in order
+const SENSOR_COLUMNS: &str = "sensor_id, name, source, detector_model, calibration_factor, height_m, \
+    placement, owner, commissioned_at, decommissioned_at, status, status_changed_at, last_reading, \
+    latitude, longitude, cadence_secs";
 
 #[derive(sqlx::FromRow)]
 struct AggregatedRow {
@@ -18,20 +34,46 @@ struct AggregatedRow {
 #[derive(Debug)]
 pub struct SqliteStorage {
     pool: Pool<Sqlite>,
+    /// Database file, `None` in memory
+    path: Option<PathBuf>,
+    /// Serializes rollup read-modify-write cycles
+    rollup_lock: Mutex<()>,
 }
 
 impl SqliteStorage {
     pub async fn new(database_path: &str) -> anyhow::Result<Self> {
+        Self::connect(database_path, true).await
+    }
+
+    /// Open a database whose WAL is archived by `WarmBackup`
+    ///
+    /// Automatic checkpoints are off, so no frame is moved into the database
+    /// file, and then dropped from the WAL, before it is archived.
+    pub async fn with_wal_archiving(database_path: &str) -> anyhow::Result<Self> {
+        Self::connect(database_path, false).await
+    }
+
+    async fn connect(database_path: &str, auto_checkpoint: bool) -> anyhow::Result<Self> {
+        if database_path == ":memory:" {
+            return Self::in_memory().await;
+        }
+
         // Ensure parent directory exists
         if let Some(parent) = Path::new(database_path).parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
 
+        let mut options = SqliteConnectOptions::from_str(&format!("sqlite:{}", database_path))?
+            .create_if_missing(true);
+        if !auto_checkpoint {
+            options = options.pragma("wal_autocheckpoint", "0");
+        }
+
         let pool = SqlitePoolOptions::new()
             .max_connections(10)
             .min_connections(2)
             .acquire_timeout(std::time::Duration::from_secs(30))
-            .connect(&format!("sqlite:{}", database_path))
+            .connect_with(options)
             .await?;
 
         // Enable WAL mode for better concurrent performance
@@ -45,10 +87,88 @@ impl SqliteStorage {
 
         info!("SQLite warm storage initialized at {}", database_path);
 
-        Ok(Self { pool })
+        Ok(Self { pool, path: Some(PathBuf::from(database_path)), rollup_lock: Mutex::new(()) })
+    }
+
+    /// Database living only in process memory, already migrated
+    ///
+    /// Uses a single connection that is never recycled, since every SQLite
+    /// connection to `:memory:` opens a fresh, empty database.
+    pub async fn in_memory() -> anyhow::Result<Self> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+
+        let storage = Self { pool, path: None, rollup_lock: Mutex::new(()) };
+        storage.apply_schema().await?;
+
+        info!("SQLite storage initialized in memory");
+        Ok(storage)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub(crate) fn pool(&self) -> &Pool<Sqlite> {
+        &self.pool
     }
 
     pub async fn run_migrations(&self) -> anyhow::Result<()> {
+        self.apply_schema().await?;
+
+        let has_rollups: Option<i64> = sqlx::query_scalar("SELECT 1 FROM rollups LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?;
+        if has_rollups.is_none() {
+            self.backfill_rollups().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply pending schema migrations to the database at `database_path`
+    ///
+    /// With `dry_run` the file is opened read-only, or not at all if it does
+    /// not exist yet, and the report lists what would be applied.
+    pub async fn migrate(database_path: &str, dry_run: bool) -> anyhow::Result<MigrationReport> {
+        let applied = if !dry_run {
+            // Checkpoints are left to the daemon, which may be archiving the WAL
+            let storage = Self::with_wal_archiving(database_path).await?;
+            let applied = applied_migrations(&storage.pool).await?;
+            storage.apply_schema().await?;
+            applied
+        } else if Path::new(database_path).exists() {
+            let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", database_path))?.read_only(true);
+            let pool = SqlitePoolOptions::new().max_connections(1).connect_with(options).await?;
+            applied_migrations(&pool).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(MigrationReport {
+            backend: format!("sqlite:{}", database_path),
+            current_version: applied.iter().copied().max(),
+            setup: Vec::new(),
+            pending: sqlx::migrate!("./migrations")
+                .iter()
+                .filter(|m| !applied.contains(&m.version))
+                .map(|m| PendingMigration {
+                    version: m.version,
+                    description: m.description.to_string(),
+                    statements: vec![m.sql.trim().to_string()],
+                })
+                .collect(),
+            dry_run,
+        })
+    }
+
+    /// Schema migrations only, without backfilling derived tables
+    pub async fn apply_schema(&self) -> anyhow::Result<()> {
         sqlx::migrate!("./migrations")
             .run(&self.pool)
             .await?;
@@ -66,12 +186,13 @@ impl SqliteStorage {
             r#"
             INSERT INTO radiation_readings_warm (
                 sensor_id, bucket, timestamp, latitude, longitude,
-                dose_rate, uncertainty, quality_flag, source, cell_id
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                dose_rate, uncertainty, quality_flag, source, cell_id, qc_reasons
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(sensor_id, bucket, timestamp) DO UPDATE SET
                 dose_rate = excluded.dose_rate,
                 uncertainty = excluded.uncertainty,
-                quality_flag = excluded.quality_flag
+                quality_flag = excluded.quality_flag,
+                qc_reasons = excluded.qc_reasons
             "#
         )
         .bind(reading.sensor_id.to_string())
@@ -84,6 +205,7 @@ impl SqliteStorage {
         .bind(format!("{:?}", reading.quality_flag))
         .bind(&reading.source)
         .bind(&reading.cell_id)
+        .bind(serde_json::to_string(&reading.qc_reasons)?)
         .execute(&self.pool)
         .await?;
 
@@ -101,8 +223,8 @@ impl SqliteStorage {
         let start_naive = start.naive_utc();
         let end_naive = end.naive_utc();
 
-        let points = match aggregation {
-            AggregationLevel::Raw => {
+        let points = match aggregation.resolve(end - start) {
+            AggregationLevel::Raw | AggregationLevel::Auto => {
                 self.query_raw(sensor_ids, start_naive, end_naive).await?
             }
             AggregationLevel::OneMinute => {
@@ -161,6 +283,8 @@ impl SqliteStorage {
                     min: dose_rate,
                     max: dose_rate,
                     avg: dose_rate,
+                    p95: None,
+                    sensor_id: None,
                 }
             })
             .collect();
@@ -225,6 +349,8 @@ impl SqliteStorage {
                     min: row.min_dose,
                     max: row.max_dose,
                     avg: row.avg_dose,
+                    p95: None,
+                    sensor_id: None,
                 }
             })
             .collect();
@@ -232,82 +358,6 @@ impl SqliteStorage {
         Ok(points)
     }
 
-    #[instrument(skip(self))]
-    pub async fn query_geo(
-        &self,
-        center: GeoPoint,
-        radius_km: f64,
-        time_window: TimeRange,
-    ) -> anyhow::Result<Vec<SensorReading>> {
-        // Calculate bounding box for efficient filtering
-        let lat_delta = radius_km / 111.0; // 1 degree lat ~ 111 km
-        let lon_delta = radius_km / (111.0 * center.latitude.to_radians().cos());
-
-        let min_lat = center.latitude - lat_delta;
-        let max_lat = center.latitude + lat_delta;
-        let min_lon = center.longitude - lon_delta;
-        let max_lon = center.longitude + lon_delta;
-
-        let start_naive = time_window.start.naive_utc();
-        let end_naive = time_window.end.naive_utc();
-
-        let rows = sqlx::query(
-            r#"
-            SELECT DISTINCT ON (sensor_id)
-                sensor_id, timestamp, latitude, longitude, dose_rate, quality_flag
-            FROM radiation_readings_warm
-            WHERE timestamp >= ? AND timestamp <= ?
-            AND latitude BETWEEN ? AND ?
-            AND longitude BETWEEN ? AND ?
-            ORDER BY sensor_id, timestamp DESC
-            "#
-        )
-        .bind(start_naive)
-        .bind(end_naive)
-        .bind(min_lat)
-        .bind(max_lat)
-        .bind(min_lon)
-        .bind(max_lon)
-        .fetch_all(&self.pool)
-        .await?;
-
-        let mut results = Vec::new();
-
-        for row in rows {
-            let sensor_id_str: String = row.get(0);
-            let sensor_id = Uuid::parse_str(&sensor_id_str)?;
-            let timestamp: NaiveDateTime = row.get(1);
-            let latitude: f64 = row.get(2);
-            let longitude: f64 = row.get(3);
-            let dose_rate: f64 = row.get(4);
-            let quality_str: String = row.get(5);
-
-            // Filter by actual haversine distance
-            let distance = haversine_distance(
-                center.latitude, center.longitude,
-                latitude, longitude,
-            );
-
-            if distance <= radius_km {
-                let quality = match quality_str.as_str() {
-                    "Valid" => QualityFlag::Valid,
-                    "Suspect" => QualityFlag::Suspect,
-                    _ => QualityFlag::Invalid,
-                };
-
-                results.push(SensorReading {
-                    sensor_id,
-                    timestamp: DateTime::from_naive_utc_and_offset(timestamp, Utc),
-                    location: GeoPoint { latitude, longitude },
-                    dose_rate,
-                    quality,
-                });
-            }
-        }
-
-        Ok(results)
-    }
-
     pub async fn get_sensor_latest(
         &self,
         sensor_id: &Uuid,
@@ -347,6 +397,11 @@ impl SqliteStorage {
             _ => QualityFlag::Invalid,
         };
 
+        let qc_reasons = row.try_get::<String, _>("qc_reasons")
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
         Ok(RadiationReading {
             sensor_id,
             bucket: row.get("bucket"),
@@ -358,6 +413,10 @@ impl SqliteStorage {
             quality_flag: quality,
             source: row.get("source"),
             cell_id: row.get("cell_id"),
+            track_id: None,
+            altitude_m: None,
+            qc_reasons,
+            provenance: None,
         })
     }
 
@@ -394,6 +453,262 @@ impl SqliteStorage {
         Ok(deleted)
     }
 
+    /// Timestamp of the oldest reading still in the warm tier
+    pub async fn oldest_reading_time(&self) -> anyhow::Result<Option<DateTime<Utc>>> {
+        let oldest: Option<NaiveDateTime> = sqlx::query_scalar(
+            "SELECT MIN(timestamp) FROM radiation_readings_warm"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(oldest.map(|ts| ts.and_utc()))
+    }
+
+    /// Every reading with `start <= timestamp < end`, oldest first
+    pub async fn readings_in_window(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<RadiationReading>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM radiation_readings_warm
+            WHERE timestamp >= ? AND timestamp < ?
+            ORDER BY timestamp ASC, sensor_id ASC
+            "#
+        )
+        .bind(start.naive_utc())
+        .bind(end.naive_utc())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut readings = Vec::with_capacity(rows.len());
+        for row in rows {
+            readings.push(self.row_to_reading(row).await?);
+        }
+
+        Ok(readings)
+    }
+
+    /// Delete readings by primary key in a single transaction
+    pub async fn delete_readings(&self, readings: &[RadiationReading]) -> anyhow::Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = 0;
+
+        for reading in readings {
+            let timestamp = DateTime::from_timestamp(reading.timestamp, 0)
+                .ok_or_else(|| anyhow::anyhow!("Invalid timestamp {}", reading.timestamp))?;
+
+            deleted += sqlx::query(
+                r#"
+                DELETE FROM radiation_readings_warm
+                WHERE sensor_id = ? AND bucket = ? AND timestamp = ?
+                "#
+            )
+            .bind(reading.sensor_id.to_string())
+            .bind(reading.bucket)
+            .bind(timestamp.naive_utc())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// The reading already stored under this reading's key, if any
+    pub async fn stored_reading(&self, reading: &RadiationReading) -> anyhow::Result<Option<RadiationReading>> {
+        let timestamp = DateTime::from_timestamp(reading.timestamp, 0)
+            .ok_or_else(|| anyhow::anyhow!("Invalid timestamp {}", reading.timestamp))?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM radiation_readings_warm
+            WHERE sensor_id = ? AND bucket = ? AND timestamp = ?
+            "#
+        )
+        .bind(reading.sensor_id.to_string())
+        .bind(reading.bucket)
+        .bind(timestamp.naive_utc())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.row_to_reading(row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Fold readings into the per-sensor and per-cell rollups at every level
+    ///
+    /// Readings for buckets that already closed are merged like any other, so
+    /// late data corrects the rollup in place.
+    pub async fn apply_rollups(&self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        let mut deltas: HashMap<RollupKey, RollupStats> = HashMap::new();
+        for reading in readings {
+            for key in rollup::keys_for(&reading.sensor_id, reading.timestamp, reading.latitude, reading.longitude) {
+                deltas.entry(key).or_default().add(reading.dose_rate_microsieverts);
+            }
+        }
+
+        self.update_rollups(deltas.into_iter().map(|(key, delta)| (key, delta, None)).collect()).await
+    }
+
+    /// Replace a reading's previous version with its current one in the rollups
+    ///
+    /// The previous value is retracted from the buckets it was counted in,
+    /// which differ from the current ones when the location changed.
+    pub async fn correct_rollups(&self, reading: &RadiationReading, previous: &RadiationReading) -> anyhow::Result<()> {
+        let mut deltas: HashMap<RollupKey, (RollupStats, Option<f64>)> = HashMap::new();
+        for key in rollup::keys_for(&previous.sensor_id, previous.timestamp, previous.latitude, previous.longitude) {
+            deltas.entry(key).or_default().1 = Some(previous.dose_rate_microsieverts);
+        }
+        for key in rollup::keys_for(&reading.sensor_id, reading.timestamp, reading.latitude, reading.longitude) {
+            deltas.entry(key).or_default().0.add(reading.dose_rate_microsieverts);
+        }
+
+        metrics::counter!("cherenkov_db_rollup_corrections_total").increment(1);
+        self.update_rollups(deltas.into_iter().map(|(key, (delta, retract))| (key, delta, retract)).collect()).await
+    }
+
+    /// Merge each delta into its bucket after retracting the value it replaces, if any
+    async fn update_rollups(&self, deltas: Vec<(RollupKey, RollupStats, Option<f64>)>) -> anyhow::Result<()> {
+        let _guard = self.rollup_lock.lock().await;
+        let now = Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+
+        for (key, delta, retract) in deltas {
+            let existing = sqlx::query(
+                r#"
+                SELECT count, sum, min, max, histogram FROM rollups
+                WHERE level = ? AND scope = ? AND scope_id = ? AND bucket_start = ?
+                "#
+            )
+            .bind(key.level.as_str())
+            .bind(key.scope.as_str())
+            .bind(&key.scope_id)
+            .bind(key.bucket_start)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let mut stats = match existing {
+                Some(row) => {
+                    let mut stats = row_to_rollup(&row)?;
+                    if let Some(previous) = retract {
+                        stats.retract(previous);
+                    }
+                    stats
+                }
+                // Nothing counted there, and nothing to retract
+                None if delta.count == 0 => continue,
+                None => RollupStats::default(),
+            };
+            stats.merge(&delta);
+
+            // Late means the following bucket had already closed too
+            if key.bucket_start + 2 * key.level.width_secs() <= now {
+                metrics::counter!(
+                    "cherenkov_db_rollup_late_updates_total",
+                    "level" => key.level.as_str()
+                ).increment(1);
+            }
+
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO rollups (
+                    level, scope, scope_id, bucket_start, count, sum, min, max, histogram, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+                "#
+            )
+            .bind(key.level.as_str())
+            .bind(key.scope.as_str())
+            .bind(&key.scope_id)
+            .bind(key.bucket_start)
+            .bind(stats.count as i64)
+            .bind(stats.sum)
+            .bind(stats.min)
+            .bind(stats.max)
+            .bind(serde_json::to_string(&stats.histogram)?)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Rollup buckets with `start <= bucket_start <= end`, merged across `scope_ids`
+    pub async fn query_rollups(
+        &self,
+        level: RollupLevel,
+        scope: RollupScope,
+        scope_ids: &[String],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<(i64, RollupStats)>> {
+        if scope_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut query_builder = QueryBuilder::new(
+            "SELECT bucket_start, count, sum, min, max, histogram FROM rollups WHERE level = "
+        );
+        query_builder.push_bind(level.as_str());
+        query_builder.push(" AND scope = ");
+        query_builder.push_bind(scope.as_str());
+        query_builder.push(" AND bucket_start >= ");
+        query_builder.push_bind(level.bucket_start(start.timestamp()));
+        query_builder.push(" AND bucket_start <= ");
+        query_builder.push_bind(end.timestamp());
+        query_builder.push(" AND scope_id IN (");
+
+        let mut separated = query_builder.separated(", ");
+        for scope_id in scope_ids {
+            separated.push_bind(scope_id.to_lowercase());
+        }
+        separated.push_unseparated(") ");
+        query_builder.push("ORDER BY bucket_start ASC");
+
+        let rows = query_builder.build().fetch_all(&self.pool).await?;
+
+        let mut buckets: Vec<(i64, RollupStats)> = Vec::new();
+        for row in rows {
+            let bucket_start: i64 = row.get("bucket_start");
+            let stats = row_to_rollup(&row)?;
+            match buckets.last_mut() {
+                Some((last, merged)) if *last == bucket_start => merged.merge(&stats),
+                _ => buckets.push((bucket_start, stats)),
+            }
+        }
+
+        Ok(buckets)
+    }
+
+    /// Build rollups from the readings already in the warm tier, one day at a time
+    async fn backfill_rollups(&self) -> anyhow::Result<()> {
+        let Some(oldest) = self.oldest_reading_time().await? else {
+            return Ok(());
+        };
+
+        let day = RollupLevel::Day.width_secs();
+        let mut start = RollupLevel::Day.bucket_start(oldest.timestamp());
+        let now = Utc::now().timestamp();
+        let mut total = 0;
+
+        while start <= now {
+            let window_start = DateTime::from_timestamp(start, 0).unwrap_or(oldest);
+            let window_end = DateTime::from_timestamp(start + day, 0).unwrap_or(oldest);
+            let readings = self.readings_in_window(window_start, window_end).await?;
+            self.apply_rollups(&readings).await?;
+            total += readings.len();
+            start += day;
+        }
+
+        info!("Backfilled rollups from {} warm readings", total);
+        Ok(())
+    }
+
     /// List all sensors
     pub async fn list_sensors(&self) -> anyhow::Result<Vec<SensorInfo>> {
         let rows = sqlx::query(
@@ -445,21 +760,59 @@ impl SqliteStorage {
         .fetch_all(&self.pool)
         .await?;
 
-        let anomalies: Vec<AnomalyRecord> = rows
-            .into_iter()
-            .map(|row| {
-                let sensor_id_str: String = row.get(1);
-                AnomalyRecord {
-                    anomaly_id: row.get(0),
-                    sensor_id: Uuid::parse_str(&sensor_id_str).unwrap_or_else(|_| Uuid::new_v4()),
-                    severity: row.get(2),
-                    z_score: row.get(3),
-                    detected_at: row.get::<NaiveDateTime, _>(4).and_utc().timestamp(),
-                }
-            })
-            .collect();
+        Ok(rows.iter().map(row_to_anomaly).collect())
+    }
+
+    /// Anomalies matching a query, with every filter, the cursor and the limit in SQL
+    pub async fn query_anomalies(&self, query: &AnomalyQuery) -> anyhow::Result<Vec<AnomalyRecord>> {
+        let since = DateTime::from_timestamp(query.since, 0)
+            .unwrap_or_else(Utc::now)
+            .naive_utc();
+
+        let mut builder = QueryBuilder::new(
+            "SELECT anomaly_id, sensor_id, severity, z_score, detected_at FROM anomalies WHERE detected_at >= "
+        );
+        builder.push_bind(since);
+
+        if let Some(severity) = &query.severity {
+            builder.push(" AND severity IN (");
+            let mut separated = builder.separated(", ");
+            for s in severity {
+                separated.push_bind(s.clone());
+            }
+            separated.push_unseparated(")");
+        }
+
+        if !query.sensor_ids.is_empty() {
+            builder.push(" AND sensor_id IN (");
+            let mut separated = builder.separated(", ");
+            for id in &query.sensor_ids {
+                separated.push_bind(id.to_string());
+            }
+            separated.push_unseparated(")");
+        }
+
+        let (direction, comparison) = match query.order {
+            SortOrder::Ascending => ("ASC", ">"),
+            SortOrder::Descending => ("DESC", "<"),
+        };
+
+        if let Some(cursor) = &query.cursor {
+            let after = DateTime::from_timestamp(cursor.timestamp, 0)
+                .ok_or_else(|| anyhow::anyhow!("Invalid cursor timestamp"))?
+                .naive_utc();
+            builder.push(format!(" AND (detected_at, anomaly_id) {} (", comparison));
+            builder.push_bind(after);
+            builder.push(", ");
+            builder.push_bind(cursor.key.clone());
+            builder.push(")");
+        }
+
+        builder.push(format!(" ORDER BY detected_at {0}, anomaly_id {0} LIMIT ", direction));
+        builder.push_bind(query.limit.min(i64::MAX as usize) as i64);
 
-        Ok(anomalies)
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        Ok(rows.iter().map(row_to_anomaly).collect())
     }
 
     /// Get count of anomalies in last N hours
@@ -479,21 +832,22 @@ impl SqliteStorage {
         Ok(count)
     }
 
-    /// Store domain event for audit trail
+    /// Store domain event for audit trail, appending it to the event log
     pub async fn store_event(&self, event: &crate::DomainEvent) -> anyhow::Result<()> {
         let timestamp = DateTime::from_timestamp(event.timestamp, 0)
             .unwrap_or_else(|| Utc::now());
 
+        // Writes are serialized, so the next sequence cannot be taken twice
         sqlx::query(
             r#"
             INSERT INTO domain_events (
-                event_id, event_type, aggregate_id, payload, timestamp
-            ) VALUES (?, ?, ?, ?, ?)
+                event_id, event_type, aggregate_id, payload, timestamp, sequence
+            ) VALUES (?, ?, ?, ?, ?, (SELECT COALESCE(MAX(sequence), 0) + 1 FROM domain_events))
             ON CONFLICT(event_id) DO NOTHING
             "#
         )
         .bind(&event.event_id)
-        .bind(format!("{:?}", event.event_type))
+        .bind(event.event_type.as_str())
         .bind(event.aggregate_id.to_string())
         .bind(event.payload.to_string())
         .bind(timestamp.naive_utc())
@@ -503,6 +857,434 @@ impl SqliteStorage {
         Ok(())
     }
 
+    /// Logged events matching a query, with every filter, the cursor and the limit in SQL
+    pub async fn query_events(&self, query: &EventQuery) -> anyhow::Result<Vec<StoredEvent>> {
+        let mut builder = QueryBuilder::new(
+            "SELECT sequence, event_id, event_type, aggregate_id, payload, timestamp FROM domain_events WHERE sequence IS NOT NULL"
+        );
+
+        if !query.aggregate_ids.is_empty() {
+            builder.push(" AND aggregate_id IN (");
+            let mut separated = builder.separated(", ");
+            for id in &query.aggregate_ids {
+                separated.push_bind(id.to_string());
+            }
+            separated.push_unseparated(")");
+        }
+
+        if !query.event_types.is_empty() {
+            builder.push(" AND event_type IN (");
+            let mut separated = builder.separated(", ");
+            for event_type in &query.event_types {
+                separated.push_bind(event_type.as_str());
+            }
+            separated.push_unseparated(")");
+        }
+
+        for (bound, comparison) in [(query.from, ">="), (query.to, "<=")] {
+            if let Some(bound) = bound {
+                let bound = DateTime::from_timestamp(bound, 0)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid event time bound"))?
+                    .naive_utc();
+                builder.push(format!(" AND timestamp {} ", comparison));
+                builder.push_bind(bound);
+            }
+        }
+
+        let (direction, comparison) = match query.order {
+            SortOrder::Ascending => ("ASC", ">"),
+            SortOrder::Descending => ("DESC", "<"),
+        };
+
+        if let Some(after) = query.after {
+            builder.push(format!(" AND sequence {} ", comparison));
+            builder.push_bind(after);
+        }
+
+        builder.push(format!(" ORDER BY sequence {} LIMIT ", direction));
+        builder.push_bind(query.limit.min(i64::MAX as usize) as i64);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        rows.iter().map(row_to_event).collect()
+    }
+
+    pub async fn load_checkpoint(&self, name: &str) -> anyhow::Result<Option<Checkpoint>> {
+        let row = sqlx::query("SELECT sequence, state FROM projection_checkpoints WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| {
+            let state: String = row.get("state");
+            Ok(Checkpoint {
+                name: name.to_string(),
+                sequence: row.get("sequence"),
+                state: serde_json::from_str(&state)?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Save a checkpoint, unless the stored one is further along
+    pub async fn save_checkpoint(&self, checkpoint: &Checkpoint) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO projection_checkpoints (name, sequence, state, updated_at)
+            VALUES (?, ?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(name) DO UPDATE SET
+                sequence = excluded.sequence,
+                state = excluded.state,
+                updated_at = excluded.updated_at
+            WHERE excluded.sequence >= projection_checkpoints.sequence
+            "#
+        )
+        .bind(&checkpoint.name)
+        .bind(checkpoint.sequence)
+        .bind(checkpoint.state.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Append a reading's position to its track, creating the track on first sight
+    #[instrument(skip(self, reading))]
+    pub async fn record_track_point(&self, reading: &RadiationReading) -> anyhow::Result<()> {
+        let Some(track_id) = reading.track_id else {
+            return Ok(());
+        };
+        let timestamp = DateTime::from_timestamp(reading.timestamp, 0)
+            .unwrap_or_else(Utc::now)
+            .naive_utc();
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO tracks (track_id, sensor_id, source, started_at, ended_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(track_id) DO NOTHING
+            "#
+        )
+        .bind(track_id.to_string())
+        .bind(reading.sensor_id.to_string())
+        .bind(&reading.source)
+        .bind(timestamp)
+        .bind(timestamp)
+        .execute(&mut *tx)
+        .await?;
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO track_points (
+                track_id, timestamp, latitude, longitude, altitude_m, dose_rate
+            ) VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(track_id, timestamp) DO NOTHING
+            "#
+        )
+        .bind(track_id.to_string())
+        .bind(timestamp)
+        .bind(reading.latitude)
+        .bind(reading.longitude)
+        .bind(reading.altitude_m)
+        .bind(reading.dose_rate_microsieverts)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // Only new points extend the summary, so replays stay idempotent
+        if inserted > 0 {
+            sqlx::query(
+                r#"
+                UPDATE tracks SET
+                    started_at = MIN(started_at, ?),
+                    ended_at = MAX(ended_at, ?),
+                    point_count = point_count + 1,
+                    max_dose_rate = MAX(max_dose_rate, ?)
+                WHERE track_id = ?
+                "#
+            )
+            .bind(timestamp)
+            .bind(timestamp)
+            .bind(reading.dose_rate_microsieverts)
+            .bind(track_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get a track with its full ordered geo-path
+    pub async fn get_track(&self, track_id: &Uuid) -> anyhow::Result<Option<Track>> {
+        let summary = sqlx::query(
+            r#"
+            SELECT track_id, sensor_id, source, started_at, ended_at, point_count, max_dose_rate
+            FROM tracks
+            WHERE track_id = ?
+            "#
+        )
+        .bind(track_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(summary) = summary else {
+            return Ok(None);
+        };
+
+        let rows = sqlx::query(
+            r#"
+            SELECT timestamp, latitude, longitude, altitude_m, dose_rate
+            FROM track_points
+            WHERE track_id = ?
+            ORDER BY timestamp
+            "#
+        )
+        .bind(track_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let points = rows
+            .into_iter()
+            .map(|row| TrackPoint {
+                timestamp: row.get::<NaiveDateTime, _>(0).and_utc().timestamp(),
+                latitude: row.get(1),
+                longitude: row.get(2),
+                altitude_m: row.get(3),
+                speed_kmh: None,
+                dose_rate_microsieverts: row.get(4),
+            })
+            .collect();
+
+        Ok(Some(Track::new(row_to_track_summary(&summary), points)))
+    }
+
+    /// List tracks overlapping a time range, optionally for one sensor
+    pub async fn list_tracks(
+        &self,
+        sensor_id: Option<&Uuid>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<TrackSummary>> {
+        let mut query = QueryBuilder::new(
+            "SELECT track_id, sensor_id, source, started_at, ended_at, point_count, max_dose_rate FROM tracks WHERE ended_at >= "
+        );
+        query.push_bind(start.naive_utc());
+        query.push(" AND started_at <= ");
+        query.push_bind(end.naive_utc());
+        if let Some(sensor_id) = sensor_id {
+            query.push(" AND sensor_id = ");
+            query.push_bind(sensor_id.to_string());
+        }
+        query.push(" ORDER BY started_at DESC LIMIT ");
+        query.push_bind(limit as i64);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+
+        Ok(rows.iter().map(row_to_track_summary).collect())
+    }
+
+    /// Record a source fetch, once; readings refer to it by `fetch_id`
+    pub async fn record_fetch(&self, fetch: &SourceFetch) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO source_fetches (fetch_id, source, started_at, completed_at, readings)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(fetch_id) DO NOTHING
+            "#
+        )
+        .bind(fetch.fetch_id.to_string())
+        .bind(&fetch.source)
+        .bind(fetch.started_at)
+        .bind(fetch.completed_at)
+        .bind(fetch.readings as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Store where a reading's dose rate came from, with the QC decision it was written with
+    ///
+    /// Conversion and QC settings are stored once per combination, so a
+    /// reading costs one short row.
+    #[instrument(skip(self, reading, provenance))]
+    pub async fn store_provenance(&self, reading: &RadiationReading, provenance: &ReadingProvenance) -> anyhow::Result<()> {
+        let qc_thresholds = provenance.qc_thresholds.as_deref().unwrap_or_default();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO provenance_profiles (raw_unit, conversion_factor, normalizer_version, qc_thresholds)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT DO NOTHING
+            "#
+        )
+        .bind(&provenance.raw_unit)
+        .bind(provenance.conversion_factor)
+        .bind(&provenance.normalizer_version)
+        .bind(qc_thresholds)
+        .execute(&mut *tx)
+        .await?;
+
+        let qc_reasons = match reading.qc_reasons.is_empty() {
+            true => None,
+            false => Some(serde_json::to_string(&reading.qc_reasons)?),
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO reading_provenance (sensor_id, timestamp, fetch, profile, raw_value, qc_flag, qc_reasons)
+            VALUES (
+                ?, ?,
+                (SELECT id FROM source_fetches WHERE fetch_id = ?),
+                (SELECT id FROM provenance_profiles
+                 WHERE raw_unit = ? AND conversion_factor = ? AND normalizer_version = ? AND qc_thresholds = ?),
+                ?, ?, ?
+            )
+            ON CONFLICT(sensor_id, timestamp) DO UPDATE SET
+                fetch = excluded.fetch,
+                profile = excluded.profile,
+                raw_value = excluded.raw_value,
+                qc_flag = excluded.qc_flag,
+                qc_reasons = excluded.qc_reasons
+            "#
+        )
+        .bind(reading.sensor_id.to_string())
+        .bind(reading.timestamp)
+        .bind(provenance.fetch_id.map(|id| id.to_string()))
+        .bind(&provenance.raw_unit)
+        .bind(provenance.conversion_factor)
+        .bind(&provenance.normalizer_version)
+        .bind(qc_thresholds)
+        .bind(provenance.raw_value)
+        .bind(reading.quality_flag.as_str())
+        .bind(qc_reasons)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Lineage of the reading `sensor_id` took at `timestamp`, if it was recorded
+    pub async fn reading_lineage(&self, sensor_id: &Uuid, timestamp: i64) -> anyhow::Result<Option<ReadingLineage>> {
+        let row = sqlx::query(
+            r#"
+            SELECT p.raw_value, p.qc_flag, p.qc_reasons,
+                   pp.raw_unit, pp.conversion_factor, pp.normalizer_version, pp.qc_thresholds,
+                   f.fetch_id, f.source, f.started_at, f.completed_at, f.readings
+            FROM reading_provenance p
+            INNER JOIN provenance_profiles pp ON pp.id = p.profile
+            LEFT JOIN source_fetches f ON f.id = p.fetch
+            WHERE p.sensor_id = ? AND p.timestamp = ?
+            "#
+        )
+        .bind(sensor_id.to_string())
+        .bind(timestamp)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let fetch = match row.get::<Option<String>, _>(7) {
+            Some(fetch_id) => Some(SourceFetch {
+                fetch_id: Uuid::parse_str(&fetch_id)?,
+                source: row.get(8),
+                started_at: row.get(9),
+                completed_at: row.get(10),
+                readings: row.get::<i64, _>(11) as u64,
+            }),
+            None => None,
+        };
+        let qc_reasons = match row.get::<Option<String>, _>(2) {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Vec::new(),
+        };
+        let qc_thresholds: String = row.get(6);
+
+        Ok(Some(ReadingLineage {
+            sensor_id: *sensor_id,
+            timestamp,
+            fetch,
+            raw_value: row.get(0),
+            raw_unit: row.get(3),
+            conversion_factor: row.get(4),
+            normalizer_version: row.get(5),
+            qc: QcDecision {
+                flag: QualityFlag::parse(&row.get::<String, _>(1)).unwrap_or(QualityFlag::Invalid),
+                thresholds: (!qc_thresholds.is_empty()).then_some(qc_thresholds),
+                reasons: qc_reasons,
+            },
+        }))
+    }
+
+    /// Store hourly weather, replacing what a source reported earlier for the same hour and point
+    pub async fn store_weather(&self, observations: &[WeatherObservation]) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for observation in observations {
+            sqlx::query(
+                r#"
+                INSERT INTO weather_observations (latitude, longitude, timestamp, source, precipitation_mm)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(timestamp, latitude, longitude) DO UPDATE SET
+                    source = excluded.source,
+                    precipitation_mm = excluded.precipitation_mm
+                "#
+            )
+            .bind(observation.latitude)
+            .bind(observation.longitude)
+            .bind(WeatherObservation::hour_of(observation.timestamp))
+            .bind(&observation.source)
+            .bind(observation.precipitation_mm)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Weather reported inside a bounding box over `[from, to]`, oldest first
+    pub async fn weather_in(
+        &self,
+        (min_lat, min_lon): (f64, f64),
+        (max_lat, max_lon): (f64, f64),
+        from: i64,
+        to: i64,
+    ) -> anyhow::Result<Vec<WeatherObservation>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT latitude, longitude, timestamp, source, precipitation_mm
+            FROM weather_observations
+            WHERE timestamp BETWEEN ? AND ?
+              AND latitude BETWEEN ? AND ?
+              AND longitude BETWEEN ? AND ?
+            ORDER BY timestamp
+            "#
+        )
+        .bind(from)
+        .bind(to)
+        .bind(min_lat)
+        .bind(max_lat)
+        .bind(min_lon)
+        .bind(max_lon)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| WeatherObservation {
+                latitude: row.get(0),
+                longitude: row.get(1),
+                timestamp: row.get(2),
+                source: row.get(3),
+                precipitation_mm: row.get(4),
+            })
+            .collect())
+    }
+
     /// List all sensors with their latest location and timestamp
     pub async fn list_sensors_with_location(&self) -> anyhow::Result<Vec<SensorRecord>> {
         let rows = sqlx::query(
@@ -512,7 +1294,8 @@ impl SqliteStorage {
                 r.source,
                 r.latitude,
                 r.longitude,
-                r.timestamp
+                r.timestamp,
+                EXISTS (SELECT 1 FROM tracks t WHERE t.sensor_id = r.sensor_id) AS mobile
             FROM radiation_readings_warm r
             INNER JOIN (
                 SELECT sensor_id, MAX(timestamp) as max_ts
@@ -538,12 +1321,411 @@ impl SqliteStorage {
                     latitude: row.get(2),
                     longitude: row.get(3),
                     timestamp: timestamp_naive.and_utc().timestamp(),
+                    mobile: row.get(5),
                 }
             })
             .collect();
 
         Ok(sensors)
     }
+
+    /// Register a reading's sensor on first sight and advance its last report
+    pub async fn touch_sensor(&self, reading: &RadiationReading) -> anyhow::Result<()> {
+        self.touch_sensor_at(reading, Utc::now()).await
+    }
+
+    /// As `touch_sensor`, for a reading delivered at `delivered`, which also
+    /// feeds the sensor's learned delivery cadence
+    #[instrument(skip(self, reading))]
+    pub async fn touch_sensor_at(&self, reading: &RadiationReading, delivered: DateTime<Utc>) -> anyhow::Result<()> {
+        let timestamp = DateTime::from_timestamp(reading.timestamp, 0)
+            .unwrap_or_else(Utc::now)
+            .naive_utc();
+
+        // Backfilled readings must not move the sensor back in time, but do
+        // count as a delivery. Arrivals within one delivery gap of the last
+        // one are the same batch and leave the cadence alone.
+        let gap = "(julianday(excluded.last_delivery) - julianday(last_delivery)) * 86400.0";
+        let new_delivery = format!("(last_delivery IS NOT NULL AND {} >= {})", gap, MIN_DELIVERY_GAP_SECS);
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO sensors (
+                sensor_id, source, status, first_seen, last_reading, latitude, longitude, status_changed_at,
+                last_delivery
+            ) VALUES (?, ?, 'online', ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(sensor_id) DO UPDATE SET
+                latitude = CASE WHEN last_reading IS NULL OR excluded.last_reading >= last_reading
+                    THEN excluded.latitude ELSE latitude END,
+                longitude = CASE WHEN last_reading IS NULL OR excluded.last_reading >= last_reading
+                    THEN excluded.longitude ELSE longitude END,
+                last_reading = MAX(COALESCE(last_reading, excluded.last_reading), excluded.last_reading),
+                cadence_secs = CASE WHEN {new} THEN
+                    CASE WHEN cadence_secs IS NULL THEN {gap}
+                    ELSE {alpha} * {gap} + (1.0 - {alpha}) * cadence_secs END
+                    ELSE cadence_secs END,
+                cadence_intervals = cadence_intervals + CASE WHEN {new} THEN 1 ELSE 0 END,
+                last_delivery = CASE WHEN last_delivery IS NULL OR {new}
+                    THEN excluded.last_delivery ELSE last_delivery END
+            "#,
+            new = new_delivery,
+            gap = gap,
+            alpha = CADENCE_ALPHA,
+        ))
+        .bind(reading.sensor_id.to_string())
+        .bind(&reading.source)
+        .bind(timestamp)
+        .bind(timestamp)
+        .bind(reading.latitude)
+        .bind(reading.longitude)
+        .bind(Utc::now().naive_utc())
+        .bind(delivered.naive_utc())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Register a sensor, returning `None` if the ID is already taken
+    pub async fn create_sensor(&self, sensor: &NewSensor) -> anyhow::Result<Option<Sensor>> {
+        let sensor_id = sensor.sensor_id.unwrap_or_else(Uuid::new_v4);
+        let now = Utc::now().naive_utc();
+
+        // Nothing has been reported yet, so the sensor starts offline
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO sensors (
+                sensor_id, name, source, detector_model, calibration_factor, height_m,
+                placement, owner, commissioned_at, status, status_changed_at, latitude, longitude
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'offline', ?, ?, ?)
+            ON CONFLICT(sensor_id) DO NOTHING
+            "#
+        )
+        .bind(sensor_id.to_string())
+        .bind(&sensor.name)
+        .bind(&sensor.source)
+        .bind(&sensor.detector_model)
+        .bind(sensor.calibration_factor.unwrap_or(1.0))
+        .bind(sensor.height_m)
+        .bind(sensor.placement.map(|p| p.as_str()))
+        .bind(&sensor.owner)
+        .bind(sensor.commissioned_at.and_then(|t| DateTime::from_timestamp(t, 0)).map(|t| t.naive_utc()))
+        .bind(now)
+        .bind(sensor.latitude)
+        .bind(sensor.longitude)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if inserted == 0 {
+            return Ok(None);
+        }
+
+        self.get_sensor(&sensor_id).await
+    }
+
+    /// Apply a partial metadata update, returning `None` for unknown sensors
+    pub async fn update_sensor(&self, sensor_id: &Uuid, update: &SensorUpdate) -> anyhow::Result<Option<Sensor>> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE sensors SET
+                name = COALESCE(?, name),
+                detector_model = COALESCE(?, detector_model),
+                calibration_factor = COALESCE(?, calibration_factor),
+                height_m = COALESCE(?, height_m),
+                placement = COALESCE(?, placement),
+                owner = COALESCE(?, owner),
+                commissioned_at = COALESCE(?, commissioned_at),
+                status_changed_at = CASE WHEN ? IS NOT NULL AND ? != status
+                    THEN ? ELSE status_changed_at END,
+                status = COALESCE(?, status)
+            WHERE sensor_id = ?
+            "#
+        )
+        .bind(&update.name)
+        .bind(&update.detector_model)
+        .bind(update.calibration_factor)
+        .bind(update.height_m)
+        .bind(update.placement.map(|p| p.as_str()))
+        .bind(&update.owner)
+        .bind(update.commissioned_at.and_then(|t| DateTime::from_timestamp(t, 0)).map(|t| t.naive_utc()))
+        .bind(update.status.map(|s| s.as_str()))
+        .bind(update.status.map(|s| s.as_str()))
+        .bind(Utc::now().naive_utc())
+        .bind(update.status.map(|s| s.as_str()))
+        .bind(sensor_id.to_string())
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if updated == 0 {
+            return Ok(None);
+        }
+
+        self.get_sensor(sensor_id).await
+    }
+
+    /// Retire a sensor; it keeps its history but is no longer swept or listed by default
+    pub async fn decommission_sensor(&self, sensor_id: &Uuid, at: DateTime<Utc>) -> anyhow::Result<Option<Sensor>> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE sensors SET
+                status = 'decommissioned',
+                decommissioned_at = COALESCE(decommissioned_at, ?),
+                status_changed_at = CASE WHEN status != 'decommissioned'
+                    THEN ? ELSE status_changed_at END
+            WHERE sensor_id = ?
+            "#
+        )
+        .bind(at.naive_utc())
+        .bind(at.naive_utc())
+        .bind(sensor_id.to_string())
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if updated == 0 {
+            return Ok(None);
+        }
+
+        self.get_sensor(sensor_id).await
+    }
+
+    /// Get a registered sensor
+    pub async fn get_sensor(&self, sensor_id: &Uuid) -> anyhow::Result<Option<Sensor>> {
+        let row = sqlx::query(&format!("SELECT {} FROM sensors WHERE sensor_id = ?", SENSOR_COLUMNS))
+            .bind(sensor_id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_sensor).transpose()
+    }
+
+    /// List registered sensors, optionally including decommissioned ones
+    pub async fn list_registered_sensors(&self, include_decommissioned: bool) -> anyhow::Result<Vec<Sensor>> {
+        let filter = if include_decommissioned { "" } else { "WHERE status != 'decommissioned'" };
+        let rows = sqlx::query(&format!("SELECT {} FROM sensors {} ORDER BY sensor_id", SENSOR_COLUMNS, filter))
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(row_to_sensor).collect()
+    }
+
+    /// Move sensors between online, degraded and offline by how long ago
+    /// they last delivered a reading, against their learned cadence
+    ///
+    /// Sensors in maintenance or decommissioned are left alone. Returns the
+    /// transitions that were applied.
+    pub async fn sweep_sensor_status(
+        &self,
+        now: DateTime<Utc>,
+        thresholds: &LivenessThresholds,
+    ) -> anyhow::Result<Vec<StatusTransition>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT sensor_id, status, last_delivery, cadence_secs, cadence_intervals
+            FROM sensors
+            WHERE status NOT IN ('maintenance', 'decommissioned')
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut transitions = Vec::new();
+        let mut tx = self.pool.begin().await?;
+
+        for row in rows {
+            let sensor_id: String = row.get(0);
+            let status: String = row.get(1);
+            let Some(from) = SensorState::parse(&status) else {
+                warn!("Skipping sensor {} with unknown status '{}'", sensor_id, status);
+                continue;
+            };
+            let last_delivery: Option<NaiveDateTime> = row.get(2);
+            let gap = last_delivery.map(|t| now.timestamp() - t.and_utc().timestamp());
+            let intervals: i64 = row.get(4);
+            let to = thresholds.state_for_gap(gap, row.get(3), intervals.try_into().unwrap_or(u32::MAX));
+
+            if to == from {
+                continue;
+            }
+
+            sqlx::query("UPDATE sensors SET status = ?, status_changed_at = ? WHERE sensor_id = ? AND status = ?")
+                .bind(to.as_str())
+                .bind(now.naive_utc())
+                .bind(&sensor_id)
+                .bind(from.as_str())
+                .execute(&mut *tx)
+                .await?;
+
+            transitions.push(StatusTransition {
+                sensor_id: Uuid::parse_str(&sensor_id).unwrap_or_default(),
+                from,
+                to,
+                at: now.timestamp(),
+            });
+        }
+
+        tx.commit().await?;
+        Ok(transitions)
+    }
+}
+
+#[async_trait::async_trait]
+impl ReadingStore for SqliteStorage {
+    async fn write_reading(&self, reading: &RadiationReading) -> anyhow::Result<()> {
+        SqliteStorage::write_reading(self, reading).await
+    }
+
+    async fn query_by_time_range(&self, sensor_id: Uuid, from: i64, to: i64) -> anyhow::Result<Vec<RadiationReading>> {
+        let (Some(from), Some(to)) = (DateTime::from_timestamp(from, 0), DateTime::from_timestamp(to, 0)) else {
+            return Ok(vec![]);
+        };
+
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM radiation_readings_warm
+            WHERE sensor_id = ? AND timestamp >= ? AND timestamp <= ?
+            ORDER BY timestamp ASC
+            "#
+        )
+        .bind(sensor_id.to_string())
+        .bind(from.naive_utc())
+        .bind(to.naive_utc())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut readings = Vec::with_capacity(rows.len());
+        for row in rows {
+            readings.push(self.row_to_reading(row).await?);
+        }
+        Ok(readings)
+    }
+
+    async fn query_sensor_page(
+        &self,
+        sensor_id: Uuid,
+        from: i64,
+        to: i64,
+        order: SortOrder,
+        limit: usize,
+    ) -> anyhow::Result<Vec<RadiationReading>> {
+        let (Some(from), Some(to)) = (DateTime::from_timestamp(from, 0), DateTime::from_timestamp(to, 0)) else {
+            return Ok(vec![]);
+        };
+        let direction = match order {
+            SortOrder::Ascending => "ASC",
+            SortOrder::Descending => "DESC",
+        };
+
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT * FROM radiation_readings_warm
+            WHERE sensor_id = ? AND timestamp >= ? AND timestamp <= ?
+            ORDER BY timestamp {} LIMIT ?
+            "#,
+            direction
+        ))
+        .bind(sensor_id.to_string())
+        .bind(from.naive_utc())
+        .bind(to.naive_utc())
+        .bind(limit.min(i64::MAX as usize) as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut readings = Vec::with_capacity(rows.len());
+        for row in rows {
+            readings.push(self.row_to_reading(row).await?);
+        }
+        Ok(readings)
+    }
+
+    async fn query_by_geohash(&self, geohash: &str, from: i64, to: i64) -> anyhow::Result<Vec<RadiationReading>> {
+        let (Some(from), Some(to)) = (DateTime::from_timestamp(from, 0), DateTime::from_timestamp(to, 0)) else {
+            return Ok(vec![]);
+        };
+        let bbox = geohash::decode_bbox(geohash)
+            .map_err(|e| anyhow::anyhow!("Geohash error: {:?}", e))?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM radiation_readings_warm
+            WHERE latitude BETWEEN ? AND ?
+            AND longitude BETWEEN ? AND ?
+            AND timestamp >= ? AND timestamp <= ?
+            "#
+        )
+        .bind(bbox.min().y)
+        .bind(bbox.max().y)
+        .bind(bbox.min().x)
+        .bind(bbox.max().x)
+        .bind(from.naive_utc())
+        .bind(to.naive_utc())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut readings = Vec::with_capacity(rows.len());
+        for row in rows {
+            readings.push(self.row_to_reading(row).await?);
+        }
+        Ok(readings)
+    }
+
+    async fn latest_reading(&self, sensor_id: Uuid) -> anyhow::Result<Option<RadiationReading>> {
+        self.get_sensor_latest(&sensor_id).await
+    }
+
+    async fn query_bucket(&self, bucket: i64) -> anyhow::Result<Vec<RadiationReading>> {
+        let rows = sqlx::query("SELECT * FROM radiation_readings_warm WHERE bucket = ?")
+            .bind(bucket)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut readings = Vec::with_capacity(rows.len());
+        for row in rows {
+            readings.push(self.row_to_reading(row).await?);
+        }
+        Ok(readings)
+    }
+
+    async fn delete_readings(&self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        SqliteStorage::delete_readings(self, readings).await?;
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        SqliteStorage::health_check(self).await
+    }
+}
+
+#[async_trait::async_trait]
+impl EventStore for SqliteStorage {
+    async fn store_event(&self, event: &crate::DomainEvent) -> anyhow::Result<()> {
+        SqliteStorage::store_event(self, event).await
+    }
+
+    async fn get_anomalies(&self, since: i64, limit: usize) -> anyhow::Result<Vec<AnomalyRecord>> {
+        SqliteStorage::get_anomalies(self, since, limit).await
+    }
+
+    async fn get_anomaly_count(&self, hours: i64) -> anyhow::Result<i64> {
+        SqliteStorage::get_anomaly_count(self, hours).await
+    }
+
+    async fn query_anomalies(&self, query: &AnomalyQuery) -> anyhow::Result<Vec<AnomalyRecord>> {
+        SqliteStorage::query_anomalies(self, query).await
+    }
+
+    async fn query_events(&self, query: &EventQuery) -> anyhow::Result<Vec<StoredEvent>> {
+        SqliteStorage::query_events(self, query).await
+    }
+
+    async fn load_checkpoint(&self, name: &str) -> anyhow::Result<Option<Checkpoint>> {
+        SqliteStorage::load_checkpoint(self, name).await
+    }
+
+    async fn save_checkpoint(&self, checkpoint: &Checkpoint) -> anyhow::Result<()> {
+        SqliteStorage::save_checkpoint(self, checkpoint).await
+    }
 }
 
 
@@ -572,21 +1754,102 @@ pub struct SensorRecord {
     pub latitude: f64,
     pub longitude: f64,
     pub timestamp: i64,
+    /// Sensor has reported survey tracks, so its location is only its latest position
+    pub mobile: bool,
 }
 
+/// Versions recorded by sqlx as successfully applied
+async fn applied_migrations(pool: &Pool<Sqlite>) -> anyhow::Result<Vec<i64>> {
+    let tracked: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    if tracked.is_none() {
+        return Ok(Vec::new());
+    }
 
-/// Calculate haversine distance between two points in kilometers
-fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    const R: f64 = 6371.0; // Earth's radius in km
+    Ok(sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+        .fetch_all(pool)
+        .await?)
+}
 
-    let lat1_rad = lat1.to_radians();
-    let lat2_rad = lat2.to_radians();
-    let delta_lat = (lat2 - lat1).to_radians();
-    let delta_lon = (lon2 - lon1).to_radians();
+fn row_to_anomaly(row: &sqlx::sqlite::SqliteRow) -> AnomalyRecord {
+    let sensor_id_str: String = row.get(1);
+    AnomalyRecord {
+        anomaly_id: row.get(0),
+        sensor_id: Uuid::parse_str(&sensor_id_str).unwrap_or_else(|_| Uuid::new_v4()),
+        severity: row.get(2),
+        z_score: row.get(3),
+        detected_at: row.get::<NaiveDateTime, _>(4).and_utc().timestamp(),
+    }
+}
 
-    let a = (delta_lat / 2.0).sin().powi(2)
-        + lat1_rad.cos() * lat2_rad.cos() * (delta_lon / 2.0).sin().powi(2);
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+fn row_to_event(row: &sqlx::sqlite::SqliteRow) -> anyhow::Result<StoredEvent> {
+    let event_type: String = row.get("event_type");
+    let aggregate_id: String = row.get("aggregate_id");
+    let payload: String = row.get("payload");
+    Ok(StoredEvent {
+        sequence: row.get("sequence"),
+        event: crate::DomainEvent {
+            event_id: row.get("event_id"),
+            event_type: EventType::parse(&event_type)
+                .ok_or_else(|| anyhow::anyhow!("Unknown event type: {}", event_type))?,
+            aggregate_id: Uuid::parse_str(&aggregate_id)?,
+            payload: serde_json::from_str(&payload)?,
+            timestamp: row.get::<NaiveDateTime, _>("timestamp").and_utc().timestamp(),
+        },
+    })
+}
+
+fn row_to_rollup(row: &sqlx::sqlite::SqliteRow) -> anyhow::Result<RollupStats> {
+    let histogram: String = row.get("histogram");
+    Ok(RollupStats {
+        count: row.get::<i64, _>("count") as u64,
+        sum: row.get("sum"),
+        min: row.get("min"),
+        max: row.get("max"),
+        histogram: serde_json::from_str(&histogram)?,
+    })
+}
 
-    R * c
+fn row_to_sensor(row: &sqlx::sqlite::SqliteRow) -> anyhow::Result<Sensor> {
+    let sensor_id: String = row.get(0);
+    let status: String = row.get(10);
+    let status = SensorState::parse(&status)
+        .ok_or_else(|| anyhow::anyhow!("sensor {} has unknown status '{}'", sensor_id, status))?;
+    let timestamp = |index: usize| row.get::<Option<NaiveDateTime>, _>(index).map(|t| t.and_utc().timestamp());
+    Ok(Sensor {
+        sensor_id: Uuid::parse_str(&sensor_id).unwrap_or_default(),
+        name: row.get(1),
+        source: row.get(2),
+        detector_model: row.get(3),
+        calibration_factor: row.get(4),
+        height_m: row.get(5),
+        placement: row.get::<Option<String>, _>(6).as_deref().and_then(Placement::parse),
+        owner: row.get(7),
+        commissioned_at: timestamp(8),
+        decommissioned_at: timestamp(9),
+        status,
+        status_changed_at: timestamp(11),
+        last_reading: timestamp(12),
+        latitude: row.get(13),
+        longitude: row.get(14),
+        cadence_secs: row.get(15),
+    })
 }
+
+fn row_to_track_summary(row: &sqlx::sqlite::SqliteRow) -> TrackSummary {
+    let track_id: String = row.get(0);
+    let sensor_id: String = row.get(1);
+    TrackSummary {
+        track_id: Uuid::parse_str(&track_id).unwrap_or_default(),
+        sensor_id: Uuid::parse_str(&sensor_id).unwrap_or_default(),
+        source: row.get(2),
+        started_at: row.get::<NaiveDateTime, _>(3).and_utc().timestamp(),
+        ended_at: row.get::<NaiveDateTime, _>(4).and_utc().timestamp(),
+        point_count: row.get(5),
+        max_dose_rate: row.get(6),
+    }
+}
+
@@ -1,35 +1,50 @@
-use chrono::{DateTime, Utc};
-use std::path::Path;
-use tracing::{info, error, instrument};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{info, debug, error, instrument};
 use uuid::Uuid;
 
 use crate::RadiationReading;
 
-/// Cold storage for historical archive using Parquet format
-/// This is a stub implementation - full S3 integration requires
-/// the `cold-storage` feature flag and AWS SDK
+/// Geohash precision of the `cell=` partition, roughly 1250 x 625 km
+const CELL_PRECISION: usize = 2;
+
+/// Cold storage for the historical archive as Parquet files
+///
+/// Files are Hive-partitioned as
+/// `date=YYYY-MM-DD/cell=<geohash>/readings_<min>_<max>_<uuid>.parquet`,
+/// with rows sorted by sensor and time. Queries prune partitions by date,
+/// files by the time range in their name and row groups by column statistics
+/// before filtering rows. Writing and reading Parquet requires the
+/// `cold-storage` feature.
 #[derive(Debug)]
 pub struct ColdStorage {
-    local_path: String,
+    config: ColdStorageConfig,
     enabled: bool,
 }
 
 impl ColdStorage {
-    pub fn new(local_path: &str) -> anyhow::Result<Self> {
+    pub fn new(config: ColdStorageConfig) -> anyhow::Result<Self> {
+        if !cfg!(feature = "cold-storage") {
+            return Err(anyhow::anyhow!(
+                "Cold storage requires cherenkov-db to be built with the `cold-storage` feature"
+            ));
+        }
+
         // Ensure directory exists
-        std::fs::create_dir_all(local_path)?;
-        
-        info!("Cold storage initialized at {}", local_path);
+        std::fs::create_dir_all(&config.local_path)?;
+
+        info!("Cold storage initialized at {}", config.local_path);
 
         Ok(Self {
-            local_path: local_path.to_string(),
+            config,
             enabled: true,
         })
     }
 
     pub fn disabled() -> Self {
         Self {
-            local_path: String::new(),
+            config: ColdStorageConfig::default(),
             enabled: false,
         }
     }
@@ -38,68 +53,112 @@ impl ColdStorage {
         self.enabled
     }
 
+    /// Write readings as one Parquet file per date/cell partition, returning the file paths
     #[instrument(skip(self, readings))]
     pub async fn archive_readings(
         &self,
         readings: &[RadiationReading],
-    ) -> anyhow::Result<String> {
+    ) -> anyhow::Result<Vec<String>> {
         if !self.enabled {
             return Err(anyhow::anyhow!("Cold storage is disabled"));
         }
 
         if readings.is_empty() {
-            return Ok(String::new());
+            return Ok(Vec::new());
+        }
+
+        let mut partitions: BTreeMap<PathBuf, Vec<RadiationReading>> = BTreeMap::new();
+        for reading in readings {
+            partitions
+                .entry(partition_dir(reading))
+                .or_default()
+                .push(reading.clone());
         }
 
-        // Generate filename based on date range
-        let min_timestamp = readings.iter()
-            .map(|r| r.timestamp)
-            .min()
-            .unwrap_or(0);
-        
-        let max_timestamp = readings.iter()
-            .map(|r| r.timestamp)
-            .max()
-            .unwrap_or(0);
-
-        let filename = format!(
-            "readings_{}_{}_{}.parquet",
-            min_timestamp,
-            max_timestamp,
-            Uuid::new_v4()
-        );
-
-        let filepath = Path::new(&self.local_path).join(&filename);
-
-        // For now, write as JSON (Parquet requires arrow/parquet crate)
-        // In production, this would use Parquet format
-        let json_data = serde_json::to_string_pretty(readings)?;
-        tokio::fs::write(&filepath, json_data).await?;
-
-        info!("Archived {} readings to {}", readings.len(), filepath.display());
-
-        Ok(filepath.to_string_lossy().to_string())
+        let root = PathBuf::from(&self.config.local_path);
+        let compression = self.config.compression;
+        let row_group_size = self.config.row_group_size;
+
+        let written = tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<String>> {
+            let mut written = Vec::with_capacity(partitions.len());
+
+            for (dir, mut rows) in partitions {
+                rows.sort_by(|a, b| {
+                    a.sensor_id.cmp(&b.sensor_id).then(a.timestamp.cmp(&b.timestamp))
+                });
+
+                let min_timestamp = rows.iter().map(|r| r.timestamp).min().unwrap_or(0);
+                let max_timestamp = rows.iter().map(|r| r.timestamp).max().unwrap_or(0);
+
+                let dir = root.join(dir);
+                std::fs::create_dir_all(&dir)?;
+
+                let filename = format!(
+                    "readings_{}_{}_{}.parquet",
+                    min_timestamp,
+                    max_timestamp,
+                    Uuid::new_v4()
+                );
+                let filepath = dir.join(&filename);
+
+                // Readers never see a partially written file
+                let tmp = dir.join(format!(".{}.tmp", filename));
+                parquet_io::write_file(&tmp, &rows, compression, row_group_size)?;
+                std::fs::rename(&tmp, &filepath)?;
+
+                debug!("Archived {} readings to {}", rows.len(), filepath.display());
+                written.push(filepath.to_string_lossy().to_string());
+            }
+
+            Ok(written)
+        })
+        .await??;
+
+        info!("Archived {} readings into {} files", readings.len(), written.len());
+
+        Ok(written)
     }
 
+    /// Readings for the given sensors within `[start, end]`, all sensors if none are given
     #[instrument(skip(self))]
     pub async fn query_range(
         &self,
-        _sensor_ids: &[String],
-        _start: DateTime<Utc>,
-        _end: DateTime<Utc>,
+        sensor_ids: &[String],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
     ) -> anyhow::Result<Vec<RadiationReading>> {
         if !self.enabled {
             return Ok(vec![]);
         }
 
-        // TODO: Implement query against archived Parquet files
-        // This would require:
-        // 1. Listing relevant Parquet files based on date range
-        // 2. Reading and filtering data
-        // 3. Returning matching readings
-        
-        info!("Cold storage query not yet implemented");
-        Ok(vec![])
+        let filter = RangeFilter {
+            sensor_ids: sensor_ids.iter().map(|s| s.to_lowercase()).collect(),
+            start: start.timestamp(),
+            end: end.timestamp(),
+        };
+        let root = PathBuf::from(&self.config.local_path);
+
+        tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<RadiationReading>> {
+            let start_date = start.date_naive();
+            let end_date = end.date_naive();
+
+            let mut readings = Vec::new();
+            for archive in collect_archives(&root)? {
+                // Partition pruning on the date directory, then file pruning on the name
+                if archive.date.is_some_and(|d| d < start_date || d > end_date) {
+                    continue;
+                }
+                if archive.info.end_timestamp < filter.start || archive.info.start_timestamp > filter.end {
+                    continue;
+                }
+
+                readings.extend(parquet_io::read_file(Path::new(&archive.info.path), &filter)?);
+            }
+
+            readings.sort_by_key(|r| r.timestamp);
+            Ok(readings)
+        })
+        .await?
     }
 
     #[instrument(skip(self))]
@@ -110,39 +169,15 @@ impl ColdStorage {
             return Ok(vec![]);
         }
 
-        let mut archives = Vec::new();
-        let mut entries = tokio::fs::read_dir(&self.local_path).await?;
-
-        while let Some(entry) = entries.next_entry().await? {
-            let metadata = entry.metadata().await?;
-            let path = entry.path();
-            
-            if let Some(filename) = path.file_stem().and_then(|s| s.to_str()) {
-                // Parse filename for timestamps
-                let parts: Vec<&str> = filename.split('_').collect();
-                if parts.len() >= 3 {
-                    if let (Ok(start), Ok(end)) = (
-                        parts[1].parse::<i64>(),
-                        parts[2].parse::<i64>()
-                    ) {
-                        archives.push(ArchiveInfo {
-                            filename: filename.to_string(),
-                            start_timestamp: start,
-                            end_timestamp: end,
-                            size_bytes: metadata.len(),
-                            created: metadata.created()
-                                .ok()
-                                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                                .map(|d| d.as_secs() as i64)
-                                .unwrap_or(0),
-                        });
-                    }
-                }
-            }
-        }
+        let root = PathBuf::from(&self.config.local_path);
+        let mut archives: Vec<ArchiveInfo> = tokio::task::spawn_blocking(move || collect_archives(&root))
+            .await??
+            .into_iter()
+            .map(|a| a.info)
+            .collect();
 
         // Sort by start timestamp
-        archives.sort_by(|a, b| a.start_timestamp.cmp(&b.start_timestamp));
+        archives.sort_by_key(|a| a.start_timestamp);
 
         Ok(archives)
     }
@@ -162,14 +197,11 @@ impl ColdStorage {
 
         for archive in archives {
             if archive.end_timestamp < before_timestamp {
-                let path = Path::new(&self.local_path)
-                    .join(format!("{}.parquet", archive.filename));
-                
-                if let Err(e) = tokio::fs::remove_file(&path).await {
-                    error!("Failed to delete archive {}: {}", path.display(), e);
+                if let Err(e) = tokio::fs::remove_file(&archive.path).await {
+                    error!("Failed to delete archive {}: {}", archive.path, e);
                 } else {
                     deleted += 1;
-                    info!("Deleted old archive: {}", path.display());
+                    info!("Deleted old archive: {}", archive.path);
                 }
             }
         }
@@ -182,7 +214,7 @@ impl ColdStorage {
             return true; // Disabled is considered healthy
         }
 
-        match tokio::fs::metadata(&self.local_path).await {
+        match tokio::fs::metadata(&self.config.local_path).await {
             Ok(_) => true,
             Err(e) => {
                 error!("Cold storage health check failed: {}", e);
@@ -192,9 +224,398 @@ impl ColdStorage {
     }
 }
 
+/// `date=YYYY-MM-DD/cell=<geohash>` directory a reading belongs in
+fn partition_dir(reading: &RadiationReading) -> PathBuf {
+    let date = DateTime::from_timestamp(reading.timestamp, 0)
+        .map(|t| t.date_naive().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let coord = geohash::Coord { x: reading.longitude, y: reading.latitude };
+    let cell = geohash::encode(coord, CELL_PRECISION).unwrap_or_else(|_| "unknown".to_string());
+
+    PathBuf::from(format!("date={}", date)).join(format!("cell={}", cell))
+}
+
+struct ArchiveFile {
+    info: ArchiveInfo,
+    date: Option<NaiveDate>,
+}
+
+/// Recursively find archive files below `root`
+fn collect_archives(root: &Path) -> anyhow::Result<Vec<ArchiveFile>> {
+    let mut archives = Vec::new();
+    let mut dirs = vec![(root.to_path_buf(), None)];
+
+    while let Some((dir, date)) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+
+            if metadata.is_dir() {
+                let name = entry.file_name();
+                let partition_date = name
+                    .to_str()
+                    .and_then(|n| n.strip_prefix("date="))
+                    .and_then(|d| d.parse::<NaiveDate>().ok());
+                dirs.push((path, partition_date.or(date)));
+                continue;
+            }
+
+            if path.extension().and_then(|e| e.to_str()) != Some("parquet") {
+                continue;
+            }
+
+            let Some(filename) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            // Parse filename for timestamps
+            let parts: Vec<&str> = filename.split('_').collect();
+            if parts.len() >= 3 {
+                if let (Ok(start), Ok(end)) = (
+                    parts[1].parse::<i64>(),
+                    parts[2].parse::<i64>()
+                ) {
+                    archives.push(ArchiveFile {
+                        info: ArchiveInfo {
+                            filename: filename.to_string(),
+                            path: path.to_string_lossy().to_string(),
+                            start_timestamp: start,
+                            end_timestamp: end,
+                            size_bytes: metadata.len(),
+                            created: metadata.created()
+                                .ok()
+                                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                                .map(|d| d.as_secs() as i64)
+                                .unwrap_or(0),
+                        },
+                        date,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(archives)
+}
+
+/// Sensor and time predicate pushed down into Parquet reads
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "cold-storage"), allow(dead_code))]
+struct RangeFilter {
+    /// Lowercase hyphenated sensor IDs; empty matches every sensor
+    sensor_ids: HashSet<String>,
+    start: i64,
+    end: i64,
+}
+
+#[cfg_attr(not(feature = "cold-storage"), allow(dead_code))]
+impl RangeFilter {
+    fn matches(&self, sensor_id: &str, timestamp: i64) -> bool {
+        timestamp >= self.start
+            && timestamp <= self.end
+            && (self.sensor_ids.is_empty() || self.sensor_ids.contains(sensor_id))
+    }
+}
+
+#[cfg(feature = "cold-storage")]
+mod parquet_io {
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use arrow::array::{
+        Array, ArrayRef, AsArray, BooleanArray, Float32Array, Float64Array, Int64Array,
+        RecordBatch, StringArray,
+    };
+    use arrow::datatypes::{DataType, Field, Float32Type, Float64Type, Int64Type, Schema, SchemaRef};
+    use arrow::error::ArrowError;
+    use parquet::arrow::arrow_reader::{ArrowPredicateFn, ParquetRecordBatchReaderBuilder, RowFilter};
+    use parquet::arrow::{ArrowWriter, ProjectionMask};
+    use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+    use parquet::file::metadata::{KeyValue, RowGroupMetaData};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::statistics::Statistics;
+    use uuid::Uuid;
+
+    use super::{CompressionType, RangeFilter};
+    use crate::{QualityFlag, RadiationReading};
+
+    const SENSOR_ID: usize = 0;
+    const TIMESTAMP: usize = 2;
+
+    /// Arrow schema mirroring `RadiationReading`, QC reasons stored as JSON
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("sensor_id", DataType::Utf8, false),
+            Field::new("bucket", DataType::Int64, false),
+            Field::new("timestamp", DataType::Int64, false),
+            Field::new("latitude", DataType::Float64, false),
+            Field::new("longitude", DataType::Float64, false),
+            Field::new("dose_rate_microsieverts", DataType::Float64, false),
+            Field::new("uncertainty", DataType::Float32, false),
+            Field::new("quality_flag", DataType::Utf8, false),
+            Field::new("source", DataType::Utf8, false),
+            Field::new("cell_id", DataType::Utf8, false),
+            Field::new("track_id", DataType::Utf8, true),
+            Field::new("altitude_m", DataType::Float64, true),
+            Field::new("qc_reasons", DataType::Utf8, false),
+        ]))
+    }
+
+    fn compression(kind: CompressionType) -> Compression {
+        match kind {
+            CompressionType::None => Compression::UNCOMPRESSED,
+            CompressionType::Snappy => Compression::SNAPPY,
+            CompressionType::Gzip => Compression::GZIP(GzipLevel::default()),
+            CompressionType::Zstd => Compression::ZSTD(ZstdLevel::default()),
+            CompressionType::Lz4 => Compression::LZ4_RAW,
+        }
+    }
+
+    fn parse_quality_flag(value: &str) -> QualityFlag {
+        match value {
+            "valid" => QualityFlag::Valid,
+            "suspect" => QualityFlag::Suspect,
+            _ => QualityFlag::Invalid,
+        }
+    }
+
+    fn to_batch(readings: &[RadiationReading]) -> anyhow::Result<RecordBatch> {
+        let qc_reasons = readings
+            .iter()
+            .map(|r| serde_json::to_string(&r.qc_reasons))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(readings.iter().map(|r| r.sensor_id.to_string()))),
+            Arc::new(Int64Array::from_iter_values(readings.iter().map(|r| r.bucket))),
+            Arc::new(Int64Array::from_iter_values(readings.iter().map(|r| r.timestamp))),
+            Arc::new(Float64Array::from_iter_values(readings.iter().map(|r| r.latitude))),
+            Arc::new(Float64Array::from_iter_values(readings.iter().map(|r| r.longitude))),
+            Arc::new(Float64Array::from_iter_values(readings.iter().map(|r| r.dose_rate_microsieverts))),
+            Arc::new(Float32Array::from_iter_values(readings.iter().map(|r| r.uncertainty))),
+            Arc::new(StringArray::from_iter_values(readings.iter().map(|r| r.quality_flag.as_str()))),
+            Arc::new(StringArray::from_iter_values(readings.iter().map(|r| r.source.as_str()))),
+            Arc::new(StringArray::from_iter_values(readings.iter().map(|r| r.cell_id.as_str()))),
+            Arc::new(StringArray::from_iter(readings.iter().map(|r| r.track_id.map(|t| t.to_string())))),
+            Arc::new(Float64Array::from_iter(readings.iter().map(|r| r.altitude_m))),
+            Arc::new(StringArray::from_iter_values(qc_reasons)),
+        ];
+
+        Ok(RecordBatch::try_new(schema(), columns)?)
+    }
+
+    fn from_batch(batch: &RecordBatch) -> anyhow::Result<Vec<RadiationReading>> {
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .ok_or_else(|| anyhow::anyhow!("Archive is missing column {}", name))
+        };
+
+        let sensor_id = column("sensor_id")?.as_string::<i32>();
+        let bucket = column("bucket")?.as_primitive::<Int64Type>();
+        let timestamp = column("timestamp")?.as_primitive::<Int64Type>();
+        let latitude = column("latitude")?.as_primitive::<Float64Type>();
+        let longitude = column("longitude")?.as_primitive::<Float64Type>();
+        let dose_rate = column("dose_rate_microsieverts")?.as_primitive::<Float64Type>();
+        let uncertainty = column("uncertainty")?.as_primitive::<Float32Type>();
+        let quality_flag = column("quality_flag")?.as_string::<i32>();
+        let source = column("source")?.as_string::<i32>();
+        let cell_id = column("cell_id")?.as_string::<i32>();
+        let track_id = column("track_id")?.as_string::<i32>();
+        let altitude_m = column("altitude_m")?.as_primitive::<Float64Type>();
+        let qc_reasons = column("qc_reasons")?.as_string::<i32>();
+
+        (0..batch.num_rows())
+            .map(|i| {
+                Ok(RadiationReading {
+                    sensor_id: Uuid::parse_str(sensor_id.value(i))?,
+                    bucket: bucket.value(i),
+                    timestamp: timestamp.value(i),
+                    latitude: latitude.value(i),
+                    longitude: longitude.value(i),
+                    dose_rate_microsieverts: dose_rate.value(i),
+                    uncertainty: uncertainty.value(i),
+                    quality_flag: parse_quality_flag(quality_flag.value(i)),
+                    source: source.value(i).to_string(),
+                    cell_id: cell_id.value(i).to_string(),
+                    track_id: if track_id.is_null(i) {
+                        None
+                    } else {
+                        Some(Uuid::parse_str(track_id.value(i))?)
+                    },
+                    altitude_m: (!altitude_m.is_null(i)).then(|| altitude_m.value(i)),
+                    qc_reasons: serde_json::from_str(qc_reasons.value(i))?,
+                    provenance: None,
+                })
+            })
+            .collect()
+    }
+
+    pub(super) fn write_file(
+        path: &Path,
+        readings: &[RadiationReading],
+        kind: CompressionType,
+        row_group_size: usize,
+    ) -> anyhow::Result<()> {
+        let mut writer = ParquetWriter::create(path, kind, row_group_size, Vec::new())?;
+        writer.write(readings)?;
+        writer.close()
+    }
+
+    /// Parquet file written a batch of readings at a time
+    pub(crate) struct ParquetWriter {
+        writer: ArrowWriter<File>,
+    }
+
+    impl ParquetWriter {
+        /// Create `path`, with `metadata` stored as key-value pairs in the footer
+        pub(crate) fn create(
+            path: &Path,
+            kind: CompressionType,
+            row_group_size: usize,
+            metadata: Vec<(String, String)>,
+        ) -> anyhow::Result<Self> {
+            let metadata: Vec<KeyValue> = metadata.into_iter().map(|(k, v)| KeyValue::new(k, v)).collect();
+            let props = WriterProperties::builder()
+                .set_compression(compression(kind))
+                .set_max_row_group_size(row_group_size)
+                .set_key_value_metadata((!metadata.is_empty()).then_some(metadata))
+                .build();
+
+            let file = File::create(path)?;
+            Ok(Self {
+                writer: ArrowWriter::try_new(file, schema(), Some(props))?,
+            })
+        }
+
+        pub(crate) fn write(&mut self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+            self.writer.write(&to_batch(readings)?)?;
+            Ok(())
+        }
+
+        pub(crate) fn close(self) -> anyhow::Result<()> {
+            self.writer.close()?;
+            Ok(())
+        }
+    }
+
+    /// Whether column statistics allow a row group to contain matching rows
+    fn row_group_may_match(row_group: &RowGroupMetaData, filter: &RangeFilter) -> bool {
+        if let Some(Statistics::Int64(stats)) = row_group.column(TIMESTAMP).statistics() {
+            if let (Some(min), Some(max)) = (stats.min_opt(), stats.max_opt()) {
+                if *max < filter.start || *min > filter.end {
+                    return false;
+                }
+            }
+        }
+
+        if filter.sensor_ids.is_empty() {
+            return true;
+        }
+
+        match row_group.column(SENSOR_ID).statistics() {
+            Some(Statistics::ByteArray(stats)) => match (stats.min_bytes_opt(), stats.max_bytes_opt()) {
+                (Some(min), Some(max)) => filter
+                    .sensor_ids
+                    .iter()
+                    .any(|id| id.as_bytes() >= min && id.as_bytes() <= max),
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+
+    pub(super) fn read_file(path: &Path, filter: &RangeFilter) -> anyhow::Result<Vec<RadiationReading>> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?;
+
+        let row_groups: Vec<usize> = builder
+            .metadata()
+            .row_groups()
+            .iter()
+            .enumerate()
+            .filter(|(_, rg)| row_group_may_match(rg, filter))
+            .map(|(i, _)| i)
+            .collect();
+        if row_groups.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Evaluate the predicate on the two key columns before decoding the rest
+        let mask = ProjectionMask::roots(builder.parquet_schema(), [SENSOR_ID, TIMESTAMP]);
+        let row_filter = filter.clone();
+        let predicate = ArrowPredicateFn::new(mask, move |batch: RecordBatch| {
+            let sensor_id = batch.column(0).as_string::<i32>();
+            let timestamp = batch.column(1).as_primitive::<Int64Type>();
+            let matches: BooleanArray = (0..batch.num_rows())
+                .map(|i| Some(row_filter.matches(sensor_id.value(i), timestamp.value(i))))
+                .collect();
+            Ok::<_, ArrowError>(matches)
+        });
+
+        let reader = builder
+            .with_row_groups(row_groups)
+            .with_row_filter(RowFilter::new(vec![Box::new(predicate)]))
+            .build()?;
+
+        let mut readings = Vec::new();
+        for batch in reader {
+            readings.extend(from_batch(&batch?)?);
+        }
+        Ok(readings)
+    }
+}
+
+#[cfg(not(feature = "cold-storage"))]
+mod parquet_io {
+    use std::path::Path;
+
+    use super::{CompressionType, RangeFilter};
+    use crate::RadiationReading;
+
+    pub(super) fn write_file(
+        _path: &Path,
+        _readings: &[RadiationReading],
+        _kind: CompressionType,
+        _row_group_size: usize,
+    ) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("Parquet archives require the `cold-storage` feature"))
+    }
+
+    pub(super) fn read_file(_path: &Path, _filter: &RangeFilter) -> anyhow::Result<Vec<RadiationReading>> {
+        Err(anyhow::anyhow!("Parquet archives require the `cold-storage` feature"))
+    }
+
+    pub(crate) struct ParquetWriter;
+
+    impl ParquetWriter {
+        pub(crate) fn create(
+            _path: &Path,
+            _kind: CompressionType,
+            _row_group_size: usize,
+            _metadata: Vec<(String, String)>,
+        ) -> anyhow::Result<Self> {
+            Err(anyhow::anyhow!("Parquet files require the `cold-storage` feature"))
+        }
+
+        pub(crate) fn write(&mut self, _readings: &[RadiationReading]) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub(crate) fn close(self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+pub(crate) use parquet_io::ParquetWriter;
+
 #[derive(Debug, Clone)]
 pub struct ArchiveInfo {
     pub filename: String,
+    pub path: String,
     pub start_timestamp: i64,
     pub end_timestamp: i64,
     pub size_bytes: u64,
@@ -210,6 +631,8 @@ pub struct ColdStorageConfig {
     pub s3_endpoint: Option<String>,
     pub compression: CompressionType,
     pub retention_days: u32,
+    /// Maximum rows per Parquet row group
+    pub row_group_size: usize,
 }
 
 impl Default for ColdStorageConfig {
@@ -221,6 +644,7 @@ impl Default for ColdStorageConfig {
             s3_endpoint: None,
             compression: CompressionType::Zstd,
             retention_days: 365,
+            row_group_size: 65_536,
         }
     }
 }
@@ -261,9 +685,9 @@ pub mod s3 {
 
     impl S3ColdStorage {
         pub async fn new(config: &ColdStorageConfig) -> anyhow::Result<Self> {
-            let aws_config = aws_config::load_from_env().await;
+            let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
             let client = S3Client::new(&aws_config);
-            
+
             let bucket = config.s3_bucket.clone()
                 .ok_or_else(|| anyhow::anyhow!("S3 bucket not configured"))?;
 
@@ -280,7 +704,7 @@ pub mod s3 {
             key: &str,
         ) -> anyhow::Result<()> {
             let stream = ByteStream::from(data);
-            
+
             self.client
                 .put_object()
                 .bucket(&self.bucket)
@@ -0,0 +1,295 @@
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::Path;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::projection::{Checkpoint, StoredEvent};
+use crate::query::{AnomalyQuery, EventQuery, Page, SortOrder};
+use crate::schema::Replication;
+use crate::scylla::ScyllaConfig;
+use crate::sqlite::AnomalyRecord;
+use crate::{DomainEvent, RadiationReading};
+
+/// Storage for raw readings, implemented by every tier that holds them
+#[async_trait]
+pub trait ReadingStore: Send + Sync {
+    async fn write_reading(&self, reading: &RadiationReading) -> anyhow::Result<()>;
+
+    async fn write_batch(&self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        for reading in readings {
+            self.write_reading(reading).await?;
+        }
+        Ok(())
+    }
+
+    /// Readings of one sensor with `from <= timestamp <= to`
+    async fn query_by_time_range(&self, sensor_id: Uuid, from: i64, to: i64) -> anyhow::Result<Vec<RadiationReading>>;
+
+    /// The first `limit` readings of one sensor with `from <= timestamp <= to`, in `order`
+    ///
+    /// Stores that can bound the read should; this default reads the whole range.
+    async fn query_sensor_page(
+        &self,
+        sensor_id: Uuid,
+        from: i64,
+        to: i64,
+        order: SortOrder,
+        limit: usize,
+    ) -> anyhow::Result<Vec<RadiationReading>> {
+        let mut readings = self.query_by_time_range(sensor_id, from, to).await?;
+        readings.sort_by_key(|r| r.timestamp);
+        if order == SortOrder::Descending {
+            readings.reverse();
+        }
+        readings.truncate(limit);
+        Ok(readings)
+    }
+
+    /// Readings inside a geohash cell with `from <= timestamp <= to`
+    async fn query_by_geohash(&self, geohash: &str, from: i64, to: i64) -> anyhow::Result<Vec<RadiationReading>>;
+
+    async fn latest_reading(&self, sensor_id: Uuid) -> anyhow::Result<Option<RadiationReading>>;
+
+    /// All readings in one hour bucket, across sensors
+    async fn query_bucket(&self, bucket: i64) -> anyhow::Result<Vec<RadiationReading>>;
+
+    /// Delete readings by `(sensor_id, bucket, timestamp)`
+    async fn delete_readings(&self, readings: &[RadiationReading]) -> anyhow::Result<()>;
+
+    async fn health_check(&self) -> bool;
+}
+
+/// Audit trail of domain events and the anomalies derived from them
+#[async_trait]
+pub trait EventStore: Send + Sync {
+    async fn store_event(&self, event: &DomainEvent) -> anyhow::Result<()>;
+
+    /// Anomalies detected at or after `since`, newest first
+    async fn get_anomalies(&self, since: i64, limit: usize) -> anyhow::Result<Vec<AnomalyRecord>>;
+
+    /// Anomalies detected in the last `hours`
+    async fn get_anomaly_count(&self, hours: i64) -> anyhow::Result<i64>;
+
+    /// Up to `query.limit` anomalies matching a query, in its order and past its cursor
+    async fn query_anomalies(&self, query: &AnomalyQuery) -> anyhow::Result<Vec<AnomalyRecord>> {
+        let matching = self.get_anomalies(query.since, usize::MAX).await?
+            .into_iter()
+            .filter(|a| query.matches(a))
+            .collect();
+        let page = Page::paginate(
+            matching,
+            |a: &AnomalyRecord| (a.detected_at, a.anomaly_id.clone()),
+            query.order,
+            query.cursor.as_ref(),
+            Some(query.limit),
+        );
+        Ok(page.items)
+    }
+
+    /// Up to `query.limit` logged events matching a query, in sequence order
+    async fn query_events(&self, query: &EventQuery) -> anyhow::Result<Vec<StoredEvent>>;
+
+    async fn load_checkpoint(&self, name: &str) -> anyhow::Result<Option<Checkpoint>>;
+
+    /// Save a projection's checkpoint, unless the stored one is further along
+    async fn save_checkpoint(&self, checkpoint: &Checkpoint) -> anyhow::Result<()>;
+}
+
+/// Key-value cache holding serialized values with a TTL
+#[async_trait]
+pub trait Cache: Send + Sync {
+    async fn get_raw(&self, key: &str) -> anyhow::Result<Option<String>>;
+
+    async fn set_raw(&self, key: &str, value: String, ttl_seconds: u64) -> anyhow::Result<()>;
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+
+    /// Remove every key starting with `prefix`
+    async fn delete_prefix(&self, prefix: &str) -> anyhow::Result<()>;
+
+    /// Values of several keys, in one round trip where the backend allows
+    async fn get_many(&self, keys: &[String]) -> anyhow::Result<Vec<Option<String>>> {
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            values.push(self.get_raw(key).await?);
+        }
+        Ok(values)
+    }
+
+    /// Set several keys with the same TTL, in one round trip where the backend allows
+    async fn set_many(&self, entries: &[(String, String)], ttl_seconds: u64) -> anyhow::Result<()> {
+        for (key, value) in entries {
+            self.set_raw(key, value.clone(), ttl_seconds).await?;
+        }
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool;
+}
+
+/// Typed helpers shared by every cache, using the same key layout as `RedisCache`
+impl dyn Cache {
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        match self.get_raw(key).await? {
+            Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn set_json<T: Serialize + ?Sized>(&self, key: &str, value: &T, ttl_seconds: u64) -> anyhow::Result<()> {
+        self.set_raw(key, serde_json::to_string(value)?, ttl_seconds).await
+    }
+
+    pub async fn get_sensor_latest(&self, sensor_id: &Uuid) -> anyhow::Result<Option<RadiationReading>> {
+        self.get_json(&format!("sensor:{}:latest", sensor_id)).await
+    }
+
+    pub async fn set_sensor_latest(
+        &self,
+        sensor_id: &Uuid,
+        reading: &RadiationReading,
+        ttl_seconds: u64,
+    ) -> anyhow::Result<()> {
+        self.set_json(&format!("sensor:{}:latest", sensor_id), reading, ttl_seconds).await
+    }
+
+    pub async fn get_query_result<T: DeserializeOwned>(&self, cache_key: &str) -> anyhow::Result<Option<T>> {
+        self.get_json(&format!("query:{}", cache_key)).await
+    }
+
+    pub async fn set_query_result<T: Serialize + ?Sized>(
+        &self,
+        cache_key: &str,
+        points: &T,
+        ttl_seconds: u64,
+    ) -> anyhow::Result<()> {
+        self.set_json(&format!("query:{}", cache_key), points, ttl_seconds).await
+    }
+
+    pub async fn invalidate_sensor(&self, sensor_id: &Uuid) -> anyhow::Result<()> {
+        self.delete_prefix(&format!("sensor:{}:", sensor_id)).await
+    }
+}
+
+/// Backend for the hot tier
+#[derive(Debug, Clone)]
+pub enum HotTier {
+    Scylla(ScyllaConfig),
+    /// SQLite database file, kept separate from the warm tier
+    Sqlite(String),
+    /// Process memory, lost on restart
+    Memory,
+}
+
+/// Backend for the cache
+#[derive(Debug, Clone)]
+pub enum CacheTier {
+    Redis(String),
+    Memory,
+}
+
+/// Which implementation backs each tier
+///
+/// The warm tier is always SQLite, either a file or `:memory:`.
+#[derive(Debug, Clone)]
+pub struct StorageBackends {
+    pub hot: HotTier,
+    pub warm_path: String,
+    pub cache: CacheTier,
+}
+
+impl StorageBackends {
+    /// ScyllaDB, SQLite and Redis, as in a full deployment
+    pub fn cluster(scylla: ScyllaConfig, sqlite_path: &str, redis_url: &str) -> Self {
+        Self {
+            hot: HotTier::Scylla(scylla),
+            warm_path: sqlite_path.to_string(),
+            cache: CacheTier::Redis(redis_url.to_string()),
+        }
+    }
+
+    /// SQLite files under `data_dir` and an in-process cache, no external services
+    pub fn embedded(data_dir: impl AsRef<Path>) -> Self {
+        let data_dir = data_dir.as_ref();
+        Self {
+            hot: HotTier::Sqlite(data_dir.join("cherenkov_hot.db").to_string_lossy().into_owned()),
+            warm_path: data_dir.join("cherenkov_warm.db").to_string_lossy().into_owned(),
+            cache: CacheTier::Memory,
+        }
+    }
+
+    /// Everything in process memory, for tests and demos
+    pub fn in_memory() -> Self {
+        Self {
+            hot: HotTier::Memory,
+            warm_path: ":memory:".to_string(),
+            cache: CacheTier::Memory,
+        }
+    }
+
+    /// Backends chosen by environment
+    ///
+    /// `STORAGE_PROFILE` picks `cluster` (default), `embedded` or `memory`;
+    /// `HOT_TIER` (`scylla`, `sqlite`, `memory`) and `CACHE_TIER` (`redis`,
+    /// `memory`) then override single tiers. `DATA_DIR`, `SCYLLA_HOSTS`,
+    /// `SCYLLA_KEYSPACE` and `REDIS_URL` locate the backends, and
+    /// `SCYLLA_REPLICATION_STRATEGY` (`simple`, `network`) with
+    /// `SCYLLA_REPLICATION_FACTOR` set up a keyspace created by migrations.
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        let data_dir = var("DATA_DIR").unwrap_or_else(|| "./data".to_string());
+        let redis_url = var("REDIS_URL").unwrap_or_else(|| "redis://127.0.0.1:6379".to_string());
+        let mut scylla = ScyllaConfig::default();
+        if let Some(nodes) = var("SCYLLA_HOSTS") {
+            scylla.nodes = nodes.split(',').map(|n| n.trim().to_string()).collect();
+        }
+        if let Some(keyspace) = var("SCYLLA_KEYSPACE") {
+            scylla.keyspace = keyspace;
+        }
+        let replication_factor = var("SCYLLA_REPLICATION_FACTOR").and_then(|f| f.parse().ok());
+        match var("SCYLLA_REPLICATION_STRATEGY") {
+            Some(strategy) => {
+                // A single node can only hold one replica
+                let factor = replication_factor.unwrap_or(if strategy.eq_ignore_ascii_case("simple") { 1 } else { 3 });
+                match Replication::parse(&strategy, factor) {
+                    Some(replication) => scylla.replication = replication,
+                    None => warn!("Unknown SCYLLA_REPLICATION_STRATEGY {}, keeping {:?}", strategy, scylla.replication),
+                }
+            }
+            None => {
+                if let Some(replication_factor) = replication_factor {
+                    scylla.replication = Replication::NetworkTopology { replication_factor };
+                }
+            }
+        }
+
+        let mut backends = match var("STORAGE_PROFILE").as_deref() {
+            Some("embedded") => Self::embedded(&data_dir),
+            Some("memory") => Self::in_memory(),
+            _ => Self::cluster(
+                scylla.clone(),
+                &Path::new(&data_dir).join("cherenkov_warm.db").to_string_lossy(),
+                &redis_url,
+            ),
+        };
+
+        match var("HOT_TIER").as_deref() {
+            Some("scylla") => backends.hot = HotTier::Scylla(scylla),
+            Some("sqlite") => {
+                backends.hot = HotTier::Sqlite(Path::new(&data_dir).join("cherenkov_hot.db").to_string_lossy().into_owned())
+            }
+            Some("memory") => backends.hot = HotTier::Memory,
+            _ => {}
+        }
+
+        match var("CACHE_TIER").as_deref() {
+            Some("redis") => backends.cache = CacheTier::Redis(redis_url),
+            Some("memory") => backends.cache = CacheTier::Memory,
+            _ => {}
+        }
+
+        backends
+    }
+}
@@ -0,0 +1,382 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::sqlite::SqliteStorage;
+use crate::storage::ColdStorage;
+use crate::store::ReadingStore;
+use crate::{DatabaseConfig, RadiationReading, BUCKET_SECS};
+
+/// Width of the buckets some sources used to store readings in, before the
+/// bucket was derived from the timestamp
+const LEGACY_BUCKET_SECS: i64 = 86_400;
+
+/// Scheduling for the background tier migration job
+#[derive(Debug, Clone)]
+pub struct TieringConfig {
+    /// Time between migration runs
+    pub interval: std::time::Duration,
+    /// Readings deleted from a source tier per statement batch
+    pub delete_batch_size: usize,
+    /// Where the migration cursor is persisted, `None` keeps it in memory
+    pub state_path: Option<String>,
+}
+
+impl Default for TieringConfig {
+    fn default() -> Self {
+        Self {
+            interval: std::time::Duration::from_secs(3600),
+            delete_batch_size: 500,
+            state_path: Some("./data/tiering_state.json".to_string()),
+        }
+    }
+}
+
+/// Progress through the hot tier, persisted between runs
+///
+/// The warm tier needs no cursor: whatever is still there past its retention
+/// is by definition not yet migrated.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TieringCursor {
+    /// First hour bucket not yet moved out of the hot tier
+    pub next_hot_bucket: Option<i64>,
+}
+
+/// What a single migration run did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TieringReport {
+    pub hot_to_warm: u64,
+    pub warm_to_cold: u64,
+    /// Warm readings dropped past retention because the cold tier is disabled
+    pub warm_expired: u64,
+    pub cold_archives_expired: u64,
+}
+
+/// Moves readings hot → warm → cold as they age and enforces retention
+///
+/// Every move copies first, reads the destination back, and only deletes
+/// from the source once every key is confirmed present. Copies are keyed by
+/// `(sensor_id, timestamp)`, so a run interrupted at any point can simply be
+/// repeated without duplicating data.
+pub struct TierMigrator {
+    hot: Option<Arc<dyn ReadingStore>>,
+    warm: Arc<SqliteStorage>,
+    cold: Arc<ColdStorage>,
+    hot_retention: Duration,
+    warm_retention: Duration,
+    cold_retention: Option<Duration>,
+    config: TieringConfig,
+    cursor: TieringCursor,
+}
+
+impl TierMigrator {
+    pub fn new(
+        warm: Arc<SqliteStorage>,
+        cold: Arc<ColdStorage>,
+        retention: &DatabaseConfig,
+        config: TieringConfig,
+    ) -> Self {
+        let cursor = config
+            .state_path
+            .as_deref()
+            .and_then(|path| load_cursor(Path::new(path)))
+            .unwrap_or_default();
+
+        Self {
+            hot: None,
+            warm,
+            cold,
+            hot_retention: Duration::days(retention.hot_retention_days),
+            warm_retention: Duration::days(retention.warm_retention_days),
+            cold_retention: retention.cold_retention_days.map(Duration::days),
+            config,
+            cursor,
+        }
+    }
+
+    /// Also drain the hot tier; without it only warm and cold are managed
+    pub fn with_hot(mut self, hot: Arc<dyn ReadingStore>) -> Self {
+        self.hot = Some(hot);
+        self
+    }
+
+    pub fn cursor(&self) -> &TieringCursor {
+        &self.cursor
+    }
+
+    /// Run on the configured interval until `shutdown` is cancelled
+    pub async fn run(mut self, shutdown: CancellationToken) {
+        let mut interval = tokio::time::interval(self.config.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+
+            match self.run_once(Utc::now(), &shutdown).await {
+                Ok(report) if report == TieringReport::default() => debug!("Tier migration: nothing to do"),
+                Ok(report) => info!("Tier migration finished: {:?}", report),
+                Err(e) => error!("Tier migration failed: {}", e),
+            }
+        }
+
+        info!("Tier migration stopped");
+    }
+
+    /// One full pass over all tiers as of `now`
+    ///
+    /// Stops between units of work once `shutdown` is cancelled; the next run
+    /// picks up where this one left off.
+    pub async fn run_once(
+        &mut self,
+        now: DateTime<Utc>,
+        shutdown: &CancellationToken,
+    ) -> anyhow::Result<TieringReport> {
+        let mut report = TieringReport::default();
+
+        // Hot first, so readings that aged past both cutoffs continue to cold in the same run
+        if let Some(hot) = self.hot.clone() {
+            self.migrate_hot(hot.as_ref(), now, shutdown, &mut report).await?;
+        }
+        self.migrate_warm(now, shutdown, &mut report).await?;
+        self.expire_cold(now, &mut report).await?;
+
+        Ok(report)
+    }
+
+    async fn migrate_hot(
+        &mut self,
+        hot: &dyn ReadingStore,
+        now: DateTime<Utc>,
+        shutdown: &CancellationToken,
+        report: &mut TieringReport,
+    ) -> anyhow::Result<()> {
+        // Buckets before this one lie entirely past hot retention
+        let cutoff_bucket = (now - self.hot_retention).timestamp().div_euclid(BUCKET_SECS);
+        let first_bucket = cutoff_bucket - self.warm_retention.num_hours();
+        let mut bucket = self.cursor.next_hot_bucket.unwrap_or(first_bucket);
+
+        while bucket < cutoff_bucket && !shutdown.is_cancelled() {
+            let readings = hot.query_bucket(bucket).await?;
+            self.move_to_warm(hot, &readings, report).await?;
+
+            bucket += 1;
+            self.cursor.next_hot_bucket = Some(bucket);
+            self.save_cursor().await?;
+        }
+
+        let lag = (cutoff_bucket - bucket).max(0) * BUCKET_SECS;
+        metrics::gauge!("cherenkov_db_tiering_lag_seconds", "tier" => "hot").set(lag as f64);
+
+        self.migrate_legacy_hot(hot, now, shutdown, report).await
+    }
+
+    /// Move hot readings stored under day buckets, numbered far below any
+    /// hour bucket of real data, so the hour walk never reaches them
+    async fn migrate_legacy_hot(
+        &mut self,
+        hot: &dyn ReadingStore,
+        now: DateTime<Utc>,
+        shutdown: &CancellationToken,
+        report: &mut TieringReport,
+    ) -> anyhow::Result<()> {
+        let cutoff = (now - self.hot_retention).timestamp();
+        let first_day = (cutoff - self.warm_retention.num_seconds()).div_euclid(LEGACY_BUCKET_SECS);
+
+        for day in first_day..=cutoff.div_euclid(LEGACY_BUCKET_SECS) {
+            if shutdown.is_cancelled() {
+                break;
+            }
+            let readings: Vec<RadiationReading> = hot.query_bucket(day).await?
+                .into_iter()
+                .filter(|r| r.bucket != RadiationReading::bucket_for(r.timestamp) && r.timestamp < cutoff)
+                .collect();
+            self.move_to_warm(hot, &readings, report).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Copy readings to the warm tier under their hour bucket, verify, then
+    /// delete them from the hot tier under the key they were stored with
+    async fn move_to_warm(
+        &self,
+        hot: &dyn ReadingStore,
+        readings: &[RadiationReading],
+        report: &mut TieringReport,
+    ) -> anyhow::Result<()> {
+        let (Some(first), Some(last)) = (
+            readings.iter().map(|r| r.timestamp).min(),
+            readings.iter().map(|r| r.timestamp).max(),
+        ) else {
+            return Ok(());
+        };
+
+        for reading in readings {
+            let bucket = RadiationReading::bucket_for(reading.timestamp);
+            self.warm.write_reading(&RadiationReading { bucket, ..reading.clone() }).await?;
+        }
+
+        let copied = self.warm.readings_in_window(timestamp(first)?, timestamp(last + 1)?).await?;
+        self.verify("warm", readings, &copied)?;
+
+        for chunk in readings.chunks(self.config.delete_batch_size.max(1)) {
+            hot.delete_readings(chunk).await?;
+        }
+
+        report.hot_to_warm += readings.len() as u64;
+        metrics::counter!(
+            "cherenkov_db_tiering_moved_total",
+            "from" => "hot", "to" => "warm"
+        ).increment(readings.len() as u64);
+
+        Ok(())
+    }
+
+    async fn migrate_warm(
+        &mut self,
+        now: DateTime<Utc>,
+        shutdown: &CancellationToken,
+        report: &mut TieringReport,
+    ) -> anyhow::Result<()> {
+        let cutoff = now - self.warm_retention;
+
+        if !self.cold.is_enabled() {
+            // Nowhere to move it, so retention means dropping it
+            if self.warm.oldest_reading_time().await?.is_some_and(|oldest| oldest < cutoff) {
+                let expired = self.warm.archive_old_data(cutoff).await?;
+                report.warm_expired += expired;
+                metrics::counter!("cherenkov_db_tiering_expired_total", "tier" => "warm").increment(expired);
+            }
+            return Ok(());
+        }
+
+        // One UTC day at a time, matching the cold tier's date partitions
+        while !shutdown.is_cancelled() {
+            let Some(oldest) = self.warm.oldest_reading_time().await? else {
+                break;
+            };
+            if oldest >= cutoff {
+                break;
+            }
+
+            let day_start = oldest
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .map(|d| d.and_utc())
+                .unwrap_or(oldest);
+            let end = (day_start + Duration::days(1)).min(cutoff);
+
+            let readings = self.warm.readings_in_window(day_start, end).await?;
+            let window_end = end - Duration::seconds(1);
+
+            // A previous run may have archived this window before being interrupted
+            let archived = self.cold.query_range(&[], day_start, window_end).await?;
+            let pending = missing(&readings, &archived);
+            if !pending.is_empty() {
+                self.cold.archive_readings(&pending).await?;
+            }
+
+            let archived = self.cold.query_range(&[], day_start, window_end).await?;
+            self.verify("cold", &readings, &archived)?;
+
+            let mut deleted = 0;
+            for chunk in readings.chunks(self.config.delete_batch_size.max(1)) {
+                deleted += self.warm.delete_readings(chunk).await?;
+            }
+            if deleted == 0 {
+                anyhow::bail!("No warm readings deleted for window starting {}, stopping", day_start);
+            }
+
+            report.warm_to_cold += deleted;
+            metrics::counter!(
+                "cherenkov_db_tiering_moved_total",
+                "from" => "warm", "to" => "cold"
+            ).increment(deleted);
+        }
+
+        let lag = match self.warm.oldest_reading_time().await? {
+            Some(oldest) if oldest < cutoff => (cutoff - oldest).num_seconds(),
+            _ => 0,
+        };
+        metrics::gauge!("cherenkov_db_tiering_lag_seconds", "tier" => "warm").set(lag as f64);
+
+        Ok(())
+    }
+
+    async fn expire_cold(&self, now: DateTime<Utc>, report: &mut TieringReport) -> anyhow::Result<()> {
+        let Some(retention) = self.cold_retention else {
+            return Ok(());
+        };
+
+        let expired = self.cold.delete_old_archives(now - retention).await?;
+        report.cold_archives_expired += expired;
+        metrics::counter!("cherenkov_db_tiering_expired_total", "tier" => "cold").increment(expired);
+
+        Ok(())
+    }
+
+    /// Refuse to delete from the source unless every reading reached `tier`
+    fn verify(
+        &self,
+        tier: &'static str,
+        expected: &[RadiationReading],
+        found: &[RadiationReading],
+    ) -> anyhow::Result<()> {
+        let absent = missing(expected, found).len();
+        if absent == 0 {
+            return Ok(());
+        }
+
+        metrics::counter!("cherenkov_db_tiering_verify_failures_total", "tier" => tier).increment(1);
+        warn!("{} of {} readings missing from {} tier after copy", absent, expected.len(), tier);
+        anyhow::bail!("Verification failed: {} readings missing from {} tier, source left intact", absent, tier)
+    }
+
+    async fn save_cursor(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.config.state_path else {
+            return Ok(());
+        };
+
+        let path = PathBuf::from(path);
+        let data = serde_json::to_vec(&self.cursor)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+}
+
+/// Readings from `expected` whose key does not appear in `found`
+fn missing(expected: &[RadiationReading], found: &[RadiationReading]) -> Vec<RadiationReading> {
+    let present: HashSet<(Uuid, i64)> = found.iter().map(|r| (r.sensor_id, r.timestamp)).collect();
+    expected
+        .iter()
+        .filter(|r| !present.contains(&(r.sensor_id, r.timestamp)))
+        .cloned()
+        .collect()
+}
+
+fn load_cursor(path: &Path) -> Option<TieringCursor> {
+    let data = std::fs::read(path).ok()?;
+    match serde_json::from_slice(&data) {
+        Ok(cursor) => Some(cursor),
+        Err(e) => {
+            warn!("Ignoring unreadable tiering cursor {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn timestamp(secs: i64) -> anyhow::Result<DateTime<Utc>> {
+    DateTime::from_timestamp(secs, 0).ok_or_else(|| anyhow::anyhow!("Invalid timestamp {}", secs))
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::haversine_distance;
+
+/// Summary of a mobile survey session (bGeigie drive, drone flight)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackSummary {
+    pub track_id: Uuid,
+    pub sensor_id: Uuid,
+    pub source: String,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub point_count: i64,
+    pub max_dose_rate: f64,
+}
+
+/// Single position along a track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackPoint {
+    pub timestamp: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_m: Option<f64>,
+    /// Ground speed from the previous point, `None` for the first point
+    pub speed_kmh: Option<f64>,
+    pub dose_rate_microsieverts: f64,
+}
+
+/// Track with its ordered geo-path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Track {
+    pub summary: TrackSummary,
+    pub points: Vec<TrackPoint>,
+    pub distance_km: f64,
+}
+
+impl Track {
+    /// Build a track from points, ordering them and deriving speed and distance
+    pub fn new(summary: TrackSummary, mut points: Vec<TrackPoint>) -> Self {
+        points.sort_by_key(|p| p.timestamp);
+
+        let mut distance_km = 0.0;
+        for i in 1..points.len() {
+            let (prev, next) = (&points[i - 1], &points[i]);
+            let km = haversine_distance(prev.latitude, prev.longitude, next.latitude, next.longitude);
+            let hours = (next.timestamp - prev.timestamp) as f64 / 3600.0;
+            distance_km += km;
+            points[i].speed_kmh = (hours > 0.0).then(|| km / hours);
+        }
+
+        Self {
+            summary,
+            points,
+            distance_km,
+        }
+    }
+
+    /// GeoJSON FeatureCollection with one LineString per segment, colored by dose
+    ///
+    /// Each segment carries the mean dose rate of its endpoints so clients can
+    /// style the path without recomputing anything.
+    pub fn to_geojson(&self) -> serde_json::Value {
+        let features: Vec<_> = self.points
+            .windows(2)
+            .map(|pair| {
+                let (a, b) = (&pair[0], &pair[1]);
+                let dose = (a.dose_rate_microsieverts + b.dose_rate_microsieverts) / 2.0;
+                json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "LineString",
+                        "coordinates": [position(a), position(b)],
+                    },
+                    "properties": {
+                        "start": a.timestamp,
+                        "end": b.timestamp,
+                        "dose_rate": dose,
+                        "color": dose_color(dose),
+                        "speed_kmh": b.speed_kmh,
+                        "altitude_m": b.altitude_m,
+                    },
+                })
+            })
+            .collect();
+
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+            "properties": {
+                "track_id": self.summary.track_id,
+                "sensor_id": self.summary.sensor_id,
+                "source": self.summary.source,
+                "started_at": self.summary.started_at,
+                "ended_at": self.summary.ended_at,
+                "distance_km": self.distance_km,
+            },
+        })
+    }
+}
+
+/// GeoJSON position, including altitude when known
+fn position(point: &TrackPoint) -> Vec<f64> {
+    match point.altitude_m {
+        Some(altitude) => vec![point.longitude, point.latitude, altitude],
+        None => vec![point.longitude, point.latitude],
+    }
+}
+
+/// Map color for a dose rate in µSv/h, following the Safecast map scale
+pub fn dose_color(dose_rate: f64) -> &'static str {
+    match dose_rate {
+        d if d < 0.1 => "#00ff00",
+        d if d < 0.2 => "#7fff00",
+        d if d < 0.5 => "#ffff00",
+        d if d < 1.0 => "#ffa500",
+        d if d < 5.0 => "#ff0000",
+        _ => "#800080",
+    }
+}
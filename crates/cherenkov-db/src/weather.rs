@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// Weather over one hour at a point, as reported by a weather source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherObservation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Start of the hour, unix seconds
+    pub timestamp: i64,
+    pub source: String,
+    /// Precipitation over the hour
+    pub precipitation_mm: f64,
+}
+
+impl WeatherObservation {
+    /// Start of the hour `timestamp` falls in
+    pub fn hour_of(timestamp: i64) -> i64 {
+        timestamp - timestamp.rem_euclid(3600)
+    }
+}
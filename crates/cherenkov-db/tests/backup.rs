@@ -0,0 +1,178 @@
+//! Warm tier snapshots, WAL archiving and point-in-time restore.
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::{ConnectOptions, Connection};
+use uuid::Uuid;
+
+use cherenkov_db::backup::{self, BackupConfig, WarmBackup};
+use cherenkov_db::sqlite::SqliteStorage;
+use cherenkov_db::{DomainEvent, EventQuery, EventType};
+use common::temp_dir;
+
+async fn open(dir: &Path) -> (Arc<SqliteStorage>, WarmBackup) {
+    let path = dir.join("warm.db").to_string_lossy().into_owned();
+    let warm = Arc::new(SqliteStorage::with_wal_archiving(&path).await.expect("sqlite opens"));
+    warm.run_migrations().await.unwrap();
+    let backup = WarmBackup::new(warm.clone(), BackupConfig::new(dir.join("backups"))).unwrap();
+    (warm, backup)
+}
+
+async fn store_events(warm: &SqliteStorage, count: usize) {
+    for _ in 0..count {
+        let event = DomainEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: EventType::AlertTriggered,
+            aggregate_id: Uuid::new_v4(),
+            payload: serde_json::json!({ "padding": "x".repeat(2000) }),
+            timestamp: Utc::now().timestamp(),
+        };
+        warm.store_event(&event).await.unwrap();
+    }
+}
+
+async fn count_events(path: &Path) -> usize {
+    let restored = SqliteStorage::new(&path.to_string_lossy()).await.unwrap();
+    restored.query_events(&EventQuery::all().limit(usize::MAX)).await.unwrap().len()
+}
+
+async fn pause() {
+    tokio::time::sleep(Duration::from_millis(20)).await;
+}
+
+#[tokio::test]
+async fn test_restore_to_a_point_in_time() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let (warm, mut backup) = open(dir).await;
+    let backups = dir.join("backups");
+
+    store_events(&warm, 3).await;
+    let snapshot = backup.snapshot().await.unwrap();
+    assert_eq!(snapshot.tables["domain_events"].rows, 3);
+
+    store_events(&warm, 2).await;
+    assert!(backup.archive_wal().await.unwrap().archived_bytes > 0);
+    pause().await;
+    let midway = Utc::now();
+    pause().await;
+
+    store_events(&warm, 4).await;
+    let report = backup.archive_wal().await.unwrap();
+    assert!(report.archived_bytes > 0 && report.snapshot.is_none());
+
+    let earlier = backup::restore(&backups, &dir.join("midway.db"), Some(midway)).await.unwrap();
+    assert_eq!(earlier.snapshot, snapshot.taken_at);
+    assert_eq!(count_events(&earlier.path).await, 5);
+
+    let latest = backup::restore(&backups, &dir.join("latest.db"), None).await.unwrap();
+    assert_eq!(count_events(&latest.path).await, 9);
+    assert!(backup::restore(&backups, &latest.path, None).await.is_err(), "never overwrites");
+}
+
+#[tokio::test]
+async fn test_archive_follows_the_wal_across_restarts() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let (warm, mut backup) = open(dir).await;
+    let backups = dir.join("backups");
+
+    backup.snapshot().await.unwrap();
+    for _ in 0..3 {
+        store_events(&warm, 5).await;
+        let report = backup.archive_wal().await.unwrap();
+        assert!(report.checkpointed, "nothing else holds the WAL");
+    }
+    let verified = backup::verify(&backups).await.unwrap();
+    assert!(verified.is_ok(), "{:?}", verified);
+    assert!(verified.generations.len() >= 2, "a drained WAL restarts for the next write");
+
+    let latest = backup::restore(&backups, &dir.join("latest.db"), None).await.unwrap();
+    assert_eq!(count_events(&latest.path).await, 15);
+
+    // A checkpoint the archiver did not make may drop frames it never saw
+    store_events(&warm, 2).await;
+    let path = dir.join("warm.db");
+    let mut other = sqlx::sqlite::SqliteConnectOptions::new().filename(&path).connect().await.unwrap();
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut other).await.unwrap();
+    other.close().await.unwrap();
+    store_events(&warm, 1).await;
+
+    let report = backup.archive_wal().await.unwrap();
+    assert!(report.snapshot.is_some(), "a broken chain starts again from a snapshot");
+    store_events(&warm, 1).await;
+    backup.archive_wal().await.unwrap();
+
+    let after_break = backup::restore(&backups, &dir.join("after_break.db"), None).await.unwrap();
+    assert_eq!(count_events(&after_break.path).await, 19);
+}
+
+#[tokio::test]
+async fn test_forced_snapshots_are_pruned() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let path = dir.join("warm.db").to_string_lossy().into_owned();
+    let warm = Arc::new(SqliteStorage::with_wal_archiving(&path).await.unwrap());
+    warm.run_migrations().await.unwrap();
+    let backups = dir.join("backups");
+    let config = BackupConfig { keep_snapshots: 1, ..BackupConfig::new(&backups) };
+    let mut backup = WarmBackup::new(warm.clone(), config).unwrap();
+
+    backup.snapshot().await.unwrap();
+    for _ in 0..3 {
+        store_events(&warm, 2).await;
+        let mut other = sqlx::sqlite::SqliteConnectOptions::new().filename(&path).connect().await.unwrap();
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut other).await.unwrap();
+        other.close().await.unwrap();
+        store_events(&warm, 1).await;
+        pause().await;
+        assert!(backup.archive_wal().await.unwrap().snapshot.is_some());
+    }
+
+    assert_eq!(backup::list_snapshots(&backups).unwrap().len(), 1, "forced snapshots count towards the limit");
+    let latest = backup::restore(&backups, &dir.join("latest.db"), None).await.unwrap();
+    assert_eq!(count_events(&latest.path).await, 9);
+}
+
+#[tokio::test]
+async fn test_verify_detects_damaged_backups() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let (warm, mut backup) = open(dir).await;
+    let backups = dir.join("backups");
+
+    store_events(&warm, 3).await;
+    let snapshot = backup.snapshot().await.unwrap();
+    store_events(&warm, 3).await;
+    backup.archive_wal().await.unwrap();
+    assert!(backup::verify(&backups).await.unwrap().is_ok());
+    assert_eq!(backup::list_snapshots(&backups).unwrap().len(), 1);
+
+    // Flip a byte inside the last archived frame
+    let generation = std::fs::read_dir(backups.join("wal")).unwrap().next().unwrap().unwrap().path();
+    let segment = std::fs::read_dir(&generation)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "frames"))
+        .max()
+        .unwrap();
+    let mut frames = std::fs::read(&segment).unwrap();
+    let last = frames.len() - 100;
+    frames[last] ^= 0xff;
+    std::fs::write(&segment, frames).unwrap();
+
+    let snapshot_path = backups.join("snapshots").join(format!("{}.db", snapshot.taken_at));
+    let mut bytes = std::fs::read(&snapshot_path).unwrap();
+    bytes.extend_from_slice(&[0u8; 16]);
+    std::fs::write(&snapshot_path, bytes).unwrap();
+
+    let report = backup::verify(&backups).await.unwrap();
+    assert!(!report.is_ok());
+    assert!(report.snapshots[0].problems.iter().any(|p| p.contains("checksum")));
+    assert!(report.generations.iter().any(|g| !g.problems.is_empty()));
+}
@@ -0,0 +1,179 @@
+//! Tag-invalidated query caching, its local layer and request coalescing.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use cherenkov_db::query_cache::{query_tags, reading_tags, sensor_tag};
+use cherenkov_db::{
+    Cache, DatabaseConfig, DatabaseError, MemoryCache, QueryCache, RadiationDatabase,
+    SpatialQuery, StorageBackends, TimeRangeQuery,
+};
+use common::reading;
+
+/// Shared cache counting single-key reads, standing in for Redis; tag
+/// versions come through `get_many` and are not counted
+#[derive(Default)]
+struct CountingCache {
+    inner: MemoryCache,
+    reads: AtomicUsize,
+}
+
+#[async_trait]
+impl Cache for CountingCache {
+    async fn get_raw(&self, key: &str) -> anyhow::Result<Option<String>> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        self.inner.get_raw(key).await
+    }
+
+    async fn set_raw(&self, key: &str, value: String, ttl_seconds: u64) -> anyhow::Result<()> {
+        self.inner.set_raw(key, value, ttl_seconds).await
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.inner.delete(key).await
+    }
+
+    async fn get_many(&self, keys: &[String]) -> anyhow::Result<Vec<Option<String>>> {
+        self.inner.get_many(keys).await
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> anyhow::Result<()> {
+        self.inner.delete_prefix(prefix).await
+    }
+
+    async fn health_check(&self) -> bool {
+        true
+    }
+}
+
+async fn counted(loads: &AtomicUsize, value: u32) -> Result<u32, DatabaseError> {
+    loads.fetch_add(1, Ordering::SeqCst);
+    Ok(value)
+}
+
+#[tokio::test]
+async fn test_invalidating_a_tag_only_reloads_entries_that_depend_on_it() {
+    let cache = QueryCache::new(Arc::new(MemoryCache::default()));
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    let tags = [sensor_tag(&a)];
+    let loads = AtomicUsize::new(0);
+
+    assert_eq!(cache.get_or_load("q", &tags, 60, || counted(&loads, 1)).await.unwrap(), 1);
+    assert_eq!(cache.get_or_load("q", &tags, 60, || counted(&loads, 2)).await.unwrap(), 1);
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+    cache.invalidate(&[sensor_tag(&b)]).await.unwrap();
+    assert_eq!(cache.get_or_load("q", &tags, 60, || counted(&loads, 3)).await.unwrap(), 1);
+
+    cache.invalidate(&[sensor_tag(&a)]).await.unwrap();
+    assert_eq!(cache.get_or_load("q", &tags, 60, || counted(&loads, 4)).await.unwrap(), 4);
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+    // Failed loads are not cached
+    let failed: Result<u32, _> = cache
+        .get_or_load("broken", &tags, 60, || async { Err(DatabaseError::Query("boom".to_string())) })
+        .await;
+    assert!(failed.is_err());
+    assert_eq!(cache.get_or_load("broken", &tags, 60, || counted(&loads, 5)).await.unwrap(), 5);
+}
+
+#[tokio::test]
+async fn test_concurrent_misses_are_coalesced() {
+    let cache = Arc::new(QueryCache::new(Arc::new(MemoryCache::default())));
+    let loads = Arc::new(AtomicUsize::new(0));
+    let tags = vec![sensor_tag(&Uuid::new_v4())];
+
+    let mut handles = Vec::new();
+    for _ in 0..16 {
+        let (cache, loads, tags) = (cache.clone(), loads.clone(), tags.clone());
+        handles.push(tokio::spawn(async move {
+            cache
+                .get_or_load("slow", &tags, 60, || async {
+                    loads.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok::<_, DatabaseError>(vec![1.5, 2.5])
+                })
+                .await
+                .unwrap()
+        }));
+    }
+
+    for handle in handles {
+        assert_eq!(handle.await.unwrap(), vec![1.5, 2.5]);
+    }
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+    assert_eq!(cache.inflight(), 0);
+}
+
+#[tokio::test]
+async fn test_local_layer_sees_invalidations_from_other_processes() {
+    let shared = Arc::new(CountingCache::default());
+    let api = QueryCache::new(shared.clone()).with_local(100, 60);
+    let ingest = QueryCache::new(shared.clone());
+    let sensor = Uuid::new_v4();
+    let tags = [sensor_tag(&sensor)];
+    let loads = AtomicUsize::new(0);
+
+    api.get_or_load("q", &tags, 60, || counted(&loads, 1)).await.unwrap();
+    let reads = shared.reads.load(Ordering::SeqCst);
+    assert_eq!(api.get_or_load("q", &tags, 60, || counted(&loads, 2)).await.unwrap(), 1);
+    assert_eq!(shared.reads.load(Ordering::SeqCst), reads, "served from the local layer");
+
+    // A write elsewhere bumps the version kept in the shared cache
+    ingest.invalidate(&tags).await.unwrap();
+    assert_eq!(api.get_or_load("q", &tags, 60, || counted(&loads, 3)).await.unwrap(), 3);
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_readings_share_tags_with_queries_that_cover_them() {
+    let sensor = Uuid::new_v4();
+    let written = reading_tags(&reading(sensor, 1_709_251_200, 0.1));
+
+    let by_sensor = TimeRangeQuery::new(vec![sensor, sensor], 0, 1);
+    assert_eq!(query_tags(&by_sensor), vec![sensor_tag(&sensor)]);
+
+    for area in [
+        SpatialQuery::bounding_box(37.4, 37.5, 141.0, 141.1),
+        SpatialQuery::bounding_box(30.0, 45.0, 130.0, 150.0),
+    ] {
+        let tags = query_tags(&TimeRangeQuery::new(Vec::new(), 0, 1).within(area));
+        assert!(tags.iter().any(|t| written.contains(t)), "{:?} misses {:?}", tags, written);
+    }
+    let elsewhere = query_tags(&TimeRangeQuery::new(Vec::new(), 0, 1).within(SpatialQuery::bounding_box(50.0, 50.1, 8.0, 8.1)));
+    assert!(elsewhere.iter().all(|t| !written.contains(t)));
+
+    let everything = query_tags(&TimeRangeQuery::new(Vec::new(), 0, 1));
+    assert!(everything.iter().all(|t| written.contains(t)));
+}
+
+#[tokio::test]
+async fn test_writes_are_visible_to_cached_series_immediately() {
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens");
+    let sensor = Uuid::new_v4();
+    let now = Utc::now().timestamp();
+
+    db.write_reading(&reading(sensor, now - 600, 0.1)).await.unwrap();
+    let query = TimeRangeQuery::new(vec![sensor], now - 3600, now);
+    let area = TimeRangeQuery::new(Vec::new(), now - 3600, now)
+        .within(SpatialQuery::bounding_box(37.0, 38.0, 141.0, 142.0));
+    assert_eq!(db.query_series(&query).await.unwrap().items.len(), 1);
+    assert_eq!(db.query_series(&area).await.unwrap().items.len(), 1);
+    let latest = db.get_sensor_latest(&sensor.to_string()).await.unwrap().unwrap();
+    assert_eq!(latest.timestamp, now - 600);
+
+    db.write_reading(&reading(sensor, now - 60, 0.2)).await.unwrap();
+    assert_eq!(db.query_series(&query).await.unwrap().items.len(), 2);
+    assert_eq!(db.query_series(&area).await.unwrap().items.len(), 2);
+    let latest = db.get_sensor_latest(&sensor.to_string()).await.unwrap().unwrap();
+    assert_eq!(latest.timestamp, now - 60);
+}
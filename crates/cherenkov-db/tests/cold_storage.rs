@@ -0,0 +1,164 @@
+//! Parquet cold tier against a local directory.
+#![cfg(feature = "cold-storage")]
+
+mod common;
+
+use chrono::DateTime;
+use uuid::Uuid;
+
+use cherenkov_db::{
+    ColdStorage, ColdStorageConfig, DatabaseConfig, QcCheck, QcReason, QualityFlag, RadiationDatabase,
+    RadiationReading, StorageBackends,
+};
+use common::temp_dir;
+
+/// 2024-01-15 09:00:00 UTC
+const DAY_ONE: i64 = 1705309200;
+const DAY: i64 = 86_400;
+
+fn storage(dir: &std::path::Path, row_group_size: usize) -> ColdStorage {
+    ColdStorage::new(ColdStorageConfig {
+        local_path: dir.to_string_lossy().into_owned(),
+        row_group_size,
+        ..ColdStorageConfig::default()
+    })
+    .expect("cold storage opens")
+}
+
+fn reading(sensor_id: Uuid, timestamp: i64, latitude: f64, longitude: f64) -> RadiationReading {
+    RadiationReading {
+        latitude,
+        longitude,
+        cell_id: "u0yj".to_string(),
+        ..common::reading(sensor_id, timestamp, 0.1 + (timestamp % 100) as f64 / 1000.0)
+    }
+}
+
+fn at(ts: i64) -> chrono::DateTime<chrono::Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap()
+}
+
+#[tokio::test]
+async fn test_archive_is_partitioned_by_date_and_cell() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let cold = storage(dir, 1024);
+    let sensor = Uuid::new_v4();
+
+    let files = cold
+        .archive_readings(&[
+            reading(sensor, DAY_ONE, 50.1, 8.6),
+            reading(sensor, DAY_ONE + 60, 50.1, 8.6),
+            reading(sensor, DAY_ONE + DAY, 50.1, 8.6),
+            reading(Uuid::new_v4(), DAY_ONE, 37.4, 141.0),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(files.len(), 3);
+    assert!(files.iter().all(|f| f.ends_with(".parquet")));
+    assert!(dir.join("date=2024-01-15/cell=u0").is_dir());
+    assert!(dir.join("date=2024-01-16/cell=u0").is_dir());
+    assert!(dir.join("date=2024-01-15/cell=xn").is_dir());
+
+    // Real Parquet, not JSON with a .parquet name
+    let bytes = std::fs::read(&files[0]).unwrap();
+    assert_eq!(&bytes[..4], b"PAR1");
+
+    let archives = cold.list_archives().await.unwrap();
+    assert_eq!(archives.len(), 3);
+    assert_eq!(cold.delete_old_archives(at(DAY_ONE + DAY)).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_round_trip_preserves_every_field() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let cold = storage(dir, 1024);
+
+    let mut mobile = reading(Uuid::new_v4(), DAY_ONE, 35.68, 139.76);
+    mobile.track_id = Some(Uuid::new_v4());
+    mobile.altitude_m = Some(41.5);
+    mobile.quality_flag = QualityFlag::Suspect;
+    mobile.qc_reasons = vec![QcReason {
+        check: QcCheck::Spike,
+        flag: QualityFlag::Suspect,
+        value: 4.2,
+        limit: 3.0,
+        detail: Some("rate of change".to_string()),
+    }];
+
+    cold.archive_readings(std::slice::from_ref(&mobile)).await.unwrap();
+    let restored = cold.query_range(&[], at(DAY_ONE - 1), at(DAY_ONE + 1)).await.unwrap();
+
+    assert_eq!(restored.len(), 1);
+    assert_eq!(
+        serde_json::to_value(&restored[0]).unwrap(),
+        serde_json::to_value(&mobile).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_query_filters_by_sensor_and_time() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    // Small row groups so statistics pruning has something to skip
+    let cold = storage(dir, 8);
+    let wanted = Uuid::new_v4();
+    let other = Uuid::new_v4();
+
+    let mut readings = Vec::new();
+    for i in 0..48 {
+        readings.push(reading(wanted, DAY_ONE + i * 600, 50.1, 8.6));
+        readings.push(reading(other, DAY_ONE + i * 600, 50.1, 8.6));
+    }
+    cold.archive_readings(&readings).await.unwrap();
+
+    let start = DAY_ONE + 3600;
+    let end = DAY_ONE + 2 * 3600;
+    let found = cold
+        .query_range(&[wanted.to_string()], at(start), at(end))
+        .await
+        .unwrap();
+
+    // Bounds are inclusive: 10:00, 10:10, ... 11:00
+    assert_eq!(found.len(), 7);
+    assert!(found.iter().all(|r| r.sensor_id == wanted));
+    assert!(found.iter().all(|r| r.timestamp >= start && r.timestamp <= end));
+    assert!(found.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+    // Uppercase IDs match too, and ranges outside any partition find nothing
+    let upper = wanted.to_string().to_uppercase();
+    assert_eq!(cold.query_range(&[upper], at(start), at(end)).await.unwrap().len(), 7);
+    assert!(cold
+        .query_range(&[], at(DAY_ONE + 10 * DAY), at(DAY_ONE + 11 * DAY))
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_old_readings_are_archived_before_the_write_returns() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let config = DatabaseConfig {
+        enable_cold_archive: true,
+        cold_storage: ColdStorageConfig {
+            local_path: dir.to_string_lossy().into_owned(),
+            ..ColdStorageConfig::default()
+        },
+        ..DatabaseConfig::default()
+    };
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), config)
+        .await
+        .expect("in-memory database opens");
+    let sensor = Uuid::new_v4();
+
+    let batch: Vec<_> = (0..6).map(|i| reading(sensor, DAY_ONE + i * 60, 50.1, 8.6)).collect();
+    db.write_readings(&batch).await.unwrap();
+
+    // One archive for the batch, readable as soon as the write returns
+    let cold = storage(dir, 1024);
+    assert_eq!(cold.list_archives().await.unwrap().len(), 1);
+    assert_eq!(cold.query_range(&[], at(DAY_ONE), at(DAY_ONE + DAY)).await.unwrap().len(), 6);
+}
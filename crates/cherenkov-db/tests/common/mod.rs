@@ -0,0 +1,32 @@
+//! Helpers shared by the integration tests; each test binary uses its own subset.
+#![allow(dead_code)]
+
+use tempfile::TempDir;
+use uuid::Uuid;
+
+use cherenkov_db::{QualityFlag, RadiationReading};
+
+/// Scratch directory, removed along with its contents when dropped
+pub fn temp_dir() -> TempDir {
+    tempfile::Builder::new().prefix("cherenkov-").tempdir().expect("temp dir is created")
+}
+
+/// A valid Safecast reading near Fukushima Daiichi
+pub fn reading(sensor_id: Uuid, timestamp: i64, dose_rate: f64) -> RadiationReading {
+    RadiationReading {
+        sensor_id,
+        bucket: timestamp / 3600,
+        timestamp,
+        latitude: 37.42,
+        longitude: 141.03,
+        dose_rate_microsieverts: dose_rate,
+        uncertainty: 0.01,
+        quality_flag: QualityFlag::Valid,
+        source: "safecast".to_string(),
+        cell_id: String::new(),
+        track_id: None,
+        altitude_m: None,
+        qc_reasons: Vec::new(),
+        provenance: None,
+    }
+}
@@ -0,0 +1,172 @@
+//! Streaming exports and the background jobs that run them.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use cherenkov_db::export::export_readings;
+use cherenkov_db::{
+    DatabaseConfig, ExportFormat, ExportJobs, ExportStatus, QcCheck, QcReason, QualityFlag, RadiationDatabase,
+    StorageBackends, TimeRangeQuery,
+};
+use common::{reading, temp_dir};
+
+/// Two sensors reporting every two hours over a day, so exports span several chunks
+async fn seeded() -> (RadiationDatabase, Vec<Uuid>, i64) {
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens");
+    let sensors = vec![Uuid::new_v4(), Uuid::new_v4()];
+    let now = Utc::now().timestamp();
+
+    for i in 0..12 {
+        for (n, sensor) in sensors.iter().enumerate() {
+            let mut r = reading(*sensor, now - 86_000 + i * 7200, 0.1 + n as f64);
+            if i == 5 {
+                r.quality_flag = QualityFlag::Suspect;
+                r.qc_reasons = vec![QcReason {
+                    check: QcCheck::Spike,
+                    flag: QualityFlag::Suspect,
+                    value: 4.0,
+                    limit: 3.0,
+                    detail: None,
+                }];
+            }
+            db.write_reading(&r).await.unwrap();
+        }
+    }
+
+    (db, sensors, now)
+}
+
+#[tokio::test]
+async fn test_csv_and_geojson_exports_carry_qc_and_provenance() {
+    let (db, sensors, now) = seeded().await;
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let query = TimeRangeQuery::new(sensors.clone(), now - 86_400, now);
+
+    let csv_path = dir.join("readings.csv");
+    let mut progress = Vec::new();
+    let summary = export_readings(&db, &query, ExportFormat::Csv, &csv_path, |n| progress.push(n))
+        .await
+        .unwrap();
+    assert_eq!(summary.readings, 24);
+    assert!(progress.len() > 1, "exported in one chunk: {:?}", progress);
+    assert_eq!(progress.last(), Some(&24));
+
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 25);
+    assert!(lines[0].starts_with("sensor_id,timestamp,time,latitude,longitude"));
+    assert_eq!(lines.iter().filter(|l| l.contains(",suspect,")).count(), 2);
+    assert!(csv.contains("spike"));
+
+    let geojson_path = dir.join("readings.geojson");
+    export_readings(&db, &query.clone().with_quality([QualityFlag::Suspect]), ExportFormat::GeoJson, &geojson_path, |_| {})
+        .await
+        .unwrap();
+    let collection: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&geojson_path).unwrap()).unwrap();
+    assert_eq!(collection["type"], "FeatureCollection");
+    let features = collection["features"].as_array().unwrap();
+    assert_eq!(features.len(), 2);
+    assert_eq!(features[0]["geometry"]["coordinates"], serde_json::json!([141.03, 37.42]));
+    assert_eq!(features[0]["properties"]["qc_reasons"][0]["check"], "spike");
+    assert_eq!(collection["metadata"]["quality_flags"], serde_json::json!(["Suspect"]));
+    assert!(collection["metadata"]["generator"].as_str().unwrap().starts_with("cherenkov-db"));
+}
+
+#[tokio::test]
+async fn test_netcdf_export_is_a_cf_time_series() {
+    let (db, sensors, now) = seeded().await;
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let path = dir.join("readings.nc");
+
+    let query = TimeRangeQuery::new(sensors.clone(), now - 86_400, now);
+    let summary = export_readings(&db, &query, ExportFormat::NetCdf, &path, |_| {}).await.unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(summary.bytes, bytes.len() as u64);
+
+    // 64-bit offset classic format with one record per reading
+    assert_eq!(&bytes[..4], b"CDF\x02");
+    assert_eq!(u32::from_be_bytes(bytes[4..8].try_into().unwrap()), 24);
+    let header = String::from_utf8_lossy(&bytes);
+    for needle in ["featureType", "timeSeries", "CF-1.8", "timeseries_id", "instance_dimension", "flag_meanings"] {
+        assert!(header.contains(needle), "missing {}", needle);
+    }
+    assert!(!dir.join("readings.nc.records").exists());
+
+    // Records are time, dose rate, uncertainty, flag, QC mask and station index
+    let last = &bytes[bytes.len() - 32..];
+    let time = f64::from_be_bytes(last[0..8].try_into().unwrap());
+    let dose_rate = f64::from_be_bytes(last[8..16].try_into().unwrap());
+    let station = i32::from_be_bytes(last[28..32].try_into().unwrap());
+    assert_eq!(time as i64, now - 86_000 + 11 * 7200);
+    assert!(station == 0 || station == 1);
+    assert!((dose_rate - 0.1).abs() < 1e-9 || (dose_rate - 1.1).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_export_jobs_run_in_the_background() {
+    let (db, sensors, now) = seeded().await;
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let jobs = ExportJobs::new(Arc::new(db), dir);
+
+    let job = jobs
+        .start(TimeRangeQuery::new(vec![sensors[0]], now - 86_400, now), ExportFormat::GeoJson)
+        .unwrap();
+    assert_eq!(job.status, ExportStatus::Running);
+    assert!(jobs.file(&job.id).is_none());
+
+    let mut finished = None;
+    for _ in 0..200 {
+        let polled = jobs.get(&job.id).expect("job is tracked");
+        if polled.status != ExportStatus::Running {
+            finished = Some(polled);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let finished = finished.expect("export finishes");
+    assert_eq!(finished.status, ExportStatus::Completed, "{:?}", finished.error);
+    assert_eq!(finished.readings_written, 12);
+    assert_eq!(finished.provenance.unwrap().sensor_ids, vec![sensors[0]]);
+
+    let file = jobs.file(&job.id).expect("completed export has a file");
+    assert_eq!(std::fs::metadata(&file).unwrap().len(), finished.bytes.unwrap());
+
+    assert_eq!(jobs.prune(chrono::Duration::zero()).await, 1);
+    assert!(jobs.get(&job.id).is_none());
+    assert!(!file.exists());
+}
+
+#[cfg(feature = "cold-storage")]
+#[tokio::test]
+async fn test_parquet_export_keeps_provenance_in_the_footer() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let (db, sensors, now) = seeded().await;
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let path = dir.join("readings.parquet");
+
+    export_readings(&db, &TimeRangeQuery::new(sensors, now - 86_400, now), ExportFormat::Parquet, &path, |_| {})
+        .await
+        .unwrap();
+
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    let metadata = reader.metadata().file_metadata();
+    assert_eq!(metadata.num_rows(), 24);
+    let provenance = metadata
+        .key_value_metadata()
+        .and_then(|kv| kv.iter().find(|kv| kv.key == "cherenkov_provenance"))
+        .and_then(|kv| kv.value.clone())
+        .expect("provenance in footer");
+    assert!(provenance.contains("cherenkov-db"));
+}
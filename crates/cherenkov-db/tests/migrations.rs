@@ -0,0 +1,79 @@
+//! Versioned schema migrations for ScyllaDB and SQLite.
+
+mod common;
+
+use cherenkov_db::schema::{create_keyspace, MIGRATIONS};
+use cherenkov_db::sqlite::SqliteStorage;
+use cherenkov_db::{DatabaseConfig, RadiationDatabase, Replication, StorageBackends};
+use common::temp_dir;
+
+#[test]
+fn test_scylla_migrations_are_ordered_and_idempotent() {
+    assert_eq!(MIGRATIONS[0].version, 1);
+    assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+    for migration in MIGRATIONS {
+        assert!(!migration.statements.is_empty());
+        assert!(
+            migration.statements.iter().all(|s| s.contains("IF NOT EXISTS")),
+            "migration {} cannot be safely re-run",
+            migration.version
+        );
+    }
+
+    let single_node = Replication::parse("simple", 1).unwrap();
+    assert_eq!(
+        create_keyspace("cherenkov", &single_node),
+        "CREATE KEYSPACE IF NOT EXISTS cherenkov WITH REPLICATION = {'class': 'SimpleStrategy', 'replication_factor': 1}"
+    );
+    assert_eq!(
+        Replication::parse("NetworkTopologyStrategy", 3),
+        Some(Replication::NetworkTopology { replication_factor: 3 })
+    );
+    assert_eq!(Replication::default().to_cql(), "{'class': 'NetworkTopologyStrategy', 'replication_factor': 3}");
+    assert!(Replication::parse("everywhere", 1).is_none());
+}
+
+#[tokio::test]
+async fn test_sqlite_dry_run_reports_without_applying() {
+    let tmp = temp_dir();
+    let dir = tmp.path().join("warm");
+    let path = dir.join("warm.db").to_string_lossy().into_owned();
+
+    // Nothing is created for a dry run against a missing database
+    let planned = SqliteStorage::migrate(&path, true).await.unwrap();
+    assert!(planned.dry_run);
+    assert_eq!(planned.current_version, None);
+    assert!(!planned.pending.is_empty());
+    assert!(planned.pending.windows(2).all(|w| w[0].version < w[1].version));
+    assert!(!dir.exists());
+
+    let applied = SqliteStorage::migrate(&path, false).await.unwrap();
+    let versions = |r: &cherenkov_db::MigrationReport| r.pending.iter().map(|m| m.version).collect::<Vec<_>>();
+    assert_eq!(versions(&applied), versions(&planned));
+
+    let again = SqliteStorage::migrate(&path, true).await.unwrap();
+    assert!(again.is_up_to_date());
+    assert_eq!(again.current_version, planned.pending.last().map(|m| m.version));
+}
+
+#[tokio::test]
+async fn test_migrate_covers_every_backend_before_open() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let backends = StorageBackends::embedded(dir);
+
+    let reports = RadiationDatabase::migrate(&backends, true).await.unwrap();
+    assert_eq!(reports.len(), 2);
+    assert!(reports.iter().all(|r| r.backend.starts_with("sqlite:") && !r.is_up_to_date()));
+
+    RadiationDatabase::migrate(&backends, false).await.unwrap();
+    let reports = RadiationDatabase::migrate(&backends, true).await.unwrap();
+    assert!(reports.iter().all(|r| r.is_up_to_date()));
+
+    let db = RadiationDatabase::open(backends, DatabaseConfig::default()).await.unwrap();
+    db.run_migrations().await.unwrap();
+    drop(db);
+
+    // Memory backends have nothing to migrate
+    assert!(RadiationDatabase::migrate(&StorageBackends::in_memory(), false).await.unwrap().is_empty());
+}
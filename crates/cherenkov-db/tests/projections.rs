@@ -0,0 +1,138 @@
+//! Read models projected from the event log, and querying the log itself.
+
+use serde_json::json;
+use uuid::Uuid;
+
+use cherenkov_db::sqlite::SqliteStorage;
+use cherenkov_db::{
+    Checkpoint, DatabaseConfig, DomainEvent, EventQuery, EventStore, EventType, MemoryStore, Projection, Projections,
+    RadiationDatabase, SensorState, SensorStatusView, SortOrder, StorageBackends,
+};
+
+fn event(event_type: EventType, aggregate_id: Uuid, timestamp: i64, payload: serde_json::Value) -> DomainEvent {
+    DomainEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type,
+        aggregate_id,
+        payload,
+        timestamp,
+    }
+}
+
+#[tokio::test]
+async fn test_read_models_follow_the_log() {
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens");
+    let (sensor, incident) = (Uuid::new_v4(), Uuid::new_v4());
+
+    db.store_event(&event(EventType::SensorOffline, sensor, 100, json!({ "from": "online", "to": "offline" })))
+        .await
+        .unwrap();
+    db.store_event(&event(EventType::AnomalyDetected, sensor, 110, json!({ "severity": "Warning" }))).await.unwrap();
+    db.store_event(&event(EventType::AnomalyDetected, sensor, 120, json!({ "severity": "critical" }))).await.unwrap();
+    db.store_event(&event(EventType::IncidentCreated, incident, 130, json!({ "severity": "critical", "title": "Spike" })))
+        .await
+        .unwrap();
+
+    let statuses = db.sensor_statuses().await.unwrap();
+    assert_eq!(statuses.sensors[&sensor].state, SensorState::Offline);
+    let incidents = db.open_incidents().await.unwrap();
+    assert_eq!(incidents.len(), 1);
+    assert_eq!(incidents[0].title.as_deref(), Some("Spike"));
+
+    let counts = db.anomaly_counts().await.unwrap();
+    let count = &counts.sensors[&sensor];
+    assert_eq!(count.total, 2);
+    assert_eq!(count.by_severity["warning"], 1);
+    assert_eq!(count.last_detected_at, 120);
+
+    // An older status change arriving late does not override the newer one
+    db.store_event(&event(EventType::SensorOnline, sensor, 90, json!({ "from": "degraded", "to": "online" })))
+        .await
+        .unwrap();
+    db.store_event(&event(EventType::SensorOnline, sensor, 140, json!({ "from": "offline", "to": "online" })))
+        .await
+        .unwrap();
+    db.store_event(&event(EventType::IncidentResolved, incident, 150, json!({}))).await.unwrap();
+
+    assert_eq!(db.sensor_statuses().await.unwrap().sensors[&sensor].state, SensorState::Online);
+    assert!(db.open_incidents().await.unwrap().is_empty());
+    let timeline = db.alert_timeline(10).await.unwrap();
+    let types: Vec<EventType> = timeline.iter().map(|e| e.event.event_type).collect();
+    assert_eq!(types, vec![EventType::IncidentResolved, EventType::IncidentCreated]);
+}
+
+#[tokio::test]
+async fn test_event_queries_filter_and_page_by_sequence() {
+    let store = MemoryStore::new();
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    for i in 0..5 {
+        store.store_event(&event(EventType::AnomalyDetected, a, 100 + i, json!({}))).await.unwrap();
+        store.store_event(&event(EventType::SensorOffline, b, 100 + i, json!({}))).await.unwrap();
+    }
+
+    let by_aggregate = store.query_events(&EventQuery::all().for_aggregates(vec![a])).await.unwrap();
+    assert_eq!(by_aggregate.len(), 5);
+    assert!(by_aggregate.windows(2).all(|w| w[0].sequence < w[1].sequence));
+
+    let by_type = EventQuery::all().of_types(vec![EventType::SensorOffline]).between(Some(102), None);
+    assert_eq!(store.query_events(&by_type).await.unwrap().len(), 3);
+
+    let newest = EventQuery::all().order_by(SortOrder::Descending).limit(3);
+    let first = store.query_events(&newest).await.unwrap();
+    assert_eq!(first.iter().map(|e| e.sequence).collect::<Vec<_>>(), vec![10, 9, 8]);
+    let second = store.query_events(&newest.clone().after(8)).await.unwrap();
+    assert_eq!(second.iter().map(|e| e.sequence).collect::<Vec<_>>(), vec![7, 6, 5]);
+
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default()).await.unwrap();
+    for i in 0..3 {
+        db.store_event(&event(EventType::AlertTriggered, a, i, json!({}))).await.unwrap();
+    }
+    let page = db.query_events(&EventQuery::all().limit(2)).await.unwrap();
+    assert_eq!(page.items.len(), 2);
+    assert_eq!(page.next_cursor.as_deref(), Some("2"));
+    let rest = db.query_events(&EventQuery::all().limit(2).after(2)).await.unwrap();
+    assert_eq!(rest.items.len(), 1);
+    assert!(rest.next_cursor.is_none());
+}
+
+#[tokio::test]
+async fn test_projections_resume_from_checkpoints() {
+    let store = SqliteStorage::new(":memory:").await.expect("sqlite opens");
+    store.run_migrations().await.unwrap();
+    let sensor = Uuid::new_v4();
+
+    for i in 0..3 {
+        store.store_event(&event(EventType::AnomalyDetected, sensor, 100 + i, json!({ "severity": "warning" })))
+            .await
+            .unwrap();
+    }
+    let mut first = Projections::default();
+    assert_eq!(first.catch_up(&store).await.unwrap(), 3);
+    first.checkpoint(&store).await.unwrap();
+
+    store.store_event(&event(EventType::AnomalyDetected, sensor, 200, json!({ "severity": "critical" })))
+        .await
+        .unwrap();
+
+    // Another process starts from the checkpoint and reads only the new event
+    let mut resumed = Projections::default();
+    assert_eq!(resumed.catch_up(&store).await.unwrap(), 1);
+    assert_eq!(resumed.sequence(), 4);
+    assert_eq!(resumed.anomaly_counts.state.sensors[&sensor].total, 4);
+
+    let mut rebuilt = Projections::default();
+    assert_eq!(rebuilt.rebuild(&store).await.unwrap(), 4);
+    assert_eq!(rebuilt.anomaly_counts.state.sensors[&sensor].by_severity["critical"], 1);
+
+    // A checkpoint never moves backwards
+    let stale = Checkpoint {
+        name: SensorStatusView::NAME.to_string(),
+        sequence: 1,
+        state: json!({ "sensors": {} }),
+    };
+    store.save_checkpoint(&stale).await.unwrap();
+    let saved = store.load_checkpoint(SensorStatusView::NAME).await.unwrap().unwrap();
+    assert_eq!(saved.sequence, 4);
+}
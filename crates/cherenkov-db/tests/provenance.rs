@@ -0,0 +1,87 @@
+//! Per-reading lineage: source fetch, raw value and unit, conversion and QC decision.
+
+mod common;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use cherenkov_db::{
+    DatabaseConfig, QcCheck, QcReason, QualityFlag, RadiationDatabase, RadiationReading, ReadingProvenance,
+    SourceFetch, StorageBackends,
+};
+
+fn reading(sensor_id: Uuid, timestamp: i64, provenance: Option<ReadingProvenance>) -> RadiationReading {
+    RadiationReading {
+        provenance,
+        ..common::reading(sensor_id, timestamp, 35.0 * 0.00294)
+    }
+}
+
+async fn database() -> RadiationDatabase {
+    RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens")
+}
+
+#[tokio::test]
+async fn test_lineage_links_reading_to_fetch_and_qc() {
+    let db = database().await;
+    let now = Utc::now().timestamp();
+    let fetch = SourceFetch {
+        fetch_id: Uuid::new_v4(),
+        source: "safecast".to_string(),
+        started_at: now - 2,
+        completed_at: now - 1,
+        readings: 3,
+    };
+    db.record_fetch(&fetch).await.unwrap();
+    // Recording the same fetch twice is harmless
+    db.record_fetch(&fetch).await.unwrap();
+
+    let sensor = Uuid::new_v4();
+    let mut provenance = ReadingProvenance::converted(35.0, "cpm", 0.00294, "1");
+    provenance.fetch_id = Some(fetch.fetch_id);
+    provenance.qc_thresholds = Some("source:safecast".to_string());
+    let mut flagged = reading(sensor, now - 86_400 * 10, Some(provenance));
+    flagged.quality_flag = QualityFlag::Suspect;
+    flagged.qc_reasons = vec![QcReason {
+        check: QcCheck::Step,
+        flag: QualityFlag::Suspect,
+        value: 0.1,
+        limit: 0.05,
+        detail: None,
+    }];
+    db.write_reading(&flagged).await.unwrap();
+
+    let lineage = db.reading_lineage(&sensor, flagged.timestamp).await.unwrap().expect("lineage stored");
+    assert_eq!(lineage.fetch, Some(fetch));
+    assert_eq!(lineage.raw_value, 35.0);
+    assert_eq!(lineage.raw_unit, "cpm");
+    assert_eq!(lineage.normalizer_version, "1");
+    assert!((lineage.dose_rate_microsieverts() - flagged.dose_rate_microsieverts).abs() < 1e-12);
+    assert_eq!(lineage.qc.flag, QualityFlag::Suspect);
+    assert_eq!(lineage.qc.thresholds.as_deref(), Some("source:safecast"));
+    assert_eq!(lineage.qc.reasons, flagged.qc_reasons);
+
+    // Readings without provenance, or never stored, have no lineage
+    let bare = reading(Uuid::new_v4(), now - 86_400 * 10, None);
+    db.write_reading(&bare).await.unwrap();
+    assert!(db.reading_lineage(&bare.sensor_id, bare.timestamp).await.unwrap().is_none());
+    assert!(db.reading_lineage(&sensor, flagged.timestamp + 1).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_lineage_without_recorded_fetch() {
+    let db = database().await;
+    let sensor = Uuid::new_v4();
+    let timestamp = Utc::now().timestamp() - 86_400 * 10;
+    let mut provenance = ReadingProvenance::converted(0.12, "usv", 1.0, "1");
+    provenance.fetch_id = Some(Uuid::new_v4());
+    db.write_reading(&reading(sensor, timestamp, Some(provenance))).await.unwrap();
+
+    let lineage = db.reading_lineage(&sensor, timestamp).await.unwrap().expect("lineage stored");
+    assert!(lineage.fetch.is_none());
+    assert!(lineage.qc.thresholds.is_none());
+    assert!(lineage.qc.reasons.is_empty());
+    assert_eq!(lineage.raw_unit, "usv");
+}
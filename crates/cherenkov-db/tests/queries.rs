@@ -0,0 +1,249 @@
+//! Query objects: combined filters, cursor paging and tier plans.
+
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use cherenkov_db::{
+    Access, AggregationLevel, AnomalyQuery, Cursor, DatabaseConfig, DomainEvent, EventStore, EventType, MemoryStore,
+    QualityFlag, QueryPlan, RadiationDatabase, RadiationReading, SortOrder, SpatialQuery, StorageBackends, Tier,
+    TimeRangeQuery,
+};
+
+fn reading(sensor_id: Uuid, timestamp: i64, latitude: f64, quality_flag: QualityFlag, source: &str) -> RadiationReading {
+    RadiationReading {
+        latitude,
+        quality_flag,
+        source: source.to_string(),
+        ..common::reading(sensor_id, timestamp, 0.1)
+    }
+}
+
+fn anomaly_event(sensor_id: Uuid, timestamp: i64, severity: &str) -> DomainEvent {
+    DomainEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: EventType::AnomalyDetected,
+        aggregate_id: sensor_id,
+        payload: serde_json::json!({ "severity": severity, "z_score": 4.2 }),
+        timestamp,
+    }
+}
+
+async fn open() -> RadiationDatabase {
+    RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens")
+}
+
+/// Every reading of `query`, a page of `limit` at a time
+async fn walk_pages(db: &RadiationDatabase, query: &TimeRangeQuery, limit: usize) -> Vec<(i64, Uuid)> {
+    let mut paged = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut page_query = query.clone().limit(limit);
+        if let Some(cursor) = &cursor {
+            page_query = page_query.after(Cursor::decode(cursor).unwrap());
+        }
+        let page = db.query_readings(&page_query).await.unwrap();
+        assert!(page.items.len() <= limit);
+        paged.extend(page.items.into_iter().map(|r| (r.timestamp, r.sensor_id)));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return paged,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_combined_filters_and_cursor_pages() {
+    let db = open().await;
+    let now = Utc::now().timestamp();
+    let (near, far, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    for i in 0..6 {
+        let at = now - 3000 + i * 300;
+        db.write_reading(&reading(near, at, 37.4, QualityFlag::Valid, "safecast")).await.unwrap();
+        // Shares timestamps with `near`, so ties are broken by sensor
+        db.write_reading(&reading(other, at, 37.5, QualityFlag::Valid, "safecast")).await.unwrap();
+    }
+    db.write_reading(&reading(near, now - 100, 37.4, QualityFlag::Suspect, "safecast")).await.unwrap();
+    db.write_reading(&reading(near, now - 90, 37.4, QualityFlag::Valid, "epa")).await.unwrap();
+    db.write_reading(&reading(far, now - 80, 45.0, QualityFlag::Valid, "safecast")).await.unwrap();
+
+    let query = TimeRangeQuery::new(vec![near, far, other], now - 3600, now)
+        .within(SpatialQuery::bounding_box(37.0, 38.0, 140.0, 142.0))
+        .with_quality([QualityFlag::Valid])
+        .with_sources(["safecast".to_string()]);
+
+    let all = db.query_readings(&query).await.unwrap();
+    assert_eq!(all.items.len(), 12);
+    assert!(all.next_cursor.is_none());
+    assert!(all.items.iter().all(|r| r.sensor_id != far && r.source == "safecast"));
+    assert!(all.items.windows(2).all(|w| (w[0].timestamp, w[0].sensor_id) < (w[1].timestamp, w[1].sensor_id)));
+
+    // Pages of five walk the same readings in order, forwards and backwards
+    for order in [SortOrder::Ascending, SortOrder::Descending] {
+        let paged = walk_pages(&db, &query.clone().order_by(order), 5).await;
+
+        let mut expected: Vec<(i64, Uuid)> = all.items.iter().map(|r| (r.timestamp, r.sensor_id)).collect();
+        if order == SortOrder::Descending {
+            expected.reverse();
+        }
+        assert_eq!(paged, expected);
+    }
+
+    // Raw series points carry their sensor
+    let series = db.query_series(&query.clone().limit(3)).await.unwrap();
+    assert_eq!(series.items.len(), 3);
+    assert!(series.items.iter().all(|p| p.sensor_id.is_some()));
+    assert!(series.next_cursor.is_some());
+    assert!(Cursor::decode("not a cursor").is_err());
+}
+
+#[tokio::test]
+async fn test_scans_and_cells_page_across_tiers() {
+    let db = open().await;
+    let now = Utc::now().timestamp();
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+    // Safecast-shaped readings with day buckets, half of them old enough for warm
+    let mut expected = Vec::new();
+    for i in 0..8 {
+        let at = if i < 4 { now - 10 * 86_400 + i * 7200 } else { now - 20_000 + i * 1800 };
+        for sensor in [a, b] {
+            let reading = RadiationReading {
+                bucket: at / 86_400,
+                ..reading(sensor, at, 37.4, QualityFlag::Valid, "safecast")
+            };
+            db.write_reading(&reading).await.unwrap();
+            expected.push((at, sensor));
+        }
+    }
+    expected.sort();
+
+    let scan = TimeRangeQuery::new(vec![], now - 30 * 86_400, now).with_sources(["safecast".to_string()]);
+    let cells = TimeRangeQuery::new(vec![], now - 30 * 86_400, now)
+        .within(SpatialQuery::bounding_box(37.0, 38.0, 140.0, 142.0));
+    for query in [scan, cells] {
+        assert_eq!(walk_pages(&db, &query, 3).await, expected);
+
+        let mut descending = walk_pages(&db, &query.clone().order_by(SortOrder::Descending), 3).await;
+        descending.reverse();
+        assert_eq!(descending, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_aggregates_are_computed_when_filters_bypass_rollups() {
+    let db = open().await;
+    let sensor = Uuid::new_v4();
+    let now = Utc::now().timestamp();
+    let hour = now - now.rem_euclid(3600);
+
+    let mut values = Vec::new();
+    for i in 0..4 {
+        let mut r = reading(sensor, hour - 3600 + i * 600, 37.4, QualityFlag::Valid, "safecast");
+        r.dose_rate_microsieverts = 0.1 * (i + 1) as f64;
+        values.push(r.dose_rate_microsieverts);
+        db.write_reading(&r).await.unwrap();
+    }
+    db.write_reading(&reading(sensor, hour - 2900, 37.4, QualityFlag::Valid, "epa")).await.unwrap();
+
+    let query = TimeRangeQuery::new(vec![sensor], hour - 3600, hour - 1)
+        .with_aggregation(AggregationLevel::OneHour)
+        .with_sources(["safecast".to_string()]);
+    assert!(db.plan(&query).steps.iter().all(|s| s.tier != Tier::Rollups));
+
+    let points = db.query_series(&query).await.unwrap().items;
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].count, 4);
+    assert_eq!(points[0].timestamp.timestamp(), hour - 3600);
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    assert!((points[0].avg - mean).abs() < 1e-9);
+}
+
+#[test]
+fn test_plan_fans_out_across_tiers() {
+    let config = DatabaseConfig::default();
+    let now = DateTime::from_timestamp(1_709_251_200, 0).unwrap();
+    let at = |days: i64| (now - Duration::days(days)).timestamp();
+    let sensor = Uuid::new_v4();
+
+    let query = TimeRangeQuery::new(vec![sensor], at(60), at(0));
+    let plan = QueryPlan::for_query(&query, &config, true, now);
+    let tiers: Vec<Tier> = plan.steps.iter().map(|s| s.tier).collect();
+    assert_eq!(tiers, vec![Tier::Hot, Tier::Warm, Tier::Cold]);
+    assert!(plan.steps.iter().all(|s| s.access == Access::Sensors(vec![sensor])));
+    assert_eq!(plan.steps[2].from, at(60));
+    assert_eq!(plan.steps[2].to, at(config.warm_retention_days));
+    assert_eq!(plan.steps[1].to, at(config.hot_retention_days));
+
+    // Without a cold tier, and for a recent window, only the tiers that can hold it
+    let plan = QueryPlan::for_query(&query, &config, false, now);
+    assert_eq!(plan.steps.len(), 2);
+    let recent = TimeRangeQuery::new(vec![sensor], at(1), at(0));
+    let plan = QueryPlan::for_query(&recent, &config, true, now);
+    assert_eq!(plan.steps.iter().map(|s| s.tier).collect::<Vec<_>>(), vec![Tier::Hot]);
+
+    // An area without sensors reads covering cells, and scans the cold archive
+    let area = TimeRangeQuery::new(Vec::new(), at(60), at(0))
+        .within(SpatialQuery::bounding_box(37.0, 38.0, 140.0, 141.0));
+    let plan = QueryPlan::for_query(&area, &config, true, now);
+    assert!(matches!(&plan.steps[0].access, Access::Cells(cells) if !cells.is_empty()));
+    assert_eq!(plan.steps[2].access, Access::Scan);
+
+    // Aggregates come from rollups only for a pure sensor filter
+    let hourly = query.clone().with_aggregation(AggregationLevel::OneHour);
+    let plan = QueryPlan::for_query(&hourly, &config, true, now);
+    assert_eq!(plan.steps.len(), 1);
+    assert_eq!(plan.steps[0].tier, Tier::Rollups);
+    let filtered = hourly.with_quality([QualityFlag::Valid]);
+    let plan = QueryPlan::for_query(&filtered, &config, true, now);
+    assert!(plan.steps.iter().all(|s| s.tier != Tier::Rollups));
+
+    // The cursor narrows the window still to be read
+    let page = query.order_by(SortOrder::Descending).after(Cursor { timestamp: at(3), key: String::new() });
+    let plan = QueryPlan::for_query(&page, &config, true, now);
+    assert_eq!(plan.steps[0].to, at(3));
+}
+
+#[tokio::test]
+async fn test_anomaly_query_filters_and_pages() {
+    let store = MemoryStore::new();
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    let now = Utc::now().timestamp();
+
+    for i in 0..5 {
+        store.store_event(&anomaly_event(a, now - i * 60, "warning")).await.unwrap();
+    }
+    store.store_event(&anomaly_event(a, now - 30, "critical")).await.unwrap();
+    store.store_event(&anomaly_event(b, now - 20, "warning")).await.unwrap();
+    store.store_event(&anomaly_event(a, now - 7200, "warning")).await.unwrap();
+
+    let query = AnomalyQuery::since(now - 3600)
+        .with_severity(vec!["warning".to_string()])
+        .for_sensors(vec![a])
+        .limit(2);
+
+    let first = store.query_anomalies(&query).await.unwrap();
+    assert_eq!(first.iter().map(|r| r.detected_at).collect::<Vec<_>>(), vec![now, now - 60]);
+
+    let last = first.last().unwrap();
+    let cursor = Cursor { timestamp: last.detected_at, key: last.anomaly_id.clone() };
+    let second = store.query_anomalies(&query.clone().after(cursor)).await.unwrap();
+    assert_eq!(second.iter().map(|r| r.detected_at).collect::<Vec<_>>(), vec![now - 120, now - 180]);
+
+    let oldest_first = store
+        .query_anomalies(&AnomalyQuery::since(now - 3600).order_by(SortOrder::Ascending).limit(10))
+        .await
+        .unwrap();
+    assert_eq!(oldest_first.len(), 7);
+    assert_eq!(oldest_first[0].detected_at, now - 240);
+
+    // The SQLite event store pushes every filter down into SQL
+    let db = open().await;
+    let pushed_down = query.after(Cursor { timestamp: now, key: "a".to_string() });
+    let page = db.query_anomalies(&pushed_down).await.unwrap();
+    assert!(page.items.is_empty() && page.next_cursor.is_none());
+}
@@ -0,0 +1,102 @@
+//! Sensor liveness in the registry, judged against each sensor's learned delivery cadence.
+
+mod common;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use cherenkov_db::sqlite::SqliteStorage;
+use cherenkov_db::{LivenessThresholds, SensorState};
+use common::reading;
+
+/// 2024-01-15T11:00:00Z
+const START: i64 = 1705316400;
+
+fn at(ts: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap()
+}
+
+async fn registry() -> SqliteStorage {
+    let warm = SqliteStorage::new(":memory:").await.expect("sqlite opens");
+    warm.run_migrations().await.unwrap();
+    warm
+}
+
+/// Deliver `count` readings `every` seconds apart, returning the last delivery time
+async fn deliver(warm: &SqliteStorage, sensor_id: Uuid, every: i64, count: i64) -> i64 {
+    for i in 0..count {
+        let ts = START + i * every;
+        warm.touch_sensor_at(&reading(sensor_id, ts, 0.1), at(ts)).await.unwrap();
+    }
+    START + (count - 1) * every
+}
+
+fn changes(transitions: &[cherenkov_db::StatusTransition]) -> Vec<(Uuid, SensorState)> {
+    transitions.iter().map(|t| (t.sensor_id, t.to)).collect()
+}
+
+#[tokio::test]
+async fn test_sweep_follows_the_learned_cadence() {
+    let warm = registry().await;
+    let thresholds = LivenessThresholds::default();
+    let fast = Uuid::new_v4();
+    let slow = Uuid::new_v4();
+    let last = deliver(&warm, fast, 600, 10).await;
+    deliver(&warm, slow, 3600, 10).await;
+
+    for (sensor, every) in [(fast, 600.0), (slow, 3600.0)] {
+        let cadence = warm.get_sensor(&sensor).await.unwrap().unwrap().cadence_secs.unwrap();
+        assert!((cadence - every).abs() < 0.01, "{}", cadence);
+    }
+
+    // 40 minutes is degraded for a 10-minute station and nothing for an hourly one
+    let transitions = warm.sweep_sensor_status(at(last + 2400), &thresholds).await.unwrap();
+    assert_eq!(changes(&transitions), vec![(fast, SensorState::Degraded)]);
+    assert!(warm.sweep_sensor_status(at(last + 2500), &thresholds).await.unwrap().is_empty());
+
+    let transitions = warm.sweep_sensor_status(at(last + 4000), &thresholds).await.unwrap();
+    assert_eq!(changes(&transitions), vec![(fast, SensorState::Offline)]);
+}
+
+#[tokio::test]
+async fn test_backfill_is_a_delivery() {
+    let warm = registry().await;
+    let thresholds = LivenessThresholds::default();
+    let sensor = Uuid::new_v4();
+
+    // A day of readings handed over in one batch
+    let now = START + 86_400;
+    for i in 0..24 {
+        warm.touch_sensor_at(&reading(sensor, START + i * 3600, 0.1), at(now)).await.unwrap();
+    }
+
+    let registered = warm.get_sensor(&sensor).await.unwrap().unwrap();
+    assert_eq!(registered.last_reading, Some(START + 23 * 3600));
+    assert_eq!(registered.cadence_secs, None, "one batch is one delivery");
+
+    // Old readings, but the sensor is clearly still reporting
+    assert!(warm.sweep_sensor_status(at(now + 60), &thresholds).await.unwrap().is_empty());
+    assert_eq!(warm.get_sensor(&sensor).await.unwrap().unwrap().status, SensorState::Online);
+}
+
+#[test]
+fn test_fixed_gaps_apply_until_the_cadence_is_learned() {
+    let thresholds = LivenessThresholds::default();
+    assert_eq!(thresholds.state_for_gap(Some(60), None, 0), SensorState::Online);
+    assert_eq!(thresholds.state_for_gap(Some(2 * 3600), None, 0), SensorState::Degraded);
+    assert_eq!(thresholds.state_for_gap(Some(7 * 3600), None, 0), SensorState::Offline);
+    assert_eq!(thresholds.state_for_gap(None, None, 0), SensorState::Offline);
+
+    // Too few intervals to trust, and a floor for fast reporters
+    assert_eq!(thresholds.state_for_gap(Some(2400), Some(600.0), 2), SensorState::Online);
+    assert_eq!(thresholds.state_for_gap(Some(150), Some(5.0), 10), SensorState::Online);
+    assert_eq!(thresholds.state_for_gap(Some(200), Some(5.0), 10), SensorState::Degraded);
+}
+
+#[test]
+fn test_unknown_states_are_refused() {
+    assert_eq!(SensorState::parse("online"), Some(SensorState::Online));
+    assert_eq!(SensorState::parse("active"), Some(SensorState::Online));
+    assert_eq!(SensorState::parse("maintenance"), Some(SensorState::Maintenance));
+    assert_eq!(SensorState::parse("bogus"), None);
+}
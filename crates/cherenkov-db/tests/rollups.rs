@@ -0,0 +1,229 @@
+//! Continuous rollups maintained in the warm tier.
+
+mod common;
+
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use cherenkov_db::rollup;
+use cherenkov_db::sqlite::SqliteStorage;
+use cherenkov_db::{
+    AggregationLevel, DatabaseConfig, RadiationDatabase, RadiationReading, RollupLevel, RollupScope,
+    RollupStats, StorageBackends, Tier, TimeRangeQuery,
+};
+use common::{reading, temp_dir};
+
+/// 2024-03-01 00:00:00 UTC
+const START: i64 = 1709251200;
+
+async fn warm_storage(dir: &Path) -> SqliteStorage {
+    std::fs::create_dir_all(dir).unwrap();
+    let path = format!("{}?mode=rwc", dir.join("warm.db").display());
+    let warm = SqliteStorage::new(&path).await.expect("sqlite opens");
+    warm.run_migrations().await.unwrap();
+    warm
+}
+
+fn at(ts: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap()
+}
+
+async fn sensor_buckets(
+    warm: &SqliteStorage,
+    level: RollupLevel,
+    sensor: Uuid,
+    start: i64,
+    end: i64,
+) -> Vec<(i64, RollupStats)> {
+    warm.query_rollups(level, RollupScope::Sensor, &[sensor.to_string()], at(start), at(end))
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_stats_merge_and_percentile() {
+    let mut low = RollupStats::default();
+    let mut high = RollupStats::default();
+    for i in 1..=100 {
+        low.add(i as f64 / 100.0);
+        high.add(i as f64);
+    }
+
+    assert_eq!(low.count, 100);
+    assert!((low.avg() - 0.505).abs() < 1e-9);
+    assert!((low.p95() - 0.95).abs() / 0.95 < 0.07, "p95 within half a bin: {}", low.p95());
+
+    low.merge(&high);
+    assert_eq!(low.count, 200);
+    assert_eq!(low.min, 0.01);
+    assert_eq!(low.max, 100.0);
+    assert!((low.p95() - 90.0).abs() / 90.0 < 0.07, "merged p95: {}", low.p95());
+}
+
+#[test]
+fn test_auto_level_follows_span() {
+    assert!(matches!(AggregationLevel::Auto.resolve(Duration::hours(1)), AggregationLevel::Raw));
+    assert!(matches!(AggregationLevel::Auto.resolve(Duration::days(1)), AggregationLevel::OneMinute));
+    assert!(matches!(AggregationLevel::Auto.resolve(Duration::days(30)), AggregationLevel::OneHour));
+    assert!(matches!(AggregationLevel::Auto.resolve(Duration::days(365)), AggregationLevel::OneDay));
+    assert!(matches!(AggregationLevel::OneHour.resolve(Duration::days(365)), AggregationLevel::OneHour));
+}
+
+#[tokio::test]
+async fn test_rollups_per_level_sensor_and_cell() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let warm = warm_storage(dir).await;
+    let sensors = [Uuid::new_v4(), Uuid::new_v4()];
+
+    // Two hours of 30-second readings from two sensors in the same cell
+    let mut readings = Vec::new();
+    for i in 0..240 {
+        for (n, sensor) in sensors.iter().enumerate() {
+            readings.push(reading(*sensor, START + i * 30, 0.1 * (n + 1) as f64));
+        }
+    }
+    warm.apply_rollups(&readings).await.unwrap();
+
+    let minutes = sensor_buckets(&warm, RollupLevel::Minute, sensors[0], START, START + 7200).await;
+    assert_eq!(minutes.len(), 120);
+    assert!(minutes.iter().all(|(_, s)| s.count == 2));
+
+    let hours = sensor_buckets(&warm, RollupLevel::Hour, sensors[0], START, START + 7200).await;
+    assert_eq!(hours.iter().map(|(start, _)| *start).collect::<Vec<_>>(), vec![START, START + 3600]);
+    assert!(hours.iter().all(|(_, s)| s.count == 120 && (s.avg() - 0.1).abs() < 1e-9));
+
+    // The cell rollup combines both sensors
+    let cell = rollup::cell_of(37.42, 141.03).unwrap();
+    let days = warm
+        .query_rollups(RollupLevel::Day, RollupScope::Cell, &[cell], at(START), at(START + 7200))
+        .await
+        .unwrap();
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].1.count, 480);
+    assert_eq!(days[0].1.min, 0.1);
+    assert_eq!(days[0].1.max, 0.2);
+
+    // Five-minute points are served by regrouping the minute rollup
+    let points = rollup::regroup(minutes, 300);
+    assert_eq!(points.len(), 24);
+    assert!(points.iter().all(|p| p.count == 10 && p.p95.is_some()));
+}
+
+#[tokio::test]
+async fn test_late_and_corrected_readings_update_closed_buckets() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let warm = warm_storage(dir).await;
+    let sensor = Uuid::new_v4();
+
+    let on_time: Vec<_> = (0..10).map(|i| reading(sensor, START + i * 60, 0.1)).collect();
+    warm.apply_rollups(&on_time).await.unwrap();
+
+    // A reading for the first hour turns up long after it closed
+    let late = reading(sensor, START + 30, 2.0);
+    warm.apply_rollups(std::slice::from_ref(&late)).await.unwrap();
+
+    let hours = sensor_buckets(&warm, RollupLevel::Hour, sensor, START, START + 3600).await;
+    assert_eq!(hours[0].1.count, 11);
+    assert_eq!(hours[0].1.max, 2.0);
+
+    // The source then corrects that value
+    let mut corrected = late.clone();
+    corrected.dose_rate_microsieverts = 0.2;
+    warm.correct_rollups(&corrected, &late).await.unwrap();
+
+    let hours = sensor_buckets(&warm, RollupLevel::Hour, sensor, START, START + 3600).await;
+    let stats = &hours[0].1;
+    assert_eq!(stats.count, 11);
+    assert!((stats.sum - 1.2).abs() < 1e-9);
+    assert!(stats.max < 0.25, "max no longer reflects the retracted value: {}", stats.max);
+}
+
+#[tokio::test]
+async fn test_corrected_location_is_retracted_from_the_old_cell() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let warm = warm_storage(dir).await;
+    let sensor = Uuid::new_v4();
+
+    let original = reading(sensor, START, 0.1);
+    warm.apply_rollups(std::slice::from_ref(&original)).await.unwrap();
+
+    // The source later fixes the reading's position, a few cells away
+    let moved = RadiationReading {
+        latitude: 37.9,
+        longitude: 140.5,
+        ..original.clone()
+    };
+    warm.correct_rollups(&moved, &original).await.unwrap();
+
+    let cell_count = |latitude, longitude| {
+        let cell = rollup::cell_of(latitude, longitude).unwrap();
+        let warm = &warm;
+        async move {
+            warm.query_rollups(RollupLevel::Hour, RollupScope::Cell, &[cell], at(START), at(START))
+                .await
+                .unwrap()
+                .iter()
+                .map(|(_, stats)| stats.count)
+                .sum::<u64>()
+        }
+    };
+    assert_eq!(cell_count(original.latitude, original.longitude).await, 0);
+    assert_eq!(cell_count(moved.latitude, moved.longitude).await, 1);
+    assert_eq!(sensor_buckets(&warm, RollupLevel::Hour, sensor, START, START).await[0].1.count, 1);
+}
+
+#[tokio::test]
+async fn test_redelivered_hot_readings_are_counted_once() {
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens");
+    let sensor = Uuid::new_v4();
+    let now = Utc::now().timestamp();
+    let hour = now - now.rem_euclid(3600);
+    let query = TimeRangeQuery::new(vec![sensor], hour - 3600, hour - 1).with_aggregation(AggregationLevel::OneHour);
+    assert!(db.plan(&query).steps.iter().all(|s| s.tier == Tier::Rollups));
+
+    // The source delivers the same reading twice
+    let delivered = reading(sensor, hour - 1800, 0.1);
+    db.write_reading(&delivered).await.unwrap();
+    db.write_reading(&delivered).await.unwrap();
+
+    let points = db.query_series(&query).await.unwrap().items;
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].count, 1);
+
+    // Then corrects it
+    db.write_reading(&reading(sensor, hour - 1800, 0.3)).await.unwrap();
+
+    let points = db.query_series(&query).await.unwrap().items;
+    assert_eq!(points[0].count, 1);
+    assert!((points[0].avg - 0.3).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_existing_warm_readings_are_backfilled() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let sensor = Uuid::new_v4();
+
+    let warm = warm_storage(dir).await;
+    for i in 0..5 {
+        warm.write_reading(&reading(sensor, START + i * 3600, 0.3)).await.unwrap();
+    }
+
+    // Migrating a database that has readings but no rollups builds them
+    warm.run_migrations().await.unwrap();
+    let days = sensor_buckets(&warm, RollupLevel::Day, sensor, START, START + 86_400).await;
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].1.count, 5);
+
+    // And does not count them twice on the next start
+    warm.run_migrations().await.unwrap();
+    let days = sensor_buckets(&warm, RollupLevel::Day, sensor, START, START + 86_400).await;
+    assert_eq!(days[0].1.count, 5);
+}
@@ -0,0 +1,172 @@
+//! Radius coverage, the sensor R-tree and spatial queries on the database.
+
+mod common;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use cherenkov_db::spatial::{self, SensorIndex, SensorLocation};
+use cherenkov_db::{
+    DatabaseConfig, GeoPoint, NewSensor, RadiationDatabase, RadiationReading, SpatialQuery,
+    StorageBackends, TimeRange,
+};
+
+fn point(latitude: f64, longitude: f64) -> GeoPoint {
+    GeoPoint { latitude, longitude }
+}
+
+fn location(sensor_id: Uuid, latitude: f64, longitude: f64, timestamp: i64) -> SensorLocation {
+    SensorLocation { sensor_id, latitude, longitude, timestamp }
+}
+
+fn reading(sensor_id: Uuid, timestamp: i64, latitude: f64, longitude: f64) -> RadiationReading {
+    RadiationReading {
+        latitude,
+        longitude,
+        ..common::reading(sensor_id, timestamp, 0.1)
+    }
+}
+
+/// Point `distance_km` from `center` along `bearing_deg`
+fn destination(center: &GeoPoint, distance_km: f64, bearing_deg: f64) -> GeoPoint {
+    let angular = distance_km / 6371.0;
+    let (lat, lon, bearing) = (center.latitude.to_radians(), center.longitude.to_radians(), bearing_deg.to_radians());
+    let lat2 = (lat.sin() * angular.cos() + lat.cos() * angular.sin() * bearing.cos()).asin();
+    let lon2 = lon + (bearing.sin() * angular.sin() * lat.cos()).atan2(angular.cos() - lat.sin() * lat2.sin());
+    point(lat2.to_degrees(), (lon2.to_degrees() + 540.0) % 360.0 - 180.0)
+}
+
+#[test]
+fn test_covering_cells_contain_the_whole_radius() {
+    let cases = [
+        (point(37.42, 141.03), 2.0),
+        (point(37.42, 141.03), 60.0),
+        // Straddling the antimeridian and close to the pole
+        (point(-16.5, 179.99), 25.0),
+        (point(89.8, 10.0), 40.0),
+        (point(0.0, 0.0), 3000.0),
+    ];
+
+    for (center, radius) in cases {
+        let cells = spatial::covering_cells(&center, radius);
+        assert!(!cells.is_empty());
+        assert!(cells.len() <= 32, "{} cells for {} km", cells.len(), radius);
+
+        for bearing in (0..360).step_by(5) {
+            let edge = destination(&center, radius * 0.999, bearing as f64);
+            let hash = geohash::encode(geohash::Coord { x: edge.longitude, y: edge.latitude }, 8).unwrap();
+            assert!(
+                cells.iter().any(|cell| hash.starts_with(cell.as_str())),
+                "{:?} at {} km, bearing {} not covered by {:?}",
+                center, radius, bearing, cells
+            );
+        }
+    }
+
+    // Cells shrink with the radius
+    let fine = spatial::covering_cells(&point(37.42, 141.03), 0.5);
+    let coarse = spatial::covering_cells(&point(37.42, 141.03), 200.0);
+    assert!(fine[0].len() > coarse[0].len());
+}
+
+#[test]
+fn test_sensor_index_nearest_and_bounding_box() {
+    let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+    let index = SensorIndex::from_locations([
+        location(ids[0], 35.68, 139.69, 0), // Tokyo
+        location(ids[1], 34.69, 135.50, 0), // Osaka
+        location(ids[2], 37.42, 141.03, 0), // Fukushima Daiichi
+        location(ids[3], -16.50, -179.95, 0), // Fiji, across the antimeridian
+    ]);
+    assert_eq!(index.len(), 4);
+
+    let nearest = index.nearest(&point(35.6, 139.8), 2);
+    assert_eq!(nearest[0].location.sensor_id, ids[0]);
+    assert_eq!(nearest[1].location.sensor_id, ids[2]);
+    assert!((nearest[0].distance_km - 13.5).abs() < 1.0, "{}", nearest[0].distance_km);
+
+    // Nearest is measured on the sphere, not in degrees
+    let nearest = index.nearest(&point(-16.5, 179.9), 1);
+    assert_eq!(nearest[0].location.sensor_id, ids[3]);
+    assert!(nearest[0].distance_km < 20.0);
+
+    let honshu = index.within(&SpatialQuery::bounding_box(34.0, 38.0, 135.0, 140.0));
+    let found: Vec<Uuid> = honshu.iter().map(|s| s.sensor_id).collect();
+    assert_eq!(found.len(), 2);
+    assert!(found.contains(&ids[0]) && found.contains(&ids[1]));
+
+    let pacific = index.within(&SpatialQuery::bounding_box(-20.0, -10.0, 175.0, -175.0));
+    assert_eq!(pacific.len(), 1);
+    assert_eq!(pacific[0].sensor_id, ids[3]);
+
+    // A mobile sensor moves; a late reading from its old spot does not move it back
+    index.upsert(location(ids[1], 43.06, 141.35, 10));
+    index.upsert(location(ids[1], 34.69, 135.50, 5));
+    assert_eq!(index.get(&ids[1]).map(|l| l.latitude), Some(43.06));
+    assert_eq!(index.within(&SpatialQuery::bounding_box(34.0, 38.0, 135.0, 140.0)).len(), 1);
+}
+
+#[tokio::test]
+async fn test_query_geo_finds_readings_across_cell_borders() {
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens");
+    let now = Utc::now().timestamp();
+
+    // The precision-4 cell boundary at 140.625°E, with a sensor 1 km either side
+    let center = point(37.5, 140.625);
+    let west = Uuid::new_v4();
+    let east = Uuid::new_v4();
+    let far = Uuid::new_v4();
+    db.write_reading(&reading(west, now - 60, 37.5, 140.614)).await.unwrap();
+    db.write_reading(&reading(east, now - 60, 37.5, 140.636)).await.unwrap();
+    db.write_reading(&reading(far, now - 60, 37.5, 141.5)).await.unwrap();
+
+    let window = TimeRange {
+        start: DateTime::from_timestamp(now - 3600, 0).unwrap(),
+        end: DateTime::from_timestamp(now, 0).unwrap(),
+    };
+    let readings = db.query_geo(center.clone(), 5.0, window).await.unwrap();
+    let mut found: Vec<Uuid> = readings.iter().map(|r| r.sensor_id).collect();
+    found.sort();
+    let mut expected = vec![west, east];
+    expected.sort();
+    assert_eq!(found, expected);
+
+    let nearest = db.nearest_sensors(&center, 3);
+    assert_eq!(nearest.len(), 3);
+    assert_eq!(nearest[2].location.sensor_id, far);
+}
+
+#[tokio::test]
+async fn test_query_bbox_skips_decommissioned_sensors() {
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens");
+
+    let mut ids = Vec::new();
+    for longitude in [140.0, 140.5] {
+        let sensor = db
+            .create_sensor(&NewSensor {
+                source: "safecast".to_string(),
+                latitude: Some(37.0),
+                longitude: Some(longitude),
+                ..NewSensor::default()
+            })
+            .await
+            .unwrap()
+            .expect("sensor registered");
+        ids.push(sensor.sensor_id);
+    }
+    db.decommission_sensor(&ids[1]).await.unwrap();
+
+    // Registered coordinates are indexed before the sensors report
+    assert_eq!(db.reload_sensor_index().await.unwrap(), 2);
+
+    let query = SpatialQuery::bounding_box(36.0, 38.0, 139.0, 141.0);
+    let active = db.query_bbox(&query).await.unwrap();
+    assert_eq!(active.iter().map(|s| s.sensor_id).collect::<Vec<_>>(), vec![ids[0]]);
+
+    let all = db.query_bbox(&SpatialQuery { active_only: false, ..query }).await.unwrap();
+    assert_eq!(all.len(), 2);
+}
@@ -0,0 +1,125 @@
+//! Storage traits and the embedded backends behind them.
+
+mod common;
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use cherenkov_db::sqlite::SqliteStorage;
+use cherenkov_db::{
+    Cache, ColdStorage, DatabaseConfig, DomainEvent, EventStore, EventType, MemoryCache, MemoryStore,
+    RadiationDatabase, ReadingStore, StorageBackends, TieringConfig, TierMigrator, TimeRangeQuery,
+};
+use common::reading;
+
+fn anomaly_event(sensor_id: Uuid, timestamp: i64) -> DomainEvent {
+    DomainEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: EventType::AnomalyDetected,
+        aggregate_id: sensor_id,
+        payload: serde_json::json!({ "severity": "warning", "z_score": 4.2 }),
+        timestamp,
+    }
+}
+
+#[tokio::test]
+async fn test_in_memory_database_round_trip() {
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens");
+    let sensor = Uuid::new_v4();
+    let now = Utc::now().timestamp();
+
+    for i in 0..5 {
+        db.write_reading(&reading(sensor, now - 600 + i * 60, 0.1 * (i + 1) as f64))
+            .await
+            .unwrap();
+    }
+
+    let points = db.query_series(&TimeRangeQuery::new(vec![sensor], now - 3600, now)).await.unwrap();
+    assert_eq!(points.items.len(), 5);
+
+    let latest = db.get_sensor_latest(&sensor.to_string()).await.unwrap().expect("latest reading");
+    assert_eq!(latest.timestamp, now - 600 + 4 * 60);
+
+    db.store_event(&anomaly_event(sensor, now)).await.unwrap();
+    assert!(db.health_check().await.is_healthy());
+}
+
+#[tokio::test]
+async fn test_memory_store_reads_anomalies_from_events() {
+    let store = MemoryStore::new();
+    let sensor = Uuid::new_v4();
+    let now = Utc::now().timestamp();
+
+    let event = anomaly_event(sensor, now);
+    store.store_event(&event).await.unwrap();
+    store.store_event(&event).await.unwrap();
+    store.store_event(&anomaly_event(sensor, now - 7200)).await.unwrap();
+
+    let anomalies = store.get_anomalies(now - 60, 10).await.unwrap();
+    assert_eq!(anomalies.len(), 1);
+    assert_eq!(anomalies[0].sensor_id, sensor);
+    assert_eq!(anomalies[0].severity, "warning");
+    assert_eq!(store.get_anomaly_count(3).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_memory_cache_expiry_and_prefix_delete() {
+    let cache = MemoryCache::new(2);
+
+    cache.set_raw("sensor:a:latest", "1".to_string(), 60).await.unwrap();
+    cache.set_raw("sensor:a:stats", "2".to_string(), 60).await.unwrap();
+    // Full of live entries, so this evicts the one closest to expiring
+    cache.set_raw("gone", "3".to_string(), 0).await.unwrap();
+    assert_eq!(cache.get_raw("sensor:a:latest").await.unwrap(), None);
+    assert_eq!(cache.get_raw("gone").await.unwrap(), None);
+
+    cache.set_raw("sensor:b:latest", "4".to_string(), 120).await.unwrap();
+    assert_eq!(cache.get_raw("sensor:b:latest").await.unwrap().as_deref(), Some("4"));
+
+    cache.delete_prefix("sensor:b:").await.unwrap();
+    assert_eq!(cache.get_raw("sensor:b:latest").await.unwrap(), None);
+
+    // Typed helpers share the Redis key layout
+    let cache: Arc<dyn Cache> = Arc::new(MemoryCache::default());
+    let sensor = Uuid::new_v4();
+    let latest = reading(sensor, 1_709_251_200, 0.2);
+    cache.set_sensor_latest(&sensor, &latest, 60).await.unwrap();
+    assert!(cache.get_raw(&format!("sensor:{}:latest", sensor)).await.unwrap().is_some());
+    cache.invalidate_sensor(&sensor).await.unwrap();
+    assert!(cache.get_sensor_latest(&sensor).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_memory_hot_tier_drains_into_warm() {
+    let hot = Arc::new(MemoryStore::new());
+    let warm = Arc::new(SqliteStorage::new(":memory:").await.expect("sqlite opens"));
+    warm.run_migrations().await.unwrap();
+
+    let sensor = Uuid::new_v4();
+    let now = Utc::now();
+    let config = DatabaseConfig::default();
+    let old = (now - chrono::Duration::days(config.hot_retention_days + 1)).timestamp();
+    let recent = now.timestamp() - 60;
+
+    hot.write_batch(&[reading(sensor, old, 0.1), reading(sensor, recent, 0.2)]).await.unwrap();
+
+    let cold = Arc::new(ColdStorage::disabled());
+    let mut migrator = TierMigrator::new(
+        warm.clone(),
+        cold,
+        &config,
+        TieringConfig { state_path: None, ..TieringConfig::default() },
+    )
+    .with_hot(hot.clone());
+
+    let report = migrator.run_once(now, &CancellationToken::new()).await.unwrap();
+    assert_eq!(report.hot_to_warm, 1);
+    assert_eq!(hot.len().await, 1);
+    assert_eq!(hot.latest_reading(sensor).await.unwrap().map(|r| r.timestamp), Some(recent));
+    assert_eq!(warm.query_by_time_range(sensor, old, old).await.unwrap().len(), 1);
+}
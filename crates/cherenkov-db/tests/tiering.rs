@@ -0,0 +1,229 @@
+//! Warm → cold migration and retention enforcement against local storage.
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use cherenkov_db::sqlite::SqliteStorage;
+use cherenkov_db::{
+    ColdStorage, DatabaseConfig, MemoryStore, RadiationDatabase, RadiationReading, ReadingStore,
+    StorageBackends, TierMigrator, TieringConfig,
+};
+use common::temp_dir;
+
+/// 2024-03-01 00:00:00 UTC
+const NOW: i64 = 1709251200;
+const DAY: i64 = 86_400;
+
+async fn warm_storage(dir: &Path) -> Arc<SqliteStorage> {
+    std::fs::create_dir_all(dir).unwrap();
+    let path = format!("{}?mode=rwc", dir.join("warm.db").display());
+    let warm = SqliteStorage::new(&path).await.expect("sqlite opens");
+    warm.run_migrations().await.unwrap();
+    Arc::new(warm)
+}
+
+fn reading(sensor_id: Uuid, timestamp: i64) -> RadiationReading {
+    RadiationReading {
+        latitude: 50.1,
+        longitude: 8.6,
+        cell_id: "u0yj".to_string(),
+        ..common::reading(sensor_id, timestamp, 0.12)
+    }
+}
+
+fn at(ts: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap()
+}
+
+fn migrator(warm: Arc<SqliteStorage>, cold: Arc<ColdStorage>) -> TierMigrator {
+    let retention = DatabaseConfig {
+        warm_retention_days: 30,
+        ..DatabaseConfig::default()
+    };
+    TierMigrator::new(
+        warm,
+        cold,
+        &retention,
+        TieringConfig {
+            delete_batch_size: 7,
+            state_path: None,
+            ..TieringConfig::default()
+        },
+    )
+}
+
+#[tokio::test]
+async fn test_warm_retention_drops_expired_readings_without_cold_tier() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let warm = warm_storage(dir).await;
+    let sensor = Uuid::new_v4();
+
+    for ts in [NOW - 40 * DAY, NOW - 31 * DAY, NOW - 29 * DAY, NOW - DAY] {
+        warm.write_reading(&reading(sensor, ts)).await.unwrap();
+    }
+
+    let mut tiering = migrator(warm.clone(), Arc::new(ColdStorage::disabled()));
+    let report = tiering.run_once(at(NOW), &CancellationToken::new()).await.unwrap();
+    assert_eq!(report.warm_expired, 2);
+    assert_eq!(report.warm_to_cold, 0);
+
+    let remaining = warm.readings_in_window(at(0), at(NOW)).await.unwrap();
+    let timestamps: Vec<_> = remaining.iter().map(|r| r.timestamp).collect();
+    assert_eq!(timestamps, vec![NOW - 29 * DAY, NOW - DAY]);
+
+    // Nothing left past retention, so a second run is a no-op
+    let report = tiering.run_once(at(NOW), &CancellationToken::new()).await.unwrap();
+    assert_eq!(report.warm_expired, 0);
+}
+
+#[tokio::test]
+async fn test_moves_safecast_readings_out_of_the_hot_tier() {
+    let db = RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens");
+    let taken = Utc::now().timestamp() - 2 * DAY;
+    // Safecast hands over readings with day buckets
+    let safecast = RadiationReading {
+        bucket: taken / DAY,
+        ..reading(Uuid::new_v4(), taken)
+    };
+    db.write_reading(&safecast).await.unwrap();
+
+    let mut tiering = db.tier_migrator(TieringConfig {
+        state_path: None,
+        ..TieringConfig::default()
+    });
+    let later = Utc::now() + chrono::Duration::days(8);
+    let report = tiering.run_once(later, &CancellationToken::new()).await.unwrap();
+    assert_eq!(report.hot_to_warm, 1);
+}
+
+#[tokio::test]
+async fn test_moves_legacy_day_buckets_out_of_the_hot_tier() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let warm = warm_storage(dir).await;
+    let hot = Arc::new(MemoryStore::new());
+    let sensor = Uuid::new_v4();
+
+    // Stored before buckets were derived from the timestamp: one past hot
+    // retention, one still within it
+    for ts in [NOW - 9 * DAY, NOW - 2 * DAY] {
+        hot.write_reading(&RadiationReading { bucket: ts / DAY, ..reading(sensor, ts) }).await.unwrap();
+    }
+
+    let mut tiering = migrator(warm.clone(), Arc::new(ColdStorage::disabled())).with_hot(hot.clone());
+    let report = tiering.run_once(at(NOW), &CancellationToken::new()).await.unwrap();
+    assert_eq!(report.hot_to_warm, 1);
+    assert_eq!(hot.len().await, 1);
+
+    let moved = warm.readings_in_window(at(0), at(NOW)).await.unwrap();
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].bucket, RadiationReading::bucket_for(NOW - 9 * DAY));
+}
+
+#[cfg(feature = "cold-storage")]
+fn cold_storage(dir: &Path) -> Arc<ColdStorage> {
+    Arc::new(
+        ColdStorage::new(cherenkov_db::ColdStorageConfig {
+            local_path: dir.join("cold").to_string_lossy().into_owned(),
+            ..cherenkov_db::ColdStorageConfig::default()
+        })
+        .expect("cold storage opens"),
+    )
+}
+
+#[cfg(feature = "cold-storage")]
+#[tokio::test]
+async fn test_moves_aged_warm_readings_to_cold() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let warm = warm_storage(dir).await;
+    let cold = cold_storage(dir);
+    let sensors = [Uuid::new_v4(), Uuid::new_v4()];
+
+    // Three days past retention plus a recent day that must stay warm
+    let mut aged = Vec::new();
+    for day in [33, 32, 31] {
+        for i in 0..12 {
+            for sensor in sensors {
+                aged.push(reading(sensor, NOW - day * DAY + i * 1800));
+            }
+        }
+    }
+    for r in &aged {
+        warm.write_reading(r).await.unwrap();
+    }
+    warm.write_reading(&reading(sensors[0], NOW - 2 * DAY)).await.unwrap();
+
+    let mut tiering = migrator(warm.clone(), cold.clone());
+    let report = tiering.run_once(at(NOW), &CancellationToken::new()).await.unwrap();
+    assert_eq!(report.warm_to_cold, aged.len() as u64);
+
+    let archived = cold.query_range(&[], at(0), at(NOW)).await.unwrap();
+    assert_eq!(archived.len(), aged.len());
+    assert_eq!(cold.list_archives().await.unwrap().len(), 3, "one archive per day partition");
+
+    let remaining = warm.readings_in_window(at(0), at(NOW)).await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].timestamp, NOW - 2 * DAY);
+
+    // Rerunning finds nothing to move
+    let report = tiering.run_once(at(NOW), &CancellationToken::new()).await.unwrap();
+    assert_eq!(report, Default::default());
+}
+
+#[cfg(feature = "cold-storage")]
+#[tokio::test]
+async fn test_resumes_interrupted_move_without_duplicates() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let warm = warm_storage(dir).await;
+    let cold = cold_storage(dir);
+    let sensor = Uuid::new_v4();
+
+    let aged: Vec<_> = (0..10).map(|i| reading(sensor, NOW - 35 * DAY + i * 600)).collect();
+    for r in &aged {
+        warm.write_reading(r).await.unwrap();
+    }
+
+    // A previous run archived part of the window, then died before deleting from warm
+    cold.archive_readings(&aged[..6]).await.unwrap();
+
+    let mut tiering = migrator(warm.clone(), cold.clone());
+    let report = tiering.run_once(at(NOW), &CancellationToken::new()).await.unwrap();
+    assert_eq!(report.warm_to_cold, 10);
+
+    let archived = cold.query_range(&[], at(0), at(NOW)).await.unwrap();
+    assert_eq!(archived.len(), 10, "already archived readings are not written twice");
+    assert!(warm.oldest_reading_time().await.unwrap().is_none());
+}
+
+#[cfg(feature = "cold-storage")]
+#[tokio::test]
+async fn test_cancelled_run_leaves_warm_intact() {
+    let tmp = temp_dir();
+    let dir = tmp.path();
+    let warm = warm_storage(dir).await;
+    let cold = cold_storage(dir);
+    warm.write_reading(&reading(Uuid::new_v4(), NOW - 40 * DAY)).await.unwrap();
+
+    let shutdown = CancellationToken::new();
+    shutdown.cancel();
+
+    let mut tiering = migrator(warm.clone(), cold.clone());
+    let report = tiering.run_once(at(NOW), &shutdown).await.unwrap();
+    assert_eq!(report.warm_to_cold, 0);
+    assert!(warm.oldest_reading_time().await.unwrap().is_some());
+
+    // The next run picks it up
+    let report = tiering.run_once(at(NOW), &CancellationToken::new()).await.unwrap();
+    assert_eq!(report.warm_to_cold, 1);
+}
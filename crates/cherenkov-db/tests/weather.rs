@@ -0,0 +1,60 @@
+//! Hourly weather stored from weather sources and looked up near sensors.
+
+use cherenkov_db::{DatabaseConfig, RadiationDatabase, StorageBackends, WeatherObservation};
+
+/// 2024-01-15T08:00:00Z
+const HOUR: i64 = 1705305600;
+
+fn observation(latitude: f64, longitude: f64, timestamp: i64, precipitation_mm: f64) -> WeatherObservation {
+    WeatherObservation {
+        latitude,
+        longitude,
+        timestamp,
+        source: "open_meteo".to_string(),
+        precipitation_mm,
+    }
+}
+
+async fn database() -> RadiationDatabase {
+    RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+        .await
+        .expect("in-memory database opens")
+}
+
+#[tokio::test]
+async fn test_weather_near_picks_the_nearest_point() {
+    let db = database().await;
+    db.store_weather(&[
+        observation(37.42, 141.03, HOUR, 1.2),
+        observation(37.42, 141.03, HOUR + 3600, 3.4),
+        // About 30 km away
+        observation(37.70, 141.03, HOUR, 9.0),
+        // Timestamps inside the hour are stored against its start
+        observation(37.42, 141.03, HOUR - 3600 + 900, 0.5),
+    ])
+    .await
+    .unwrap();
+
+    let weather = db.weather_near(37.43, 141.04, 50.0, HOUR - 3600, HOUR + 3600).await.unwrap();
+    let hours: Vec<(i64, f64)> = weather.iter().map(|o| (o.timestamp, o.precipitation_mm)).collect();
+    assert_eq!(hours, vec![(HOUR - 3600, 0.5), (HOUR, 1.2), (HOUR + 3600, 3.4)]);
+
+    // Only the requested hours, and the far point once it is the nearest
+    let weather = db.weather_near(37.69, 141.03, 50.0, HOUR, HOUR).await.unwrap();
+    assert_eq!(weather, vec![observation(37.70, 141.03, HOUR, 9.0)]);
+
+    // Nothing beyond the radius
+    assert!(db.weather_near(38.5, 141.03, 50.0, HOUR, HOUR).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_weather_is_replaced_on_refetch() {
+    let db = database().await;
+    db.store_weather(&[observation(37.42, 141.03, HOUR, 1.2)]).await.unwrap();
+    // Forecast hours are revised as they become observations
+    db.store_weather(&[observation(37.42, 141.03, HOUR, 2.5)]).await.unwrap();
+
+    let weather = db.weather_near(37.42, 141.03, 10.0, HOUR, HOUR).await.unwrap();
+    assert_eq!(weather.len(), 1);
+    assert_eq!(weather[0].precipitation_mm, 2.5);
+}
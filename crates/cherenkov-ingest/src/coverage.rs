@@ -0,0 +1,189 @@
+//! Network coverage per region and around watched facilities, rolled up
+//! from the sensor registry's liveness states.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use cherenkov_core::CherenkovEvent;
+use cherenkov_db::{Sensor, SensorState};
+
+use crate::dedup::haversine_km;
+
+/// Regions and sites whose coverage is reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoverageConfig {
+    /// Size of the lat/lon grid cells used as coverage regions
+    pub region_degrees: f64,
+    /// Fraction of a region's sensors that must be offline to report coverage loss
+    pub loss_fraction: f64,
+    /// Sites whose surrounding coverage is reported separately
+    pub facilities: Vec<WatchedSite>,
+}
+
+impl Default for CoverageConfig {
+    fn default() -> Self {
+        Self {
+            region_degrees: 5.0,
+            loss_fraction: 0.5,
+            facilities: Vec::new(),
+        }
+    }
+}
+
+/// Facility around which coverage is monitored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedSite {
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub radius_km: f64,
+}
+
+/// Tracks which regions and facilities have lost coverage
+///
+/// A station going dark near a facility can matter as much as a spike, so
+/// each site is tallied on its own as well as in its grid region.
+#[derive(Debug, Default)]
+pub struct CoverageMonitor {
+    config: CoverageConfig,
+    healthy: HashMap<String, bool>,
+}
+
+impl CoverageMonitor {
+    pub fn new(config: CoverageConfig) -> Self {
+        Self {
+            config,
+            healthy: HashMap::new(),
+        }
+    }
+
+    /// Emit a `HealthUpdate` for each region or facility whose coverage changed
+    ///
+    /// Sensors in maintenance, decommissioned or without a location are not counted.
+    pub fn update(&mut self, sensors: &[Sensor]) -> Vec<CherenkovEvent> {
+        // component -> (offline, total)
+        let mut tallies: HashMap<String, (usize, usize)> = HashMap::new();
+
+        for sensor in sensors.iter().filter(|s| s.status.is_automatic()) {
+            let (Some(lat), Some(lon)) = (sensor.latitude, sensor.longitude) else {
+                continue;
+            };
+            let offline = (sensor.status == SensorState::Offline) as usize;
+
+            let tally = tallies.entry(self.region_key(lat, lon)).or_default();
+            tally.0 += offline;
+            tally.1 += 1;
+
+            for site in &self.config.facilities {
+                if haversine_km(lat, lon, site.lat, site.lon) <= site.radius_km {
+                    let tally = tallies.entry(format!("coverage:facility:{}", site.name)).or_default();
+                    tally.0 += offline;
+                    tally.1 += 1;
+                }
+            }
+        }
+
+        let mut events = Vec::new();
+        for (component, (offline, total)) in tallies {
+            let healthy = (offline as f64 / total as f64) < self.config.loss_fraction;
+            let previous = self.healthy.insert(component.clone(), healthy);
+
+            // New components start healthy and only report once they lose coverage
+            if previous.unwrap_or(true) == healthy {
+                continue;
+            }
+
+            metrics::counter!(
+                "cherenkov_ingest_coverage_changes_total",
+                "healthy" => healthy.to_string()
+            ).increment(1);
+
+            events.push(CherenkovEvent::HealthUpdate {
+                component,
+                healthy,
+                message: Some(format!("{} of {} sensors offline", offline, total)),
+            });
+        }
+        events
+    }
+
+    fn region_key(&self, lat: f64, lon: f64) -> String {
+        let size = self.config.region_degrees;
+        format!("coverage:{}:{}", (lat / size).floor() as i64, (lon / size).floor() as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn sensor(lat: f64, lon: f64, status: SensorState) -> Sensor {
+        Sensor {
+            sensor_id: Uuid::new_v4(),
+            name: None,
+            source: "safecast".to_string(),
+            detector_model: None,
+            calibration_factor: 1.0,
+            height_m: None,
+            placement: None,
+            owner: None,
+            commissioned_at: None,
+            decommissioned_at: None,
+            status,
+            status_changed_at: None,
+            last_reading: None,
+            cadence_secs: None,
+            latitude: Some(lat),
+            longitude: Some(lon),
+        }
+    }
+
+    fn coverage(events: &[CherenkovEvent]) -> Vec<(String, bool)> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                CherenkovEvent::HealthUpdate { component, healthy, .. } => Some((component.clone(), *healthy)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reports_region_and_facility_coverage_loss() {
+        let mut monitor = CoverageMonitor::new(CoverageConfig {
+            facilities: vec![WatchedSite {
+                name: "fukushima-daiichi".to_string(),
+                lat: 37.4211,
+                lon: 141.0328,
+                radius_km: 30.0,
+            }],
+            ..CoverageConfig::default()
+        });
+
+        // Two stations near the plant, three further inland in the same region
+        let near = [(37.45, 141.00), (37.40, 140.98)];
+        let inland = [(37.75, 140.47), (37.76, 140.45), (37.05, 140.89)];
+        let network = |near_state| {
+            let mut sensors: Vec<Sensor> = near.iter().map(|(lat, lon)| sensor(*lat, *lon, near_state)).collect();
+            sensors.extend(inland.iter().map(|(lat, lon)| sensor(*lat, *lon, SensorState::Online)));
+            sensors
+        };
+        assert!(monitor.update(&network(SensorState::Online)).is_empty());
+
+        // Two of five sensors in the region are offline, below the loss fraction
+        assert_eq!(
+            coverage(&monitor.update(&network(SensorState::Offline))),
+            vec![("coverage:facility:fukushima-daiichi".to_string(), false)]
+        );
+        assert!(monitor.update(&network(SensorState::Offline)).is_empty(), "reported once");
+
+        // Coverage is reported restored once the plant's stations come back
+        assert_eq!(
+            coverage(&monitor.update(&network(SensorState::Online))),
+            vec![("coverage:facility:fukushima-daiichi".to_string(), true)]
+        );
+    }
+}
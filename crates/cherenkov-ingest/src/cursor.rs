@@ -0,0 +1,132 @@
+use std::path::{Path, PathBuf};
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::RequestBuilder;
+use serde::{Serialize, Deserialize};
+use tracing::{debug, warn};
+
+/// Incremental fetch position for a data source
+///
+/// Sources fill in whichever fields their upstream API supports: a
+/// high-water mark (timestamp plus record ID for tie-breaking) for APIs
+/// with `since`-style filters, and HTTP validators for conditional GETs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceCursor {
+    /// Unix timestamp of the newest record seen
+    pub last_timestamp: Option<i64>,
+    /// Upstream ID of the newest record at `last_timestamp`
+    pub last_id: Option<String>,
+    /// `ETag` of the last successful response
+    pub etag: Option<String>,
+    /// `Last-Modified` of the last successful response
+    pub last_modified: Option<String>,
+}
+
+impl SourceCursor {
+    /// Attach `If-None-Match` / `If-Modified-Since` from stored validators
+    pub fn conditional(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+
+    /// Remember validators from a successful response
+    pub fn record_validators(&mut self, headers: &HeaderMap) {
+        if let Some(etag) = headers.get(ETAG).and_then(|v| v.to_str().ok()) {
+            self.etag = Some(etag.to_string());
+        }
+        if let Some(last_modified) = headers.get(LAST_MODIFIED).and_then(|v| v.to_str().ok()) {
+            self.last_modified = Some(last_modified.to_string());
+        }
+    }
+
+    /// Whether a record is strictly newer than the high-water mark
+    pub fn is_after(&self, timestamp: i64, id: &str) -> bool {
+        match self.last_timestamp {
+            None => true,
+            Some(last) if timestamp != last => timestamp > last,
+            Some(_) => match &self.last_id {
+                Some(last_id) => compare_ids(id, last_id).is_gt(),
+                None => false,
+            },
+        }
+    }
+
+    /// Move the high-water mark forward if the record is newer
+    pub fn advance(&mut self, timestamp: i64, id: &str) {
+        if self.is_after(timestamp, id) {
+            self.last_timestamp = Some(timestamp);
+            self.last_id = Some(id.to_string());
+        }
+    }
+}
+
+/// Compare upstream IDs numerically when both parse, lexically otherwise
+fn compare_ids(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// File-backed store for per-source cursors
+///
+/// Each source gets one JSON file under the store directory, written via a
+/// temporary file and rename so a crash never leaves a truncated cursor.
+#[derive(Debug, Clone)]
+pub struct CursorStore {
+    dir: PathBuf,
+}
+
+impl CursorStore {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path_for(&self, source: &str) -> PathBuf {
+        let file_name: String = source
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+
+    /// Load the persisted cursor for a source, if any
+    pub async fn load(&self, source: &str) -> Option<SourceCursor> {
+        let path = self.path_for(source);
+        let data = match tokio::fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Failed to read cursor {}: {}", path.display(), e);
+                return None;
+            }
+        };
+
+        match serde_json::from_slice(&data) {
+            Ok(cursor) => Some(cursor),
+            Err(e) => {
+                warn!("Ignoring corrupt cursor {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Persist the cursor for a source
+    pub async fn save(&self, source: &str, cursor: &SourceCursor) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        let path = self.path_for(source);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(cursor)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+
+        debug!("Saved cursor for {} to {}", source, path.display());
+        Ok(())
+    }
+}
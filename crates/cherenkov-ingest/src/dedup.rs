@@ -0,0 +1,494 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use dashmap::DashMap;
+use serde::{Serialize, Deserialize};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use cherenkov_db::RadiationReading;
+
+/// Deduplication configuration
+#[derive(Debug, Clone)]
+pub struct DedupConfig {
+    /// How long a key is retained after it was recorded
+    pub window_secs: u64,
+    /// Hard bound on retained keys; oldest are evicted first
+    pub max_entries: usize,
+    /// Stations closer than this are candidates for aliasing
+    pub alias_radius_m: f64,
+    /// Maximum timestamp difference for two readings to count as co-observations
+    pub alias_time_tolerance_secs: i64,
+    /// Maximum relative dose rate difference for co-observations
+    pub alias_value_tolerance: f64,
+    /// Co-observations required before two stations are linked
+    pub alias_min_matches: u32,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 86400,
+            max_entries: 1_000_000,
+            alias_radius_m: 100.0,
+            alias_time_tolerance_secs: 600,
+            alias_value_tolerance: 0.05,
+            alias_min_matches: 3,
+        }
+    }
+}
+
+/// Content key for a reading: same sensor, same time, same value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DedupKey {
+    pub sensor_id: Uuid,
+    pub timestamp: i64,
+    pub value_hash: u64,
+}
+
+impl DedupKey {
+    pub fn from_reading(reading: &RadiationReading) -> Self {
+        Self {
+            sensor_id: reading.sensor_id,
+            timestamp: reading.timestamp,
+            value_hash: value_hash(reading.dose_rate_microsieverts),
+        }
+    }
+
+    /// Redis key used when deduplication is shared between instances
+    pub fn redis_key(&self) -> String {
+        format!("dedup:{}:{}:{:016x}", self.sensor_id, self.timestamp, self.value_hash)
+    }
+}
+
+/// Stable FNV-1a hash of a dose rate quantized to 1 nSv/h
+///
+/// `DefaultHasher` is not stable across Rust releases, which would
+/// invalidate persisted state on upgrade.
+fn value_hash(dose_rate: f64) -> u64 {
+    let quantized = (dose_rate * 1000.0).round() as i64;
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in quantized.to_le_bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Result of checking a reading against the deduplicator
+#[derive(Debug, Clone, PartialEq)]
+pub enum DedupOutcome {
+    /// First time this content has been seen
+    New,
+    /// Exact content already ingested
+    Duplicate,
+    /// Reported by an aliased station whose canonical twin already covers it
+    Alias { canonical: Uuid },
+}
+
+/// Last known position and value of a station, for alias detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StationObservation {
+    source: String,
+    latitude: f64,
+    longitude: f64,
+    timestamp: i64,
+    dose_rate: f64,
+}
+
+/// Persisted deduplicator state
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DedupSnapshot {
+    pub keys: Vec<DedupKey>,
+    pub aliases: HashMap<Uuid, Uuid>,
+}
+
+/// Content-based deduplication with cross-network station aliasing
+///
+/// Keys are evicted by arrival time, so a reading with a bogus future
+/// timestamp cannot flush the window and an old reading re-delivered within
+/// it is still caught. Late and out-of-order readings are accepted as long
+/// as their exact content has not been seen.
+#[derive(Debug, Clone)]
+pub struct Deduplicator {
+    config: DedupConfig,
+    seen: Arc<DashMap<DedupKey, ()>>,
+    /// Arrival time (unix seconds) -> keys recorded then
+    by_arrival: Arc<Mutex<BTreeMap<i64, Vec<DedupKey>>>>,
+    stations: Arc<DashMap<Uuid, StationObservation>>,
+    /// Grid cell (0.01 degree) -> stations in that cell
+    grid: Arc<DashMap<(i32, i32), HashSet<Uuid>>>,
+    pair_matches: Arc<DashMap<(Uuid, Uuid), u32>>,
+    /// alias sensor -> canonical sensor
+    aliases: Arc<DashMap<Uuid, Uuid>>,
+}
+
+impl Deduplicator {
+    pub fn new(config: DedupConfig) -> Self {
+        Self {
+            config,
+            seen: Arc::new(DashMap::new()),
+            by_arrival: Arc::new(Mutex::new(BTreeMap::new())),
+            stations: Arc::new(DashMap::new()),
+            grid: Arc::new(DashMap::new()),
+            pair_matches: Arc::new(DashMap::new()),
+            aliases: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Check a reading and record it if it is new
+    pub fn check(&self, reading: &RadiationReading) -> DedupOutcome {
+        self.check_at(reading, chrono::Utc::now().timestamp())
+    }
+
+    fn check_at(&self, reading: &RadiationReading, now: i64) -> DedupOutcome {
+        let key = DedupKey::from_reading(reading);
+        if self.seen.contains_key(&key) {
+            return DedupOutcome::Duplicate;
+        }
+
+        if let Some(canonical) = self.covered_by_alias(reading) {
+            return DedupOutcome::Alias { canonical };
+        }
+
+        self.record(key, now);
+        self.observe_station(reading);
+        DedupOutcome::New
+    }
+
+    /// Whether exact content has been recorded
+    pub fn contains(&self, key: &DedupKey) -> bool {
+        self.seen.contains_key(key)
+    }
+
+    fn record(&self, key: DedupKey, now: i64) {
+        self.seen.insert(key, ());
+
+        let mut by_arrival = self.by_arrival.lock().unwrap_or_else(|e| e.into_inner());
+        by_arrival.entry(now).or_default().push(key);
+
+        let cutoff = now - self.config.window_secs as i64;
+        while let Some((&arrived, _)) = by_arrival.first_key_value() {
+            if arrived >= cutoff && self.seen.len() <= self.config.max_entries {
+                break;
+            }
+            if let Some((_, keys)) = by_arrival.pop_first() {
+                for k in keys {
+                    self.seen.remove(&k);
+                }
+            }
+        }
+    }
+
+    fn covered_by_alias(&self, reading: &RadiationReading) -> Option<Uuid> {
+        let canonical = *self.aliases.get(&reading.sensor_id)?;
+        let twin = self.stations.get(&canonical)?;
+
+        if (twin.timestamp - reading.timestamp).abs() <= self.config.alias_time_tolerance_secs {
+            Some(canonical)
+        } else {
+            // Canonical station has nothing recent, keep the alias reading
+            None
+        }
+    }
+
+    fn observe_station(&self, reading: &RadiationReading) {
+        let observation = StationObservation {
+            source: reading.source.clone(),
+            latitude: reading.latitude,
+            longitude: reading.longitude,
+            timestamp: reading.timestamp,
+            dose_rate: reading.dose_rate_microsieverts,
+        };
+
+        let cell = grid_cell(reading.latitude, reading.longitude);
+        if let Some(previous) = self.stations.insert(reading.sensor_id, observation.clone()) {
+            let previous_cell = grid_cell(previous.latitude, previous.longitude);
+            if previous_cell != cell {
+                if let Some(mut members) = self.grid.get_mut(&previous_cell) {
+                    members.remove(&reading.sensor_id);
+                }
+            }
+        }
+        self.grid.entry(cell).or_default().insert(reading.sensor_id);
+
+        if self.aliases.contains_key(&reading.sensor_id) {
+            return;
+        }
+
+        for candidate in self.neighbours(cell, reading.sensor_id) {
+            if self.is_co_observation(&observation, &candidate.1) {
+                self.count_match(reading.sensor_id, candidate.0);
+            }
+        }
+    }
+
+    fn neighbours(&self, cell: (i32, i32), exclude: Uuid) -> Vec<(Uuid, StationObservation)> {
+        let mut result = Vec::new();
+        for dlat in -1..=1 {
+            for dlon in -1..=1 {
+                let Some(members) = self.grid.get(&(cell.0 + dlat, cell.1 + dlon)) else {
+                    continue;
+                };
+                for sensor_id in members.iter().filter(|id| **id != exclude) {
+                    if let Some(obs) = self.stations.get(sensor_id) {
+                        result.push((*sensor_id, obs.clone()));
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    fn is_co_observation(&self, a: &StationObservation, b: &StationObservation) -> bool {
+        if a.source == b.source {
+            return false;
+        }
+        if (a.timestamp - b.timestamp).abs() > self.config.alias_time_tolerance_secs {
+            return false;
+        }
+
+        let distance_m = haversine_km(a.latitude, a.longitude, b.latitude, b.longitude) * 1000.0;
+        if distance_m > self.config.alias_radius_m {
+            return false;
+        }
+
+        let scale = a.dose_rate.abs().max(b.dose_rate.abs());
+        scale == 0.0 || (a.dose_rate - b.dose_rate).abs() / scale <= self.config.alias_value_tolerance
+    }
+
+    fn count_match(&self, sensor: Uuid, other: Uuid) {
+        let pair = if sensor < other { (sensor, other) } else { (other, sensor) };
+        let mut count = self.pair_matches.entry(pair).or_insert(0);
+        *count += 1;
+
+        if *count >= self.config.alias_min_matches {
+            drop(count);
+            self.pair_matches.remove(&pair);
+
+            // Whichever station completes the match becomes the alias,
+            // following the other one to its canonical station if it has one
+            let canonical = self.aliases.get(&other).map(|c| *c).unwrap_or(other);
+            if canonical != sensor {
+                self.aliases.insert(sensor, canonical);
+                info!("Linked station {} as alias of {}", sensor, canonical);
+                metrics::counter!("cherenkov_ingest_station_aliases_total").increment(1);
+            }
+        }
+    }
+
+    /// Canonical station for an aliased sensor
+    pub fn canonical(&self, sensor_id: &Uuid) -> Option<Uuid> {
+        self.aliases.get(sensor_id).map(|c| *c)
+    }
+
+    /// All alias links as (alias, canonical)
+    pub fn aliases(&self) -> Vec<(Uuid, Uuid)> {
+        self.aliases.iter().map(|e| (*e.key(), *e.value())).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    #[allow(dead_code)]
+    pub fn clear(&self) {
+        self.seen.clear();
+        self.by_arrival.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Keys are listed oldest arrival first
+    pub fn snapshot(&self) -> DedupSnapshot {
+        let by_arrival = self.by_arrival.lock().unwrap_or_else(|e| e.into_inner());
+        DedupSnapshot {
+            keys: by_arrival.values().flatten().copied().collect(),
+            aliases: self.aliases.iter().map(|e| (*e.key(), *e.value())).collect(),
+        }
+    }
+
+    /// Restored keys count as arriving now, so they get a full window again
+    pub fn restore(&self, snapshot: DedupSnapshot) {
+        let now = chrono::Utc::now().timestamp();
+        for key in snapshot.keys {
+            self.record(key, now);
+        }
+        for (alias, canonical) in snapshot.aliases {
+            self.aliases.insert(alias, canonical);
+        }
+    }
+
+    /// Persist state so deduplication survives restarts
+    pub async fn save(&self, path: &str) -> anyhow::Result<()> {
+        let data = bincode::serialize(&self.snapshot())?;
+        if let Some(parent) = Path::new(path).parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = format!("{}.tmp", path);
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        debug!("Saved {} dedup keys to {}", self.len(), path);
+        Ok(())
+    }
+
+    /// Load state written by `save`; a missing file is not an error
+    pub async fn load(&self, path: &str) -> anyhow::Result<()> {
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        match bincode::deserialize::<DedupSnapshot>(&data) {
+            Ok(snapshot) => {
+                info!("Restored {} dedup keys and {} aliases", snapshot.keys.len(), snapshot.aliases.len());
+                self.restore(snapshot);
+            }
+            Err(e) => warn!("Ignoring corrupt dedup state {}: {}", path, e),
+        }
+        Ok(())
+    }
+}
+
+/// Dedup key store shared through Redis, for multiple ingest instances
+pub struct RedisDedupStore {
+    connection: redis::aio::MultiplexedConnection,
+    ttl_secs: u64,
+}
+
+impl RedisDedupStore {
+    pub async fn new(redis_url: &str, ttl_secs: u64) -> anyhow::Result<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let connection = client.get_multiplexed_async_connection().await?;
+        Ok(Self { connection, ttl_secs })
+    }
+
+    /// Atomically claim a key; returns false if another instance already has it
+    pub async fn claim(&self, key: &DedupKey) -> anyhow::Result<bool> {
+        let mut conn = self.connection.clone();
+        let result: Option<String> = redis::cmd("SET")
+            .arg(key.redis_key())
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.ttl_secs)
+            .query_async(&mut conn)
+            .await?;
+        Ok(result.is_some())
+    }
+}
+
+fn grid_cell(latitude: f64, longitude: f64) -> (i32, i32) {
+    ((latitude * 100.0).floor() as i32, (longitude * 100.0).floor() as i32)
+}
+
+pub(crate) fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    const R: f64 = 6371.0;
+
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * R * a.sqrt().atan2((1.0 - a).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cherenkov_db::QualityFlag;
+
+    fn reading(sensor_id: Uuid, source: &str, timestamp: i64, dose_rate: f64) -> RadiationReading {
+        RadiationReading {
+            sensor_id,
+            bucket: timestamp / 3600,
+            timestamp,
+            latitude: 50.0,
+            longitude: 8.0,
+            dose_rate_microsieverts: dose_rate,
+            uncertainty: 0.1,
+            quality_flag: QualityFlag::Valid,
+            source: source.to_string(),
+            cell_id: String::new(),
+            track_id: None,
+            altitude_m: None,
+            qc_reasons: Vec::new(),
+            provenance: None,
+        }
+    }
+
+    #[test]
+    fn test_out_of_order_readings_are_kept() {
+        let dedup = Deduplicator::new(DedupConfig::default());
+        let sensor = Uuid::new_v4();
+
+        assert_eq!(dedup.check(&reading(sensor, "a", 1_000, 0.1)), DedupOutcome::New);
+        assert_eq!(dedup.check(&reading(sensor, "a", 900, 0.1)), DedupOutcome::New);
+        assert_eq!(dedup.check(&reading(sensor, "a", 1_000, 0.1)), DedupOutcome::Duplicate);
+        // Same time, corrected value is different content
+        assert_eq!(dedup.check(&reading(sensor, "a", 1_000, 0.2)), DedupOutcome::New);
+    }
+
+    #[test]
+    fn test_eviction_by_arrival_time() {
+        let dedup = Deduplicator::new(DedupConfig {
+            window_secs: 100,
+            ..DedupConfig::default()
+        });
+        let sensor = Uuid::new_v4();
+        let now = 1_000_000;
+
+        dedup.check_at(&reading(sensor, "a", 1_000, 0.1), now);
+        // A timestamp far in the future does not flush the window
+        dedup.check_at(&reading(sensor, "a", 9_000_000, 0.1), now + 10);
+        assert_eq!(
+            dedup.check_at(&reading(sensor, "a", 1_000, 0.1), now + 20),
+            DedupOutcome::Duplicate,
+            "old re-delivery within the window"
+        );
+
+        dedup.check_at(&reading(sensor, "a", 2_000, 0.1), now + 105);
+        assert!(!dedup.contains(&DedupKey::from_reading(&reading(sensor, "a", 1_000, 0.1))));
+        assert_eq!(dedup.len(), 2);
+    }
+
+    #[test]
+    fn test_cross_network_station_is_aliased() {
+        let dedup = Deduplicator::new(DedupConfig::default());
+        let eurdep = Uuid::new_v4();
+        let national = Uuid::new_v4();
+
+        for i in 0..3 {
+            let ts = 1_000 + i * 600;
+            dedup.check(&reading(eurdep, "eurdep", ts, 0.11));
+            dedup.check(&reading(national, "national", ts + 30, 0.112));
+        }
+
+        assert_eq!(dedup.canonical(&national), Some(eurdep));
+        assert_eq!(
+            dedup.check(&reading(national, "national", 2_500, 0.111)),
+            DedupOutcome::Alias { canonical: eurdep }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_state_survives_restart() {
+        let path = std::env::temp_dir().join(format!("dedup-{}.bin", Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let sensor = Uuid::new_v4();
+
+        let dedup = Deduplicator::new(DedupConfig::default());
+        dedup.check(&reading(sensor, "a", 1_000, 0.1));
+        dedup.save(path).await.unwrap();
+
+        let restored = Deduplicator::new(DedupConfig::default());
+        restored.load(path).await.unwrap();
+        assert_eq!(restored.check(&reading(sensor, "a", 1_000, 0.1)), DedupOutcome::Duplicate);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,488 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use regex::{Captures, Regex};
+use serde::{Serialize, Deserialize};
+use tokio::net::TcpListener;
+use tracing::{info, warn, debug};
+
+use crate::pipeline::DataSource;
+
+/// Upstream origin each source talks to, used when recording
+const UPSTREAM_ORIGINS: &[(&str, &str)] = &[
+    ("safecast", "https://api.safecast.org"),
+    ("uradmonitor", "https://data.uradmonitor.com"),
+    ("epa_radnet", "https://www.epa.gov"),
+    ("openaq", "https://api.openaq.org"),
+    ("open_meteo", "https://api.open-meteo.com"),
+    ("nasa_firms", "https://firms.modaps.eosdis.nasa.gov"),
+    ("noaa_gfs", "https://nomads.ncep.noaa.gov"),
+    ("iaea_pris", "https://pris.iaea.org"),
+];
+
+/// Query parameters never written to fixture files
+const REDACTED_PARAMS: &[&str] = &["api_key", "apikey", "key", "token", "map_key"];
+
+/// Response headers kept in fixtures
+const KEPT_HEADERS: &[&str] = &["content-type", "etag", "last-modified"];
+
+/// Whether sources talk to live upstreams or to recorded fixtures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixtureMode {
+    Live,
+    /// Forward to upstreams and store every response
+    Record,
+    /// Serve stored responses without touching the network
+    Replay,
+}
+
+impl FixtureMode {
+    pub fn from_env_value(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "record" => FixtureMode::Record,
+            "replay" => FixtureMode::Replay,
+            _ => FixtureMode::Live,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FixtureConfig {
+    pub mode: FixtureMode,
+    pub dir: PathBuf,
+    /// Shift timestamps in replayed bodies so the newest data looks current
+    pub time_shift: bool,
+    /// Local bind address, port 0 picks a free port
+    pub bind: SocketAddr,
+    /// Values scrubbed from recorded paths, e.g. API keys embedded in URLs
+    pub secrets: Vec<String>,
+}
+
+impl Default for FixtureConfig {
+    fn default() -> Self {
+        Self {
+            mode: FixtureMode::Live,
+            dir: PathBuf::from("./fixtures"),
+            time_shift: false,
+            bind: SocketAddr::from(([127, 0, 0, 1], 0)),
+            secrets: Vec::new(),
+        }
+    }
+}
+
+/// One recorded HTTP exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    pub path: String,
+    #[serde(default)]
+    pub query: String,
+    pub status: u16,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: String,
+    /// Unix time the response was recorded, the reference for time shifting
+    pub recorded_at: i64,
+}
+
+/// Directory of fixtures, one subdirectory per source
+#[derive(Debug, Clone)]
+pub struct FixtureStore {
+    dir: PathBuf,
+}
+
+impl FixtureStore {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn file_for(&self, source: &str, path: &str, query: &str) -> PathBuf {
+        let stem: String = path
+            .trim_matches('/')
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .take(80)
+            .collect();
+        let stem = if stem.is_empty() { "index".to_string() } else { stem };
+        self.dir.join(source).join(format!("{}-{:08x}.json", stem, fnv32(query)))
+    }
+
+    pub async fn save(&self, source: &str, fixture: &Fixture) -> anyhow::Result<()> {
+        let path = self.file_for(source, &fixture.path, &fixture.query);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, serde_json::to_vec_pretty(fixture)?).await?;
+        debug!("Recorded fixture {}", path.display());
+        Ok(())
+    }
+
+    /// Find the best fixture for a request
+    ///
+    /// Tries an exact path and query match first, then the same path with
+    /// volatile parts (dates, numbers) ignored. Queries usually carry "since"
+    /// parameters that never match a recording, so exact matches are the
+    /// exception. Anything else is a miss, so a request the fixtures were not
+    /// recorded for fails instead of being answered with another response.
+    pub async fn lookup(&self, source: &str, path: &str, query: &str) -> Option<Fixture> {
+        if let Ok(data) = tokio::fs::read(self.file_for(source, path, query)).await {
+            if let Ok(fixture) = serde_json::from_slice(&data) {
+                return Some(fixture);
+            }
+        }
+
+        let mut candidates = self.load_all(source).await;
+        candidates.sort_by(|a, b| a.path.cmp(&b.path).then(a.query.cmp(&b.query)));
+
+        let template = path_template(path);
+        candidates.into_iter().find(|f| path_template(&f.path) == template)
+    }
+
+    async fn load_all(&self, source: &str) -> Vec<Fixture> {
+        let mut fixtures = Vec::new();
+        let Ok(mut entries) = tokio::fs::read_dir(self.dir.join(source)).await else {
+            return fixtures;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.path().extension().map_or(true, |ext| ext != "json") {
+                continue;
+            }
+            match tokio::fs::read(entry.path()).await.map(|data| serde_json::from_slice::<Fixture>(&data)) {
+                Ok(Ok(fixture)) => fixtures.push(fixture),
+                Ok(Err(e)) => warn!("Ignoring malformed fixture {}: {}", entry.path().display(), e),
+                Err(e) => warn!("Failed to read fixture {}: {}", entry.path().display(), e),
+            }
+        }
+
+        fixtures
+    }
+}
+
+/// Local HTTP server that records or replays upstream responses
+///
+/// Sources are pointed at `http://<addr>/<source>` via
+/// [`DataSource::set_base_url`]; the first path segment selects the source
+/// and the rest is the upstream path.
+pub struct FixtureServer {
+    addr: SocketAddr,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+struct ServerState {
+    mode: FixtureMode,
+    store: FixtureStore,
+    time_shift: bool,
+    secrets: Vec<String>,
+    client: reqwest::Client,
+}
+
+impl ServerState {
+    fn scrub(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .filter(|secret| !secret.is_empty())
+            .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), "REDACTED"))
+    }
+}
+
+impl FixtureServer {
+    pub async fn start(config: FixtureConfig) -> anyhow::Result<Self> {
+        let state = Arc::new(ServerState {
+            mode: config.mode.clone(),
+            store: FixtureStore::new(&config.dir),
+            time_shift: config.time_shift,
+            secrets: config.secrets.clone(),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(60))
+                .build()?,
+        });
+
+        let listener = TcpListener::bind(config.bind).await?;
+        let addr = listener.local_addr()?;
+        let app = Router::new().fallback(handle_request).with_state(state);
+
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                warn!("Fixture server stopped: {}", e);
+            }
+        });
+
+        info!("Fixture server in {:?} mode on {} using {}", config.mode, addr, config.dir.display());
+        Ok(Self { addr, handle })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn base_url_for(&self, source: &str) -> String {
+        format!("http://{}/{}", self.addr, source)
+    }
+
+    /// Point every source at this server
+    pub fn route(&self, sources: &mut [Box<dyn DataSource + Send>]) {
+        for source in sources.iter_mut() {
+            let base_url = self.base_url_for(&source.name());
+            source.set_base_url(&base_url);
+        }
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_request(
+    State(state): State<Arc<ServerState>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Response {
+    let (source, path) = match uri.path().trim_start_matches('/').split_once('/') {
+        Some((source, rest)) => (source.to_string(), format!("/{}", rest)),
+        None => (uri.path().trim_start_matches('/').to_string(), "/".to_string()),
+    };
+    let query = uri.query().unwrap_or_default().to_string();
+
+    let result = match state.mode {
+        FixtureMode::Record => record(&state, method, &source, &path, &query, &headers).await,
+        _ => replay(&state, &source, &path, &query).await,
+    };
+
+    match result {
+        Ok(fixture) => fixture_response(fixture),
+        Err(e) => {
+            warn!("Fixture request {} {}{} failed: {}", source, path, query, e);
+            (StatusCode::BAD_GATEWAY, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn record(
+    state: &ServerState,
+    method: Method,
+    source: &str,
+    path: &str,
+    query: &str,
+    headers: &HeaderMap,
+) -> anyhow::Result<Fixture> {
+    let origin = UPSTREAM_ORIGINS
+        .iter()
+        .find(|(name, _)| *name == source)
+        .map(|(_, origin)| *origin)
+        .ok_or_else(|| anyhow::anyhow!("No upstream known for source {}", source))?;
+
+    let url = if query.is_empty() {
+        format!("{}{}", origin, path)
+    } else {
+        format!("{}{}?{}", origin, path, query)
+    };
+
+    // reqwest and axum use different `http` versions, so convert via strings
+    let method = reqwest::Method::from_bytes(method.as_str().as_bytes())?;
+    let mut request = state.client.request(method, &url);
+    for (name, value) in headers {
+        // Conditional headers would record bodiless 304s
+        if !matches!(name.as_str(), "host" | "if-none-match" | "if-modified-since") {
+            request = request.header(name.as_str(), value.as_bytes());
+        }
+    }
+
+    let response = request.send().await?;
+    let fixture = Fixture {
+        path: state.scrub(path),
+        query: redact_query(&state.scrub(query)),
+        status: response.status().as_u16(),
+        headers: response
+            .headers()
+            .iter()
+            .filter(|(name, _)| KEPT_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: response.text().await?,
+        recorded_at: Utc::now().timestamp(),
+    };
+
+    state.store.save(source, &fixture).await?;
+    Ok(fixture)
+}
+
+async fn replay(state: &ServerState, source: &str, path: &str, query: &str) -> anyhow::Result<Fixture> {
+    let mut fixture = state
+        .store
+        .lookup(source, &state.scrub(path), &redact_query(&state.scrub(query)))
+        .await
+        .ok_or_else(|| anyhow::anyhow!("No fixture for {} {}", source, path))?;
+
+    if state.time_shift {
+        fixture.body = shift_timestamps(&fixture.body, Utc::now().timestamp() - fixture.recorded_at);
+    }
+
+    Ok(fixture)
+}
+
+fn fixture_response(fixture: Fixture) -> Response {
+    let mut response = (
+        StatusCode::from_u16(fixture.status).unwrap_or(StatusCode::OK),
+        fixture.body,
+    )
+        .into_response();
+
+    for (name, value) in &fixture.headers {
+        if let (Ok(name), Ok(value)) = (
+            axum::http::HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+
+    response
+}
+
+/// Replace the scheme and host of `url` with `base_url`, keeping path and query
+pub fn rebase(url: &str, base_url: Option<&str>) -> String {
+    let Some(base_url) = base_url else {
+        return url.to_string();
+    };
+
+    let after_scheme = url.find("://").map_or(0, |i| i + 3);
+    let path_start = url[after_scheme..].find('/').map_or(url.len(), |i| after_scheme + i);
+    format!("{}{}", base_url.trim_end_matches('/'), &url[path_start..])
+}
+
+/// Move every timestamp in a response body forward by `shift_secs`
+///
+/// Handles ISO 8601 date-times (with or without seconds, fraction and
+/// offset), bare ISO dates (shifted by whole days) and epoch seconds in
+/// `"time"`/`"timestamp"` JSON fields.
+pub fn shift_timestamps(body: &str, shift_secs: i64) -> String {
+    let shift = chrono::Duration::seconds(shift_secs);
+
+    let shifted = iso_timestamp_regex().replace_all(body, |caps: &Captures| {
+        let date = &caps[1];
+        let Some(time) = caps.get(3) else {
+            return NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(|d| (d + chrono::Duration::days(shift_secs.div_euclid(86400))).format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|_| caps[0].to_string());
+        };
+
+        let seconds = caps.get(4).map_or(":00", |m| m.as_str());
+        let text = format!("{} {}{}", date, time.as_str(), seconds);
+        match NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S") {
+            Ok(dt) => {
+                let format = if caps.get(4).is_some() { "%Y-%m-%d{}%H:%M:%S" } else { "%Y-%m-%d{}%H:%M" };
+                (dt + shift).format(&format.replace("{}", &caps[2])).to_string()
+            }
+            Err(_) => caps[0].to_string(),
+        }
+    });
+
+    epoch_field_regex()
+        .replace_all(&shifted, |caps: &Captures| {
+            let value: i64 = caps[3].parse().unwrap_or_default();
+            format!("\"{}\"{}{}", &caps[1], &caps[2], value + shift_secs)
+        })
+        .into_owned()
+}
+
+fn iso_timestamp_regex() -> &'static Regex {
+    static ISO: OnceLock<Regex> = OnceLock::new();
+    ISO.get_or_init(|| {
+        Regex::new(r"\b(\d{4}-\d{2}-\d{2})(?:([T ])(\d{2}:\d{2})(:\d{2})?)?").expect("valid timestamp regex")
+    })
+}
+
+fn epoch_field_regex() -> &'static Regex {
+    static EPOCH: OnceLock<Regex> = OnceLock::new();
+    EPOCH.get_or_init(|| Regex::new(r#""(time|timestamp)"(\s*:\s*)(\d{9,10})\b"#).expect("valid epoch regex"))
+}
+
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| {
+            let name = pair.split('=').next().unwrap_or_default().to_lowercase();
+            !REDACTED_PARAMS.contains(&name.as_str())
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Path with digits collapsed, so dated or numbered paths match across runs
+fn path_template(path: &str) -> String {
+    let mut template = String::with_capacity(path.len());
+    let mut in_digits = false;
+    for c in path.chars() {
+        if c.is_ascii_digit() {
+            if !in_digits {
+                template.push('#');
+            }
+            in_digits = true;
+        } else {
+            template.push(c);
+            in_digits = false;
+        }
+    }
+    template
+}
+
+fn fnv32(input: &str) -> u32 {
+    input.bytes().fold(0x811c_9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_keeps_path_and_query() {
+        assert_eq!(
+            rebase("https://api.safecast.org/measurements.json?page=2", Some("http://127.0.0.1:9000/safecast/")),
+            "http://127.0.0.1:9000/safecast/measurements.json?page=2"
+        );
+        assert_eq!(rebase("https://www.epa.gov/radnet", None), "https://www.epa.gov/radnet");
+    }
+
+    #[test]
+    fn test_shift_timestamps_preserves_format() {
+        let body = r#"{"captured_at":"2024-01-15T10:30:00Z","hour":"2024-01-15T10:00","day":"2024-01-15","time":1705314600}"#;
+        let shifted = shift_timestamps(body, 86400 + 3600);
+        assert_eq!(
+            shifted,
+            r#"{"captured_at":"2024-01-16T11:30:00Z","hour":"2024-01-16T11:00","day":"2024-01-16","time":1705404600}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lookup_misses_unrecorded_paths() {
+        let dir = std::env::temp_dir().join(format!("cherenkov-fixtures-{}", uuid::Uuid::new_v4()));
+        let store = FixtureStore::new(&dir);
+        let fixture = Fixture {
+            path: "/v2/latest".to_string(),
+            query: "limit=100".to_string(),
+            status: 200,
+            headers: HashMap::new(),
+            body: "{}".to_string(),
+            recorded_at: 0,
+        };
+        store.save("openaq", &fixture).await.unwrap();
+
+        assert!(store.lookup("openaq", "/v2/latest", "limit=100&date_from=2024-01-15").await.is_some());
+        assert!(store.lookup("openaq", "/v2/locations", "").await.is_none());
+        assert!(store.lookup("safecast", "/v2/latest", "").await.is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
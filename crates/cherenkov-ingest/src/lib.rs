@@ -2,6 +2,12 @@ pub mod sources;
 pub mod pipeline;
 pub mod normalizer;
 pub mod metrics;
+pub mod cursor;
+pub mod dedup;
+pub mod qc;
+pub mod fixtures;
+pub mod sensor_status;
+pub mod coverage;
 
 pub mod sources_extra;
 
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn, error};
 
 use cherenkov_ingest::{
@@ -7,10 +8,15 @@ use cherenkov_ingest::{
         EpaRadnetSource, NasaFirmsSource, NoaaGfsSource, OpenAqSource, OpenMeteoSource,
         SafecastSource, UradmonitorSource
     },
+    fixtures::{FixtureConfig, FixtureMode, FixtureServer},
     pipeline::{IngestionPipeline, PipelineConfig, DataSource},
+    qc::QcConfig,
+    sensor_status::SensorStatusTracker,
     sources_extra,
 };
-use cherenkov_db::{RadiationDatabase, DatabaseConfig, scylla::ScyllaConfig};
+use cherenkov_db::{RadiationDatabase, DatabaseConfig, LivenessThresholds, TieringConfig, StorageBackends};
+use cherenkov_db::backup::{self, BackupConfig, WarmBackup};
+use cherenkov_db::sqlite::SqliteStorage;
 use cherenkov_observability::init_observability;
 use cherenkov_core::EventBus;
 
@@ -21,18 +27,30 @@ async fn main() -> anyhow::Result<()> {
     
     info!("Starting Cherenkov Ingest Daemon v{}", env!("CARGO_PKG_VERSION"));
     
+    // Backends come from STORAGE_PROFILE and friends, see `StorageBackends::from_env`
+    let backends = StorageBackends::from_env();
+
+    // `cherenkov-ingest migrate [--dry-run]` migrates every backend and exits
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return migrate(&backends, args.iter().any(|a| a == "--dry-run")).await;
+    }
+
+    // `cherenkov-ingest backup <command>` manages warm tier backups and exits
+    if args.first().map(String::as_str) == Some("backup") {
+        return backup_command(&backends, &args[1..]).await;
+    }
+
+    // Run migrations, creating the ScyllaDB keyspace on a fresh cluster
+    RadiationDatabase::migrate(&backends, false).await?;
+
     // Initialize database
-    let scylla_config = ScyllaConfig::default();
     let db = Arc::new(
-        RadiationDatabase::new(
-            scylla_config,
-            "./data/cherenkov_warm.db",
-            "redis://127.0.0.1:6379",
-            DatabaseConfig::default(),
-        ).await?
+        RadiationDatabase::open(backends, DatabaseConfig {
+            backup: BackupConfig::from_env(),
+            ..DatabaseConfig::default()
+        }).await?
     );
-    
-    // Run migrations
     db.run_migrations().await?;
     
     // Initialize EventBus for inter-crate communication
@@ -48,19 +66,36 @@ async fn main() -> anyhow::Result<()> {
         circuit_breaker_threshold: 5,
         circuit_breaker_reset_secs: 30,
         dlq_max_size: 10000,
-        dedup_window_secs: 60,
+        dedup_window_secs: 86400,
+        dedup_max_entries: 1_000_000,
+        dedup_state_path: Some("./data/dedup_state.bin".to_string()),
+        dedup_redis_url: std::env::var("DEDUP_REDIS_URL").ok(),
+        cursor_dir: "./data/cursors".to_string(),
+        dlq_path: Some("./data/dlq.bin".to_string()),
+        shutdown_timeout_secs: std::env::var("SHUTDOWN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30),
+        qc: load_qc_config(),
     };
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs);
     
     let pipeline = Arc::new(IngestionPipeline::new(config, db.clone(), event_bus.clone()));
 
     
     // Create data sources
-    let sources = create_sources();
+    let mut sources = create_sources();
+
+    // Route sources through the fixture server when recording or replaying,
+    // keeping it alive for the lifetime of the daemon
+    let _fixture_server = start_fixture_server(&mut sources).await?;
     
     // Start pipeline
+    let shutdown = CancellationToken::new();
     let pipeline_clone = pipeline.clone();
-    let pipeline_handle = tokio::spawn(async move {
-        if let Err(e) = pipeline_clone.run(sources).await {
+    let pipeline_shutdown = shutdown.clone();
+    let mut pipeline_handle = tokio::spawn(async move {
+        if let Err(e) = pipeline_clone.run(sources, pipeline_shutdown).await {
             error!("Pipeline error: {}", e);
         }
     });
@@ -71,21 +106,156 @@ async fn main() -> anyhow::Result<()> {
     // Start DLQ replayer
     let dlq_handle = tokio::spawn(dlq_replayer(pipeline.clone()));
     
+    // Start sensor status sweeps
+    let status_tracker = SensorStatusTracker::new(
+        db.clone(),
+        event_bus.clone(),
+        LivenessThresholds::default(),
+        Duration::from_secs(60),
+    )
+    .with_qc(pipeline.qc().clone());
+    let status_handle = tokio::spawn(status_tracker.run());
+
+    // Start tier migration and retention enforcement
+    let tiering = db.tier_migrator(TieringConfig {
+        interval: Duration::from_secs(
+            std::env::var("TIERING_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+        ),
+        ..TieringConfig::default()
+    });
+    let mut tiering_handle = tokio::spawn(tiering.run(shutdown.clone()));
+
+    // Start warm tier snapshots and WAL archiving, when BACKUP_DIR is set
+    let mut backup_handle = match db.warm_backup() {
+        Some(backup) => tokio::spawn(backup.run(shutdown.clone())),
+        None => tokio::spawn(shutdown.clone().cancelled_owned()),
+    };
+    
     // Start EventBus metrics reporter
     let metrics_handle = tokio::spawn(eventbus_metrics_reporter(event_bus.clone()));
     
     // Wait for all tasks
     tokio::select! {
-        _ = pipeline_handle => warn!("Pipeline exited"),
+        _ = &mut pipeline_handle => warn!("Pipeline exited"),
         _ = health_handle => warn!("Health server exited"),
         _ = dlq_handle => warn!("DLQ replayer exited"),
+        _ = status_handle => warn!("Sensor status tracker exited"),
+        _ = &mut tiering_handle => warn!("Tier migration exited"),
+        _ = &mut backup_handle => warn!("Warm tier backups exited"),
         _ = metrics_handle => warn!("EventBus metrics exited"),
         _ = tokio::signal::ctrl_c() => info!("Shutdown signal received"),
     }
     
     info!("Cherenkov Ingest Daemon shutting down");
+
+    // Stop the sources and let the writer drain, flush and persist its state;
+    // every stage shares the one shutdown timeout
+    shutdown.cancel();
+    let deadline = tokio::time::Instant::now() + shutdown_timeout;
+    if !pipeline_handle.is_finished() {
+        match tokio::time::timeout_at(deadline, pipeline_handle).await {
+            Ok(_) => info!("Pipeline drained"),
+            Err(_) => warn!("Pipeline did not drain within {:?}, exiting anyway", shutdown_timeout),
+        }
+    }
+
+    // Tier migration stops between units of work, its cursor is already saved
+    if !tiering_handle.is_finished() && tokio::time::timeout_at(deadline, tiering_handle).await.is_err() {
+        warn!("Tier migration did not stop within {:?}", shutdown_timeout);
+    }
+
+    // A last WAL archive run picks up the writes the pipeline just flushed
+    if !backup_handle.is_finished() && tokio::time::timeout_at(deadline, backup_handle).await.is_err() {
+        warn!("Warm tier backups did not stop within {:?}", shutdown_timeout);
+    }
+
+    Ok(())
+
+}
+
+/// Print each backend's pending migrations, applying them unless `dry_run`
+async fn migrate(backends: &StorageBackends, dry_run: bool) -> anyhow::Result<()> {
+    for report in RadiationDatabase::migrate(backends, dry_run).await? {
+        let version = report.current_version.map_or("none".to_string(), |v| v.to_string());
+        println!("{} (schema version {})", report.backend, version);
+        if report.is_up_to_date() {
+            println!("  up to date");
+            continue;
+        }
+
+        let verb = if dry_run { "would apply" } else { "applied" };
+        for statement in &report.setup {
+            println!("  {} setup: {}", verb, statement);
+        }
+        for migration in &report.pending {
+            println!("  {} {}: {}", verb, migration.version, migration.description);
+            if dry_run {
+                for statement in &migration.statements {
+                    println!("{}\n", statement);
+                }
+            }
+        }
+    }
     Ok(())
+}
+
+const BACKUP_USAGE: &str = "usage: cherenkov-ingest backup snapshot|archive|list|verify|restore --to PATH [--at RFC3339]";
+
+/// Snapshot, archive, list, verify or restore the warm tier backups in `BACKUP_DIR`
+async fn backup_command(backends: &StorageBackends, args: &[String]) -> anyhow::Result<()> {
+    let config = BackupConfig::from_env().ok_or_else(|| anyhow::anyhow!("BACKUP_DIR is not set"))?;
+    let flag = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1));
 
+    match args.first().map(String::as_str) {
+        Some(command @ ("snapshot" | "archive")) => {
+            // Opened like the daemon does, so it never checkpoints behind the archiver
+            let warm = Arc::new(SqliteStorage::with_wal_archiving(&backends.warm_path).await?);
+            let mut warm_backup = WarmBackup::new(warm, config)?;
+            if command == "snapshot" {
+                let manifest = warm_backup.snapshot().await?;
+                println!("snapshot {} ({} bytes, sha256 {})", manifest.taken_at, manifest.size_bytes, manifest.sha256);
+            } else {
+                let report = warm_backup.archive_wal().await?;
+                println!("archived {} bytes of WAL generation {}", report.archived_bytes, report.generation.as_deref().unwrap_or("none"));
+                if let Some(manifest) = report.snapshot {
+                    println!("the WAL restarted unarchived, took snapshot {}", manifest.taken_at);
+                }
+            }
+        }
+        Some("list") => {
+            for manifest in backup::list_snapshots(&config.dir)? {
+                let taken_at = chrono::DateTime::from_timestamp_millis(manifest.taken_at).unwrap_or_default();
+                println!("{} {} {} bytes", manifest.taken_at, taken_at.to_rfc3339(), manifest.size_bytes);
+            }
+        }
+        Some("verify") => {
+            let report = backup::verify(&config.dir).await?;
+            for check in report.snapshots.iter().chain(&report.generations) {
+                let status = if check.problems.is_empty() { "ok".to_string() } else { check.problems.join("; ") };
+                println!("{}: {}", check.name, status);
+            }
+            if !report.is_ok() {
+                anyhow::bail!("Backups in {} failed verification", config.dir.display());
+            }
+        }
+        Some("restore") => {
+            let target = flag("--to").ok_or_else(|| anyhow::anyhow!(BACKUP_USAGE))?;
+            let at = flag("--at").map(|at| chrono::DateTime::parse_from_rfc3339(at)).transpose()?;
+            let report = backup::restore(&config.dir, std::path::Path::new(target), at.map(|at| at.with_timezone(&chrono::Utc))).await?;
+            println!(
+                "restored {} from snapshot {} and {} WAL segments, up to {}",
+                report.path.display(),
+                report.snapshot,
+                report.segments_applied,
+                report.restored_to
+            );
+        }
+        _ => anyhow::bail!(BACKUP_USAGE),
+    }
+    Ok(())
 }
 
 fn create_sources() -> Vec<Box<dyn DataSource + Send>> {
@@ -104,6 +274,36 @@ fn create_sources() -> Vec<Box<dyn DataSource + Send>> {
 }
 
 
+/// Start a fixture server if `FIXTURE_MODE` is `record` or `replay`
+///
+/// `FIXTURE_DIR` sets the fixture directory and `FIXTURE_TIME_SHIFT=1` makes
+/// replayed timestamps relative to now.
+async fn start_fixture_server(
+    sources: &mut [Box<dyn DataSource + Send>],
+) -> anyhow::Result<Option<FixtureServer>> {
+    let mode = FixtureMode::from_env_value(&std::env::var("FIXTURE_MODE").unwrap_or_default());
+    if mode == FixtureMode::Live {
+        return Ok(None);
+    }
+
+    let mut config = FixtureConfig {
+        mode,
+        time_shift: std::env::var("FIXTURE_TIME_SHIFT").is_ok_and(|v| v == "1" || v == "true"),
+        secrets: ["NASA_FIRMS_API_KEY", "SAFECAST_API_KEY", "URADMONITOR_API_KEY"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .collect(),
+        ..FixtureConfig::default()
+    };
+    if let Ok(dir) = std::env::var("FIXTURE_DIR") {
+        config.dir = dir.into();
+    }
+
+    let server = FixtureServer::start(config).await?;
+    server.route(sources);
+    Ok(Some(server))
+}
+
 async fn health_check_server(db: Arc<RadiationDatabase>) {
     let mut interval = tokio::time::interval(Duration::from_secs(30));
     
@@ -141,3 +341,20 @@ async fn eventbus_metrics_reporter(event_bus: Arc<EventBus>) {
         info!("EventBus metrics: {} active subscribers", subscriber_count);
     }
 }
+
+/// Load QC thresholds from the JSON file named by `QC_CONFIG`, if set
+fn load_qc_config() -> QcConfig {
+    let Ok(path) = std::env::var("QC_CONFIG") else {
+        return QcConfig::default();
+    };
+
+    match std::fs::read(&path).map_err(anyhow::Error::from)
+        .and_then(|data| serde_json::from_slice(&data).map_err(anyhow::Error::from))
+    {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Failed to load QC config from {}, using defaults: {}", path, e);
+            QcConfig::default()
+        }
+    }
+}
@@ -1,6 +1,30 @@
-use crate::RawReading;
+use cherenkov_db::{RadiationReading, ReadingProvenance};
 use thiserror::Error;
 
+use crate::RawReading;
+
+/// Version of the sources' unit conversions, recorded in each reading's
+/// provenance; bump it whenever a conversion factor changes
+pub const NORMALIZER_VERSION: &str = "1";
+
+/// Provenance of a dose rate converted from `raw_value` in `raw_unit` by `factor`
+pub fn provenance(raw_value: f64, raw_unit: &str, factor: f64) -> Option<ReadingProvenance> {
+    Some(ReadingProvenance::converted(raw_value, raw_unit, factor, NORMALIZER_VERSION))
+}
+
+/// Convert a reading with its detector's registered calibration factor in
+/// place of the source's default; readings without a raw value are left as-is
+pub fn recalibrate(reading: &mut RadiationReading, calibration_factor: f64) {
+    let Some(provenance) = reading.provenance.as_mut() else {
+        return;
+    };
+    if provenance.conversion_factor != 0.0 {
+        reading.uncertainty *= (calibration_factor / provenance.conversion_factor) as f32;
+    }
+    provenance.conversion_factor = calibration_factor;
+    reading.dose_rate_microsieverts = provenance.raw_value * calibration_factor;
+}
+
 #[derive(Error, Debug)]
 #[allow(dead_code)]
 pub enum NormalizeError {
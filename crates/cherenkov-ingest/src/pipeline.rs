@@ -1,16 +1,25 @@
+use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, Semaphore, RwLock};
 use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
 use futures::stream::{FuturesUnordered, StreamExt};
 use tracing::{info, warn, error, instrument, debug};
-use dashmap::DashMap;
 use chrono::Utc;
 use serde::{Serialize, Deserialize};
+use uuid::Uuid;
 
-use cherenkov_db::{RadiationDatabase, RadiationReading, QualityFlag};
+use cherenkov_db::{RadiationDatabase, RadiationReading, QualityFlag, SourceFetch, WeatherObservation};
 use cherenkov_core::{EventBus, CherenkovEvent, NormalizedReading};
 
+use crate::cursor::{CursorStore, SourceCursor};
+use crate::dedup::{DedupConfig, DedupKey, DedupOutcome, Deduplicator, RedisDedupStore};
+use crate::qc::{QcConfig, QcEngine};
+
+
+/// How often the writer persists deduplication state
+const DEDUP_SAVE_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Configuration for the ingestion pipeline
 #[derive(Debug, Clone)]
@@ -32,6 +41,20 @@ pub struct PipelineConfig {
     pub dlq_max_size: usize,
     /// Deduplication window in seconds
     pub dedup_window_secs: u64,
+    /// Maximum number of retained deduplication keys
+    pub dedup_max_entries: usize,
+    /// File the deduplication state is persisted to across restarts
+    pub dedup_state_path: Option<String>,
+    /// Redis URL for sharing deduplication keys between ingest instances
+    pub dedup_redis_url: Option<String>,
+    /// Directory for persisted per-source fetch cursors
+    pub cursor_dir: String,
+    /// File the dead letter queue is persisted to on shutdown
+    pub dlq_path: Option<String>,
+    /// How long shutdown may take to drain and flush before giving up
+    pub shutdown_timeout_secs: u64,
+    /// Quality-control thresholds
+    pub qc: QcConfig,
 }
 
 impl Default for PipelineConfig {
@@ -44,7 +67,14 @@ impl Default for PipelineConfig {
             circuit_breaker_threshold: 5,
             circuit_breaker_reset_secs: 30,
             dlq_max_size: 10000,
-            dedup_window_secs: 60,
+            dedup_window_secs: 86400,
+            dedup_max_entries: 1_000_000,
+            dedup_state_path: Some("./data/dedup_state.bin".to_string()),
+            dedup_redis_url: None,
+            cursor_dir: "./data/cursors".to_string(),
+            dlq_path: Some("./data/dlq.bin".to_string()),
+            shutdown_timeout_secs: 30,
+            qc: QcConfig::default(),
         }
     }
 }
@@ -234,80 +264,88 @@ impl DeadLetterQueue {
 
         replayed
     }
-}
 
-/// Deduplication tracker
-#[derive(Debug)]
-pub struct Deduplicator {
-    seen: Arc<DashMap<String, i64>>, // sensor_id -> last_timestamp
-    window_secs: u64,
-}
-
-impl Clone for Deduplicator {
-    fn clone(&self) -> Self {
-        Self {
-            seen: self.seen.clone(),
-            window_secs: self.window_secs,
+    /// Persist queued entries so they survive a restart
+    pub async fn save(&self, path: &str) -> anyhow::Result<()> {
+        let entries = self.get_entries().await;
+        let data = bincode::serialize(&entries)?;
+        if let Some(parent) = Path::new(path).parent() {
+            tokio::fs::create_dir_all(parent).await?;
         }
+        let tmp = format!("{}.tmp", path);
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        debug!("Saved {} DLQ entries to {}", entries.len(), path);
+        Ok(())
     }
-}
 
-impl Deduplicator {
-    pub fn new(window_secs: u64) -> Self {
-        Self {
-            seen: Arc::new(DashMap::new()),
-            window_secs,
-        }
-    }
+    /// Append entries written by `save`; a missing file is not an error
+    pub async fn load(&self, path: &str) -> anyhow::Result<usize> {
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
 
-    pub fn is_duplicate(&self, sensor_id: &str, timestamp: i64) -> bool {
-        let now = Utc::now().timestamp();
-        
-        if let Some(last_seen) = self.seen.get(sensor_id) {
-            // Check if within deduplication window
-            if now - *last_seen < self.window_secs as i64 {
-                // Check if timestamp is same or older
-                if timestamp <= *last_seen {
-                    return true;
-                }
+        let entries: Vec<DeadLetterEntry> = bincode::deserialize(&data)?;
+        let count = entries.len();
+
+        let mut queue = self.queue.write().await;
+        for entry in entries {
+            if queue.len() >= self.max_size {
+                queue.remove(0);
             }
+            queue.push(entry);
         }
-        
-        false
+
+        Ok(count)
     }
+}
 
-    pub fn record(&self, sensor_id: String, timestamp: i64) {
-        self.seen.insert(sensor_id, timestamp);
-        
-        // Cleanup old entries periodically (simplified)
-        if self.seen.len() > 10000 {
-            let now = Utc::now().timestamp();
-            self.seen.retain(|_, last_seen| now - *last_seen < self.window_secs as i64);
+/// What sources hand to the batch writer, in order
+enum Delivery {
+    Reading(RadiationReading),
+    /// Fetch position of a source once the readings sent before it are stored
+    Cursor(String, SourceCursor),
+}
+
+/// Readings waiting to be written, with the cursors they complete
+struct Batch {
+    readings: Vec<RadiationReading>,
+    cursors: Vec<(String, SourceCursor)>,
+}
+
+impl Batch {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            readings: Vec::with_capacity(capacity),
+            cursors: Vec::new(),
         }
     }
 
-    #[allow(dead_code)]
-    pub fn clear(&self) {
-        self.seen.clear();
+    fn is_empty(&self) -> bool {
+        self.readings.is_empty() && self.cursors.is_empty()
     }
 }
 
 /// Ingestion pipeline with resilience patterns
 pub struct IngestionPipeline {
     config: PipelineConfig,
-    db: Arc<RadiationDatabase>,
+    db: Arc<dyn ReadingSink>,
     event_bus: Arc<EventBus>,
     circuit_breaker: CircuitBreaker,
     dlq: DeadLetterQueue,
     deduplicator: Deduplicator,
     backpressure: Arc<Semaphore>,
+    cursor_store: CursorStore,
+    qc: QcEngine,
 }
 
 
 impl IngestionPipeline {
     pub fn new(
         config: PipelineConfig,
-        db: Arc<RadiationDatabase>,
+        db: Arc<dyn ReadingSink>,
         event_bus: Arc<EventBus>,
     ) -> Self {
         let circuit_breaker = CircuitBreaker::new(
@@ -316,8 +354,14 @@ impl IngestionPipeline {
         );
         
         let dlq = DeadLetterQueue::new(config.dlq_max_size);
-        let deduplicator = Deduplicator::new(config.dedup_window_secs);
+        let deduplicator = Deduplicator::new(DedupConfig {
+            window_secs: config.dedup_window_secs,
+            max_entries: config.dedup_max_entries,
+            ..DedupConfig::default()
+        });
         let backpressure = Arc::new(Semaphore::new(config.channel_buffer_size));
+        let cursor_store = CursorStore::new(&config.cursor_dir);
+        let qc = QcEngine::new(config.qc.clone());
 
         Self {
             config,
@@ -327,27 +371,65 @@ impl IngestionPipeline {
             dlq,
             deduplicator,
             backpressure,
+            cursor_store,
+            qc,
         }
     }
 
 
-    /// Run the ingestion pipeline with multiple sources
-    #[instrument(skip(self, sources))]
-    pub async fn run(self: Arc<Self>, sources: Vec<Box<dyn DataSource + Send>>) -> anyhow::Result<()> {
+    /// Run the ingestion pipeline with multiple sources until `shutdown` is cancelled
+    ///
+    /// On shutdown the sources stop fetching, every reading already handed to
+    /// the writer is flushed, and the DLQ and dedup state are persisted before
+    /// this returns.
+    #[instrument(skip(self, sources, shutdown))]
+    pub async fn run(
+        self: Arc<Self>,
+        sources: Vec<Box<dyn DataSource + Send>>,
+        shutdown: CancellationToken,
+    ) -> anyhow::Result<()> {
         info!("Starting ingestion pipeline with {} sources", sources.len());
 
-        let (tx, mut rx) = mpsc::channel::<RadiationReading>(self.config.channel_buffer_size);
+        if let Some(path) = &self.config.dedup_state_path {
+            if let Err(e) = self.deduplicator.load(path).await {
+                warn!("Failed to restore dedup state from {}: {}", path, e);
+            }
+        }
+
+        if let Some(path) = &self.config.dlq_path {
+            match self.dlq.load(path).await {
+                Ok(0) => {}
+                Ok(count) => info!("Restored {} DLQ entries from {}", count, path),
+                Err(e) => warn!("Failed to restore DLQ from {}: {}", path, e),
+            }
+        }
+
+        let shared_dedup = match &self.config.dedup_redis_url {
+            Some(url) => match RedisDedupStore::new(url, self.config.dedup_window_secs).await {
+                Ok(store) => Some(Arc::new(store)),
+                Err(e) => {
+                    warn!("Shared dedup store unavailable, using local state only: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let (tx, mut rx) = mpsc::channel::<Delivery>(self.config.channel_buffer_size);
         
         // Spawn source tasks
         let mut source_handles = FuturesUnordered::new();
         
         for mut source in sources {
             let tx = tx.clone();
+            let sink = self.db.clone();
+            let cursor_store = self.cursor_store.clone();
+            let shutdown = shutdown.clone();
             let permit = self.backpressure.clone().acquire_owned().await?;
             
             let handle = tokio::spawn(async move {
                 let _permit = permit; // Hold permit until task completes
-                Self::run_source(&mut *source, tx).await
+                Self::run_source(&mut *source, tx, sink, cursor_store, shutdown).await
             });
             
             source_handles.push(handle);
@@ -359,51 +441,51 @@ impl IngestionPipeline {
         let circuit_breaker = self.circuit_breaker.clone();
         let dlq = self.dlq.clone();
         let deduplicator = self.deduplicator.clone();
+        let qc = self.qc.clone();
+        let dedup_state_path = self.config.dedup_state_path.clone();
+        let dlq_path = self.config.dlq_path.clone();
+        let cursor_store = self.cursor_store.clone();
         let batch_size = self.config.batch_size;
         let batch_timeout = Duration::from_millis(self.config.batch_timeout_ms);
 
         let writer_handle = tokio::spawn(async move {
-            let mut batch = Vec::with_capacity(batch_size);
+            let mut batch = Batch::with_capacity(batch_size);
             let mut last_write = Instant::now();
+            let mut last_dedup_save = Instant::now();
 
             loop {
                 let timeout_result = timeout(batch_timeout, rx.recv()).await;
                 
                 match timeout_result {
-                    Ok(Some(reading)) => {
-                        // Check deduplication
-                        if deduplicator.is_duplicate(
-                            &reading.sensor_id.to_string(),
-                            reading.timestamp
-                        ) {
-                            metrics::counter!("cherenkov_ingest_deduplicated_total").increment(1);
+                    Ok(Some(Delivery::Reading(mut reading))) => {
+                        if !Self::accept(&deduplicator, shared_dedup.as_deref(), &reading).await {
                             continue;
                         }
-                        
-                        deduplicator.record(
-                            reading.sensor_id.to_string(),
-                            reading.timestamp
-                        );
-                        
-                        batch.push(reading);
-
-                        if batch.len() >= batch_size {
-                            Self::write_batch(&db, &event_bus, &circuit_breaker, &dlq, &mut batch).await;
+
+                        let alias_of = deduplicator.canonical(&reading.sensor_id);
+                        qc.evaluate(&mut reading, alias_of);
+                        batch.readings.push(reading);
+
+                        if batch.readings.len() >= batch_size {
+                            Self::write_batch(&db, &event_bus, &circuit_breaker, &dlq, &cursor_store, &mut batch).await;
                             last_write = Instant::now();
                         }
 
                     }
+                    Ok(Some(Delivery::Cursor(source, cursor))) => batch.cursors.push((source, cursor)),
                     Ok(None) => {
-                        // Channel closed
+                        // Channel closed and drained, all sources have stopped
                         if !batch.is_empty() {
-                            Self::write_batch(&db, &event_bus, &circuit_breaker, &dlq, &mut batch).await;
+                            Self::write_batch(&db, &event_bus, &circuit_breaker, &dlq, &cursor_store, &mut batch).await;
                         }
+                        Self::save_dedup_state(&deduplicator, dedup_state_path.as_deref()).await;
+                        Self::save_dlq(&dlq, dlq_path.as_deref()).await;
                         break;
                     }
                     Err(_) => {
                         // Timeout - flush batch
                         if !batch.is_empty() {
-                            Self::write_batch(&db, &event_bus, &circuit_breaker, &dlq, &mut batch).await;
+                            Self::write_batch(&db, &event_bus, &circuit_breaker, &dlq, &cursor_store, &mut batch).await;
                             last_write = Instant::now();
                         }
                     }
@@ -411,10 +493,14 @@ impl IngestionPipeline {
 
                 // Periodic flush if batch has been sitting too long
                 if !batch.is_empty() && last_write.elapsed() >= batch_timeout {
-                    Self::write_batch(&db, &event_bus, &circuit_breaker, &dlq, &mut batch).await;
+                    Self::write_batch(&db, &event_bus, &circuit_breaker, &dlq, &cursor_store, &mut batch).await;
                     last_write = Instant::now();
                 }
 
+                if last_dedup_save.elapsed() >= DEDUP_SAVE_INTERVAL {
+                    Self::save_dedup_state(&deduplicator, dedup_state_path.as_deref()).await;
+                    last_dedup_save = Instant::now();
+                }
             }
         });
 
@@ -441,17 +527,117 @@ impl IngestionPipeline {
         Ok(())
     }
 
-    async fn run_source(source: &mut dyn DataSource, tx: mpsc::Sender<RadiationReading>) -> anyhow::Result<()> {
+    /// Decide whether a reading is new content that should be written
+    async fn accept(
+        deduplicator: &Deduplicator,
+        shared: Option<&RedisDedupStore>,
+        reading: &RadiationReading,
+    ) -> bool {
+        match deduplicator.check(reading) {
+            DedupOutcome::New => {}
+            DedupOutcome::Duplicate => {
+                metrics::counter!("cherenkov_ingest_deduplicated_total").increment(1);
+                return false;
+            }
+            DedupOutcome::Alias { canonical } => {
+                debug!("Dropping reading from {} already covered by alias {}", reading.sensor_id, canonical);
+                metrics::counter!("cherenkov_ingest_alias_deduplicated_total").increment(1);
+                return false;
+            }
+        }
+
+        // Another instance may already have ingested the same content
+        if let Some(store) = shared {
+            match store.claim(&DedupKey::from_reading(reading)).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    metrics::counter!("cherenkov_ingest_deduplicated_total").increment(1);
+                    return false;
+                }
+                Err(e) => warn!("Shared dedup check failed, accepting reading: {}", e),
+            }
+        }
+
+        true
+    }
+
+    async fn save_dedup_state(deduplicator: &Deduplicator, path: Option<&str>) {
+        if let Some(path) = path {
+            if let Err(e) = deduplicator.save(path).await {
+                warn!("Failed to persist dedup state to {}: {}", path, e);
+            }
+        }
+    }
+
+    async fn save_dlq(dlq: &DeadLetterQueue, path: Option<&str>) {
+        if let Some(path) = path {
+            match dlq.save(path).await {
+                Ok(()) => info!("Persisted {} DLQ entries to {}", dlq.len().await, path),
+                Err(e) => warn!("Failed to persist DLQ to {}: {}", path, e),
+            }
+        }
+    }
+
+    async fn run_source(
+        source: &mut dyn DataSource,
+        tx: mpsc::Sender<Delivery>,
+        sink: Arc<dyn ReadingSink>,
+        cursor_store: CursorStore,
+        shutdown: CancellationToken,
+    ) -> anyhow::Result<()> {
+        // Resume incremental fetching from where the previous run stopped
+        if let Some(cursor) = cursor_store.load(&source.name()).await {
+            info!("Restored fetch cursor for {}: {:?}", source.name(), cursor);
+            source.restore_cursor(cursor);
+        }
+
         loop {
-            match source.fetch().await {
-                Ok(readings) => {
+            // A fetch cut short by shutdown has handed nothing to the writer yet
+            let started_at = Utc::now().timestamp();
+            let fetched = tokio::select! {
+                result = source.fetch() => result,
+                _ = shutdown.cancelled() => break,
+            };
+
+            match fetched {
+                Ok(mut readings) => {
                     let count = readings.len();
+
+                    // Recorded before any reading refers to it
+                    let fetch = SourceFetch {
+                        fetch_id: Uuid::new_v4(),
+                        source: source.name(),
+                        started_at,
+                        completed_at: Utc::now().timestamp(),
+                        readings: count as u64,
+                    };
+                    if let Err(e) = sink.record_fetch(&fetch).await {
+                        warn!("Failed to record fetch from {}: {}", source.name(), e);
+                    }
+                    for provenance in readings.iter_mut().filter_map(|r| r.provenance.as_mut()) {
+                        provenance.fetch_id = Some(fetch.fetch_id);
+                    }
+
+                    let weather = source.take_weather();
+                    if !weather.is_empty() {
+                        if let Err(e) = sink.record_weather(&weather).await {
+                            warn!("Failed to store weather from {}: {}", source.name(), e);
+                        }
+                    }
+
                     for reading in readings {
-                        if tx.send(reading).await.is_err() {
+                        if tx.send(Delivery::Reading(reading)).await.is_err() {
                             return Ok(()); // Channel closed
                         }
                     }
                     metrics::counter!("cherenkov_ingest_readings_total", "source" => source.name()).increment(count as u64);
+
+                    // Persisted by the writer once it has stored every reading above
+                    if let Some(cursor) = source.cursor() {
+                        if tx.send(Delivery::Cursor(source.name(), cursor)).await.is_err() {
+                            return Ok(());
+                        }
+                    }
                 }
                 Err(e) => {
                     warn!("Source {} fetch failed: {}", source.name(), e);
@@ -459,12 +645,39 @@ impl IngestionPipeline {
                 }
             }
             
-            tokio::time::sleep(source.poll_interval()).await;
+            tokio::select! {
+                _ = tokio::time::sleep(source.poll_interval()) => {}
+                _ = shutdown.cancelled() => break,
+            }
         }
+
+        debug!("Source {} stopped for shutdown", source.name());
+        Ok(())
     }
 
+    /// Store a batch, then persist the source cursors it completes
+    ///
+    /// Readings that could not be written are in the DLQ by then, which
+    /// replays them, so the cursors move past them too.
     async fn write_batch(
-        db: &Arc<RadiationDatabase>,
+        db: &Arc<dyn ReadingSink>,
+        event_bus: &Arc<EventBus>,
+        circuit_breaker: &CircuitBreaker,
+        dlq: &DeadLetterQueue,
+        cursor_store: &CursorStore,
+        batch: &mut Batch,
+    ) {
+        Self::write_readings(db, event_bus, circuit_breaker, dlq, &mut batch.readings).await;
+
+        for (source, cursor) in batch.cursors.drain(..) {
+            if let Err(e) = cursor_store.save(&source, &cursor).await {
+                warn!("Failed to persist cursor for {}: {}", source, e);
+            }
+        }
+    }
+
+    async fn write_readings(
+        db: &Arc<dyn ReadingSink>,
         event_bus: &Arc<EventBus>,
         circuit_breaker: &CircuitBreaker,
         dlq: &DeadLetterQueue,
@@ -484,12 +697,33 @@ impl IngestionPipeline {
             return;
         }
 
+        metrics::histogram!("cherenkov_ingest_batch_size").record(batch.len() as f64);
+
+        // One write for the whole batch, so readings for the cold tier share an archive
+        match db.write_batch(batch).await {
+            Ok(()) => {
+                circuit_breaker.record_success().await;
+                for reading in batch.drain(..) {
+                    Self::publish(event_bus, &reading).await;
+                }
+                return;
+            }
+            // Rewriting the readings that did land is harmless
+            Err(e) => warn!("Batch write failed, retrying reading by reading: {}", e),
+        }
+
         // Attempt write with retry
         let mut attempts = 0;
         let max_attempts = 3;
         let mut failed_readings = Vec::new();
 
         for reading in batch.drain(..) {
+            // Stop hammering the database once the breaker trips mid-batch
+            if !circuit_breaker.can_execute().await {
+                failed_readings.push((reading, "Circuit breaker open".to_string()));
+                continue;
+            }
+
             let mut success = false;
             
             while attempts < max_attempts && !success {
@@ -497,29 +731,7 @@ impl IngestionPipeline {
                     Ok(()) => {
                         success = true;
                         circuit_breaker.record_success().await;
-                        
-                        // Publish event to EventBus for downstream consumers
-                        let event = CherenkovEvent::NewReading(NormalizedReading {
-                            sensor_id: reading.sensor_id,
-                            timestamp: chrono::DateTime::from_timestamp(reading.timestamp, 0)
-                                .unwrap_or_else(|| chrono::Utc::now()),
-                            latitude: reading.latitude,
-                            longitude: reading.longitude,
-                            dose_rate_microsieverts: reading.dose_rate_microsieverts,
-                            uncertainty: reading.uncertainty as f64,
-                            quality_flag: match reading.quality_flag {
-                                QualityFlag::Valid => cherenkov_core::QualityFlag::Valid,
-                                QualityFlag::Suspect => cherenkov_core::QualityFlag::Suspect,
-                                QualityFlag::Invalid => cherenkov_core::QualityFlag::Invalid,
-                            },
-                            source: reading.source.clone(),
-                        });
-                        
-                        if let Err(e) = event_bus.publish(event).await {
-                            warn!("Failed to publish event to EventBus: {}", e);
-                        } else {
-                            metrics::counter!("cherenkov_ingest_events_published_total").increment(1);
-                        }
+                        Self::publish(event_bus, &reading).await;
                     }
             Err(e) => {
                 attempts += 1;
@@ -542,8 +754,32 @@ impl IngestionPipeline {
         for (reading, error) in failed_readings {
             dlq.store(reading, error).await;
         }
+    }
 
-        metrics::histogram!("cherenkov_ingest_batch_size").record(batch.len() as f64);
+    /// Publish a stored reading to the EventBus for downstream consumers
+    async fn publish(event_bus: &EventBus, reading: &RadiationReading) {
+        let event = CherenkovEvent::NewReading(NormalizedReading {
+            sensor_id: reading.sensor_id,
+            timestamp: chrono::DateTime::from_timestamp(reading.timestamp, 0)
+                .unwrap_or_else(chrono::Utc::now),
+            latitude: reading.latitude,
+            longitude: reading.longitude,
+            dose_rate_microsieverts: reading.dose_rate_microsieverts,
+            uncertainty: reading.uncertainty as f64,
+            quality_flag: match reading.quality_flag {
+                QualityFlag::Valid => cherenkov_core::QualityFlag::Valid,
+                QualityFlag::Suspect => cherenkov_core::QualityFlag::Suspect,
+                QualityFlag::Invalid => cherenkov_core::QualityFlag::Invalid,
+            },
+            source: reading.source.clone(),
+            track_id: reading.track_id,
+        });
+
+        if let Err(e) = event_bus.publish(event).await {
+            warn!("Failed to publish event to EventBus: {}", e);
+        } else {
+            metrics::counter!("cherenkov_ingest_events_published_total").increment(1);
+        }
     }
 
     /// Get pipeline statistics
@@ -552,10 +788,16 @@ impl IngestionPipeline {
         PipelineStats {
             dlq_size: self.dlq.len().await,
             circuit_breaker_open: !self.circuit_breaker.can_execute().await,
-            dedup_cache_size: self.deduplicator.seen.len(),
+            dedup_cache_size: self.deduplicator.len(),
+            station_aliases: self.deduplicator.aliases().len(),
         }
     }
 
+    /// QC engine, e.g. for registering sensor detector models
+    pub fn qc(&self) -> &QcEngine {
+        &self.qc
+    }
+
     /// Replay dead letter queue
     pub async fn replay_dlq(&self) -> usize {
         let db = self.db.clone();
@@ -581,6 +823,7 @@ pub struct PipelineStats {
     pub dlq_size: usize,
     pub circuit_breaker_open: bool,
     pub dedup_cache_size: usize,
+    pub station_aliases: usize,
 }
 
 /// Trait for data sources
@@ -589,4 +832,68 @@ pub trait DataSource: Send + Sync {
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>>;
     fn name(&self) -> String;
     fn poll_interval(&self) -> Duration;
+
+    /// Send all upstream requests to `base_url` instead of the live host,
+    /// e.g. a fixture server. Path and query are kept as-is.
+    fn set_base_url(&mut self, _base_url: &str) {}
+
+    /// Current incremental fetch position, for sources that track one
+    fn cursor(&self) -> Option<SourceCursor> {
+        None
+    }
+
+    /// Resume from a cursor persisted by a previous run
+    fn restore_cursor(&mut self, _cursor: SourceCursor) {}
+
+    /// Weather observed during the last fetch, for sources that report it
+    fn take_weather(&mut self) -> Vec<WeatherObservation> {
+        Vec::new()
+    }
+}
+
+/// Destination for readings that passed dedup and QC
+#[async_trait::async_trait]
+pub trait ReadingSink: Send + Sync {
+    async fn write_reading(&self, reading: &RadiationReading) -> anyhow::Result<()>;
+
+    /// Write readings together, durable once this returns
+    async fn write_batch(&self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        for reading in readings {
+            self.write_reading(reading).await?;
+        }
+        Ok(())
+    }
+
+    /// Record a fetch that readings' provenance refers to
+    async fn record_fetch(&self, _fetch: &SourceFetch) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Store weather that baselines use as context
+    async fn record_weather(&self, _observations: &[WeatherObservation]) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ReadingSink for RadiationDatabase {
+    async fn write_reading(&self, reading: &RadiationReading) -> anyhow::Result<()> {
+        RadiationDatabase::write_reading(self, reading).await?;
+        Ok(())
+    }
+
+    async fn write_batch(&self, readings: &[RadiationReading]) -> anyhow::Result<()> {
+        RadiationDatabase::write_readings(self, readings).await?;
+        Ok(())
+    }
+
+    async fn record_fetch(&self, fetch: &SourceFetch) -> anyhow::Result<()> {
+        RadiationDatabase::record_fetch(self, fetch).await?;
+        Ok(())
+    }
+
+    async fn record_weather(&self, observations: &[WeatherObservation]) -> anyhow::Result<()> {
+        RadiationDatabase::store_weather(self, observations).await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,462 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use chrono::Utc;
+use dashmap::DashMap;
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+use cherenkov_db::{QcCheck, QcReason, QualityFlag, RadiationReading, Sensor};
+
+use crate::dedup::haversine_km;
+use crate::normalizer;
+
+/// Scale factor turning a median absolute deviation into a standard deviation
+const MAD_SCALE: f64 = 1.4826;
+
+/// Tolerance below which two dose rates count as identical for flatline detection
+const FLATLINE_EPSILON: f64 = 1e-9;
+
+/// Thresholds for the automated QC checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QcThresholds {
+    /// Lowest physically possible dose rate in µSv/h
+    pub min_dose_rate: f64,
+    /// Dose rate above which a reading is flagged Suspect
+    pub suspect_dose_rate: f64,
+    /// Dose rate above which a reading is flagged Invalid
+    pub max_dose_rate: f64,
+    /// Robust z-score against recent history that marks a spike
+    pub spike_z: f64,
+    /// Minimum absolute deviation in µSv/h before a spike is reported
+    pub spike_min_delta: f64,
+    /// Relative change between consecutive window medians that marks a step
+    pub step_ratio: f64,
+    /// Readings per window compared for step detection
+    pub step_window: usize,
+    /// Identical consecutive readings before a sensor counts as stuck
+    pub flatline_count: usize,
+    /// Maximum change between consecutive readings in µSv/h per hour
+    pub max_rate_of_change: f64,
+    /// Tolerated clock skew for timestamps in the future
+    pub max_future_secs: i64,
+    /// Maximum movement of a fixed station between readings in km
+    pub max_location_jump_km: f64,
+    /// Readings of history kept per sensor
+    pub history_len: usize,
+}
+
+impl Default for QcThresholds {
+    fn default() -> Self {
+        Self {
+            min_dose_rate: 0.0,
+            suspect_dose_rate: 10.0,
+            max_dose_rate: 10_000.0,
+            spike_z: 6.0,
+            spike_min_delta: 0.1,
+            step_ratio: 0.5,
+            step_window: 6,
+            flatline_count: 12,
+            max_rate_of_change: 10.0,
+            max_future_secs: 300,
+            max_location_jump_km: 1.0,
+            history_len: 32,
+        }
+    }
+}
+
+/// QC configuration with per-source and per-detector overrides
+///
+/// Detector-model thresholds take precedence over source thresholds, which
+/// take precedence over the defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QcConfig {
+    pub defaults: QcThresholds,
+    /// Overrides keyed by source name, e.g. "safecast"
+    pub sources: HashMap<String, QcThresholds>,
+    /// Overrides keyed by detector model, e.g. "bGeigie Nano"
+    pub detectors: HashMap<String, QcThresholds>,
+}
+
+impl QcConfig {
+    pub fn thresholds_for(&self, source: &str, detector: Option<&str>) -> &QcThresholds {
+        detector
+            .and_then(|model| self.detectors.get(model))
+            .or_else(|| self.sources.get(source))
+            .unwrap_or(&self.defaults)
+    }
+
+    /// Name of the thresholds `thresholds_for` picks, e.g. `source:safecast`
+    pub fn thresholds_name(&self, source: &str, detector: Option<&str>) -> String {
+        match detector.filter(|model| self.detectors.contains_key(*model)) {
+            Some(model) => format!("detector:{}", model),
+            None if self.sources.contains_key(source) => format!("source:{}", source),
+            None => "defaults".to_string(),
+        }
+    }
+}
+
+/// Recent per-sensor state the stateful checks compare against
+#[derive(Debug, Default)]
+struct SensorHistory {
+    /// (timestamp, dose rate) in arrival order
+    values: VecDeque<(i64, f64)>,
+    last_location: Option<(f64, f64)>,
+    flat_run: usize,
+    step_active: bool,
+}
+
+/// Rules engine assigning a QualityFlag and QC reasons to each reading
+#[derive(Debug, Clone)]
+pub struct QcEngine {
+    config: Arc<QcConfig>,
+    history: Arc<DashMap<Uuid, SensorHistory>>,
+    detector_models: Arc<DashMap<Uuid, String>>,
+    /// Registered calibration factors, for sensors whose differ from 1.0
+    calibrations: Arc<DashMap<Uuid, f64>>,
+}
+
+impl QcEngine {
+    pub fn new(config: QcConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            history: Arc::new(DashMap::new()),
+            detector_models: Arc::new(DashMap::new()),
+            calibrations: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Associate a sensor with its detector model for threshold lookup
+    pub fn set_detector_model(&self, sensor_id: Uuid, model: impl Into<String>) {
+        self.detector_models.insert(sensor_id, model.into());
+    }
+
+    /// Convert a sensor's readings with its calibration factor; a change
+    /// forgets its history, which was in the old units
+    pub fn set_calibration(&self, sensor_id: Uuid, factor: f64) {
+        if self.calibrations.insert(sensor_id, factor) != Some(factor) {
+            self.reset_sensor(&sensor_id);
+        }
+    }
+
+    /// Take detector models and calibration factors from the sensor registry
+    pub fn sync_registry(&self, sensors: &[Sensor]) {
+        for sensor in sensors {
+            match &sensor.detector_model {
+                Some(model) => self.set_detector_model(sensor.sensor_id, model.clone()),
+                None => {
+                    self.detector_models.remove(&sensor.sensor_id);
+                }
+            }
+            if sensor.calibration_factor != 1.0 {
+                self.set_calibration(sensor.sensor_id, sensor.calibration_factor);
+            } else if self.calibrations.remove(&sensor.sensor_id).is_some() {
+                self.reset_sensor(&sensor.sensor_id);
+            }
+        }
+    }
+
+    /// Run all checks against a reading and record the outcome on it
+    ///
+    /// `alias_of` is the canonical station when the reading's sensor is a
+    /// known duplicate of another network's station.
+    pub fn evaluate(&self, reading: &mut RadiationReading, alias_of: Option<Uuid>) {
+        self.evaluate_at(reading, alias_of, Utc::now().timestamp());
+    }
+
+    fn evaluate_at(&self, reading: &mut RadiationReading, alias_of: Option<Uuid>, now: i64) {
+        if let Some(factor) = self.calibrations.get(&reading.sensor_id).map(|f| *f) {
+            normalizer::recalibrate(reading, factor);
+        }
+
+        let model = self.detector_models.get(&reading.sensor_id).map(|m| m.clone());
+        let thresholds = self.config.thresholds_for(&reading.source, model.as_deref());
+        if let Some(provenance) = reading.provenance.as_mut() {
+            provenance.qc_thresholds = Some(self.config.thresholds_name(&reading.source, model.as_deref()));
+        }
+
+        let mut reasons = Vec::new();
+        let value = reading.dose_rate_microsieverts;
+
+        check_range(thresholds, value, &mut reasons);
+
+        if reading.timestamp - now > thresholds.max_future_secs {
+            reasons.push(reason(
+                QcCheck::FutureTimestamp,
+                QualityFlag::Invalid,
+                (reading.timestamp - now) as f64,
+                thresholds.max_future_secs as f64,
+            ));
+        }
+
+        if let Some(canonical) = alias_of {
+            // Informational: the reading itself is fine, but it is not independent
+            reasons.push(QcReason {
+                detail: Some(canonical.to_string()),
+                ..reason(QcCheck::DuplicateStation, QualityFlag::Valid, 0.0, 0.0)
+            });
+        }
+
+        let invalid = reasons.iter().any(|r| r.flag == QualityFlag::Invalid);
+        {
+            let mut history = self.history.entry(reading.sensor_id).or_default();
+            check_history(thresholds, &mut history, reading, &mut reasons);
+
+            // Keep implausible values out of the baseline later readings are compared to
+            if !invalid {
+                history.values.push_back((reading.timestamp, value));
+                while history.values.len() > thresholds.history_len.max(2 * thresholds.step_window) {
+                    history.values.pop_front();
+                }
+                history.last_location = Some((reading.latitude, reading.longitude));
+            }
+        }
+
+        for r in &reasons {
+            metrics::counter!("cherenkov_ingest_qc_flagged_total", "check" => format!("{:?}", r.check)).increment(1);
+        }
+
+        reading.quality_flag = reasons
+            .iter()
+            .map(|r| r.flag.clone())
+            .fold(reading.quality_flag.clone(), worst);
+        reading.qc_reasons = reasons;
+    }
+
+    /// Forget accumulated history for a sensor, e.g. after recalibration
+    pub fn reset_sensor(&self, sensor_id: &Uuid) {
+        self.history.remove(sensor_id);
+    }
+}
+
+fn check_range(thresholds: &QcThresholds, value: f64, reasons: &mut Vec<QcReason>) {
+    if !value.is_finite() || value < thresholds.min_dose_rate {
+        reasons.push(reason(QcCheck::Range, QualityFlag::Invalid, value, thresholds.min_dose_rate));
+    } else if value > thresholds.max_dose_rate {
+        reasons.push(reason(QcCheck::Range, QualityFlag::Invalid, value, thresholds.max_dose_rate));
+    } else if value > thresholds.suspect_dose_rate {
+        reasons.push(reason(QcCheck::Range, QualityFlag::Suspect, value, thresholds.suspect_dose_rate));
+    }
+}
+
+fn check_history(
+    thresholds: &QcThresholds,
+    history: &mut SensorHistory,
+    reading: &RadiationReading,
+    reasons: &mut Vec<QcReason>,
+) {
+    let value = reading.dose_rate_microsieverts;
+
+    // Mobile sensors are expected to move; only fixed stations can jump
+    if let (None, Some((lat, lon))) = (reading.track_id, history.last_location) {
+        let moved = haversine_km(lat, lon, reading.latitude, reading.longitude);
+        if moved > thresholds.max_location_jump_km {
+            reasons.push(reason(QcCheck::LocationJump, QualityFlag::Suspect, moved, thresholds.max_location_jump_km));
+        }
+    }
+
+    let Some(&(prev_ts, prev_value)) = history.values.back() else {
+        history.flat_run = 1;
+        return;
+    };
+
+    let hours = ((reading.timestamp - prev_ts).abs().max(1)) as f64 / 3600.0;
+    let rate = (value - prev_value).abs() / hours;
+    if rate > thresholds.max_rate_of_change {
+        reasons.push(reason(QcCheck::RateOfChange, QualityFlag::Suspect, rate, thresholds.max_rate_of_change));
+    }
+
+    if (value - prev_value).abs() < FLATLINE_EPSILON {
+        history.flat_run += 1;
+    } else {
+        history.flat_run = 1;
+    }
+    if history.flat_run >= thresholds.flatline_count {
+        reasons.push(reason(
+            QcCheck::Flatline,
+            QualityFlag::Suspect,
+            history.flat_run as f64,
+            thresholds.flatline_count as f64,
+        ));
+    }
+
+    let recent: Vec<f64> = history.values.iter().map(|&(_, v)| v).collect();
+    if recent.len() >= thresholds.step_window {
+        let center = median(&recent);
+        let mad = median(&recent.iter().map(|v| (v - center).abs()).collect::<Vec<_>>());
+        let deviation = (value - center).abs();
+        let limit = (thresholds.spike_z * MAD_SCALE * mad).max(thresholds.spike_min_delta);
+        if deviation > limit {
+            reasons.push(reason(QcCheck::Spike, QualityFlag::Suspect, deviation, limit));
+        }
+    }
+
+    // Compare the window ending at this reading with the one before it
+    let window = thresholds.step_window;
+    if window > 0 && recent.len() + 1 >= 2 * window {
+        let mut with_current = recent;
+        with_current.push(value);
+        let n = with_current.len();
+        let before = median(&with_current[n - 2 * window..n - window]);
+        let after = median(&with_current[n - window..]);
+        let ratio = if before > 0.0 { (after - before).abs() / before } else { 0.0 };
+
+        if ratio > thresholds.step_ratio {
+            // Report a level shift once rather than for every reading of the new level
+            if !history.step_active {
+                reasons.push(reason(QcCheck::Step, QualityFlag::Suspect, ratio, thresholds.step_ratio));
+            }
+            history.step_active = true;
+        } else {
+            history.step_active = false;
+        }
+    }
+}
+
+fn reason(check: QcCheck, flag: QualityFlag, value: f64, limit: f64) -> QcReason {
+    QcReason {
+        check,
+        flag,
+        value,
+        limit,
+        detail: None,
+    }
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+fn worst(a: QualityFlag, b: QualityFlag) -> QualityFlag {
+    fn rank(flag: &QualityFlag) -> u8 {
+        match flag {
+            QualityFlag::Valid => 0,
+            QualityFlag::Suspect => 1,
+            QualityFlag::Invalid => 2,
+        }
+    }
+    if rank(&b) > rank(&a) { b } else { a }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn reading(sensor_id: Uuid, timestamp: i64, dose_rate: f64) -> RadiationReading {
+        RadiationReading {
+            sensor_id,
+            bucket: timestamp / 3600,
+            timestamp,
+            latitude: 50.0,
+            longitude: 8.0,
+            dose_rate_microsieverts: dose_rate,
+            uncertainty: 0.1,
+            quality_flag: QualityFlag::Valid,
+            source: "safecast".to_string(),
+            cell_id: String::new(),
+            track_id: None,
+            altitude_m: None,
+            qc_reasons: Vec::new(),
+            provenance: None,
+        }
+    }
+
+    fn checks(reading: &RadiationReading) -> Vec<QcCheck> {
+        reading.qc_reasons.iter().map(|r| r.check).collect()
+    }
+
+    #[test]
+    fn test_range_and_future_timestamp() {
+        let engine = QcEngine::new(QcConfig::default());
+
+        let mut negative = reading(Uuid::new_v4(), NOW, -0.5);
+        engine.evaluate_at(&mut negative, None, NOW);
+        assert_eq!(negative.quality_flag, QualityFlag::Invalid);
+        assert_eq!(checks(&negative), vec![QcCheck::Range]);
+
+        let mut future = reading(Uuid::new_v4(), NOW + 3600, 0.1);
+        engine.evaluate_at(&mut future, None, NOW);
+        assert_eq!(future.quality_flag, QualityFlag::Invalid);
+        assert_eq!(checks(&future), vec![QcCheck::FutureTimestamp]);
+    }
+
+    #[test]
+    fn test_spike_is_flagged_against_history() {
+        let engine = QcEngine::new(QcConfig::default());
+        let sensor = Uuid::new_v4();
+
+        for i in 0..10 {
+            let mut r = reading(sensor, NOW + i * 300, 0.10 + (i % 3) as f64 * 0.01);
+            engine.evaluate_at(&mut r, None, NOW + 3600);
+            assert_eq!(r.quality_flag, QualityFlag::Valid);
+        }
+
+        let mut spike = reading(sensor, NOW + 3000, 1.5);
+        engine.evaluate_at(&mut spike, None, NOW + 3600);
+        assert_eq!(spike.quality_flag, QualityFlag::Suspect);
+        assert!(checks(&spike).contains(&QcCheck::Spike));
+    }
+
+    #[test]
+    fn test_flatline_and_detector_override() {
+        let mut config = QcConfig::default();
+        config.detectors.insert("stuck-prone".to_string(), QcThresholds {
+            flatline_count: 3,
+            ..QcThresholds::default()
+        });
+        let engine = QcEngine::new(config);
+        let sensor = Uuid::new_v4();
+        engine.set_detector_model(sensor, "stuck-prone");
+
+        let flags: Vec<_> = (0..4)
+            .map(|i| {
+                let mut r = reading(sensor, NOW + i * 60, 0.12);
+                engine.evaluate_at(&mut r, None, NOW + 3600);
+                checks(&r).contains(&QcCheck::Flatline)
+            })
+            .collect();
+        assert_eq!(flags, vec![false, false, true, true]);
+    }
+
+    #[test]
+    fn test_registered_calibration_replaces_the_source_factor() {
+        let engine = QcEngine::new(QcConfig::default());
+        let sensor = Uuid::new_v4();
+        engine.set_calibration(sensor, 0.004);
+
+        let mut r = RadiationReading {
+            provenance: normalizer::provenance(35.0, "cpm", 0.00294),
+            ..reading(sensor, NOW, 35.0 * 0.00294)
+        };
+        engine.evaluate_at(&mut r, None, NOW);
+        assert!((r.dose_rate_microsieverts - 0.14).abs() < 1e-9);
+        assert_eq!(r.provenance.unwrap().conversion_factor, 0.004);
+
+        // Without the raw value there is nothing to convert
+        let mut bare = reading(sensor, NOW + 60, 0.1);
+        engine.evaluate_at(&mut bare, None, NOW + 60);
+        assert_eq!(bare.dose_rate_microsieverts, 0.1);
+    }
+
+    #[test]
+    fn test_duplicate_station_is_informational() {
+        let engine = QcEngine::new(QcConfig::default());
+        let canonical = Uuid::new_v4();
+
+        let mut r = reading(Uuid::new_v4(), NOW, 0.1);
+        engine.evaluate_at(&mut r, Some(canonical), NOW);
+        assert_eq!(r.quality_flag, QualityFlag::Valid);
+        assert_eq!(r.qc_reasons[0].check, QcCheck::DuplicateStation);
+        assert_eq!(r.qc_reasons[0].detail, Some(canonical.to_string()));
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use tracing::{info, warn};
+
+use cherenkov_core::{CherenkovEvent, EventBus, SensorStatus};
+use cherenkov_db::{LivenessThresholds, RadiationDatabase, SensorState, StatusTransition};
+
+use crate::coverage::{CoverageConfig, CoverageMonitor};
+use crate::qc::QcEngine;
+
+/// Periodically sweeps the sensor registry and publishes status transitions
+/// and coverage changes, keeping QC's detector models and calibration
+/// factors in step with the registry
+pub struct SensorStatusTracker {
+    db: Arc<RadiationDatabase>,
+    event_bus: Arc<EventBus>,
+    thresholds: LivenessThresholds,
+    interval: Duration,
+    coverage: CoverageMonitor,
+    qc: Option<QcEngine>,
+}
+
+impl SensorStatusTracker {
+    pub fn new(
+        db: Arc<RadiationDatabase>,
+        event_bus: Arc<EventBus>,
+        thresholds: LivenessThresholds,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            db,
+            event_bus,
+            thresholds,
+            interval,
+            coverage: CoverageMonitor::default(),
+            qc: None,
+        }
+    }
+
+    pub fn with_coverage(mut self, config: CoverageConfig) -> Self {
+        self.coverage = CoverageMonitor::new(config);
+        self
+    }
+
+    pub fn with_qc(mut self, qc: QcEngine) -> Self {
+        self.qc = Some(qc);
+        self
+    }
+
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(self.interval);
+
+        loop {
+            interval.tick().await;
+
+            match self.db.sweep_sensor_status(&self.thresholds).await {
+                Ok(transitions) => {
+                    if !transitions.is_empty() {
+                        info!("Applied {} sensor status transitions", transitions.len());
+                    }
+                    publish_transitions(&self.event_bus, &transitions).await;
+                }
+                Err(e) => {
+                    warn!("Sensor status sweep failed: {}", e);
+                    continue;
+                }
+            }
+
+            match self.db.list_registered_sensors(false).await {
+                Ok(sensors) => {
+                    if let Some(qc) = &self.qc {
+                        qc.sync_registry(&sensors);
+                    }
+                    for event in self.coverage.update(&sensors) {
+                        if let Err(e) = self.event_bus.publish(event).await {
+                            warn!("Failed to publish coverage change: {}", e);
+                        }
+                    }
+                }
+                Err(e) => warn!("Failed to list registered sensors: {}", e),
+            }
+        }
+    }
+}
+
+/// Publish a `SensorStatusChange` for every transition, returning how many were sent
+pub async fn publish_transitions(event_bus: &EventBus, transitions: &[StatusTransition]) -> usize {
+    let mut published = 0;
+
+    for transition in transitions {
+        let Some(status) = to_event_status(transition.to) else {
+            continue;
+        };
+
+        metrics::counter!(
+            "cherenkov_ingest_sensor_status_changes_total",
+            "status" => transition.to.as_str()
+        ).increment(1);
+
+        let event = CherenkovEvent::SensorStatusChange {
+            sensor_id: transition.sensor_id,
+            status,
+            timestamp: DateTime::from_timestamp(transition.at, 0).unwrap_or_else(Utc::now),
+        };
+
+        match event_bus.publish(event).await {
+            Ok(()) => published += 1,
+            Err(e) => warn!("Failed to publish status change for {}: {}", transition.sensor_id, e),
+        }
+    }
+
+    published
+}
+
+fn to_event_status(state: SensorState) -> Option<SensorStatus> {
+    match state {
+        SensorState::Online => Some(SensorStatus::Online),
+        SensorState::Degraded => Some(SensorStatus::Degraded),
+        SensorState::Offline => Some(SensorStatus::Offline),
+        SensorState::Maintenance => Some(SensorStatus::Maintenance),
+        SensorState::Decommissioned => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn transition(to: SensorState) -> StatusTransition {
+        StatusTransition {
+            sensor_id: Uuid::new_v4(),
+            from: SensorState::Online,
+            to,
+            at: 1705316400,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transitions_become_status_events() {
+        let event_bus = EventBus::new(16);
+        let mut rx = event_bus.subscribe();
+        let offline = transition(SensorState::Offline);
+
+        let published = publish_transitions(
+            &event_bus,
+            &[offline.clone(), transition(SensorState::Decommissioned)],
+        ).await;
+        assert_eq!(published, 1);
+
+        match rx.try_recv().unwrap() {
+            CherenkovEvent::SensorStatusChange { sensor_id, status, timestamp } => {
+                assert_eq!(sensor_id, offline.sensor_id);
+                assert!(matches!(status, SensorStatus::Offline));
+                assert_eq!(timestamp.timestamp(), offline.at);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_state_follows_learned_cadence() {
+        let thresholds = LivenessThresholds::default();
+        // Ten minutes is long for a sensor delivering every minute, not for an hourly one
+        assert_eq!(thresholds.state_for_gap(Some(600), Some(60.0), 10), SensorState::Offline);
+        assert_eq!(thresholds.state_for_gap(Some(600), Some(3600.0), 10), SensorState::Online);
+        assert_eq!(thresholds.state_for_gap(Some(4 * 3600), Some(3600.0), 10), SensorState::Degraded);
+    }
+}
@@ -4,6 +4,8 @@ use std::time::Duration;
 use tracing::info;
 use cherenkov_db::{RadiationReading, QualityFlag};
 use uuid::Uuid;
+use crate::fixtures::rebase;
+use crate::normalizer;
 use crate::pipeline::DataSource;
 use crate::SourceConfig;
 use scraper::{Html, Selector};
@@ -17,6 +19,8 @@ use chrono::{DateTime, Utc, NaiveDateTime, TimeZone};
 /// from stations across the United States.
 pub struct EpaRadnetSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
     config: SourceConfig,
 }
 
@@ -47,6 +51,7 @@ impl EpaRadnetSource {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            base_url: None,
             config: SourceConfig {
                 name: "epa_radnet".to_string(),
                 url: "https://www.epa.gov/radnet/radnet-data".to_string(),
@@ -163,11 +168,15 @@ impl DataSource for EpaRadnetSource {
         Duration::from_secs(self.config.poll_interval_secs)
     }
 
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
-        let url = "https://www.epa.gov/radnet/radnet-data";
+        let url = rebase("https://www.epa.gov/radnet/radnet-data", self.base_url.as_deref());
         
         let response = self.client
-            .get(url)
+            .get(&url)
             .header("User-Agent", "Cherenkov/1.0 (Radiation Monitoring)")
             .send()
             .await?;
@@ -184,8 +193,11 @@ impl DataSource for EpaRadnetSource {
         // Fallback: Try CSV endpoint if HTML scraping returns no data
         let mut final_readings = readings;
         if final_readings.is_empty() {
-            let csv_url = "https://www.epa.gov/sites/default/files/2023-06/radnet-near-real-time-data.csv";
-            let csv_response = self.client.get(csv_url).send().await;
+            let csv_url = rebase(
+                "https://www.epa.gov/sites/default/files/2023-06/radnet-near-real-time-data.csv",
+                self.base_url.as_deref(),
+            );
+            let csv_response = self.client.get(&csv_url).send().await;
             
             if let Ok(resp) = csv_response {
                 if resp.status().is_success() {
@@ -275,7 +287,8 @@ impl EpaRadnetSource {
             };
             
             // Convert μR/h to μSv/h (1 μR ≈ 0.00877 μSv)
-            let dose_rate = gamma_value * 0.00877;
+            let factor = 0.00877;
+            let dose_rate = gamma_value * factor;
             
             let sensor_id = format!("epa:{}", station_name.to_lowercase().replace(" ", "_"));
             
@@ -290,6 +303,10 @@ impl EpaRadnetSource {
                 quality_flag: QualityFlag::Valid,
                 source: "epa_radnet".to_string(),
                 cell_id: format!("{:.2},{:.2}", lat, lon),
+                track_id: None,
+                altitude_m: None,
+                qc_reasons: Vec::new(),
+                provenance: normalizer::provenance(gamma_value, "uR/h", factor),
             });
         }
         
@@ -341,14 +358,12 @@ impl EpaRadnetSource {
                 None
             }.unwrap_or_else(Utc::now);
             
-            let dose_rate = if let Some(idx) = gamma_idx {
-                fields.get(idx)
-                    .and_then(|f| f.parse::<f64>().ok())
-                    .map(|cpm| cpm * 0.0057)
-                    .unwrap_or(0.0)
-            } else {
-                0.0
-            };
+            const USV_PER_CPM: f64 = 0.0057;
+            let cpm = gamma_idx
+                .and_then(|idx| fields.get(idx))
+                .and_then(|f| f.parse::<f64>().ok())
+                .unwrap_or(0.0);
+            let dose_rate = cpm * USV_PER_CPM;
             
             if dose_rate <= 0.0 {
                 continue;
@@ -369,6 +384,10 @@ impl EpaRadnetSource {
                 quality_flag: QualityFlag::Valid,
                 source: "epa_radnet".to_string(),
                 cell_id: format!("{:.2},{:.2}", lat, lon),
+                track_id: None,
+                altitude_m: None,
+                qc_reasons: Vec::new(),
+                provenance: normalizer::provenance(cpm, "cpm", USV_PER_CPM),
             });
         }
         
@@ -4,6 +4,8 @@ use std::time::Duration;
 use tracing::{info, warn, instrument};
 use cherenkov_db::{RadiationReading, QualityFlag};
 use uuid::Uuid;
+use crate::fixtures::rebase;
+use crate::normalizer;
 use crate::pipeline::DataSource;
 use crate::SourceConfig;
 use chrono::{Utc, NaiveDateTime};
@@ -16,6 +18,8 @@ use chrono::{Utc, NaiveDateTime};
 /// Fire data can correlate with radiation releases from nuclear incidents.
 pub struct NasaFirmsSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
     config: SourceConfig,
     api_key: String,
 }
@@ -42,6 +46,7 @@ impl NasaFirmsSource {
     pub fn new(api_key: String) -> Self {
         Self {
             client: Client::new(),
+            base_url: None,
             config: SourceConfig {
                 name: "nasa_firms".to_string(),
                 url: "https://firms.modaps.eosdis.nasa.gov/api/area/csv".to_string(),
@@ -136,11 +141,12 @@ impl NasaFirmsSource {
         
         // Create proxy reading based on fire radiative power
         // FRP > 1000 MW may indicate significant thermal anomaly
-        let proxy_dose = if fire.frp > 1000.0 {
-            fire.frp / 10000.0 // Proxy conversion
+        let factor = if fire.frp > 1000.0 {
+            1.0 / 10000.0 // Proxy conversion
         } else {
-            fire.frp / 50000.0
+            1.0 / 50000.0
         };
+        let proxy_dose = fire.frp * factor;
         
         let sensor_id = format!("firms-{}-{}-{:.4}-{:.4}", 
             fire.satellite.to_lowercase(),
@@ -161,6 +167,10 @@ impl NasaFirmsSource {
 
             source: "nasa_firms".to_string(),
             cell_id: format!("{:.2},{:.2}", fire.latitude, fire.longitude),
+            track_id: None,
+            altitude_m: None,
+            qc_reasons: Vec::new(),
+            provenance: normalizer::provenance(fire.frp, "MW", factor),
         })
     }
 }
@@ -175,6 +185,10 @@ impl DataSource for NasaFirmsSource {
         Duration::from_secs(self.config.poll_interval_secs)
     }
 
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
     #[instrument(skip(self))]
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
         // Fetch global fire data for last 24 hours
@@ -186,6 +200,7 @@ impl DataSource for NasaFirmsSource {
             chrono::Local::now().format("%Y-%m-%d")
         );
         
+        let url = rebase(&url, self.base_url.as_deref());
         let response = self.client
             .get(&url)
             .header("User-Agent", "Cherenkov/1.0 (Radiation Monitoring)")
@@ -204,6 +219,7 @@ impl DataSource for NasaFirmsSource {
                     chrono::Local::now().format("%Y-%m-%d")
                 );
                 
+                let viirs_url = rebase(&viirs_url, self.base_url.as_deref());
                 let viirs_resp = self.client.get(&viirs_url).send().await;
                 match viirs_resp {
                     Ok(r) if r.status().is_success() => r.text().await?,
@@ -4,6 +4,8 @@ use std::time::Duration;
 use tracing::{info, warn, instrument};
 use cherenkov_db::{RadiationReading, QualityFlag};
 use uuid::Uuid;
+use crate::fixtures::rebase;
+use crate::normalizer;
 use crate::pipeline::DataSource;
 use crate::SourceConfig;
 use chrono::{DateTime, Utc, Timelike};
@@ -15,6 +17,8 @@ use chrono::{DateTime, Utc, Timelike};
 /// GFS provides wind, temperature, and pressure data on a global grid.
 pub struct NoaaGfsSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
     config: SourceConfig,
 }
 
@@ -35,6 +39,7 @@ impl NoaaGfsSource {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            base_url: None,
             config: SourceConfig {
                 name: "noaa_gfs".to_string(),
                 url: "https://nomads.ncep.noaa.gov/cgi-bin/filter_gfs_0p25.pl".to_string(),
@@ -110,6 +115,7 @@ impl NoaaGfsSource {
             run_date
         );
         
+        let url = rebase(&url, self.base_url.as_deref());
         let response = self.client
             .get(&url)
             .header("User-Agent", "Cherenkov/1.0 (Radiation Monitoring)")
@@ -188,7 +194,8 @@ impl NoaaGfsSource {
         
         // Create proxy reading based on wind speed
         // Higher winds = higher proxy value for dispersion modeling
-        let proxy_dose = wind_speed / 100.0;
+        let factor = 0.01;
+        let proxy_dose = wind_speed * factor;
         
         Some(RadiationReading {
             sensor_id: Uuid::new_v5(&Uuid::NAMESPACE_DNS, sensor_id.as_bytes()),
@@ -202,6 +209,10 @@ impl NoaaGfsSource {
 
             source: "noaa_gfs".to_string(),
             cell_id: format!("{:.2},{:.2}", point.latitude, point.longitude),
+            track_id: None,
+            altitude_m: None,
+            qc_reasons: Vec::new(),
+            provenance: normalizer::provenance(wind_speed, "m/s", factor),
         })
     }
 }
@@ -216,6 +227,10 @@ impl DataSource for NoaaGfsSource {
         Duration::from_secs(self.config.poll_interval_secs)
     }
 
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
     #[instrument(skip(self))]
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
         // GFS runs at 00, 06, 12, 18 UTC
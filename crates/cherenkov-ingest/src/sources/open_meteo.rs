@@ -2,8 +2,10 @@ use async_trait::async_trait;
 use reqwest::Client;
 use std::time::Duration;
 use tracing::{info, warn, instrument};
-use cherenkov_db::{RadiationReading, QualityFlag};
+use cherenkov_db::{RadiationReading, QualityFlag, WeatherObservation};
 use uuid::Uuid;
+use crate::fixtures::rebase;
+use crate::normalizer;
 use crate::pipeline::DataSource;
 use crate::SourceConfig;
 use chrono::{DateTime, Utc};
@@ -15,7 +17,11 @@ use serde::Deserialize;
 /// Weather data provides atmospheric conditions for radiation dispersion modeling.
 pub struct OpenMeteoSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
     config: SourceConfig,
+    /// Precipitation from the last fetch, handed to the pipeline by `take_weather`
+    weather: Vec<WeatherObservation>,
 }
 
 /// Open-Meteo API response
@@ -35,6 +41,8 @@ struct OpenMeteoHourly {
     wind_speed_10m: Vec<f64>,
     wind_direction_10m: Vec<f64>,
     pressure_msl: Vec<f64>,
+    #[serde(default)]
+    precipitation: Vec<f64>,
 }
 
 /// Weather reading with radiation correlation
@@ -47,6 +55,8 @@ pub struct WeatherReading {
     pub wind_speed_ms: f64,
     pub wind_direction_deg: f64,
     pub pressure_hpa: f64,
+    /// Precipitation over the preceding hour, if reported
+    pub precipitation_mm: Option<f64>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -54,6 +64,7 @@ impl OpenMeteoSource {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            base_url: None,
             config: SourceConfig {
                 name: "open_meteo".to_string(),
                 url: "https://api.open-meteo.com/v1/forecast".to_string(),
@@ -61,6 +72,7 @@ impl OpenMeteoSource {
                 timeout: Duration::from_secs(30),
                 retries: 3,
             },
+            weather: Vec::new(),
         }
     }
 
@@ -81,6 +93,7 @@ impl OpenMeteoSource {
                     wind_speed_ms: *response.hourly.wind_speed_10m.get(i).unwrap_or(&0.0),
                     wind_direction_deg: *response.hourly.wind_direction_10m.get(i).unwrap_or(&0.0),
                     pressure_hpa: *response.hourly.pressure_msl.get(i).unwrap_or(&1013.25),
+                    precipitation_mm: response.hourly.precipitation.get(i).copied(),
                     timestamp,
                 };
                 
@@ -110,7 +123,8 @@ impl OpenMeteoSource {
         
         // Create proxy reading based on wind speed
         // Higher winds = higher proxy value for dispersion modeling
-        let proxy_dose = weather.wind_speed_ms / 100.0;
+        let factor = 0.01;
+        let proxy_dose = weather.wind_speed_ms * factor;
         
         Some(RadiationReading {
             sensor_id: Uuid::new_v5(&Uuid::NAMESPACE_DNS, sensor_id.as_bytes()),
@@ -124,6 +138,10 @@ impl OpenMeteoSource {
 
             source: "open_meteo".to_string(),
             cell_id: format!("{:.2},{:.2}", weather.latitude, weather.longitude),
+            track_id: None,
+            altitude_m: None,
+            qc_reasons: Vec::new(),
+            provenance: normalizer::provenance(weather.wind_speed_ms, "m/s", factor),
         })
     }
 
@@ -156,6 +174,14 @@ impl DataSource for OpenMeteoSource {
         Duration::from_secs(self.config.poll_interval_secs)
     }
 
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
+    fn take_weather(&mut self) -> Vec<WeatherObservation> {
+        std::mem::take(&mut self.weather)
+    }
+
     #[instrument(skip(self))]
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
         // Fetch weather data for key regions around nuclear facilities
@@ -180,12 +206,13 @@ impl DataSource for OpenMeteoSource {
         
         for (lat, lon) in locations {
             let url = format!(
-                "{}?latitude={}&longitude={}&hourly=temperature_2m,relative_humidity_2m,wind_speed_10m,wind_direction_10m,pressure_msl&timezone=UTC",
+                "{}?latitude={}&longitude={}&hourly=temperature_2m,relative_humidity_2m,wind_speed_10m,wind_direction_10m,pressure_msl,precipitation&timezone=UTC",
                 self.config.url,
                 lat,
                 lon
             );
             
+            let url = rebase(&url, self.base_url.as_deref());
             let response = self.client
                 .get(&url)
                 .header("User-Agent", "Cherenkov/1.0 (Radiation Monitoring)")
@@ -202,6 +229,17 @@ impl DataSource for OpenMeteoSource {
                             
                             for weather in &weather_readings {
                                 self.log_significant_events(weather);
+
+                                if let Some(precipitation_mm) = weather.precipitation_mm {
+                                    self.weather.push(WeatherObservation {
+                                        latitude: weather.latitude,
+                                        longitude: weather.longitude,
+                                        // Open-Meteo labels hourly sums with the hour's end
+                                        timestamp: weather.timestamp.timestamp() - 3600,
+                                        source: self.config.name.clone(),
+                                        precipitation_mm,
+                                    });
+                                }
                                 
                                 if let Some(proxy) = self.weather_to_radiation_proxy(weather) {
                                     all_readings.push(proxy);
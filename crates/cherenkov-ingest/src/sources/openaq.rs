@@ -4,6 +4,8 @@ use std::time::Duration;
 use tracing::{info, warn};
 use cherenkov_db::{RadiationReading, QualityFlag};
 use uuid::Uuid;
+use crate::fixtures::rebase;
+use crate::normalizer;
 use crate::pipeline::DataSource;
 use crate::SourceConfig;
 
@@ -17,6 +19,8 @@ use serde::Deserialize;
 /// Air quality data provides correlation with radiation transport patterns.
 pub struct OpenAqSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
     config: SourceConfig,
 }
 
@@ -83,6 +87,7 @@ impl OpenAqSource {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            base_url: None,
             config: SourceConfig {
                 name: "openaq".to_string(),
                 url: "https://api.openaq.org/v2/latest".to_string(),
@@ -153,7 +158,8 @@ impl OpenAqSource {
         
         // Create proxy reading based on particulate matter
         // Higher PM = higher proxy value for transport modeling
-        let proxy_dose = (pm25.max(pm10)) / 1000.0;
+        let (pm, factor) = (pm25.max(pm10), 0.001);
+        let proxy_dose = pm * factor;
         
         Some(RadiationReading {
             sensor_id: Uuid::new_v5(&Uuid::NAMESPACE_DNS, sensor_id.as_bytes()),
@@ -167,6 +173,10 @@ impl OpenAqSource {
 
             source: "openaq".to_string(),
             cell_id: format!("{:.2},{:.2}", aq.latitude, aq.longitude),
+            track_id: None,
+            altitude_m: None,
+            qc_reasons: Vec::new(),
+            provenance: normalizer::provenance(pm, "ug/m3", factor),
         })
     }
 
@@ -202,9 +212,13 @@ impl DataSource for OpenAqSource {
         Duration::from_secs(self.config.poll_interval_secs)
     }
 
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
         // Fetch latest measurements from OpenAQ
-        let url = self.config.url.clone();
+        let url = rebase(&self.config.url, self.base_url.as_deref());
         let response = self.client
             .get(&url)
             .query(&[
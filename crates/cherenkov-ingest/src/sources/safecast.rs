@@ -1,26 +1,35 @@
 use async_trait::async_trait;
 use chrono::Utc;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::Deserialize;
-use tracing::{info, error};
+use tracing::{info, error, warn, debug};
 use std::time::Duration;
 use uuid::Uuid;
 
 use cherenkov_db::{RadiationReading, QualityFlag};
 
+use crate::cursor::SourceCursor;
+use crate::fixtures::rebase;
+use crate::normalizer;
 use crate::pipeline::DataSource;
 
 const SAFECAST_API_URL: &str = "https://api.safecast.org/measurements.json";
+const SAFECAST_PAGE_SIZE: usize = 1000;
+const SAFECAST_MAX_PAGES: usize = 20;
+/// How far back the very first fetch reaches when no cursor is stored
+const SAFECAST_INITIAL_LOOKBACK_SECS: i64 = 3600;
 
 #[derive(Debug, Clone)]
 pub struct SafecastSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
     api_key: String,
+    cursor: SourceCursor,
 }
 
 #[derive(Debug, Deserialize)]
 struct SafecastMeasurement {
-    #[allow(dead_code)]
     id: u64,
     value: f64,
     unit: String,
@@ -30,6 +39,10 @@ struct SafecastMeasurement {
     device_id: Option<u64>,
     #[allow(dead_code)]
     location_name: Option<String>,
+    /// bGeigie log import the measurement came from, i.e. one survey drive
+    measurement_import_id: Option<u64>,
+    /// Altitude in metres
+    height: Option<f64>,
 }
 
 
@@ -40,77 +53,158 @@ impl SafecastSource {
                 .timeout(Duration::from_secs(30))
                 .build()
                 .expect("Failed to create HTTP client"),
+            base_url: None,
             api_key: std::env::var("SAFECAST_API_KEY").unwrap_or_default(),
+            cursor: SourceCursor::default(),
         }
     }
 
-    fn convert_to_usv(value: f64, unit: &str) -> f64 {
+    /// Multiplier from a Safecast unit to µSv/h
+    fn usv_factor(unit: &str) -> f64 {
         match unit.to_lowercase().as_str() {
-            "cpm" => value * 0.00294,
-            "usv" => value,
-            "msv" => value * 1000.0,
-            _ => value * 0.00294,
+            "cpm" => 0.00294,
+            "usv" => 1.0,
+            "msv" => 1000.0,
+            _ => 0.00294,
         }
     }
+
+    fn to_reading(m: &SafecastMeasurement) -> Option<RadiationReading> {
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&m.captured_at)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc).timestamp())?;
+
+        let factor = Self::usv_factor(&m.unit);
+        let usv = m.value * factor;
+        // Each bGeigie log import is one mobile survey drive
+        let track_id = m.measurement_import_id
+            .map(|id| Uuid::new_v5(&Uuid::NAMESPACE_DNS, format!("safecast_import_{}", id).as_bytes()));
+        let sensor_uuid = m.device_id
+            .map(|id| Uuid::new_v5(&Uuid::NAMESPACE_DNS, format!("safecast_{}", id).as_bytes()))
+            .or(track_id)
+            // Neither device nor drive: one station per spot, so re-deliveries keep their identity
+            .unwrap_or_else(|| {
+                let spot = format!("safecast_spot_{:.4}_{:.4}", m.latitude, m.longitude);
+                Uuid::new_v5(&Uuid::NAMESPACE_DNS, spot.as_bytes())
+            });
+
+        Some(RadiationReading {
+            sensor_id: sensor_uuid,
+            bucket: RadiationReading::bucket_for(timestamp),
+            timestamp,
+            latitude: m.latitude,
+            longitude: m.longitude,
+            dose_rate_microsieverts: usv,
+            uncertainty: if usv > 10.0 { 0.5 } else { 0.1 },
+            quality_flag: QualityFlag::Valid,
+            source: "safecast".to_string(),
+            cell_id: format!("{:04x}", (m.latitude as i32 + 90) * 180 + (m.longitude as i32 + 180)),
+            track_id,
+            altitude_m: m.height,
+            qc_reasons: Vec::new(),
+            provenance: normalizer::provenance(m.value, &m.unit, factor),
+        })
+    }
 }
 
 #[async_trait]
 impl DataSource for SafecastSource {
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
-        let mut request = self.client.get(SAFECAST_API_URL);
-        
-        if !self.api_key.is_empty() {
-            request = request.header("X-API-KEY", &self.api_key);
-        }
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| anyhow::anyhow!("Fetch failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            error!("Safecast API returned error status: {}", status);
-            return Err(anyhow::anyhow!("HTTP {}", status));
+        let since = self.cursor.last_timestamp
+            .unwrap_or_else(|| Utc::now().timestamp() - SAFECAST_INITIAL_LOOKBACK_SECS);
+        let since = chrono::DateTime::from_timestamp(since, 0)
+            .unwrap_or_else(Utc::now)
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+        let per_page = SAFECAST_PAGE_SIZE.to_string();
+
+        let mut cursor = self.cursor.clone();
+        let mut readings = Vec::new();
+
+        for page in 1..=SAFECAST_MAX_PAGES {
+            let page_param = page.to_string();
+            let mut request = self.client.get(rebase(SAFECAST_API_URL, self.base_url.as_deref())).query(&[
+                ("captured_after", since.as_str()),
+                ("order", "captured_at asc"),
+                ("per_page", per_page.as_str()),
+                ("page", page_param.as_str()),
+            ]);
+
+            if !self.api_key.is_empty() {
+                request = request.header("X-API-KEY", &self.api_key);
+            }
+
+            // Validators describe the first page, so only it can short-circuit
+            if page == 1 {
+                request = self.cursor.conditional(request);
+            }
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| anyhow::anyhow!("Fetch failed: {}", e))?;
+
+            if response.status() == StatusCode::NOT_MODIFIED {
+                debug!("Safecast returned 304 Not Modified");
+                break;
+            }
+
+            if !response.status().is_success() {
+                let status = response.status();
+                error!("Safecast API returned error status: {}", status);
+                return Err(anyhow::anyhow!("HTTP {}", status));
+            }
+
+            if page == 1 {
+                cursor.record_validators(response.headers());
+            }
+
+            let measurements: Vec<SafecastMeasurement> = response
+                .json()
+                .await
+                .map_err(|e| anyhow::anyhow!("Parse error: {}", e))?;
+
+            let page_len = measurements.len();
+
+            for m in &measurements {
+                let Some(reading) = Self::to_reading(m) else {
+                    continue;
+                };
+
+                // `captured_after` is coarse, drop anything already ingested
+                let id = m.id.to_string();
+                if !self.cursor.is_after(reading.timestamp, &id) {
+                    continue;
+                }
+
+                cursor.advance(reading.timestamp, &id);
+                readings.push(reading);
+            }
+
+            if page_len < SAFECAST_PAGE_SIZE {
+                break;
+            }
+
+            if page == SAFECAST_MAX_PAGES {
+                warn!("Safecast backlog exceeds {} pages, continuing on next poll", SAFECAST_MAX_PAGES);
+            }
         }
 
-        let measurements: Vec<SafecastMeasurement> = response
-            .json()
-            .await
-            .map_err(|e| anyhow::anyhow!("Parse error: {}", e))?;
-
-        let readings: Vec<RadiationReading> = measurements
-            .into_iter()
-            .filter_map(|m| {
-                let timestamp = chrono::DateTime::parse_from_rfc3339(&m.captured_at)
-                    .ok()
-                    .map(|dt| dt.with_timezone(&Utc).timestamp())?;
-
-                let usv = Self::convert_to_usv(m.value, &m.unit);
-                let sensor_uuid = m.device_id
-                    .map(|id| Uuid::new_v5(&Uuid::NAMESPACE_DNS, format!("safecast_{}", id).as_bytes()))
-                    .unwrap_or_else(|| Uuid::new_v4());
-
-                Some(RadiationReading {
-                    sensor_id: sensor_uuid,
-                    bucket: timestamp / 86400,
-                    timestamp,
-                    latitude: m.latitude,
-                    longitude: m.longitude,
-                    dose_rate_microsieverts: usv,
-                    uncertainty: if usv > 10.0 { 0.5 } else { 0.1 },
-                    quality_flag: if usv > 10.0 { QualityFlag::Suspect } else { QualityFlag::Valid },
-                    source: "safecast".to_string(),
-                    cell_id: format!("{:04x}", (m.latitude as i32 + 90) * 180 + (m.longitude as i32 + 180)),
-                })
-            })
-            .collect();
+        self.cursor = cursor;
 
         info!("Fetched {} readings from Safecast", readings.len());
         
         Ok(readings)
     }
 
+    fn cursor(&self) -> Option<SourceCursor> {
+        Some(self.cursor.clone())
+    }
+
+    fn restore_cursor(&mut self, cursor: SourceCursor) {
+        self.cursor = cursor;
+    }
+
     fn name(&self) -> String {
         "safecast".to_string()
     }
@@ -118,6 +212,10 @@ impl DataSource for SafecastSource {
     fn poll_interval(&self) -> Duration {
         Duration::from_secs(300)
     }
+
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
 }
 
 impl Default for SafecastSource {
@@ -1,12 +1,15 @@
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::Deserialize;
-use tracing::{info, error};
+use tracing::{info, error, debug};
 use std::time::Duration;
 use uuid::Uuid;
 
 use cherenkov_db::{RadiationReading, QualityFlag};
 
+use crate::cursor::SourceCursor;
+use crate::fixtures::rebase;
+use crate::normalizer;
 use crate::pipeline::DataSource;
 
 
@@ -15,7 +18,11 @@ const URADMONITOR_API_URL: &str = "https://data.uradmonitor.com/api/v1/devices";
 #[derive(Debug, Clone)]
 pub struct UradmonitorSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
     api_key: String,
+    /// Devices are reported independently, so only HTTP validators are tracked
+    cursor: SourceCursor,
 }
 
 #[derive(Debug, Deserialize)]
@@ -61,36 +68,44 @@ impl UradmonitorSource {
                 .timeout(Duration::from_secs(30))
                 .build()
                 .expect("Failed to create HTTP client"),
+            base_url: None,
             api_key: std::env::var("URADMONITOR_API_KEY").unwrap_or_default(),
+            cursor: SourceCursor::default(),
         }
     }
 
-    fn convert_to_usv(cpm: f64) -> f64 {
-        cpm * 0.00294
-    }
+    /// Multiplier from counts per minute to µSv/h
+    const USV_PER_CPM: f64 = 0.00294;
 }
 
 
 #[async_trait]
 impl DataSource for UradmonitorSource {
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
-        let mut request = self.client.get(URADMONITOR_API_URL);
+        let mut request = self.client.get(rebase(URADMONITOR_API_URL, self.base_url.as_deref()));
         
         if !self.api_key.is_empty() {
             request = request.header("X-API-KEY", &self.api_key);
         }
 
-        let response = request
+        let response = self.cursor.conditional(request)
             .send()
             .await
             .map_err(|e| anyhow::anyhow!("Fetch failed: {}", e))?;
 
+        if response.status() == StatusCode::NOT_MODIFIED {
+            debug!("Uradmonitor returned 304 Not Modified");
+            return Ok(Vec::new());
+        }
+
         if !response.status().is_success() {
             let status = response.status();
             error!("Uradmonitor API returned error status: {}", status);
             return Err(anyhow::anyhow!("HTTP {}", status));
         }
 
+        self.cursor.record_validators(response.headers());
+
         let devices: Vec<UradDevice> = response
             .json()
             .await
@@ -100,21 +115,25 @@ impl DataSource for UradmonitorSource {
             .into_iter()
             .filter_map(|d| {
                 let radiation_cpm = d.radiation?;
-                let usv = Self::convert_to_usv(radiation_cpm);
+                let usv = radiation_cpm * Self::USV_PER_CPM;
                 let timestamp = d.last_seen as i64;
                 let sensor_uuid = Uuid::new_v5(&Uuid::NAMESPACE_DNS, format!("urad_{}", d.id).as_bytes());
 
                 Some(RadiationReading {
                     sensor_id: sensor_uuid,
-                    bucket: timestamp / 86400,
+                    bucket: RadiationReading::bucket_for(timestamp),
                     timestamp,
                     latitude: d.latitude,
                     longitude: d.longitude,
                     dose_rate_microsieverts: usv,
                     uncertainty: if usv > 10.0 { 0.5 } else { 0.1 },
-                    quality_flag: if usv > 10.0 { QualityFlag::Suspect } else { QualityFlag::Valid },
+                    quality_flag: QualityFlag::Valid,
                     source: "uradmonitor".to_string(),
                     cell_id: format!("{:04x}", (d.latitude as i32 + 90) * 180 + (d.longitude as i32 + 180)),
+                    track_id: None,
+                    altitude_m: None,
+                    qc_reasons: Vec::new(),
+                    provenance: normalizer::provenance(radiation_cpm, "cpm", Self::USV_PER_CPM),
                 })
             })
             .collect();
@@ -131,6 +150,18 @@ impl DataSource for UradmonitorSource {
     fn poll_interval(&self) -> Duration {
         Duration::from_secs(300)
     }
+
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
+    fn cursor(&self) -> Option<SourceCursor> {
+        Some(self.cursor.clone())
+    }
+
+    fn restore_cursor(&mut self, cursor: SourceCursor) {
+        self.cursor = cursor;
+    }
 }
 
 
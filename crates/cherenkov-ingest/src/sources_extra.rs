@@ -4,6 +4,8 @@ use std::time::Duration;
 use tracing::{info, warn, instrument};
 use cherenkov_db::{RadiationReading, QualityFlag};
 use uuid::Uuid;
+use crate::fixtures::rebase;
+use crate::normalizer;
 use crate::pipeline::DataSource;
 
 /// NASA FIRMS (Fire Information for Resource Management System) source
@@ -12,6 +14,8 @@ use crate::pipeline::DataSource;
 /// Data is provided in CSV format via the FIRMS API.
 pub struct NasaFirmsSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
     api_key: String,
 }
 
@@ -19,6 +23,7 @@ impl NasaFirmsSource {
     pub fn new(api_key: String) -> Self {
         Self {
             client: Client::new(),
+            base_url: None,
             api_key,
         }
     }
@@ -99,6 +104,10 @@ impl NasaFirmsSource {
                 },
                 source: "nasa_firms".to_string(),
                 cell_id: format!("{:.2},{:.2}", latitude, longitude),
+                track_id: None,
+                altitude_m: None,
+                qc_reasons: Vec::new(),
+                provenance: None,
             });
             
             if brightness > 400.0 {
@@ -121,6 +130,10 @@ impl DataSource for NasaFirmsSource {
         Duration::from_secs(3600)
     }
 
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
     #[instrument(skip(self))]
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
         let url = format!(
@@ -128,6 +141,7 @@ impl DataSource for NasaFirmsSource {
             self.api_key
         );
         
+        let url = rebase(&url, self.base_url.as_deref());
         let response = self.client.get(&url).send().await;
 
         let csv_data = match response {
@@ -161,12 +175,15 @@ impl DataSource for NasaFirmsSource {
 /// IAEA PRIS (Power Reactor Information System) source
 pub struct IaeaPrisSource {
     client: Client,
+    /// Overrides the upstream host, e.g. for fixture replay
+    base_url: Option<String>,
 }
 
 impl IaeaPrisSource {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            base_url: None,
         }
     }
 
@@ -397,12 +414,19 @@ impl DataSource for IaeaPrisSource {
         Duration::from_secs(86400)
     }
 
+    fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Some(base_url.to_string());
+    }
+
     #[instrument(skip(self))]
     async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
-        let url = "https://pris.iaea.org/PRIS/WorldStatistics/NuclearPowerReactorsByCountry.aspx";
+        let url = rebase(
+            "https://pris.iaea.org/PRIS/WorldStatistics/NuclearPowerReactorsByCountry.aspx",
+            self.base_url.as_deref(),
+        );
         
         let response = self.client
-            .get(url)
+            .get(&url)
             .header("User-Agent", "Cherenkov/1.0 (Radiation Monitoring System)")
             .send()
             .await;
@@ -462,6 +486,10 @@ impl DataSource for IaeaPrisSource {
                 quality_flag: QualityFlag::Valid,
                 source: "iaea_pris".to_string(),
                 cell_id: format!("{:.2},{:.2}", latitude, longitude),
+                track_id: None,
+                altitude_m: None,
+                qc_reasons: Vec::new(),
+                provenance: normalizer::provenance(status_value, "pris_status", 1.0),
             });
         }
         
@@ -0,0 +1,279 @@
+//! Replays recorded upstream responses from `tests/fixtures` through every
+//! source parser and the full ingestion pipeline, without network access.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use cherenkov_core::{CherenkovEvent, EventBus};
+use cherenkov_db::{QualityFlag, RadiationReading};
+use cherenkov_ingest::fixtures::{FixtureConfig, FixtureMode, FixtureServer};
+use cherenkov_ingest::pipeline::{DataSource, IngestionPipeline, PipelineConfig, ReadingSink};
+use cherenkov_ingest::sources::{
+    EpaRadnetSource, NasaFirmsSource, NoaaGfsSource, OpenAqSource, OpenMeteoSource,
+    SafecastSource, UradmonitorSource,
+};
+use cherenkov_ingest::sources_extra;
+
+const FIRMS_TEST_KEY: &str = "test-map-key";
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+async fn replay_server(time_shift: bool) -> FixtureServer {
+    FixtureServer::start(FixtureConfig {
+        mode: FixtureMode::Replay,
+        dir: fixture_dir(),
+        time_shift,
+        secrets: vec![FIRMS_TEST_KEY.to_string()],
+        ..FixtureConfig::default()
+    })
+    .await
+    .expect("fixture server starts")
+}
+
+async fn replay<S: DataSource>(mut source: S) -> Vec<RadiationReading> {
+    let server = replay_server(false).await;
+    source.set_base_url(&server.base_url_for(&source.name()));
+    source.fetch().await.expect("fetch from fixtures")
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+}
+
+#[tokio::test]
+async fn test_safecast_fixture() {
+    let readings = replay(SafecastSource::new()).await;
+
+    // The measurement with an unparseable timestamp is dropped
+    assert_eq!(readings.len(), 3);
+    assert!(readings.iter().all(|r| r.source == "safecast"));
+    assert_close(readings[0].dose_rate_microsieverts, 35.0 * 0.00294);
+    assert_close(readings[1].dose_rate_microsieverts, 0.12);
+    assert_eq!(readings[0].timestamp, 1705313100);
+
+    let provenance = readings[0].provenance.as_ref().expect("safecast reports provenance");
+    assert_eq!(provenance.raw_value, 35.0);
+    assert_eq!(provenance.raw_unit, "cpm");
+    assert_close(provenance.conversion_factor, 0.00294);
+    assert_eq!(provenance.normalizer_version, cherenkov_ingest::normalizer::NORMALIZER_VERSION);
+
+    let mobile = &readings[2];
+    let track_id = Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"safecast_import_98765");
+    assert_eq!(mobile.track_id, Some(track_id));
+    assert_eq!(mobile.sensor_id, track_id);
+    assert_eq!(mobile.altitude_m, Some(112.5));
+}
+
+#[tokio::test]
+async fn test_safecast_cursor_advances_from_fixture() {
+    let server = replay_server(false).await;
+    let mut source = SafecastSource::new();
+    source.set_base_url(&server.base_url_for("safecast"));
+
+    assert_eq!(source.fetch().await.unwrap().len(), 3);
+    let cursor = source.cursor().expect("safecast tracks a cursor");
+    assert_eq!(cursor.last_timestamp, Some(1705315200));
+    assert_eq!(cursor.etag.as_deref(), Some("\"5f1c-safecast\""));
+
+    // Replaying the same page again yields nothing new
+    assert!(source.fetch().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_uradmonitor_fixture() {
+    let readings = replay(UradmonitorSource::new()).await;
+
+    // Devices without a radiation channel are skipped
+    assert_eq!(readings.len(), 2);
+    assert_eq!(
+        readings[0].sensor_id,
+        Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"urad_82000141")
+    );
+    assert_close(readings[0].dose_rate_microsieverts, 42.0 * 0.00294);
+    assert_eq!(readings[1].timestamp, 1705316100);
+}
+
+#[tokio::test]
+async fn test_epa_radnet_fixture() {
+    let readings = replay(EpaRadnetSource::new()).await;
+
+    // The offline station has no parseable gamma value
+    assert_eq!(readings.len(), 2);
+    assert_close(readings[0].dose_rate_microsieverts, 8.2 * 0.00877);
+    assert_close(readings[0].latitude, 47.6062);
+    // 05:00 Eastern is 10:00 UTC in January
+    assert_eq!(readings[0].timestamp, 1705312800);
+
+    // Locations without coordinates fall back to the city table
+    assert_close(readings[1].dose_rate_microsieverts, 12.5 * 0.00877);
+    assert_close(readings[1].latitude, 39.7392);
+}
+
+#[tokio::test]
+async fn test_openaq_fixture() {
+    let readings = replay(OpenAqSource::new()).await;
+
+    // Only the high-particulate location with coordinates becomes a proxy
+    assert_eq!(readings.len(), 1);
+    assert_close(readings[0].dose_rate_microsieverts, 0.24);
+    assert_eq!(readings[0].quality_flag, QualityFlag::Suspect);
+    assert_eq!(readings[0].timestamp, 1705312800);
+}
+
+#[tokio::test]
+async fn test_open_meteo_fixture() {
+    let server = replay_server(false).await;
+    let mut source = OpenMeteoSource::new();
+    source.set_base_url(&server.base_url_for(&source.name()));
+    let readings = source.fetch().await.expect("fetch from fixtures");
+
+    // Two windy hours per location, and every location replays the same recording
+    assert_eq!(readings.len(), 20);
+    assert_close(readings[0].dose_rate_microsieverts, 0.12);
+    assert_close(readings[1].dose_rate_microsieverts, 0.25);
+
+    // Precipitation for every hour, dated from the start of the hour it fell in
+    let weather = source.take_weather();
+    assert_eq!(weather.len(), 30);
+    assert_eq!(weather[1].timestamp, 1705305600);
+    assert_close(weather[1].precipitation_mm, 1.2);
+    assert_eq!(weather[1].source, "open_meteo");
+    assert!(source.take_weather().is_empty());
+}
+
+#[tokio::test]
+async fn test_nasa_firms_fixture() {
+    let readings = replay(NasaFirmsSource::new(FIRMS_TEST_KEY.to_string())).await;
+
+    assert_eq!(readings.len(), 3);
+    assert!(readings.iter().all(|r| r.quality_flag == QualityFlag::Suspect));
+    // High radiative power uses the steeper conversion
+    assert_close(readings[2].dose_rate_microsieverts, 0.125);
+    assert_eq!(readings[2].timestamp, 1705283280);
+}
+
+#[tokio::test]
+async fn test_nasa_firms_extra_fixture() {
+    let readings = replay(sources_extra::NasaFirmsSource::new(FIRMS_TEST_KEY.to_string())).await;
+
+    // Low-confidence detections are dropped, high-confidence ones are valid
+    assert_eq!(readings.len(), 2);
+    assert_eq!(readings[0].quality_flag, QualityFlag::Valid);
+    assert_close(readings[1].dose_rate_microsieverts, 412.9 - 273.15);
+}
+
+#[tokio::test]
+async fn test_noaa_gfs_fixture() {
+    let readings = replay(NoaaGfsSource::new()).await;
+
+    // The temperature grid carries no wind components, so no transport proxies
+    assert!(readings.is_empty());
+}
+
+#[tokio::test]
+async fn test_iaea_pris_fixture() {
+    let readings = replay(sources_extra::IaeaPrisSource::new()).await;
+
+    // Header row and facilities without known coordinates are skipped
+    assert_eq!(readings.len(), 2);
+    assert_close(readings[0].dose_rate_microsieverts, 0.0);
+    assert_close(readings[1].dose_rate_microsieverts, 100.0);
+    assert_close(readings[1].latitude, 46.5733);
+
+    let provenance = readings[1].provenance.as_ref().expect("iaea pris reports provenance");
+    assert_eq!((provenance.raw_value, provenance.raw_unit.as_str()), (100.0, "pris_status"));
+}
+
+#[tokio::test]
+async fn test_time_shifted_replay_looks_current() {
+    let server = replay_server(true).await;
+    let mut source = SafecastSource::new();
+    source.set_base_url(&server.base_url_for("safecast"));
+
+    let readings = source.fetch().await.unwrap();
+    assert_eq!(readings.len(), 3);
+
+    // The fixture was recorded 20 minutes after its newest measurement
+    let newest = readings.iter().map(|r| r.timestamp).max().unwrap();
+    let age = Utc::now().timestamp() - newest;
+    assert!((1190..=1260).contains(&age), "newest reading is {}s old", age);
+}
+
+/// In-memory stand-in for the database
+#[derive(Default)]
+struct MemorySink {
+    readings: Mutex<Vec<RadiationReading>>,
+}
+
+#[async_trait::async_trait]
+impl ReadingSink for MemorySink {
+    async fn write_reading(&self, reading: &RadiationReading) -> anyhow::Result<()> {
+        self.readings.lock().unwrap().push(reading.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_replays_fixtures() {
+    let server = replay_server(false).await;
+    let state_dir = std::env::temp_dir().join(format!("cherenkov-fixture-replay-{}", Uuid::new_v4()));
+
+    let config = PipelineConfig {
+        batch_size: 10,
+        batch_timeout_ms: 50,
+        dedup_state_path: None,
+        dlq_path: None,
+        dedup_redis_url: None,
+        cursor_dir: state_dir.join("cursors").to_string_lossy().into_owned(),
+        ..PipelineConfig::default()
+    };
+
+    let sink = Arc::new(MemorySink::default());
+    let event_bus = Arc::new(EventBus::new(100));
+    let mut events = event_bus.subscribe();
+    let pipeline = Arc::new(IngestionPipeline::new(config, sink.clone(), event_bus.clone()));
+
+    let mut sources: Vec<Box<dyn DataSource + Send>> = vec![
+        Box::new(SafecastSource::new()),
+        Box::new(UradmonitorSource::new()),
+    ];
+    server.route(&mut sources);
+
+    let handle = tokio::spawn(pipeline.clone().run(sources, CancellationToken::new()));
+
+    let written = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let count = sink.readings.lock().unwrap().len();
+            if count >= 5 {
+                return count;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("pipeline writes every fixture reading");
+    handle.abort();
+
+    assert_eq!(written, 5);
+    let readings = sink.readings.lock().unwrap().clone();
+    assert_eq!(readings.iter().filter(|r| r.source == "safecast").count(), 3);
+    assert_eq!(readings.iter().filter(|r| r.source == "uradmonitor").count(), 2);
+    assert!(readings.iter().all(|r| r.quality_flag == QualityFlag::Valid));
+
+    let mut published = 0;
+    while let Ok(event) = events.try_recv() {
+        if matches!(event, CherenkovEvent::NewReading(_)) {
+            published += 1;
+        }
+    }
+    assert_eq!(published, 5);
+
+    let _ = std::fs::remove_dir_all(state_dir);
+}
@@ -0,0 +1,253 @@
+//! Graceful shutdown: every reading a source hands to the pipeline must end
+//! up written or in the persisted dead letter queue.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use cherenkov_core::EventBus;
+use cherenkov_db::{QualityFlag, RadiationReading};
+use cherenkov_ingest::cursor::{CursorStore, SourceCursor};
+use cherenkov_ingest::pipeline::{DataSource, DeadLetterQueue, IngestionPipeline, PipelineConfig, ReadingSink};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+const BATCH: usize = 25;
+
+/// Source producing a fresh batch of distinct readings on every fetch
+struct CountingSource {
+    sensor_id: Uuid,
+    next_timestamp: Arc<AtomicI64>,
+    produced: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl DataSource for CountingSource {
+    async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
+        let start = self.next_timestamp.fetch_add(BATCH as i64 * 60, Ordering::SeqCst);
+        let readings: Vec<_> = (0..BATCH as i64)
+            .map(|i| reading(self.sensor_id, start + i * 60))
+            .collect();
+        self.produced.fetch_add(readings.len(), Ordering::SeqCst);
+        Ok(readings)
+    }
+
+    fn name(&self) -> String {
+        format!("counting-{}", self.sensor_id)
+    }
+
+    fn poll_interval(&self) -> Duration {
+        Duration::from_millis(20)
+    }
+}
+
+fn reading(sensor_id: Uuid, timestamp: i64) -> RadiationReading {
+    RadiationReading {
+        sensor_id,
+        bucket: timestamp / 3600,
+        timestamp,
+        latitude: 50.0,
+        longitude: 8.0,
+        dose_rate_microsieverts: 0.1,
+        uncertainty: 0.01,
+        quality_flag: QualityFlag::Valid,
+        source: "counting".to_string(),
+        cell_id: String::new(),
+        track_id: None,
+        altitude_m: None,
+        qc_reasons: Vec::new(),
+        provenance: None,
+    }
+}
+
+/// Sink that is slow to write, so readings are still queued when shutdown starts
+#[derive(Default)]
+struct SlowSink {
+    written: Mutex<Vec<RadiationReading>>,
+}
+
+#[async_trait::async_trait]
+impl ReadingSink for SlowSink {
+    async fn write_reading(&self, reading: &RadiationReading) -> anyhow::Result<()> {
+        tokio::time::sleep(Duration::from_micros(200)).await;
+        self.written.lock().unwrap().push(reading.clone());
+        Ok(())
+    }
+}
+
+/// Sink that holds every batch until the test lets it through
+struct GatedSink {
+    gate: Semaphore,
+}
+
+#[async_trait::async_trait]
+impl ReadingSink for GatedSink {
+    async fn write_reading(&self, _reading: &RadiationReading) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn write_batch(&self, _readings: &[RadiationReading]) -> anyhow::Result<()> {
+        self.gate.acquire().await?.forget();
+        Ok(())
+    }
+}
+
+/// Source that fetches one batch and tracks how far it got
+struct CursorSource {
+    sensor_id: Uuid,
+    cursor: SourceCursor,
+}
+
+#[async_trait::async_trait]
+impl DataSource for CursorSource {
+    async fn fetch(&mut self) -> anyhow::Result<Vec<RadiationReading>> {
+        if self.cursor.last_timestamp.is_some() {
+            return Ok(Vec::new());
+        }
+        let start = chrono::Utc::now().timestamp() - 3600;
+        let readings: Vec<_> = (0..BATCH as i64).map(|i| reading(self.sensor_id, start + i * 60)).collect();
+        self.cursor.last_timestamp = readings.last().map(|r| r.timestamp);
+        Ok(readings)
+    }
+
+    fn name(&self) -> String {
+        "cursor".to_string()
+    }
+
+    fn poll_interval(&self) -> Duration {
+        Duration::from_millis(20)
+    }
+
+    fn cursor(&self) -> Option<SourceCursor> {
+        Some(self.cursor.clone())
+    }
+}
+
+struct FailingSink;
+
+#[async_trait::async_trait]
+impl ReadingSink for FailingSink {
+    async fn write_reading(&self, _reading: &RadiationReading) -> anyhow::Result<()> {
+        anyhow::bail!("database unavailable")
+    }
+}
+
+fn state_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("cherenkov-shutdown-{}", Uuid::new_v4()))
+}
+
+fn config(dir: &std::path::Path) -> PipelineConfig {
+    PipelineConfig {
+        // Large batches with a long timeout keep readings buffered until shutdown
+        batch_size: 10_000,
+        batch_timeout_ms: 60_000,
+        channel_buffer_size: 64,
+        circuit_breaker_threshold: 1,
+        dedup_state_path: Some(dir.join("dedup.bin").to_string_lossy().into_owned()),
+        dedup_redis_url: None,
+        dlq_path: Some(dir.join("dlq.bin").to_string_lossy().into_owned()),
+        cursor_dir: dir.join("cursors").to_string_lossy().into_owned(),
+        ..PipelineConfig::default()
+    }
+}
+
+/// Run the pipeline over a few sources, cancel it mid-flight and wait for it to drain
+async fn run_and_shut_down(config: PipelineConfig, sink: Arc<dyn ReadingSink>) -> usize {
+    let produced = Arc::new(AtomicUsize::new(0));
+    let next_timestamp = Arc::new(AtomicI64::new(chrono::Utc::now().timestamp() - 86_400));
+    let sources: Vec<Box<dyn DataSource + Send>> = (0..3)
+        .map(|_| {
+            Box::new(CountingSource {
+                sensor_id: Uuid::new_v4(),
+                next_timestamp: next_timestamp.clone(),
+                produced: produced.clone(),
+            }) as Box<dyn DataSource + Send>
+        })
+        .collect();
+
+    let pipeline = Arc::new(IngestionPipeline::new(config, sink, Arc::new(EventBus::new(100_000))));
+    let shutdown = CancellationToken::new();
+    let handle = tokio::spawn(pipeline.run(sources, shutdown.clone()));
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    shutdown.cancel();
+
+    tokio::time::timeout(Duration::from_secs(30), handle)
+        .await
+        .expect("pipeline drains before the deadline")
+        .unwrap()
+        .unwrap();
+
+    produced.load(Ordering::SeqCst)
+}
+
+#[tokio::test]
+async fn test_shutdown_writes_every_accepted_reading() {
+    let dir = state_dir();
+    let sink = Arc::new(SlowSink::default());
+
+    let produced = run_and_shut_down(config(&dir), sink.clone()).await;
+    assert!(produced > 0);
+
+    let written = sink.written.lock().unwrap();
+    assert_eq!(written.len(), produced, "every fetched reading is flushed on shutdown");
+    assert!(dir.join("dedup.bin").exists(), "dedup state is persisted");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_shutdown_persists_dead_letters() {
+    let dir = state_dir();
+    let config = config(&dir);
+    let dlq_path = config.dlq_path.clone().unwrap();
+
+    let produced = run_and_shut_down(config, Arc::new(FailingSink)).await;
+    assert!(produced > 0);
+
+    let restored = DeadLetterQueue::new(100_000);
+    assert_eq!(restored.load(&dlq_path).await.unwrap(), produced);
+    assert!(restored
+        .get_entries()
+        .await
+        .iter()
+        .all(|entry| entry.reading.source == "counting"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_cursor_is_persisted_once_its_readings_are_written() {
+    let dir = state_dir();
+    let config = PipelineConfig {
+        batch_timeout_ms: 20,
+        ..config(&dir)
+    };
+    let cursors = CursorStore::new(&config.cursor_dir);
+    let sink = Arc::new(GatedSink { gate: Semaphore::new(0) });
+    let source = CursorSource {
+        sensor_id: Uuid::new_v4(),
+        cursor: SourceCursor::default(),
+    };
+
+    let pipeline = Arc::new(IngestionPipeline::new(config, sink.clone(), Arc::new(EventBus::new(1000))));
+    let shutdown = CancellationToken::new();
+    let handle = tokio::spawn(pipeline.run(vec![Box::new(source)], shutdown.clone()));
+
+    // Fetched and handed over, but not yet stored
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(cursors.load("cursor").await.is_none());
+
+    sink.gate.add_permits(1);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let saved = cursors.load("cursor").await.expect("cursor saved after the write");
+    assert!(saved.last_timestamp.is_some());
+
+    shutdown.cancel();
+    sink.gate.add_permits(100);
+    handle.await.unwrap().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -357,6 +357,7 @@ impl MlAnomalyIntegration {
             sensor_id: sensor_id.clone(),
             dose_rate: radiation_level,
             timestamp: chrono::Utc::now(),
+            rain: None,
         }];
         
         let anomaly = {
@@ -462,6 +463,7 @@ impl MlAnomalyIntegration {
                         sensor_id: id.clone(),
                         dose_rate: *level,
                         timestamp: chrono::Utc::now(),
+                        rain: None,
                     }];
                     detector.detect(r).is_some()
                 })
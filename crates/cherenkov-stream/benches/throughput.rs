@@ -0,0 +1,189 @@
+//! Stream processor throughput and detection latency under synthetic load.
+//!
+//! The load is 1000 fixed sensors reporting a minute apart in reading time,
+//! 100k readings in all, offered as one second's worth at 100k readings/s.
+//! `throughput` pushes it through as fast as the processor takes it;
+//! `latency` paces it at 100k readings/s and measures the time from a spike
+//! being sent to its anomaly being published.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, SamplingMode, Throughput};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use cherenkov_db::RadiationReading;
+use cherenkov_stream::{Anomaly, StreamProcessor, WatermarkConfig};
+
+const SENSORS: u64 = 1000;
+const READINGS_PER_SENSOR: i64 = 100;
+/// Offered load of the latency benchmark
+const READINGS_PER_SEC: u64 = 100_000;
+/// Readings sent per pacing tick
+const BATCH: u64 = 1000;
+const SHARDS: [usize; 4] = [1, 2, 4, 8];
+
+fn reading(sensor: u64, timestamp: i64, dose_rate: f64) -> RadiationReading {
+    RadiationReading {
+        // A grid of sites, about a kilometre apart
+        latitude: 37.0 + (sensor / 40) as f64 * 0.01,
+        longitude: 140.5 + (sensor % 40) as f64 * 0.01,
+        ..common::reading(u128::from(sensor) + 1, timestamp, dose_rate)
+    }
+}
+
+/// Readings in reading time order, each sensor spiking once after it has
+/// seen enough readings to be scored
+fn load(start: i64) -> Vec<RadiationReading> {
+    let mut readings = Vec::with_capacity((SENSORS as i64 * READINGS_PER_SENSOR) as usize);
+    for i in 0..READINGS_PER_SENSOR {
+        for sensor in 0..SENSORS {
+            let noise = 0.005 * ((i as f64) * 0.7 + sensor as f64).sin();
+            let spike = if is_spike(sensor, i) { 0.5 } else { 0.0 };
+            readings.push(reading(sensor, start + i * 60, 0.1 + noise + spike));
+        }
+    }
+    readings
+}
+
+fn is_spike(sensor: u64, i: i64) -> bool {
+    i == 50 + (sensor % 40) as i64
+}
+
+async fn processor(shards: usize) -> (StreamProcessor, broadcast::Receiver<Anomaly>) {
+    let db = common::database().await;
+    let (anomaly_tx, anomalies) = broadcast::channel(READINGS_PER_SEC as usize);
+    // Released as soon as they arrive, so latency is the processing alone
+    let watermarks = WatermarkConfig {
+        out_of_orderness_secs: 0,
+        ..WatermarkConfig::default()
+    };
+    let processor = StreamProcessor::new(db, anomaly_tx)
+        .with_shards(shards)
+        .with_watermarks(watermarks);
+    (processor, anomalies)
+}
+
+/// Time to take the whole load and drain it
+async fn drain(shards: usize, readings: Vec<RadiationReading>) -> Duration {
+    let (processor, _anomalies) = processor(shards).await;
+    let ingest_tx = processor.get_ingest_tx();
+    let shutdown = CancellationToken::new();
+    let running = tokio::spawn(processor.run(shutdown.clone()));
+
+    let started = Instant::now();
+    for reading in readings {
+        ingest_tx.send(reading).await.unwrap();
+    }
+    shutdown.cancel();
+    running.await.unwrap().unwrap();
+    started.elapsed()
+}
+
+/// Mean time from sending a spike to receiving its anomaly, at the paced load
+async fn spike_latency(shards: usize, readings: Vec<RadiationReading>) -> Duration {
+    let (processor, mut anomalies) = processor(shards).await;
+    let ingest_tx = processor.get_ingest_tx();
+    let shutdown = CancellationToken::new();
+    let running = tokio::spawn(processor.run(shutdown.clone()));
+
+    let received = tokio::spawn(async move {
+        let mut received = HashMap::new();
+        loop {
+            match anomalies.recv().await {
+                Ok(anomaly) => {
+                    received.entry((anomaly.sensor_id.clone(), anomaly.timestamp.timestamp())).or_insert_with(Instant::now);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return received,
+            }
+        }
+    });
+
+    let start = readings[0].timestamp;
+    let mut sent = HashMap::new();
+    let mut pacing = tokio::time::interval(Duration::from_micros(1_000_000 * BATCH / READINGS_PER_SEC));
+    for batch in readings.chunks(BATCH as usize) {
+        pacing.tick().await;
+        for reading in batch {
+            let sensor = (reading.sensor_id.as_u128() - 1) as u64;
+            if is_spike(sensor, (reading.timestamp - start) / 60) {
+                sent.insert((reading.sensor_id.to_string(), reading.timestamp), Instant::now());
+            }
+            ingest_tx.send(reading.clone()).await.unwrap();
+        }
+    }
+    shutdown.cancel();
+    running.await.unwrap().unwrap();
+    let received = received.await.unwrap();
+
+    let latencies: Vec<Duration> = sent
+        .iter()
+        .filter_map(|(key, at)| Some(received.get(key)?.saturating_duration_since(*at)))
+        .collect();
+    assert!(!latencies.is_empty(), "no spike was detected");
+    latencies.iter().sum::<Duration>() / latencies.len() as u32
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap()
+}
+
+fn throughput(c: &mut Criterion) {
+    let runtime = runtime();
+    let readings = load(Utc::now().timestamp() - 86_400);
+
+    let mut group = c.benchmark_group("throughput");
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+    group.throughput(Throughput::Elements(readings.len() as u64));
+    for shards in SHARDS {
+        group.bench_with_input(BenchmarkId::from_parameter(shards), &shards, |b, &shards| {
+            b.to_async(&runtime).iter_custom(|iters| {
+                let readings = readings.clone();
+                async move {
+                    let mut total = Duration::ZERO;
+                    for _ in 0..iters {
+                        total += drain(shards, readings.clone()).await;
+                    }
+                    total
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn latency(c: &mut Criterion) {
+    let runtime = runtime();
+    let readings = load(Utc::now().timestamp() - 86_400);
+
+    let mut group = c.benchmark_group("latency");
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(15));
+    for shards in SHARDS {
+        group.bench_with_input(BenchmarkId::from_parameter(shards), &shards, |b, &shards| {
+            b.to_async(&runtime).iter_custom(|iters| {
+                let readings = readings.clone();
+                async move {
+                    let mut total = Duration::ZERO;
+                    for _ in 0..iters {
+                        total += spike_latency(shards, readings.clone()).await;
+                    }
+                    total
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, throughput, latency);
+criterion_main!(benches);
@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap};
 use rand::seq::SliceRandom;
 use rand::Rng;
 
+use crate::baseline::SeasonalBaseline;
+use crate::detector::{EnsembleConfig, Explanation, RunningStats, SensorEnsemble};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Anomaly {
     pub sensor_id: String,
@@ -12,41 +15,93 @@ pub struct Anomaly {
     pub dose_rate: f64,
     pub baseline: f64,
     pub algorithm: Algorithm,
+    /// Combined detector score, 1.0 at a detector's alarm threshold
+    #[serde(default)]
+    pub score: f64,
+    /// Detectors that scored the reading, highest score first
+    #[serde(default)]
+    pub explanations: Vec<Explanation>,
+    /// Dose rate expected for the time of day, season and recent rain
+    #[serde(default)]
+    pub expected: f64,
+    /// `dose_rate` less `expected`, which the detectors score
+    #[serde(default)]
+    pub residual: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Severity {
     Critical,
     Warning,
     Info,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Algorithm {
     Welford,
     IsolationForest,
+    Ewma,
+    Cusum,
+    Iqr,
+    Grubbs,
+    /// Scores of several detectors combined
+    Ensemble,
 }
 
-#[allow(dead_code)]
+impl Algorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Algorithm::Welford => "Welford",
+            Algorithm::IsolationForest => "IsolationForest",
+            Algorithm::Ewma => "EWMA",
+            Algorithm::Cusum => "CUSUM",
+            Algorithm::Iqr => "IQR",
+            Algorithm::Grubbs => "Grubbs",
+            Algorithm::Ensemble => "Ensemble",
+        }
+    }
+}
+
+/// Runs each sensor's detector ensemble, configured per sensor class
 pub struct AnomalyDetector {
-    windows: std::collections::HashMap<String, SensorWindow>,
-    isolation_forest: IsolationForest,
+    config: EnsembleConfig,
+    sensors: HashMap<String, SensorState>,
 }
 
-#[allow(dead_code)]
+/// Per-sensor detector state of an `AnomalyDetector`, for checkpointing
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DetectorState {
+    #[serde(default)]
+    pub sensors: BTreeMap<String, SensorState>,
+}
+
+/// Expected dose rate, spread of the residuals from it, and the detectors
+/// that score them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorState {
+    class: String,
+    #[serde(default)]
+    model: SeasonalBaseline,
+    residuals: RunningStats,
+    ensemble: SensorEnsemble,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IsolationForest {
     trees: Vec<IsolationTree>,
     num_trees: usize,
     subsample_size: usize,
 }
 
-#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct IsolationTree {
     root: Option<Box<Node>>,
     height_limit: usize,
+    /// Points the tree was grown from
+    sample_size: usize,
 }
 
-#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Node {
     feature: usize,
     split_value: f64,
@@ -55,47 +110,64 @@ struct Node {
     size: usize,
 }
 
+/// Residuals the z-score reported with an anomaly is taken over
+const RESIDUAL_WINDOW: usize = 1000;
+
 #[allow(dead_code)]
 impl IsolationForest {
     pub fn new(num_trees: usize, subsample_size: usize) -> Self {
-        let height_limit = (subsample_size as f64).log2().ceil() as usize;
         Self {
-            trees: (0..num_trees).map(|_| IsolationTree::new(height_limit)).collect(),
+            trees: Vec::with_capacity(num_trees),
             num_trees,
             subsample_size,
         }
     }
     
     pub fn fit(&mut self, data: &[Vec<f64>]) {
-        let mut rng = rand::thread_rng();
-        for tree in &mut self.trees {
-            let sample: Vec<Vec<f64>> = data.choose_multiple(&mut rng, self.subsample_size).cloned().collect();
-            tree.root = Some(Box::new(build_tree(&sample, 0, self.subsample_size, &mut rng)));
-        }
+        self.fit_with_rng(data, &mut rand::thread_rng());
+    }
+
+    /// Grow the trees from random subsamples of `data` drawn with `rng`
+    pub fn fit_with_rng<R: Rng>(&mut self, data: &[Vec<f64>], rng: &mut R) {
+        let sample_size = self.subsample_size.min(data.len());
+        let height_limit = (sample_size.max(2) as f64).log2().ceil() as usize;
+        self.trees = (0..self.num_trees)
+            .map(|_| {
+                let sample: Vec<Vec<f64>> = data.choose_multiple(rng, sample_size).cloned().collect();
+                IsolationTree {
+                    root: Some(Box::new(build_tree(&sample, 0, height_limit, rng))),
+                    height_limit,
+                    sample_size,
+                }
+            })
+            .collect();
+    }
+
+    pub fn is_fitted(&self) -> bool {
+        !self.trees.is_empty()
     }
     
+    /// Score in (0, 1]: around 0.5 for ordinary points, approaching 1 for
+    /// points isolated in few splits. 0.5 before the forest is fitted.
     pub fn anomaly_score(&self, point: &[f64]) -> f64 {
+        if self.trees.is_empty() {
+            return 0.5;
+        }
+
         let path_lengths: Vec<f64> = self.trees.iter()
-            .map(|tree| path_length(point, tree.root.as_ref(), 0))
+            .map(|tree| path_length(point, tree.root.as_deref(), 0))
             .collect();
         
         let avg_path_length = path_lengths.iter().sum::<f64>() / path_lengths.len() as f64;
-        let expected_length = c(self.subsample_size);
+        let expected_length = c(self.trees[0].sample_size);
+        if expected_length == 0.0 {
+            return 0.5;
+        }
         
         2.0_f64.powf(-avg_path_length / expected_length)
     }
 }
 
-impl IsolationTree {
-    fn new(height_limit: usize) -> Self {
-        Self {
-            root: None,
-            height_limit,
-        }
-    }
-}
-
-#[allow(dead_code)]
 fn build_tree<R: Rng>(data: &[Vec<f64>], current_height: usize, height_limit: usize, rng: &mut R) -> Node {
     if data.len() <= 1 || current_height >= height_limit {
         return Node {
@@ -128,116 +200,124 @@ fn build_tree<R: Rng>(data: &[Vec<f64>], current_height: usize, height_limit: us
     }
 }
 
-#[allow(dead_code)]
-fn path_length(point: &[f64], node: Option<&Box<Node>>, current_depth: usize) -> f64 {
+/// Depth at which `point` is isolated, plus the expected depth still to go
+/// in a leaf that holds more than one point
+fn path_length(point: &[f64], node: Option<&Node>, current_depth: usize) -> f64 {
     match node {
-        None => 0.0,
-        Some(n) if n.left.is_none() && n.right.is_none() => c(n.size),
+        None => current_depth as f64,
+        Some(n) if n.left.is_none() && n.right.is_none() => current_depth as f64 + c(n.size),
         Some(n) => {
             if point[n.feature] < n.split_value {
-                path_length(point, n.left.as_ref(), current_depth + 1)
+                path_length(point, n.left.as_deref(), current_depth + 1)
             } else {
-                path_length(point, n.right.as_ref(), current_depth + 1)
+                path_length(point, n.right.as_deref(), current_depth + 1)
             }
         }
     }
 }
 
-#[allow(dead_code)]
+/// Average path length of an unsuccessful search in a binary tree of `n` points
 fn c(n: usize) -> f64 {
     if n <= 1 {
         return 0.0;
     }
-    2.0 * (n as f64 - 1.0).ln() + 0.5772156649 - 2.0 * (n as f64 - 1.0) / n as f64
+    2.0 * ((n as f64 - 1.0).ln() + 0.5772156649) - 2.0 * (n as f64 - 1.0) / n as f64
 }
 
-#[allow(dead_code)]
-struct SensorWindow {
-    readings: VecDeque<f64>,
-    capacity: usize,
-    mean: f64,
-    variance: f64,
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[allow(dead_code)]
 impl AnomalyDetector {
     pub fn new() -> Self {
+        Self::with_config(EnsembleConfig::default())
+    }
+
+    pub fn with_config(config: EnsembleConfig) -> Self {
         Self {
-            windows: std::collections::HashMap::new(),
-            isolation_forest: IsolationForest::new(100, 256),
+            config,
+            sensors: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &EnsembleConfig {
+        &self.config
+    }
+
+    /// Snapshot the per-sensor detector state
+    pub fn state(&self) -> DetectorState {
+        DetectorState {
+            sensors: self.sensors.iter().map(|(id, state)| (id.clone(), state.clone())).collect(),
         }
     }
+
+    /// Resume from state taken with `state`
+    pub fn restore(&mut self, state: DetectorState) {
+        self.sensors.extend(state.sensors);
+    }
     
+    /// Run readings through the detectors of the default class, see `detect_for`
     pub fn detect(&mut self, window: Vec<Reading>) -> Option<Anomaly> {
-        if window.is_empty() {
-            return None;
+        self.detect_for("", window)
+    }
+
+    /// Score each reading against its sensor's detectors and learn from it,
+    /// returning the anomaly found for the last one
+    ///
+    /// `class` picks the detectors, see [`EnsembleConfig`], the first time a
+    /// sensor is seen.
+    pub fn detect_for(&mut self, class: &str, window: Vec<Reading>) -> Option<Anomaly> {
+        let mut anomaly = None;
+        for reading in window {
+            anomaly = self.observe(class, reading);
         }
-        
-        let sensor_id = window[0].sensor_id.clone();
-        let current = window.last().unwrap().dose_rate;
-        
-        let sensor_window = self.windows.entry(sensor_id.clone()).or_insert_with(|| {
-            SensorWindow {
-                readings: VecDeque::with_capacity(1000),
-                capacity: 1000,
-                mean: 0.0,
-                variance: 0.0,
-            }
+        anomaly
+    }
+
+    fn observe(&mut self, class: &str, reading: Reading) -> Option<Anomaly> {
+        let config = &self.config;
+        let sensor = self.sensors.entry(reading.sensor_id.clone()).or_insert_with(|| SensorState {
+            class: class.to_string(),
+            model: SeasonalBaseline::new(),
+            residuals: RunningStats::new(RESIDUAL_WINDOW),
+            ensemble: SensorEnsemble::new(config.class(class)),
         });
-        
-        for reading in &window {
-            sensor_window.update(reading.dose_rate);
-        }
-        
-        let std_dev = sensor_window.variance.sqrt();
-        if std_dev == 0.0 {
-            return None;
-        }
-        
-        let z_score = (current - sensor_window.mean) / std_dev;
-        
-        let severity = if z_score > 3.0 {
-            Severity::Critical
-        } else if z_score > 2.0 {
-            Severity::Warning
-        } else {
-            return None;
-        };
-        
+        let class = config.class(&sensor.class);
+
+        // Detectors see what the baseline does not explain, so a daily cycle
+        // or washout after rain is not taken for an anomaly
+        let timestamp = reading.timestamp.timestamp();
+        let expectation = sensor.model.expect(timestamp, reading.rain, &class.baseline);
+        let expected = expectation.map_or(reading.dose_rate, |e| e.expected());
+        let residual = reading.dose_rate - expected;
+
+        let verdict = sensor.ensemble.observe(residual, class);
+        let z_score = sensor.residuals.z_score(residual).unwrap_or(0.0);
+        sensor.residuals.push(residual);
+        sensor.model.update(timestamp, reading.dose_rate, reading.rain, &class.baseline);
+        let verdict = verdict?;
+
         Some(Anomaly {
-            sensor_id,
-            severity,
+            sensor_id: reading.sensor_id,
+            severity: verdict.severity,
             z_score,
-            timestamp: chrono::Utc::now(),
-            dose_rate: current,
-            baseline: sensor_window.mean,
-            algorithm: Algorithm::Welford,
+            timestamp: reading.timestamp,
+            dose_rate: reading.dose_rate,
+            baseline: expectation.map_or(reading.dose_rate, |e| e.expected() - e.precipitation),
+            algorithm: verdict.algorithm,
+            score: verdict.score,
+            explanations: verdict.explanations,
+            expected,
+            residual,
         })
     }
-    
-    #[allow(dead_code)]
-    pub fn detect_isolation_forest(&self, point: &[f64]) -> Option<f64> {
-        let score = self.isolation_forest.anomaly_score(point);
-        if score > 0.6 {
-            Some(score)
-        } else {
-            None
-        }
-    }
-}
 
-#[allow(dead_code)]
-impl SensorWindow {
-    fn update(&mut self, value: f64) {
-        if self.readings.len() >= self.capacity {
-            self.readings.pop_front();
-        }
-        self.readings.push_back(value);
-        
-        let n = self.readings.len() as f64;
-        let old_mean = self.mean;
-        self.mean = old_mean + (value - old_mean) / n;
-        self.variance = ((n - 1.0) * self.variance + (value - old_mean) * (value - self.mean)) / n;
+    /// Readings seen for `sensor_id`
+    pub fn samples(&self, sensor_id: &str) -> u64 {
+        self.sensors.get(sensor_id).map_or(0, |s| s.ensemble.samples())
     }
 }
 
@@ -247,4 +327,7 @@ pub struct Reading {
     pub sensor_id: String,
     pub dose_rate: f64,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Recent rain at the sensor in mm, decayed by how long ago it fell;
+    /// `None` without weather data
+    pub rain: Option<f64>,
 }
@@ -0,0 +1,167 @@
+//! Expected dose rate of a sensor: a slowly moving level, offsets by hour of
+//! day and month of year, and the rise while radon progeny washed out by
+//! rain decay on the ground.
+
+use chrono::{DateTime, Datelike, Timelike};
+use serde::{Deserialize, Serialize};
+
+/// Which components a baseline models and how fast they learn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BaselineConfig {
+    /// Offset per hour of the day (UTC)
+    pub diurnal: bool,
+    /// Offset per month of the year
+    pub seasonal: bool,
+    /// Increase in proportion to recent rain
+    pub precipitation: bool,
+    /// Weight of a reading in the level, once past the first `1 / level_alpha` readings
+    pub level_alpha: f64,
+    /// Weight of a reading in its hour's offset
+    pub diurnal_alpha: f64,
+    /// Weight of a reading in its month's offset
+    pub seasonal_alpha: f64,
+    /// Retained share of the rain response fit per wet reading
+    pub precipitation_memory: f64,
+    /// Rain index, in mm, from which a reading counts as wet
+    pub wet_threshold_mm: f64,
+}
+
+impl Default for BaselineConfig {
+    fn default() -> Self {
+        Self {
+            diurnal: true,
+            seasonal: true,
+            precipitation: true,
+            level_alpha: 0.001,
+            diurnal_alpha: 0.02,
+            seasonal_alpha: 0.002,
+            precipitation_memory: 0.995,
+            wet_threshold_mm: 0.1,
+        }
+    }
+}
+
+impl BaselineConfig {
+    /// A level only, for readings without a regular rhythm
+    pub fn level_only() -> Self {
+        Self {
+            diurnal: false,
+            seasonal: false,
+            precipitation: false,
+            ..Self::default()
+        }
+    }
+}
+
+/// Expected dose rate for a reading, by component
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Expectation {
+    pub level: f64,
+    pub diurnal: f64,
+    pub seasonal: f64,
+    /// Increase expected from recent rain
+    pub precipitation: f64,
+}
+
+impl Expectation {
+    pub fn expected(&self) -> f64 {
+        self.level + self.diurnal + self.seasonal + self.precipitation
+    }
+}
+
+/// Learned baseline of one sensor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeasonalBaseline {
+    level: Option<f64>,
+    readings: u64,
+    diurnal: [Component; 24],
+    seasonal: [Component; 12],
+    /// Least squares fit of the dry-model residual on the rain index
+    rain_sxy: f64,
+    rain_sxx: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Component {
+    offset: f64,
+    readings: u64,
+}
+
+impl Component {
+    /// Move towards `target`, averaging while there are few readings
+    fn learn(&mut self, target: f64, alpha: f64) {
+        self.readings += 1;
+        self.offset += (1.0 / self.readings as f64).max(alpha) * (target - self.offset);
+    }
+}
+
+impl SeasonalBaseline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dose rate increase per mm of rain index, once rain has been seen
+    pub fn rain_response(&self) -> f64 {
+        if self.rain_sxx > 0.0 {
+            (self.rain_sxy / self.rain_sxx).max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// What the sensor is expected to read at `timestamp` after `rain` mm of
+    /// recent rain; `None` before the first reading
+    pub fn expect(&self, timestamp: i64, rain: Option<f64>, config: &BaselineConfig) -> Option<Expectation> {
+        let level = self.level?;
+        let (hour, month) = hour_and_month(timestamp);
+        Some(Expectation {
+            level,
+            diurnal: if config.diurnal { self.diurnal[hour].offset } else { 0.0 },
+            seasonal: if config.seasonal { self.seasonal[month].offset } else { 0.0 },
+            precipitation: if config.precipitation { self.rain_response() * rain.unwrap_or(0.0) } else { 0.0 },
+        })
+    }
+
+    /// Learn from a reading of `value` at `timestamp` after `rain` mm of recent rain
+    pub fn update(&mut self, timestamp: i64, value: f64, rain: Option<f64>, config: &BaselineConfig) {
+        let Some(expectation) = self.expect(timestamp, rain, config) else {
+            self.level = Some(value);
+            self.readings = 1;
+            return;
+        };
+        let (hour, month) = hour_and_month(timestamp);
+        let rain = rain.unwrap_or(0.0);
+
+        // The rain response is fitted on what the dry model leaves unexplained
+        let dry_expected = expectation.expected() - expectation.precipitation;
+        if config.precipitation && rain >= config.wet_threshold_mm {
+            self.rain_sxy = config.precipitation_memory * self.rain_sxy + rain * (value - dry_expected);
+            self.rain_sxx = config.precipitation_memory * self.rain_sxx + rain * rain;
+        }
+
+        // Everything else learns from the reading with the rain's share removed
+        let dry = value - expectation.precipitation;
+        self.readings += 1;
+        let level_alpha = (1.0 / self.readings as f64).max(config.level_alpha);
+        let level = expectation.level + level_alpha * (dry - dry_expected);
+        self.level = Some(level);
+
+        if config.diurnal {
+            self.diurnal[hour].learn(dry - level - expectation.seasonal, config.diurnal_alpha);
+            // Hours average to zero so the profile does not drift into the level
+            let mean = self.diurnal.iter().map(|c| c.offset).sum::<f64>() / 24.0;
+            self.diurnal.iter_mut().for_each(|c| c.offset -= mean);
+            self.level = Some(level + mean);
+        }
+        if config.seasonal {
+            let diurnal = if config.diurnal { self.diurnal[hour].offset } else { 0.0 };
+            self.seasonal[month].learn(dry - level - diurnal, config.seasonal_alpha);
+        }
+    }
+}
+
+fn hour_and_month(timestamp: i64) -> (usize, usize) {
+    let time = DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+    (time.hour() as usize, time.month0() as usize)
+}
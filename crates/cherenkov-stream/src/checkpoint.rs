@@ -0,0 +1,142 @@
+//! Snapshots of the stream processor's per-sensor state.
+//!
+//! A checkpoint holds each sensor's detector state and window, and the
+//! watermark of every sensor at the moment it was taken: everything a sensor
+//! delivered up to its watermark is reflected in the state, and nothing after
+//! it. On restart the state is restored and each source is replayed from the
+//! database from the oldest watermark of its sensors, skipping every reading
+//! at or before its own sensor's watermark, which gives the same anomalies as
+//! if the processor had never stopped.
+//!
+//! With several shards, each snapshots its own sensors and the parts are
+//! merged into one file, which is split again by partition on restore, so
+//! the shard count may change between runs.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::anomaly::SensorState;
+use crate::window::TimestampedReading;
+
+/// Layout of the checkpoint file; bumped whenever it changes incompatibly
+pub const CHECKPOINT_VERSION: u32 = 3;
+
+/// Detector and window state carried across restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessorCheckpoint {
+    pub version: u32,
+    /// Watermark of each source, the oldest of its sensors', unix seconds
+    pub watermarks: BTreeMap<String, i64>,
+    /// Watermark of each sensor, unix seconds
+    pub sensor_watermarks: BTreeMap<Uuid, i64>,
+    /// Fixed sensors, by sensor id
+    pub sensors: BTreeMap<String, SensorCheckpoint>,
+    /// Mobile survey grid cells, by cell key
+    pub cells: BTreeMap<String, SensorCheckpoint>,
+}
+
+/// State of one sensor or grid cell
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SensorCheckpoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detector: Option<SensorState>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub window: Vec<TimestampedReading>,
+}
+
+impl Default for ProcessorCheckpoint {
+    fn default() -> Self {
+        Self {
+            version: CHECKPOINT_VERSION,
+            watermarks: BTreeMap::new(),
+            sensor_watermarks: BTreeMap::new(),
+            sensors: BTreeMap::new(),
+            cells: BTreeMap::new(),
+        }
+    }
+}
+
+impl ProcessorCheckpoint {
+    /// Parse a checkpoint, refusing layouts other than the current one
+    pub fn from_slice(data: &[u8]) -> anyhow::Result<Self> {
+        let value: serde_json::Value = serde_json::from_slice(data)?;
+        // Files from before versioning have no version at all
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(1);
+        if version != u64::from(CHECKPOINT_VERSION) {
+            anyhow::bail!("unsupported checkpoint version {}, expected {}", version, CHECKPOINT_VERSION);
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Combine the checkpoints of shards partitioned by sensor
+    ///
+    /// Sensors and cells are disjoint between shards. Each source takes the
+    /// oldest of the shards' watermarks, so replaying from it covers every
+    /// shard, and each sensor keeps its own. A mobile device surveying cells
+    /// of several shards takes the oldest of its watermarks too.
+    pub fn merge(parts: impl IntoIterator<Item = Self>) -> Self {
+        let mut merged = Self::default();
+        for part in parts {
+            for (source, watermark) in part.watermarks {
+                merged
+                    .watermarks
+                    .entry(source)
+                    .and_modify(|w| *w = (*w).min(watermark))
+                    .or_insert(watermark);
+            }
+            for (sensor, watermark) in part.sensor_watermarks {
+                merged
+                    .sensor_watermarks
+                    .entry(sensor)
+                    .and_modify(|w| *w = (*w).min(watermark))
+                    .or_insert(watermark);
+            }
+            merged.sensors.extend(part.sensors);
+            merged.cells.extend(part.cells);
+        }
+        merged
+    }
+
+    /// Split into one checkpoint per shard, with `shard_of` picking the
+    /// shard of a sensor id or cell key; every part keeps all watermarks
+    pub fn split(self, shards: usize, shard_of: impl Fn(&str) -> usize) -> Vec<Self> {
+        let mut parts: Vec<Self> = (0..shards)
+            .map(|_| Self {
+                watermarks: self.watermarks.clone(),
+                sensor_watermarks: self.sensor_watermarks.clone(),
+                ..Self::default()
+            })
+            .collect();
+        for (key, sensor) in self.sensors {
+            parts[shard_of(&key)].sensors.insert(key, sensor);
+        }
+        for (key, cell) in self.cells {
+            parts[shard_of(&key)].cells.insert(key, cell);
+        }
+        parts
+    }
+
+    /// Load the checkpoint at `path`; a missing file is not an error
+    pub async fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        match tokio::fs::read(path).await {
+            Ok(data) => Self::from_slice(&data).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write to `path`, replacing the previous checkpoint only once complete
+    pub async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let data = serde_json::to_vec(self)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+}
@@ -549,7 +549,7 @@ impl EventCluster {
     }
 }
 
-fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+pub(crate) fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     const R: f64 = 6371.0;
     
     let d_lat = (lat2 - lat1).to_radians();
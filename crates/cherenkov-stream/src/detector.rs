@@ -0,0 +1,819 @@
+//! Anomaly detectors behind a common [`Detector`] trait, and the per-sensor
+//! ensemble that runs a sensor class's detectors and turns their combined
+//! score into a [`Severity`].
+
+use std::collections::{HashMap, VecDeque};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{ContinuousCDF, StudentsT};
+
+use crate::anomaly::{Algorithm, IsolationForest, Severity};
+use crate::baseline::BaselineConfig;
+
+/// Class used for readings from mobile surveys, baselined per grid cell
+pub const MOBILE_CLASS: &str = "mobile";
+
+/// A streaming anomaly detector over one sensor's dose rates
+pub trait Detector: Send + Sync {
+    /// Algorithm findings of this detector are reported under
+    fn algorithm(&self) -> Algorithm;
+
+    /// Learn from `value`
+    fn update(&mut self, value: f64);
+
+    /// How anomalous `value` is against what has been learned, scaled so 1.0
+    /// is this detector's own alarm threshold. `None` while still learning.
+    fn score(&self, value: f64) -> Option<f64>;
+
+    /// The statistic behind `score` and what it was compared against
+    fn explain(&self, value: f64) -> Option<Explanation>;
+}
+
+/// Why a detector scored a value as it did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub algorithm: Algorithm,
+    /// `statistic / threshold`
+    pub score: f64,
+    pub statistic: f64,
+    pub threshold: f64,
+    /// Value the detector expected, for detectors that model one
+    pub expected: Option<f64>,
+    pub detail: String,
+}
+
+/// Mean and variance over the most recent values, updated incrementally
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningStats {
+    values: VecDeque<f64>,
+    capacity: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity.min(1024)),
+            capacity: capacity.max(1),
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if self.values.len() >= self.capacity {
+            if let Some(oldest) = self.values.pop_front() {
+                let n = self.values.len() as f64;
+                if n == 0.0 {
+                    self.mean = 0.0;
+                    self.m2 = 0.0;
+                } else {
+                    let delta = oldest - self.mean;
+                    self.mean -= delta / n;
+                    self.m2 = (self.m2 - delta * (oldest - self.mean)).max(0.0);
+                }
+            }
+        }
+
+        self.values.push_back(value);
+        let n = self.values.len() as f64;
+        let delta = value - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> f64 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        (self.m2 / self.values.len() as f64).sqrt()
+    }
+
+    /// Sample standard deviation
+    pub fn sample_std_dev(&self) -> f64 {
+        if self.values.len() < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.values.len() - 1) as f64).sqrt()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        self.values.iter().copied()
+    }
+
+    /// Deviation of `value` from the mean in standard deviations, once there is any spread
+    pub fn z_score(&self, value: f64) -> Option<f64> {
+        let std_dev = self.std_dev();
+        (self.values.len() >= 2 && std_dev > 0.0).then(|| (value - self.mean) / std_dev)
+    }
+}
+
+/// Z-score against the mean and variance of a trailing window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZScoreConfig {
+    pub window: usize,
+    /// Z-score at which the detector alarms
+    pub threshold: f64,
+}
+
+impl Default for ZScoreConfig {
+    fn default() -> Self {
+        Self { window: 1000, threshold: 3.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZScoreDetector {
+    config: ZScoreConfig,
+    stats: RunningStats,
+}
+
+impl ZScoreDetector {
+    pub fn new(config: ZScoreConfig) -> Self {
+        Self { stats: RunningStats::new(config.window), config }
+    }
+}
+
+impl Detector for ZScoreDetector {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Welford
+    }
+
+    fn update(&mut self, value: f64) {
+        self.stats.push(value);
+    }
+
+    fn score(&self, value: f64) -> Option<f64> {
+        self.stats.z_score(value).map(|z| z / self.config.threshold)
+    }
+
+    fn explain(&self, value: f64) -> Option<Explanation> {
+        let z = self.stats.z_score(value)?;
+        Some(Explanation {
+            algorithm: self.algorithm(),
+            score: z / self.config.threshold,
+            statistic: z,
+            threshold: self.config.threshold,
+            expected: Some(self.stats.mean()),
+            detail: format!("{:.2} standard deviations from the mean of the last {} readings", z, self.stats.len()),
+        })
+    }
+}
+
+/// Z-score against an exponentially weighted mean and variance, which
+/// follows slow drift sooner than a long window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EwmaConfig {
+    /// Weight of the newest reading
+    pub alpha: f64,
+    pub threshold: f64,
+}
+
+impl Default for EwmaConfig {
+    fn default() -> Self {
+        Self { alpha: 0.1, threshold: 3.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EwmaDetector {
+    config: EwmaConfig,
+    mean: Option<f64>,
+    variance: f64,
+}
+
+impl EwmaDetector {
+    pub fn new(config: EwmaConfig) -> Self {
+        Self { config, mean: None, variance: 0.0 }
+    }
+
+    /// Z-score of `value` and the moving average it is measured from
+    fn deviation(&self, value: f64) -> Option<(f64, f64)> {
+        let mean = self.mean?;
+        (self.variance > 0.0).then(|| ((value - mean) / self.variance.sqrt(), mean))
+    }
+}
+
+impl Detector for EwmaDetector {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Ewma
+    }
+
+    fn update(&mut self, value: f64) {
+        let Some(mean) = self.mean else {
+            self.mean = Some(value);
+            return;
+        };
+        let alpha = self.config.alpha;
+        let diff = value - mean;
+        self.mean = Some(mean + alpha * diff);
+        self.variance = (1.0 - alpha) * (self.variance + alpha * diff * diff);
+    }
+
+    fn score(&self, value: f64) -> Option<f64> {
+        self.deviation(value).map(|(z, _)| z / self.config.threshold)
+    }
+
+    fn explain(&self, value: f64) -> Option<Explanation> {
+        let (z, mean) = self.deviation(value)?;
+        Some(Explanation {
+            algorithm: self.algorithm(),
+            score: z / self.config.threshold,
+            statistic: z,
+            threshold: self.config.threshold,
+            expected: Some(mean),
+            detail: format!("{:.2} standard deviations from the moving average", z),
+        })
+    }
+}
+
+/// One-sided CUSUM of z-scores, which accumulates small sustained increases
+/// that no single reading would flag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CusumConfig {
+    /// Readings in the reference window
+    pub window: usize,
+    /// Allowance subtracted from each z-score, in standard deviations
+    pub slack: f64,
+    /// Cumulative sum at which the detector alarms
+    pub decision: f64,
+}
+
+impl Default for CusumConfig {
+    fn default() -> Self {
+        Self { window: 1000, slack: 0.5, decision: 5.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CusumDetector {
+    config: CusumConfig,
+    reference: RunningStats,
+    sum: f64,
+}
+
+impl CusumDetector {
+    pub fn new(config: CusumConfig) -> Self {
+        Self { reference: RunningStats::new(config.window), config, sum: 0.0 }
+    }
+
+    /// Cumulative sum once `value` is added to it
+    fn next_sum(&self, value: f64) -> Option<f64> {
+        let z = self.reference.z_score(value)?;
+        Some((self.sum + z - self.config.slack).max(0.0))
+    }
+}
+
+impl Detector for CusumDetector {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Cusum
+    }
+
+    fn update(&mut self, value: f64) {
+        if let Some(sum) = self.next_sum(value) {
+            self.sum = sum;
+        }
+        self.reference.push(value);
+    }
+
+    fn score(&self, value: f64) -> Option<f64> {
+        self.next_sum(value).map(|sum| sum / self.config.decision)
+    }
+
+    fn explain(&self, value: f64) -> Option<Explanation> {
+        let sum = self.next_sum(value)?;
+        Some(Explanation {
+            algorithm: self.algorithm(),
+            score: sum / self.config.decision,
+            statistic: sum,
+            threshold: self.config.decision,
+            expected: Some(self.reference.mean()),
+            detail: format!("cumulative excess of {:.2} standard deviations", sum),
+        })
+    }
+}
+
+/// Tukey fence above the upper quartile of a trailing window, robust to the
+/// outliers that inflate a variance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IqrConfig {
+    pub window: usize,
+    /// Fence distance above the upper quartile, in interquartile ranges
+    pub fence: f64,
+}
+
+impl Default for IqrConfig {
+    fn default() -> Self {
+        Self { window: 200, fence: 3.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IqrDetector {
+    config: IqrConfig,
+    recent: RunningStats,
+}
+
+impl IqrDetector {
+    pub fn new(config: IqrConfig) -> Self {
+        Self { recent: RunningStats::new(config.window), config }
+    }
+
+    /// Median, upper quartile and interquartile range of the window
+    fn quartiles(&self) -> Option<(f64, f64, f64)> {
+        if self.recent.len() < 4 {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.recent.values().collect();
+        sorted.sort_by(f64::total_cmp);
+        let (q1, median, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.5), quantile(&sorted, 0.75));
+        (q3 > q1).then_some((median, q3, q3 - q1))
+    }
+}
+
+/// Linear interpolation between the closest ranks of `sorted`
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let rank = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+impl Detector for IqrDetector {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Iqr
+    }
+
+    fn update(&mut self, value: f64) {
+        self.recent.push(value);
+    }
+
+    fn score(&self, value: f64) -> Option<f64> {
+        let (_, q3, iqr) = self.quartiles()?;
+        Some((value - q3) / iqr / self.config.fence)
+    }
+
+    fn explain(&self, value: f64) -> Option<Explanation> {
+        let (median, q3, iqr) = self.quartiles()?;
+        let statistic = (value - q3) / iqr;
+        Some(Explanation {
+            algorithm: self.algorithm(),
+            score: statistic / self.config.fence,
+            statistic,
+            threshold: self.config.fence,
+            expected: Some(median),
+            detail: format!("{:.2} interquartile ranges above the upper quartile {:.4}", statistic, q3),
+        })
+    }
+}
+
+/// Grubbs' test of whether a value is an outlier of a trailing window,
+/// assuming the window is normally distributed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrubbsConfig {
+    pub window: usize,
+    /// Significance level of the one-sided test
+    pub alpha: f64,
+}
+
+impl Default for GrubbsConfig {
+    fn default() -> Self {
+        Self { window: 100, alpha: 0.01 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrubbsDetector {
+    config: GrubbsConfig,
+    recent: RunningStats,
+    /// Critical value for the current window size, kept as the window fills
+    critical: Option<f64>,
+}
+
+impl GrubbsDetector {
+    pub fn new(config: GrubbsConfig) -> Self {
+        Self { recent: RunningStats::new(config.window), config, critical: None }
+    }
+
+    /// Grubbs statistic of `value` and the critical value it is tested against
+    fn statistic(&self, value: f64) -> Option<(f64, f64)> {
+        let critical = self.critical?;
+        let std_dev = self.recent.sample_std_dev();
+        (std_dev > 0.0).then(|| ((value - self.recent.mean()) / std_dev, critical))
+    }
+}
+
+/// One-sided Grubbs critical value for a sample of `n` at significance `alpha`
+fn grubbs_critical(n: usize, alpha: f64) -> Option<f64> {
+    if n < 3 {
+        return None;
+    }
+    let n = n as f64;
+    let t = StudentsT::new(0.0, 1.0, n - 2.0).ok()?.inverse_cdf(1.0 - alpha / n);
+    Some((n - 1.0) / n.sqrt() * (t * t / (n - 2.0 + t * t)).sqrt())
+}
+
+impl Detector for GrubbsDetector {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Grubbs
+    }
+
+    fn update(&mut self, value: f64) {
+        let before = self.recent.len();
+        self.recent.push(value);
+        if self.recent.len() != before || self.critical.is_none() {
+            // The value under test is one more than the window holds
+            self.critical = grubbs_critical(self.recent.len() + 1, self.config.alpha);
+        }
+    }
+
+    fn score(&self, value: f64) -> Option<f64> {
+        self.statistic(value).map(|(g, critical)| g / critical)
+    }
+
+    fn explain(&self, value: f64) -> Option<Explanation> {
+        let (g, critical) = self.statistic(value)?;
+        Some(Explanation {
+            algorithm: self.algorithm(),
+            score: g / critical,
+            statistic: g,
+            threshold: critical,
+            expected: Some(self.recent.mean()),
+            detail: format!("Grubbs statistic {:.2} against {:.2} at alpha {}", g, critical, self.config.alpha),
+        })
+    }
+}
+
+/// Isolation forest over each reading and its change from the previous one,
+/// refitted on recent history as the sensor reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IsolationForestConfig {
+    pub trees: usize,
+    /// Points each tree is grown from
+    pub subsample: usize,
+    /// Recent points kept to fit from
+    pub window: usize,
+    /// Readings between refits
+    pub refit_every: usize,
+    /// Anomaly score, between 0 and 1, at which the detector alarms
+    pub threshold: f64,
+}
+
+impl Default for IsolationForestConfig {
+    fn default() -> Self {
+        Self { trees: 50, subsample: 128, window: 512, refit_every: 256, threshold: 0.6 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IsolationForestDetector {
+    config: IsolationForestConfig,
+    forest: Option<IsolationForest>,
+    history: VecDeque<[f64; 2]>,
+    previous: Option<f64>,
+    since_fit: usize,
+    fits: u64,
+}
+
+impl IsolationForestDetector {
+    pub fn new(config: IsolationForestConfig) -> Self {
+        Self {
+            config,
+            forest: None,
+            history: VecDeque::new(),
+            previous: None,
+            since_fit: 0,
+            fits: 0,
+        }
+    }
+
+    fn features(&self, value: f64) -> [f64; 2] {
+        [value, value - self.previous.unwrap_or(value)]
+    }
+}
+
+impl Detector for IsolationForestDetector {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::IsolationForest
+    }
+
+    fn update(&mut self, value: f64) {
+        let point = self.features(value);
+        if self.history.len() >= self.config.window.max(1) {
+            self.history.pop_front();
+        }
+        self.history.push_back(point);
+        self.previous = Some(value);
+        self.since_fit += 1;
+
+        let enough = self.history.len() >= self.config.subsample.min(self.config.window).max(2);
+        if enough && (self.forest.is_none() || self.since_fit >= self.config.refit_every) {
+            let data: Vec<Vec<f64>> = self.history.iter().map(|p| p.to_vec()).collect();
+            // Seeded, so replaying the same readings grows the same trees
+            let mut rng = StdRng::seed_from_u64(self.fits);
+            let mut forest = IsolationForest::new(self.config.trees, self.config.subsample);
+            forest.fit_with_rng(&data, &mut rng);
+            self.forest = Some(forest);
+            self.fits += 1;
+            self.since_fit = 0;
+        }
+    }
+
+    fn score(&self, value: f64) -> Option<f64> {
+        let forest = self.forest.as_ref()?;
+        Some(forest.anomaly_score(&self.features(value)) / self.config.threshold)
+    }
+
+    fn explain(&self, value: f64) -> Option<Explanation> {
+        let forest = self.forest.as_ref()?;
+        let statistic = forest.anomaly_score(&self.features(value));
+        Some(Explanation {
+            algorithm: self.algorithm(),
+            score: statistic / self.config.threshold,
+            statistic,
+            threshold: self.config.threshold,
+            expected: None,
+            detail: format!("isolation score {:.3} from {} trees", statistic, self.config.trees),
+        })
+    }
+}
+
+/// A detector and its settings, as named in configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DetectorKind {
+    ZScore(ZScoreConfig),
+    Ewma(EwmaConfig),
+    Cusum(CusumConfig),
+    Iqr(IqrConfig),
+    Grubbs(GrubbsConfig),
+    IsolationForest(IsolationForestConfig),
+}
+
+/// A detector in a class's ensemble
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectorSpec {
+    #[serde(flatten)]
+    pub kind: DetectorKind,
+    /// Weight of the detector's score under [`Combiner::Mean`]
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl DetectorSpec {
+    pub fn new(kind: DetectorKind) -> Self {
+        Self { kind, weight: default_weight() }
+    }
+
+    fn build(&self) -> AnyDetector {
+        match &self.kind {
+            DetectorKind::ZScore(c) => AnyDetector::ZScore(ZScoreDetector::new(c.clone())),
+            DetectorKind::Ewma(c) => AnyDetector::Ewma(EwmaDetector::new(c.clone())),
+            DetectorKind::Cusum(c) => AnyDetector::Cusum(CusumDetector::new(c.clone())),
+            DetectorKind::Iqr(c) => AnyDetector::Iqr(IqrDetector::new(c.clone())),
+            DetectorKind::Grubbs(c) => AnyDetector::Grubbs(GrubbsDetector::new(c.clone())),
+            DetectorKind::IsolationForest(c) => AnyDetector::IsolationForest(IsolationForestDetector::new(c.clone())),
+        }
+    }
+}
+
+/// The concrete detectors, so per-sensor state can be checkpointed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum AnyDetector {
+    ZScore(ZScoreDetector),
+    Ewma(EwmaDetector),
+    Cusum(CusumDetector),
+    Iqr(IqrDetector),
+    Grubbs(GrubbsDetector),
+    IsolationForest(IsolationForestDetector),
+}
+
+impl AnyDetector {
+    fn as_detector(&self) -> &dyn Detector {
+        match self {
+            AnyDetector::ZScore(d) => d,
+            AnyDetector::Ewma(d) => d,
+            AnyDetector::Cusum(d) => d,
+            AnyDetector::Iqr(d) => d,
+            AnyDetector::Grubbs(d) => d,
+            AnyDetector::IsolationForest(d) => d,
+        }
+    }
+
+    fn as_detector_mut(&mut self) -> &mut dyn Detector {
+        match self {
+            AnyDetector::ZScore(d) => d,
+            AnyDetector::Ewma(d) => d,
+            AnyDetector::Cusum(d) => d,
+            AnyDetector::Iqr(d) => d,
+            AnyDetector::Grubbs(d) => d,
+            AnyDetector::IsolationForest(d) => d,
+        }
+    }
+}
+
+/// How the scores of a class's detectors combine into one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Combiner {
+    /// Highest score of any detector
+    Max,
+    /// Weighted mean of the scores
+    Mean,
+    /// Highest score reached by at least `votes` detectors
+    Vote { votes: usize },
+}
+
+impl Combiner {
+    /// Combine the `(score, weight)` of each detector that scored a reading
+    pub fn combine(&self, scores: &[(f64, f64)]) -> Option<f64> {
+        match self {
+            Combiner::Max => scores.iter().map(|(s, _)| *s).reduce(f64::max),
+            Combiner::Mean => {
+                let weight: f64 = scores.iter().map(|(_, w)| w).sum();
+                (weight > 0.0).then(|| scores.iter().map(|(s, w)| s * w).sum::<f64>() / weight)
+            }
+            Combiner::Vote { votes } => {
+                let mut sorted: Vec<f64> = scores.iter().map(|(s, _)| *s).collect();
+                sorted.sort_by(|a, b| b.total_cmp(a));
+                sorted.get(votes.saturating_sub(1)).copied()
+            }
+        }
+    }
+}
+
+/// Detectors run for a class of sensors, and how their verdict is graded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassConfig {
+    pub detectors: Vec<DetectorSpec>,
+    pub combiner: Combiner,
+    /// Combined score from which a reading is a warning
+    pub warning: f64,
+    /// Combined score from which a reading is critical
+    pub critical: f64,
+    /// Readings a sensor must have reported before it is scored
+    pub min_samples: u64,
+    /// Expected dose rate the detectors score the residual from
+    pub baseline: BaselineConfig,
+}
+
+impl Default for ClassConfig {
+    fn default() -> Self {
+        Self {
+            detectors: vec![
+                DetectorSpec::new(DetectorKind::ZScore(ZScoreConfig::default())),
+                DetectorSpec::new(DetectorKind::Ewma(EwmaConfig::default())),
+                DetectorSpec::new(DetectorKind::Cusum(CusumConfig::default())),
+            ],
+            combiner: Combiner::Mean,
+            warning: 2.0 / 3.0,
+            critical: 1.0,
+            min_samples: 10,
+            baseline: BaselineConfig::default(),
+        }
+    }
+}
+
+impl ClassConfig {
+    pub fn severity(&self, score: f64) -> Option<Severity> {
+        if score >= self.critical {
+            Some(Severity::Critical)
+        } else if score >= self.warning {
+            Some(Severity::Warning)
+        } else {
+            None
+        }
+    }
+}
+
+/// Detector configuration per sensor class
+///
+/// A fixed sensor's class is the name of its data source, and mobile readings
+/// are [`MOBILE_CLASS`]. Classes without an entry use `default`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnsembleConfig {
+    pub default: ClassConfig,
+    pub classes: HashMap<String, ClassConfig>,
+}
+
+impl Default for EnsembleConfig {
+    fn default() -> Self {
+        // Survey cells are visited in bursts, too sparse to follow drift
+        let mobile = ClassConfig {
+            detectors: vec![DetectorSpec::new(DetectorKind::ZScore(ZScoreConfig::default()))],
+            combiner: Combiner::Max,
+            // Grid cells are visited at random times, so only their level is learned
+            baseline: BaselineConfig::level_only(),
+            ..ClassConfig::default()
+        };
+        Self {
+            default: ClassConfig::default(),
+            classes: HashMap::from([(MOBILE_CLASS.to_string(), mobile)]),
+        }
+    }
+}
+
+impl EnsembleConfig {
+    pub fn class(&self, name: &str) -> &ClassConfig {
+        self.classes.get(name).unwrap_or(&self.default)
+    }
+}
+
+/// Graded outcome for a reading that scored as anomalous
+#[derive(Debug, Clone)]
+pub struct Verdict {
+    pub severity: Severity,
+    /// Combined score
+    pub score: f64,
+    pub algorithm: Algorithm,
+    /// Every detector that scored the reading, highest score first
+    pub explanations: Vec<Explanation>,
+}
+
+/// One sensor's detectors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorEnsemble {
+    detectors: Vec<(AnyDetector, f64)>,
+    samples: u64,
+}
+
+impl SensorEnsemble {
+    pub fn new(config: &ClassConfig) -> Self {
+        Self {
+            detectors: config.detectors.iter().map(|spec| (spec.build(), spec.weight)).collect(),
+            samples: 0,
+        }
+    }
+
+    /// Readings learned from so far
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Score `value` against what the detectors have learned, then learn from it
+    pub fn observe(&mut self, value: f64, config: &ClassConfig) -> Option<Verdict> {
+        let verdict = if self.samples >= config.min_samples { self.assess(value, config) } else { None };
+
+        for (detector, _) in &mut self.detectors {
+            detector.as_detector_mut().update(value);
+        }
+        self.samples += 1;
+
+        verdict
+    }
+
+    fn assess(&self, value: f64, config: &ClassConfig) -> Option<Verdict> {
+        let scores: Vec<(f64, f64)> = self
+            .detectors
+            .iter()
+            .filter_map(|(detector, weight)| detector.as_detector().score(value).map(|s| (s, *weight)))
+            .collect();
+        let score = config.combiner.combine(&scores)?;
+        let severity = config.severity(score)?;
+
+        let mut explanations: Vec<Explanation> = self
+            .detectors
+            .iter()
+            .filter_map(|(detector, _)| detector.as_detector().explain(value))
+            .collect();
+        explanations.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let algorithm = match self.detectors.as_slice() {
+            [(only, _)] => only.as_detector().algorithm(),
+            _ => Algorithm::Ensemble,
+        };
+
+        Some(Verdict {
+            severity,
+            score,
+            algorithm,
+            explanations,
+        })
+    }
+}
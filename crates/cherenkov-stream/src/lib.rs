@@ -3,13 +3,22 @@
 //! Real-time stream processing for anomaly detection and correlation analysis.
 
 pub mod anomaly;
+pub mod baseline;
+pub mod checkpoint;
 pub mod correlation;
+pub mod detector;
 pub mod processor;
+pub mod watermark;
+pub mod weather;
 pub mod window;
 
 pub use anomaly::{Anomaly, AnomalyDetector, Severity, Algorithm, Reading};
+pub use baseline::{BaselineConfig, SeasonalBaseline};
 pub use correlation::CorrelationEngine;
+pub use detector::{ClassConfig, Combiner, Detector, DetectorKind, DetectorSpec, EnsembleConfig, Explanation};
 pub use processor::StreamProcessor;
+pub use watermark::{Clock, EventTimeBuffer, SimulatedClock, SystemClock, WatermarkConfig};
+pub use weather::WeatherConfig;
 pub use window::SlidingWindow;
 
 
@@ -1,19 +1,13 @@
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, broadcast};
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn, error, instrument};
 use chrono::Utc;
 use uuid::Uuid;
 
-mod anomaly;
-mod window;
-mod correlation;
-mod processor;
-
-use anomaly::{Anomaly, Severity};
-use correlation::CorrelationEngine;
-use processor::StreamProcessor;
-use cherenkov_db::{RadiationDatabase, RadiationReading, DatabaseConfig, scylla::ScyllaConfig};
+use cherenkov_stream::{Anomaly, CorrelationEngine, EnsembleConfig, Severity, StreamProcessor, WatermarkConfig};
+use cherenkov_db::{RadiationDatabase, RadiationReading, BackupConfig, DatabaseConfig, StorageBackends};
 use cherenkov_observability::init_observability;
 use cherenkov_core::{EventBus, CherenkovEvent, Anomaly as CoreAnomaly, Severity as CoreSeverity};
 
@@ -25,14 +19,14 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Cherenkov Stream Processor v{}", env!("CARGO_PKG_VERSION"));
     
     // Initialize database
-    let scylla_config = ScyllaConfig::default();
+    // Backends come from STORAGE_PROFILE and friends, see `StorageBackends::from_env`
     let db = Arc::new(
-        RadiationDatabase::new(
-            scylla_config,
-            "./data/cherenkov_warm.db",
-            "redis://127.0.0.1:6379",
-            DatabaseConfig::default(),
-        ).await?
+        RadiationDatabase::open(StorageBackends::from_env(), DatabaseConfig {
+            // The ingest daemon archives the warm tier's WAL, which only works
+            // if no other process checkpoints it
+            backup: BackupConfig::from_env(),
+            ..DatabaseConfig::default()
+        }).await?
     );
     
     // Initialize EventBus for inter-crate communication
@@ -46,10 +40,31 @@ async fn main() -> anyhow::Result<()> {
     // Create broadcast channel for real-time anomaly alerts (internal)
     let (anomaly_tx, _) = broadcast::channel(1000);
     
-    // Create processor
-    let processor = StreamProcessor::new(
-        db.clone(),
-        anomaly_tx.clone(),
+    // Create processor with its own channel; the detection worker forwards
+    // its anomalies onto `anomaly_tx` after storing them
+    let (processor_tx, _) = broadcast::channel(1000);
+    let processor = StreamProcessor::new(db.clone(), processor_tx)
+        .with_detectors(load_detector_config())
+        .with_watermarks(load_watermark_config())
+        .with_shards(
+            std::env::var("STREAM_SHARDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get())),
+        )
+        .with_checkpoint("./data/stream_checkpoint.json")
+        .with_checkpoint_interval(Duration::from_secs(
+            std::env::var("STREAM_CHECKPOINT_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+        ));
+    let shutdown = CancellationToken::new();
+    let shutdown_timeout = Duration::from_secs(
+        std::env::var("SHUTDOWN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30),
     );
     
     // Start EventBus listener for new readings
@@ -79,11 +94,15 @@ async fn main() -> anyhow::Result<()> {
         db.clone(),
     ));
     
+    // Start the processor last, it consumes itself
+    let mut processor_handle = tokio::spawn(processor.run(shutdown.clone()));
+    
     // Start health check server
     let health_server = tokio::spawn(health_check_server(db.clone()));
     
     // Wait for shutdown signal
     tokio::select! {
+        _ = &mut processor_handle => warn!("Stream processor exited"),
         _ = eventbus_listener => warn!("EventBus listener exited"),
         _ = detection_worker => warn!("Detection worker exited"),
         _ = ws_broadcaster => warn!("WebSocket broadcaster exited"),
@@ -94,9 +113,52 @@ async fn main() -> anyhow::Result<()> {
 
     
     info!("Cherenkov Stream Processor shutting down");
+
+    // Let the processor drain queued readings and checkpoint its state
+    shutdown.cancel();
+    if !processor_handle.is_finished() {
+        match tokio::time::timeout(shutdown_timeout, processor_handle).await {
+            Ok(_) => info!("Stream processor drained"),
+            Err(_) => warn!("Stream processor did not drain within {:?}, exiting anyway", shutdown_timeout),
+        }
+    }
+
     Ok(())
 }
 
+/// Load detector ensembles per sensor class from the JSON file named by `DETECTOR_CONFIG`, if set
+fn load_detector_config() -> EnsembleConfig {
+    let Ok(path) = std::env::var("DETECTOR_CONFIG") else {
+        return EnsembleConfig::default();
+    };
+
+    match std::fs::read(&path).map_err(anyhow::Error::from)
+        .and_then(|data| serde_json::from_slice(&data).map_err(anyhow::Error::from))
+    {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Failed to load detector config from {}, using defaults: {}", path, e);
+            EnsembleConfig::default()
+        }
+    }
+}
+
+/// Event-time buffering from `STREAM_OUT_OF_ORDERNESS_SECS`,
+/// `STREAM_ALLOWED_LATENESS_SECS`, `STREAM_IDLE_TIMEOUT_SECS` and
+/// `STREAM_MAX_FUTURE_SKEW_SECS`
+fn load_watermark_config() -> WatermarkConfig {
+    let secs = |name: &str, default: i64| {
+        std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+    };
+    let defaults = WatermarkConfig::default();
+    WatermarkConfig {
+        out_of_orderness_secs: secs("STREAM_OUT_OF_ORDERNESS_SECS", defaults.out_of_orderness_secs),
+        allowed_lateness_secs: secs("STREAM_ALLOWED_LATENESS_SECS", defaults.allowed_lateness_secs),
+        idle_timeout_secs: secs("STREAM_IDLE_TIMEOUT_SECS", defaults.idle_timeout_secs),
+        max_future_skew_secs: secs("STREAM_MAX_FUTURE_SKEW_SECS", defaults.max_future_skew_secs),
+    }
+}
+
 /// Listen for new readings from EventBus
 async fn eventbus_listener(
     mut reading_rx: tokio::sync::broadcast::Receiver<CherenkovEvent>,
@@ -123,6 +185,10 @@ async fn eventbus_listener(
                     },
                     source: reading.source,
                     cell_id: reading.sensor_id.to_string(),
+                    track_id: reading.track_id,
+                    altitude_m: None,
+                    qc_reasons: Vec::new(),
+                    provenance: None,
                 };
                 
                 if let Err(e) = ingest_tx.send(radiation_reading).await {
@@ -174,14 +240,11 @@ async fn anomaly_detection_worker(
                 Severity::Info => CoreSeverity::Info,
             },
             z_score: anomaly.z_score,
-            detected_at: anomaly.timestamp,
+            detected_at: Utc::now(),
             timestamp: anomaly.timestamp,
             dose_rate: anomaly.dose_rate,
             baseline: anomaly.baseline,
-            algorithm: match anomaly.algorithm {
-                anomaly::Algorithm::Welford => "Welford".to_string(),
-                anomaly::Algorithm::IsolationForest => "IsolationForest".to_string(),
-            },
+            algorithm: anomaly.algorithm.as_str().to_string(),
         };
         
         let event = CherenkovEvent::AnomalyDetected(core_anomaly);
@@ -241,14 +304,11 @@ async fn websocket_broadcaster(
                 Severity::Info => CoreSeverity::Info,
             },
             z_score: anomaly.z_score,
-            detected_at: anomaly.timestamp,
+            detected_at: Utc::now(),
             timestamp: anomaly.timestamp,
             dose_rate: anomaly.dose_rate,
             baseline: anomaly.baseline,
-            algorithm: match anomaly.algorithm {
-                anomaly::Algorithm::Welford => "Welford".to_string(),
-                anomaly::Algorithm::IsolationForest => "IsolationForest".to_string(),
-            },
+            algorithm: anomaly.algorithm.as_str().to_string(),
         };
         
         let event = CherenkovEvent::AnomalyDetected(core_anomaly);
@@ -297,14 +357,11 @@ async fn correlation_engine_worker(
                     Severity::Info => CoreSeverity::Info,
                 },
                 z_score: anomaly.z_score,
-                detected_at: anomaly.timestamp,
+                detected_at: Utc::now(),
                 timestamp: anomaly.timestamp,
                 dose_rate: anomaly.dose_rate,
                 baseline: anomaly.baseline,
-                algorithm: match anomaly.algorithm {
-                    anomaly::Algorithm::Welford => "Welford".to_string(),
-                    anomaly::Algorithm::IsolationForest => "IsolationForest".to_string(),
-                },
+                algorithm: anomaly.algorithm.as_str().to_string(),
             };
             
             let event = CherenkovEvent::CorrelatedEventDetected {
@@ -1,21 +1,63 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::{mpsc, broadcast, RwLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, broadcast, oneshot};
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, debug, warn, instrument};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
 
-use cherenkov_db::{RadiationDatabase, RadiationReading};
-use crate::anomaly::{Anomaly, AnomalyDetector};
-use crate::window::SlidingWindow;
+use cherenkov_db::{Cursor, RadiationDatabase, RadiationReading, TimeRangeQuery};
+use crate::anomaly::{Anomaly, AnomalyDetector, DetectorState};
+use crate::checkpoint::ProcessorCheckpoint;
+use crate::detector::{EnsembleConfig, MOBILE_CLASS};
+use crate::watermark::{Clock, EventTimeBuffer, SystemClock, WatermarkConfig};
+use crate::weather::{WeatherConfig, WeatherLookup};
+use crate::window::{Reading as WindowReading, SlidingWindow};
+
+/// Grid resolution in degrees for baselining mobile readings by place
+const MOBILE_CELL_DEGREES: f64 = 0.01;
+
+/// How often sources are checked for having gone idle
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Readings read from the database per round trip while catching up
+const CATCH_UP_BATCH: usize = 1000;
+
+/// Readings queued per detection worker before routing waits for it
+const SHARD_QUEUE: usize = 10000;
+
+/// Prefix of the window keys of mobile survey grid cells
+const MOBILE_CELL_PREFIX: &str = "mobile:";
 
 /// Stream processor coordinating anomaly detection pipeline
+///
+/// Readings are partitioned by sensor, or by grid cell for mobile readings,
+/// across a number of shards. Each shard is a task with its own event-time
+/// buffer, windows and detectors, so shards never contend for state and a
+/// sensor's readings are always scored in order by the same shard. All
+/// shards publish onto the one anomaly channel.
 #[allow(dead_code)]
 pub struct StreamProcessor {
     db: Arc<RadiationDatabase>,
     ingest_tx: mpsc::Sender<RadiationReading>,
     ingest_rx: mpsc::Receiver<RadiationReading>,
     anomaly_tx: broadcast::Sender<Anomaly>,
-    detector: Arc<RwLock<AnomalyDetector>>,
-    windows: Arc<RwLock<HashMap<String, SlidingWindow>>>,
+    /// Detectors for each sensor class; mobile sensors get a detector of
+    /// their own so moving tracks never skew fixed-station baselines
+    detectors: EnsembleConfig,
+    /// Number of detection workers readings are partitioned across
+    shards: usize,
+    /// File detector and window state is checkpointed to, periodically and on shutdown
+    checkpoint_path: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    /// Rain lookup for precipitation-aware baselines
+    weather: WeatherConfig,
+    /// Event-time buffering ahead of the detectors
+    watermarks: WatermarkConfig,
+    /// Processing time, for releasing the readings of idle sources
+    clock: Arc<dyn Clock>,
 }
 
 #[allow(dead_code)]
@@ -31,11 +73,59 @@ impl StreamProcessor {
             ingest_tx,
             ingest_rx,
             anomaly_tx,
-            detector: Arc::new(RwLock::new(AnomalyDetector::new())),
-            windows: Arc::new(RwLock::new(HashMap::new())),
+            detectors: EnsembleConfig::default(),
+            shards: 1,
+            checkpoint_path: None,
+            checkpoint_interval: Duration::from_secs(60),
+            weather: WeatherConfig::default(),
+            watermarks: WatermarkConfig::default(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Restore state from `path` on start, and write it back every
+    /// checkpoint interval and on shutdown
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint_path = Some(path.into());
+        self
+    }
+
+    /// Time between periodic checkpoints, one minute by default
+    pub fn with_checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// Use `config` to pick each sensor class's detectors
+    pub fn with_detectors(mut self, config: EnsembleConfig) -> Self {
+        self.detectors = config;
+        self
+    }
+
+    /// Partition readings across `shards` detection workers, one by default
+    pub fn with_shards(mut self, shards: usize) -> Self {
+        self.shards = shards.max(1);
+        self
+    }
+
+    /// Use `config` to look up rain near sensors
+    pub fn with_weather(mut self, config: WeatherConfig) -> Self {
+        self.weather = config;
+        self
+    }
+
+    /// Use `config` to order readings by event time before detection
+    pub fn with_watermarks(mut self, config: WatermarkConfig) -> Self {
+        self.watermarks = config;
+        self
+    }
+
+    /// Take processing time from `clock`, as when replaying with a simulated clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub fn get_ingest_tx(&self) -> mpsc::Sender<RadiationReading> {
         self.ingest_tx.clone()
     }
@@ -44,108 +134,406 @@ impl StreamProcessor {
         self.anomaly_tx.subscribe()
     }
 
-    /// Start the processor pipeline with multiple workers
-    pub async fn run(self) -> anyhow::Result<()> {
-        info!("Stream processor starting with anomaly detection");
+    /// Start the processor pipeline and run until `shutdown` is cancelled
+    ///
+    /// State is restored from the checkpoint, if any, and readings the
+    /// database holds past its watermarks are replayed before live readings.
+    /// On shutdown, readings already queued are still processed before the
+    /// detector and window state is checkpointed.
+    pub async fn run(self, shutdown: CancellationToken) -> anyhow::Result<()> {
+        info!("Stream processor starting with anomaly detection on {} shards", self.shards);
+
+        let mut restored = ProcessorCheckpoint::default();
+        if let Some(path) = &self.checkpoint_path {
+            match ProcessorCheckpoint::load(path).await {
+                Ok(Some(checkpoint)) => {
+                    info!("Restored stream processor state for {} sensors and {} cells from {}",
+                        checkpoint.sensors.len(), checkpoint.cells.len(), path.display());
+                    restored = checkpoint;
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to restore stream processor state from {}: {}", path.display(), e),
+            }
+        }
+        let watermarks = restored.watermarks.clone();
+        let sensor_watermarks = restored.sensor_watermarks.clone();
+
+        // Spawn one detection worker per shard, each with its part of the state
+        let shards = self.shards;
+        let mut senders = Vec::with_capacity(shards);
+        let mut workers = Vec::with_capacity(shards);
+        for (index, part) in restored.split(shards, |key| shard_of(key, shards)).into_iter().enumerate() {
+            let mut detection = Detection {
+                db: self.db.clone(),
+                anomaly_tx: self.anomaly_tx.clone(),
+                detector: AnomalyDetector::with_config(self.detectors.clone()),
+                mobile_detector: AnomalyDetector::with_config(self.detectors.clone()),
+                windows: HashMap::new(),
+                weather: WeatherLookup::new(self.db.clone(), self.weather.clone(), self.clock.clone()),
+            };
+            let mut buffer = EventTimeBuffer::new(self.watermarks.clone(), self.clock.clone());
+            let (watermarks, sensor_watermarks) = detection.restore(part);
+            buffer.resume(watermarks, sensor_watermarks);
 
-        let db = self.db.clone();
-        let anomaly_tx = self.anomaly_tx.clone();
-        let detector = self.detector.clone();
-        let windows = self.windows.clone();
+            let (tx, rx) = mpsc::channel(SHARD_QUEUE);
+            senders.push(tx);
+            workers.push(tokio::spawn(Self::anomaly_detection_worker(index, rx, detection, buffer)));
+        }
 
-        // Spawn anomaly detection worker
-        let detection_handle = tokio::spawn(async move {
-            Self::anomaly_detection_worker(
-                self.ingest_rx,
-                db,
-                anomaly_tx,
-                detector,
-                windows,
-            ).await;
-        });
+        let router = Router { shards: senders };
+        router.catch_up(&self.db, watermarks, &sensor_watermarks).await;
+        Self::route(self.ingest_rx, &router, self.checkpoint_path.as_deref(), self.checkpoint_interval, shutdown).await;
 
-        // Wait for workers
-        tokio::select! {
-            _ = detection_handle => warn!("Anomaly detection worker exited"),
-            _ = tokio::signal::ctrl_c() => info!("Shutdown signal received"),
+        // Closing the shard queues lets each worker drain and hand back its state
+        drop(router);
+        let mut parts = Vec::with_capacity(workers.len());
+        for worker in workers {
+            match worker.await {
+                Ok(part) => parts.push(part),
+                Err(e) => warn!("Anomaly detection worker failed: {}", e),
+            }
+        }
+
+        // A partial checkpoint would forget the failed shard's sensors
+        if let Some(path) = &self.checkpoint_path {
+            if parts.len() == shards {
+                save_checkpoint(path, &ProcessorCheckpoint::merge(parts)).await;
+            } else {
+                warn!("Keeping the previous checkpoint after a detection worker failed");
+            }
         }
 
         info!("Stream processor shutting down");
         Ok(())
     }
 
-    #[instrument(skip(rx, db, anomaly_tx, detector, windows))]
-    async fn anomaly_detection_worker(
+    /// Hand live readings to their shards until shutdown and the queue is drained
+    #[instrument(skip(rx, router, checkpoint_path, shutdown))]
+    async fn route(
         mut rx: mpsc::Receiver<RadiationReading>,
-        db: Arc<RadiationDatabase>,
-        anomaly_tx: broadcast::Sender<Anomaly>,
-        detector: Arc<RwLock<AnomalyDetector>>,
-        windows: Arc<RwLock<HashMap<String, SlidingWindow>>>,
+        router: &Router,
+        checkpoint_path: Option<&Path>,
+        checkpoint_interval: Duration,
+        shutdown: CancellationToken,
     ) {
-        info!("Anomaly detection worker started");
+        let mut closed = false;
+        let mut checkpoint = tokio::time::interval_at(tokio::time::Instant::now() + checkpoint_interval, checkpoint_interval);
+        checkpoint.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-        while let Some(reading) = rx.recv().await {
-            // Filter invalid readings
-            if reading.dose_rate_microsieverts < 0.0 {
-                debug!("Dropping negative dose rate reading");
-                continue;
-            }
+        loop {
+            tokio::select! {
+                biased;
+                // Stop accepting new readings, but keep going until the queue is empty
+                _ = shutdown.cancelled(), if !closed => {
+                    rx.close();
+                    closed = true;
+                }
+                received = rx.recv() => {
+                    let Some(reading) = received else {
+                        break;
+                    };
 
-            let sensor_id = reading.sensor_id.to_string();
+                    // Filter invalid readings
+                    if reading.dose_rate_microsieverts < 0.0 {
+                        debug!("Dropping negative dose rate reading");
+                        continue;
+                    }
+                    router.send(reading).await;
+                }
+                _ = checkpoint.tick(), if checkpoint_path.is_some() => {
+                    if let (Some(path), Some(snapshot)) = (checkpoint_path, router.snapshot().await) {
+                        save_checkpoint(path, &snapshot).await;
+                    }
+                }
+            }
+        }
+    }
 
-            // Get or create sliding window for this sensor
-            let mut windows_guard = windows.write().await;
-            let window = windows_guard.entry(sensor_id.clone()).or_insert_with(|| {
-                SlidingWindow::new(
-                    std::time::Duration::from_secs(3600),
-                    std::time::Duration::from_secs(60),
-                ) // 1 hour window, 1 minute slide
-            });
+    /// Run one shard's readings through its event-time buffer and detectors,
+    /// returning its state once its queue is closed and drained
+    #[instrument(skip(rx, detection, buffer))]
+    async fn anomaly_detection_worker(
+        shard: usize,
+        mut rx: mpsc::Receiver<ShardMessage>,
+        mut detection: Detection,
+        mut buffer: EventTimeBuffer,
+    ) -> ProcessorCheckpoint {
+        debug!("Anomaly detection worker started");
 
-            // Add reading to window
-            window.add(reading.clone());
+        let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        idle_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-            // Run anomaly detection
-            let window_data = window.get_window(&sensor_id);
-            if window_data.len() >= 10 { // Need minimum samples
-                let mut detector_guard = detector.write().await;
-                
-                // Convert TimestampedReading to Reading for anomaly detector
-                let readings: Vec<crate::anomaly::Reading> = window_data.iter().map(|r| crate::anomaly::Reading {
-                    sensor_id: r.sensor_id.clone(),
-                    timestamp: r.timestamp,
-                    dose_rate: r.dose_rate,
-                }).collect();
-                
-                if let Some(anomaly) = detector_guard.detect(readings) {
-                    info!("Anomaly detected for sensor {}: z_score={:.2}", 
-                        sensor_id, anomaly.z_score);
-
-                    // Store anomaly in database
-                    if let Err(e) = store_anomaly(&db, &anomaly).await {
-                        warn!("Failed to store anomaly: {}", e);
+        loop {
+            let ready = tokio::select! {
+                biased;
+                received = rx.recv() => match received {
+                    Some(ShardMessage::Reading(reading)) => buffer.push(reading),
+                    Some(ShardMessage::Snapshot(reply)) => {
+                        let _ = reply.send(detection.snapshot(&buffer));
+                        continue;
                     }
+                    None => break,
+                },
+                // Sensors that went quiet would otherwise hold their last readings back
+                _ = idle_check.tick() => buffer.poll(),
+            };
+
+            for reading in ready {
+                detection.process(reading, buffer.max_watermark()).await;
+            }
+        }
+
+        // Readings still waiting for their watermark are not lost on shutdown
+        for reading in buffer.flush() {
+            detection.process(reading, buffer.max_watermark()).await;
+        }
+
+        let stats = buffer.stats();
+        info!(
+            "Anomaly detection worker {} stopped: {} readings in order, {} late, {} dropped as too late, {} dropped as from the future",
+            shard, stats.in_order, stats.late, stats.dropped, stats.future
+        );
+        detection.snapshot(&buffer)
+    }
+}
+
+/// Work for a detection worker, in the order its shard receives it
+// Readings are nearly all of the traffic, boxing them would cost an allocation each
+#[allow(clippy::large_enum_variant)]
+enum ShardMessage {
+    Reading(RadiationReading),
+    /// Reply with the shard's state, reflecting every reading queued before
+    Snapshot(oneshot::Sender<ProcessorCheckpoint>),
+}
+
+/// Queues of the detection workers, by shard
+struct Router {
+    shards: Vec<mpsc::Sender<ShardMessage>>,
+}
+
+impl Router {
+    /// Queue a reading on the shard owning its sensor or grid cell
+    async fn send(&self, reading: RadiationReading) {
+        let shard = shard_of(&partition_key(&reading), self.shards.len());
+        if self.shards[shard].send(ShardMessage::Reading(reading)).await.is_err() {
+            warn!("Anomaly detection worker {} has stopped, dropping reading", shard);
+        }
+    }
+
+    /// Collect and merge every shard's state, or nothing if a shard has stopped
+    async fn snapshot(&self) -> Option<ProcessorCheckpoint> {
+        let mut replies = Vec::with_capacity(self.shards.len());
+        for shard in &self.shards {
+            let (reply, received) = oneshot::channel();
+            shard.send(ShardMessage::Snapshot(reply)).await.ok()?;
+            replies.push(received);
+        }
+
+        let mut parts = Vec::with_capacity(replies.len());
+        for received in replies {
+            parts.push(received.await.ok()?);
+        }
+        Some(ProcessorCheckpoint::merge(parts))
+    }
 
-                    // Broadcast to subscribers
-                    if let Err(e) = anomaly_tx.send(anomaly) {
-                        warn!("Failed to broadcast anomaly: {}", e);
+    /// Replay readings stored past each source's watermark, as after a
+    /// restart, skipping those at or before their own sensor's watermark
+    async fn catch_up(&self, db: &RadiationDatabase, watermarks: BTreeMap<String, i64>, sensor_watermarks: &BTreeMap<Uuid, i64>) {
+        let until = chrono::Utc::now().timestamp();
+        let mut replayed = 0;
+
+        for (source, watermark) in watermarks {
+            let mut query = TimeRangeQuery::new(Vec::new(), watermark + 1, until)
+                .with_sources([source.clone()])
+                .limit(CATCH_UP_BATCH);
+            loop {
+                let page = match db.query_readings(&query).await {
+                    Ok(page) => page,
+                    Err(e) => {
+                        warn!("Failed to catch up {} from the database: {}", source, e);
+                        break;
+                    }
+                };
+                for reading in page.items {
+                    if sensor_watermarks.get(&reading.sensor_id).is_some_and(|w| reading.timestamp <= *w) {
+                        continue;
                     }
+                    replayed += 1;
+                    self.send(reading).await;
                 }
+                match page.next_cursor.as_deref().map(Cursor::decode) {
+                    Some(Ok(cursor)) => query = query.after(cursor),
+                    _ => break,
+                }
+            }
+        }
+
+        if replayed > 0 {
+            info!("Caught up on {} readings stored since the checkpoint", replayed);
+        }
+    }
+}
+
+/// State a detection worker runs its shard's readings through, in event-time order
+struct Detection {
+    db: Arc<RadiationDatabase>,
+    anomaly_tx: broadcast::Sender<Anomaly>,
+    detector: AnomalyDetector,
+    mobile_detector: AnomalyDetector,
+    windows: HashMap<String, SlidingWindow>,
+    weather: WeatherLookup,
+}
+
+impl Detection {
+    /// Load detector and window state from a checkpoint, returning the
+    /// source and sensor watermarks it was taken at
+    fn restore(&mut self, checkpoint: ProcessorCheckpoint) -> (BTreeMap<String, i64>, BTreeMap<Uuid, i64>) {
+        for (detector, sensors) in [(&mut self.detector, checkpoint.sensors), (&mut self.mobile_detector, checkpoint.cells)] {
+            let mut state = DetectorState::default();
+            for (key, sensor) in sensors {
+                if !sensor.window.is_empty() {
+                    self.windows.entry(key.clone()).or_insert_with(new_window).restore(sensor.window);
+                }
+                if let Some(detector) = sensor.detector {
+                    state.sensors.insert(key, detector);
+                }
+            }
+            detector.restore(state);
+        }
+        (checkpoint.watermarks, checkpoint.sensor_watermarks)
+    }
+
+    /// Snapshot per-sensor state along with the watermarks it covers
+    fn snapshot(&self, buffer: &EventTimeBuffer) -> ProcessorCheckpoint {
+        let mut checkpoint = ProcessorCheckpoint {
+            watermarks: buffer.watermarks(),
+            sensor_watermarks: buffer.sensor_watermarks(),
+            ..ProcessorCheckpoint::default()
+        };
+
+        for (key, state) in self.detector.state().sensors {
+            checkpoint.sensors.entry(key).or_default().detector = Some(state);
+        }
+        for (key, state) in self.mobile_detector.state().sensors {
+            checkpoint.cells.entry(key).or_default().detector = Some(state);
+        }
+        for (key, window) in &self.windows {
+            let sensors = if key.starts_with(MOBILE_CELL_PREFIX) { &mut checkpoint.cells } else { &mut checkpoint.sensors };
+            sensors.entry(key.clone()).or_default().window = window.readings();
+        }
+        checkpoint
+    }
+
+    /// Window and score a reading released by the event-time buffer;
+    /// `watermark` is the newest across sensors, unix seconds
+    async fn process(&mut self, reading: RadiationReading, watermark: Option<i64>) {
+        let sensor_id = reading.sensor_id.to_string();
+        let timestamp = chrono::DateTime::from_timestamp(reading.timestamp, 0).unwrap_or_default();
+
+        // Mobile readings are baselined per location rather than per sensor,
+        // fixed sensors get the detectors configured for their source
+        let (window_key, detector, class) = match reading.track_id {
+            Some(_) => (mobile_cell_key(reading.latitude, reading.longitude), &mut self.mobile_detector, MOBILE_CLASS),
+            None => (sensor_id.clone(), &mut self.detector, reading.source.as_str()),
+        };
+
+        let rain = self.weather.rain(reading.latitude, reading.longitude, reading.timestamp).await;
+
+        // Get or create sliding window for this sensor
+        let window = self.windows.entry(window_key.clone()).or_insert_with(new_window);
+
+        // A reading replayed after a restart may also arrive live
+        if window.contains(&window_key, timestamp) {
+            debug!("Skipping reading already processed for {}", window_key);
+            return;
+        }
+
+        // Add reading to window
+        if reading.track_id.is_some() {
+            window.add_reading(WindowReading {
+                timestamp,
+                dose_rate: reading.dose_rate_microsieverts,
+                sensor_id: window_key.clone(),
+            });
+        } else {
+            window.add(reading.clone());
+        }
+
+        // Run anomaly detection; detectors learn from every reading and
+        // only score once they have seen enough of the sensor
+        let latest = crate::anomaly::Reading {
+            sensor_id: window_key.clone(),
+            timestamp,
+            dose_rate: reading.dose_rate_microsieverts,
+            rain,
+        };
+        let found = detector.detect_for(class, vec![latest]);
+
+        if let Some(mut anomaly) = found {
+            anomaly.sensor_id = sensor_id.clone();
+
+            info!("Anomaly detected for sensor {}: z_score={:.2}, score={:.2} ({})",
+                sensor_id, anomaly.z_score, anomaly.score, anomaly.algorithm.as_str());
+
+            // Store anomaly in database
+            if let Err(e) = store_anomaly(&self.db, &anomaly).await {
+                warn!("Failed to store anomaly: {}", e);
             }
 
-            // Cleanup old windows periodically
-            if windows_guard.len() > 10000 {
-                windows_guard.retain(|_, w| !w.is_stale(3600));
+            // Broadcast to subscribers
+            if let Err(e) = self.anomaly_tx.send(anomaly) {
+                warn!("Failed to broadcast anomaly: {}", e);
             }
-            
-            // Drop the lock before async operations
-            drop(windows_guard);
         }
 
-        info!("Anomaly detection worker stopped");
+        // Cleanup old windows periodically, by reading time
+        if self.windows.len() > 10000 {
+            let now = watermark.and_then(|w| chrono::DateTime::from_timestamp(w, 0)).unwrap_or(timestamp);
+            self.windows.retain(|_, w| !w.is_stale(now, 3600));
+        }
+    }
+}
+
+/// Write a checkpoint, logging rather than failing
+async fn save_checkpoint(path: &Path, checkpoint: &ProcessorCheckpoint) {
+    match checkpoint.save(path).await {
+        Ok(()) => debug!("Checkpointed {} sensors and {} cells to {}",
+            checkpoint.sensors.len(), checkpoint.cells.len(), path.display()),
+        Err(e) => warn!("Failed to checkpoint stream processor state: {}", e),
+    }
+}
+
+/// Key readings are partitioned by: the sensor, or the grid cell of mobile readings
+fn partition_key(reading: &RadiationReading) -> String {
+    match reading.track_id {
+        Some(_) => mobile_cell_key(reading.latitude, reading.longitude),
+        None => reading.sensor_id.to_string(),
     }
 }
 
+/// Shard owning a partition key; FNV-1a, so it is stable across runs and builds
+fn shard_of(key: &str, shards: usize) -> usize {
+    let hash = key
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    (hash % shards.max(1) as u64) as usize
+}
+
+/// 1 hour window, 1 minute slide
+fn new_window() -> SlidingWindow {
+    SlidingWindow::new(Duration::from_secs(3600), Duration::from_secs(60))
+}
+
+/// Window key grouping mobile readings by grid cell
+fn mobile_cell_key(latitude: f64, longitude: f64) -> String {
+    format!(
+        "{}{}:{}",
+        MOBILE_CELL_PREFIX,
+        (latitude / MOBILE_CELL_DEGREES).floor() as i64,
+        (longitude / MOBILE_CELL_DEGREES).floor() as i64,
+    )
+}
+
 /// Store anomaly in database
 async fn store_anomaly(
     _db: &Arc<RadiationDatabase>,
@@ -0,0 +1,332 @@
+//! Event-time ordering of readings ahead of the detectors.
+//!
+//! Each sensor has its own watermark, the newest reading time it has
+//! delivered less the out-of-orderness it is allowed, so a device with a
+//! skewed clock only holds back its own readings. Readings wait in a buffer
+//! until the watermark passes them and are then released in reading time
+//! order, so replaying the same data gives the same results however it was
+//! batched. A reading behind the watermark is released straight away if it
+//! is within the allowed lateness and dropped otherwise; one stamped further
+//! ahead of the clock than the allowed skew is dropped before it can move
+//! the watermark at all.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use cherenkov_db::RadiationReading;
+
+/// Source of processing time, so buffering can be driven by a simulated clock
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl SimulatedClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// How long readings wait for stragglers, and how late they may still arrive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatermarkConfig {
+    /// How far behind the newest reading of a sensor its readings may arrive
+    /// and still be put in order
+    pub out_of_orderness_secs: i64,
+    /// How far behind the watermark a reading may arrive and still be
+    /// processed, out of order
+    pub allowed_lateness_secs: i64,
+    /// Processing time without readings from a sensor after which its
+    /// buffered readings are released
+    pub idle_timeout_secs: i64,
+    /// How far ahead of the clock a reading may be stamped
+    pub max_future_skew_secs: i64,
+}
+
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        Self {
+            out_of_orderness_secs: 300,
+            // Sources are polled about hourly and deliver an hour per batch
+            allowed_lateness_secs: 7200,
+            idle_timeout_secs: 60,
+            max_future_skew_secs: 300,
+        }
+    }
+}
+
+/// Readings the buffer has seen, by outcome
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatermarkStats {
+    /// Released in order once the watermark passed them
+    pub in_order: u64,
+    /// Released on arrival, behind the watermark but within the allowed lateness
+    pub late: u64,
+    /// Dropped for arriving later than the allowed lateness
+    pub dropped: u64,
+    /// Dropped for being stamped too far ahead of the clock
+    #[serde(default)]
+    pub future: u64,
+}
+
+#[derive(Debug)]
+struct SensorStream {
+    source: String,
+    /// Newest reading time seen, unix seconds
+    max_timestamp: Option<i64>,
+    /// Readings not yet released, by reading time and arrival
+    pending: BTreeMap<(i64, u64), RadiationReading>,
+    last_arrival: Option<DateTime<Utc>>,
+    /// Watermark reached by flushing the sensor, which it never falls back behind
+    flushed_to: Option<i64>,
+}
+
+impl SensorStream {
+    fn new(source: String, resumed: Option<i64>) -> Self {
+        Self {
+            source,
+            max_timestamp: resumed,
+            pending: BTreeMap::new(),
+            last_arrival: None,
+            flushed_to: resumed,
+        }
+    }
+
+    fn watermark(&self, config: &WatermarkConfig) -> Option<i64> {
+        self.max_timestamp.map(|max| max - config.out_of_orderness_secs).max(self.flushed_to)
+    }
+
+    /// Hold the watermark at or past `watermark`
+    fn resume(&mut self, watermark: i64) {
+        self.max_timestamp = self.max_timestamp.max(Some(watermark));
+        self.flushed_to = self.flushed_to.max(Some(watermark));
+    }
+
+    /// Move the watermark up to the newest reading and release everything
+    fn flush(&mut self, config: &WatermarkConfig, released: &mut Vec<RadiationReading>) -> u64 {
+        self.flushed_to = self.max_timestamp;
+        self.release(config, released)
+    }
+
+    /// Release pending readings at or before the watermark, oldest first
+    fn release(&mut self, config: &WatermarkConfig, released: &mut Vec<RadiationReading>) -> u64 {
+        let Some(watermark) = self.watermark(config) else {
+            return 0;
+        };
+        let later = self.pending.split_off(&(watermark + 1, 0));
+        let ready = std::mem::replace(&mut self.pending, later);
+        let count = ready.len() as u64;
+        released.extend(ready.into_values());
+        count
+    }
+}
+
+/// Per-sensor event-time buffer in front of the detectors
+pub struct EventTimeBuffer {
+    config: WatermarkConfig,
+    clock: Arc<dyn Clock>,
+    sensors: HashMap<Uuid, SensorStream>,
+    /// Watermarks resumed from, by source, for sensors not seen since
+    resumed: BTreeMap<String, i64>,
+    /// Watermarks resumed from, by sensor, for sensors not seen since
+    resumed_sensors: BTreeMap<Uuid, i64>,
+    arrivals: u64,
+    stats: WatermarkStats,
+}
+
+impl EventTimeBuffer {
+    pub fn new(config: WatermarkConfig, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            config,
+            clock,
+            sensors: HashMap::new(),
+            resumed: BTreeMap::new(),
+            resumed_sensors: BTreeMap::new(),
+            arrivals: 0,
+            stats: WatermarkStats::default(),
+        }
+    }
+
+    /// Take a reading, returning the readings now ready in the order they
+    /// should be processed
+    pub fn push(&mut self, reading: RadiationReading) -> Vec<RadiationReading> {
+        let now = self.clock.now();
+        let config = &self.config;
+        let mut released = Vec::new();
+
+        if reading.timestamp > now.timestamp() + config.max_future_skew_secs {
+            metrics::counter!("cherenkov_stream_late_readings_total",
+                "source" => reading.source.clone(),
+                "outcome" => "future")
+            .increment(1);
+            self.stats.future += 1;
+            return released;
+        }
+
+        let resumed = self.resumed.get(&reading.source).copied().max(self.resumed_sensors.remove(&reading.sensor_id));
+        let sensor = self
+            .sensors
+            .entry(reading.sensor_id)
+            .or_insert_with(|| SensorStream::new(reading.source.clone(), resumed));
+        sensor.last_arrival = Some(now);
+
+        if let Some(watermark) = sensor.watermark(config) {
+            if reading.timestamp <= watermark {
+                let too_late = reading.timestamp < watermark - config.allowed_lateness_secs;
+                metrics::counter!("cherenkov_stream_late_readings_total",
+                    "source" => reading.source.clone(),
+                    "outcome" => if too_late { "dropped" } else { "late" })
+                .increment(1);
+                if too_late {
+                    self.stats.dropped += 1;
+                } else {
+                    self.stats.late += 1;
+                    released.push(reading);
+                }
+                return released;
+            }
+        }
+
+        sensor.max_timestamp = Some(sensor.max_timestamp.map_or(reading.timestamp, |max| max.max(reading.timestamp)));
+        sensor.pending.insert((reading.timestamp, self.arrivals), reading);
+        self.arrivals += 1;
+        self.stats.in_order += sensor.release(config, &mut released);
+        released
+    }
+
+    /// Release the readings of sensors that have been idle for the idle timeout
+    pub fn poll(&mut self) -> Vec<RadiationReading> {
+        let now = self.clock.now();
+        let timeout = chrono::Duration::seconds(self.config.idle_timeout_secs);
+        let mut released = Vec::new();
+        for id in self.sensor_ids() {
+            let sensor = self.sensors.get_mut(&id).expect("sensor listed");
+            if sensor.last_arrival.is_some_and(|at| now - at >= timeout) && !sensor.pending.is_empty() {
+                self.stats.in_order += sensor.flush(&self.config, &mut released);
+            }
+        }
+        released
+    }
+
+    /// Release every buffered reading, as on shutdown
+    pub fn flush(&mut self) -> Vec<RadiationReading> {
+        let mut released = Vec::new();
+        for id in self.sensor_ids() {
+            let sensor = self.sensors.get_mut(&id).expect("sensor listed");
+            self.stats.in_order += sensor.flush(&self.config, &mut released);
+        }
+        released
+    }
+
+    /// Watermark of a sensor, unix seconds, once it has delivered a reading
+    pub fn watermark(&self, sensor_id: &Uuid) -> Option<i64> {
+        self.sensors.get(sensor_id).and_then(|s| s.watermark(&self.config))
+    }
+
+    /// Watermark of every source, the oldest of its sensors', so each of
+    /// them has released everything up to it
+    pub fn watermarks(&self) -> BTreeMap<String, i64> {
+        let mut watermarks = self.resumed.clone();
+        let mut seen = BTreeMap::new();
+        for sensor in self.sensors.values() {
+            if let Some(watermark) = sensor.watermark(&self.config) {
+                seen.entry(sensor.source.clone())
+                    .and_modify(|w: &mut i64| *w = (*w).min(watermark))
+                    .or_insert(watermark);
+            }
+        }
+        watermarks.extend(seen);
+        watermarks
+    }
+
+    /// Watermark of every sensor, including those resumed from and not
+    /// seen since
+    pub fn sensor_watermarks(&self) -> BTreeMap<Uuid, i64> {
+        let mut watermarks = self.resumed_sensors.clone();
+        for (id, sensor) in &self.sensors {
+            if let Some(watermark) = sensor.watermark(&self.config) {
+                watermarks.insert(*id, watermark);
+            }
+        }
+        watermarks
+    }
+
+    /// Carry on from watermarks taken with `watermarks` and
+    /// `sensor_watermarks`, as after a restart; readings at or before them
+    /// count as late
+    pub fn resume(&mut self, watermarks: BTreeMap<String, i64>, sensor_watermarks: BTreeMap<Uuid, i64>) {
+        for (source, watermark) in watermarks {
+            let resumed = self.resumed.entry(source.clone()).or_insert(watermark);
+            *resumed = (*resumed).max(watermark);
+            for sensor in self.sensors.values_mut().filter(|s| s.source == source) {
+                sensor.resume(watermark);
+            }
+        }
+        for (id, watermark) in sensor_watermarks {
+            match self.sensors.get_mut(&id) {
+                Some(sensor) => sensor.resume(watermark),
+                None => {
+                    let resumed = self.resumed_sensors.entry(id).or_insert(watermark);
+                    *resumed = (*resumed).max(watermark);
+                }
+            }
+        }
+    }
+
+    /// Newest watermark across sensors
+    pub fn max_watermark(&self) -> Option<i64> {
+        self.sensors.values().filter_map(|s| s.watermark(&self.config)).max()
+    }
+
+    /// Readings waiting for the watermark
+    pub fn pending(&self) -> usize {
+        self.sensors.values().map(|s| s.pending.len()).sum()
+    }
+
+    pub fn stats(&self) -> WatermarkStats {
+        self.stats
+    }
+
+    /// Sensors in a fixed order so releases do not depend on hashing
+    fn sensor_ids(&self) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = self.sensors.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+}
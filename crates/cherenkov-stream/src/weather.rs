@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use cherenkov_db::{RadiationDatabase, WeatherObservation};
+
+use crate::watermark::Clock;
+
+/// How rain near a sensor is turned into the index its baseline scales with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WeatherConfig {
+    /// Distance to the nearest weather point beyond which a sensor has no weather
+    pub radius_km: f64,
+    /// Time constant over which washed-out radon progeny decay
+    pub decay_secs: f64,
+    /// Hours of rain before a reading that count towards its index
+    pub lookback_hours: i64,
+    /// Size of the lat/lon grid cells that share a lookup
+    pub cell_degrees: f64,
+    /// How long a lookup is reused before weather is fetched again, and how
+    /// long after its hours have ended it is taken as final
+    pub cache_secs: u64,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            radius_km: 50.0,
+            // Lead-214 and bismuth-214 have half-lives of 27 and 20 minutes
+            decay_secs: 3600.0,
+            lookback_hours: 6,
+            cell_degrees: 0.25,
+            cache_secs: 600,
+        }
+    }
+}
+
+/// Rain index at `timestamp`: each hour's precipitation decayed by the time
+/// since the hour ended. Only hours that ended by `timestamp` count, as the
+/// rest of the current hour is still to come.
+pub fn washout_index(observations: &[WeatherObservation], timestamp: i64, decay_secs: f64) -> f64 {
+    observations
+        .iter()
+        .filter(|o| o.timestamp + 3600 <= timestamp)
+        .map(|o| {
+            let since = (timestamp - (o.timestamp + 3600)) as f64;
+            o.precipitation_mm * (-since / decay_secs).exp()
+        })
+        .sum()
+}
+
+/// Weather found by a lookup, and when it was made
+type Fetched = (DateTime<Utc>, Vec<WeatherObservation>);
+
+/// Looks up recent rain for readings, sharing lookups between nearby
+/// sensors within the same hour of reading time
+///
+/// A lookup covers the hours before the reading's hour. Once those have
+/// been over for `cache_secs` by the clock the result is final and kept,
+/// so replaying old readings does not refetch; before that it is refetched
+/// every `cache_secs`, and an empty result is not kept at all since the
+/// weather may not have been ingested yet.
+pub struct WeatherLookup {
+    db: Arc<RadiationDatabase>,
+    config: WeatherConfig,
+    clock: Arc<dyn Clock>,
+    /// By grid cell and hour
+    cache: HashMap<(i64, i64, i64), Fetched>,
+}
+
+impl WeatherLookup {
+    pub fn new(db: Arc<RadiationDatabase>, config: WeatherConfig, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            db,
+            config,
+            clock,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Rain index at a location and time, `None` without weather data nearby
+    pub async fn rain(&mut self, latitude: f64, longitude: f64, timestamp: i64) -> Option<f64> {
+        let hour = WeatherObservation::hour_of(timestamp);
+        let key = (
+            (latitude / self.config.cell_degrees).floor() as i64,
+            (longitude / self.config.cell_degrees).floor() as i64,
+            hour,
+        );
+        let ttl = chrono::Duration::seconds(self.config.cache_secs as i64);
+        let now = self.clock.now();
+
+        let fresh = self
+            .cache
+            .get(&key)
+            .is_some_and(|(fetched, _)| now - *fetched < ttl || is_final(*fetched, hour, ttl));
+        if !fresh {
+            if self.cache.len() > 10_000 {
+                // Readings arrive roughly in time order, so far-off hours are unlikely to come up again
+                let span = self.config.lookback_hours * 3600;
+                self.cache.retain(|(_, _, cached), _| (hour - cached).abs() <= span);
+            }
+            let from = hour - self.config.lookback_hours * 3600;
+            let observations = match self.db.weather_near(latitude, longitude, self.config.radius_km, from, hour - 3600).await {
+                Ok(observations) => observations,
+                Err(e) => {
+                    warn!("Failed to look up weather: {}", e);
+                    return None;
+                }
+            };
+            if observations.is_empty() && !is_final(now, hour, ttl) {
+                return None;
+            }
+            self.cache.insert(key, (now, observations));
+        }
+
+        let (_, observations) = &self.cache[&key];
+        if observations.is_empty() {
+            return None;
+        }
+        Some(washout_index(observations, timestamp, self.config.decay_secs))
+    }
+}
+
+/// Whether a lookup for the hour starting at `hour`, made at `fetched`, saw
+/// all the weather there will be
+fn is_final(fetched: DateTime<Utc>, hour: i64, ttl: chrono::Duration) -> bool {
+    fetched.timestamp() >= hour + ttl.num_seconds()
+}
@@ -3,10 +3,14 @@ use std::time::Duration;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
 
 use cherenkov_db::RadiationReading;
 
 /// Sliding window for time-series analysis per sensor
+///
+/// Windows run on reading time: a sensor's window holds the readings within
+/// `window_size` of its newest one, whenever they arrived.
 #[allow(dead_code)]
 pub struct SlidingWindow {
     window_size: Duration,
@@ -20,7 +24,7 @@ pub struct SensorWindow {
     window_size: Duration,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct TimestampedReading {
     pub timestamp: DateTime<Utc>,
@@ -56,8 +60,7 @@ impl SlidingWindow {
         });
         
         window.add(TimestampedReading {
-            timestamp: DateTime::from_timestamp(reading.timestamp, 0)
-                .unwrap_or_else(|| Utc::now()),
+            timestamp: DateTime::from_timestamp(reading.timestamp, 0).unwrap_or_default(),
             dose_rate: reading.dose_rate_microsieverts,
             sensor_id,
         });
@@ -71,14 +74,22 @@ impl SlidingWindow {
             .unwrap_or_default()
     }
 
+    /// Whether the window holds a reading of `sensor_id` taken at `timestamp`
+    pub fn contains(&self, sensor_id: &str, timestamp: DateTime<Utc>) -> bool {
+        self.sensor_windows.get(sensor_id).is_some_and(|w| {
+            let position = w.readings.partition_point(|r| r.timestamp < timestamp);
+            w.readings.get(position).is_some_and(|r| r.timestamp == timestamp)
+        })
+    }
+
     /// Get all sensor IDs in the window
     pub fn sensor_ids(&self) -> Vec<String> {
         self.sensor_windows.keys().cloned().collect()
     }
 
-    /// Clean up expired windows
-    pub fn cleanup(&mut self) {
-        let now = Utc::now();
+    /// Drop readings older than `window_size` before `now`, in reading time,
+    /// and the sensors left without any
+    pub fn cleanup(&mut self, now: DateTime<Utc>) {
         for window in self.sensor_windows.values_mut() {
             window.cleanup(now, self.window_size);
         }
@@ -102,9 +113,26 @@ impl SlidingWindow {
         });
     }
 
-    /// Check if window is stale (no recent data)
-    pub fn is_stale(&self, max_age_secs: i64) -> bool {
-        let now = Utc::now();
+    /// All readings currently held, for checkpointing
+    pub fn readings(&self) -> Vec<TimestampedReading> {
+        self.sensor_windows
+            .values()
+            .flat_map(|w| w.readings.iter().cloned())
+            .collect()
+    }
+
+    /// Refill the window from readings taken with `readings`
+    pub fn restore(&mut self, readings: Vec<TimestampedReading>) {
+        for reading in readings {
+            let window = self.sensor_windows.entry(reading.sensor_id.clone()).or_insert_with(|| {
+                SensorWindow::new(self.window_size)
+            });
+            window.add(reading);
+        }
+    }
+
+    /// Check if no reading is within `max_age_secs` before `now`, in reading time
+    pub fn is_stale(&self, now: DateTime<Utc>, max_age_secs: i64) -> bool {
         let cutoff = now - chrono::Duration::seconds(max_age_secs);
         
         self.sensor_windows.values().all(|w| {
@@ -122,9 +150,13 @@ impl SensorWindow {
         }
     }
 
+    /// Insert in reading time order; late readings land before newer ones
     fn add(&mut self, reading: TimestampedReading) {
-        self.readings.push_back(reading);
-        self.cleanup(Utc::now(), self.window_size);
+        let position = self.readings.partition_point(|r| r.timestamp <= reading.timestamp);
+        self.readings.insert(position, reading);
+        if let Some(newest) = self.readings.back().map(|r| r.timestamp) {
+            self.cleanup(newest, self.window_size);
+        }
     }
 
     fn get_readings(&self) -> Vec<TimestampedReading> {
@@ -172,9 +204,9 @@ impl ConcurrentSlidingWindow {
         guard.get_window(sensor_id)
     }
 
-    pub async fn cleanup(&self) {
+    pub async fn cleanup(&self, now: DateTime<Utc>) {
         let mut guard = self.inner.write().await;
-        guard.cleanup();
+        guard.cleanup(now);
     }
 }
 
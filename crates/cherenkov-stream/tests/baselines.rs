@@ -0,0 +1,164 @@
+//! Seasonal and precipitation-aware baselines the detectors score residuals against.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use cherenkov_db::{DatabaseConfig, RadiationDatabase, StorageBackends, WeatherObservation};
+use cherenkov_stream::weather::{washout_index, WeatherConfig, WeatherLookup};
+use cherenkov_stream::{AnomalyDetector, BaselineConfig, Reading, SeasonalBaseline, SimulatedClock};
+
+/// 2024-01-01T00:00:00Z
+const START: i64 = 1704067200;
+
+/// Dose rate that is highest around midnight, as radon builds up under a
+/// night-time inversion
+fn diurnal(timestamp: i64) -> f64 {
+    let hour = (timestamp.rem_euclid(86_400) / 3600) as f64;
+    0.1 + 0.02 * (std::f64::consts::TAU * hour / 24.0).cos()
+}
+
+/// A little deterministic noise
+fn noise(timestamp: i64) -> f64 {
+    let i = (timestamp / 3600) as f64;
+    0.004 * (i * 0.7).sin() + 0.003 * (i * 1.3).cos()
+}
+
+fn reading(timestamp: i64, dose_rate: f64, rain: Option<f64>) -> Reading {
+    Reading {
+        sensor_id: "s1".to_string(),
+        dose_rate,
+        timestamp: DateTime::<Utc>::from_timestamp(timestamp, 0).unwrap(),
+        rain,
+    }
+}
+
+#[test]
+fn test_baseline_learns_the_daily_cycle() {
+    let config = BaselineConfig::default();
+    let mut baseline = SeasonalBaseline::new();
+    for hour in 0..24 * 30 {
+        let timestamp = START + hour * 3600;
+        baseline.update(timestamp, diurnal(timestamp) + noise(timestamp), None, &config);
+    }
+
+    for hour in [0, 6, 12, 18] {
+        let timestamp = START + 24 * 30 * 3600 + hour * 3600;
+        let expectation = baseline.expect(timestamp, None, &config).unwrap();
+        assert!(
+            (expectation.expected() - diurnal(timestamp)).abs() < 0.003,
+            "hour {}: expected {} for {}",
+            hour,
+            expectation.expected(),
+            diurnal(timestamp)
+        );
+        assert!((expectation.level - 0.1).abs() < 0.003);
+    }
+
+    // Without the diurnal component the cycle is left in the residual
+    let level_only = BaselineConfig::level_only();
+    let midnight = START + 24 * 30 * 3600;
+    let flat = baseline.expect(midnight, None, &level_only).unwrap();
+    assert_eq!(flat.diurnal, 0.0);
+    assert!((flat.expected() - diurnal(midnight)).abs() > 0.015);
+}
+
+#[test]
+fn test_daily_cycle_is_not_flagged() {
+    let mut detector = AnomalyDetector::new();
+    for hour in 0..24 * 30 {
+        let timestamp = START + hour * 3600;
+        detector.detect(vec![reading(timestamp, diurnal(timestamp) + noise(timestamp), None)]);
+    }
+
+    // A day later every hour, peak and trough alike, is ordinary
+    for hour in 0..24 {
+        let timestamp = START + (24 * 31 + hour) * 3600;
+        let anomaly = detector.detect(vec![reading(timestamp, diurnal(timestamp) + noise(timestamp), None)]);
+        assert!(anomaly.is_none(), "hour {} flagged: {:?}", hour, anomaly);
+    }
+
+    // The same excess is still found on top of the cycle
+    let timestamp = START + 24 * 32 * 3600;
+    let anomaly = detector.detect(vec![reading(timestamp, diurnal(timestamp) + noise(timestamp) + 0.05, None)]).unwrap();
+    assert!((anomaly.expected - diurnal(timestamp)).abs() < 0.003);
+    assert!((anomaly.residual - 0.05).abs() < 0.01);
+}
+
+#[test]
+fn test_rain_washout_is_expected_once_learned() {
+    // Background plus 0.01 µSv/h per mm of recent rain
+    let dose = |timestamp: i64, rain: f64| 0.1 + 0.01 * rain + noise(timestamp);
+    let mut detector = AnomalyDetector::new();
+    let mut timestamp = START;
+    for day in 0..20 {
+        for hour in 0..24 {
+            // A shower every few days
+            let rain = if day % 4 == 0 && (10..13).contains(&hour) { 2.0 } else { 0.0 };
+            timestamp += 3600;
+            detector.detect(vec![reading(timestamp, dose(timestamp, rain), Some(rain))]);
+        }
+    }
+
+    // A heavier downpour raises the dose rate well outside the dry spread,
+    // but by as much as the rain explains
+    timestamp += 3600;
+    let anomaly = detector.detect(vec![reading(timestamp, dose(timestamp, 4.0), Some(4.0))]);
+    assert!(anomaly.is_none(), "washout flagged: {:?}", anomaly);
+
+    // An increase the rain does not account for is still found
+    timestamp += 3600;
+    let anomaly = detector.detect(vec![reading(timestamp, dose(timestamp, 4.0) + 0.05, Some(4.0))]).unwrap();
+    assert!((anomaly.expected - 0.14).abs() < 0.005, "expected {}", anomaly.expected);
+    assert!(anomaly.residual > 0.04);
+}
+
+#[test]
+fn test_washout_index_decays_after_rain() {
+    let observation = |hour: i64, precipitation_mm: f64| WeatherObservation {
+        latitude: 37.42,
+        longitude: 141.03,
+        timestamp: START + hour * 3600,
+        source: "open_meteo".to_string(),
+        precipitation_mm,
+    };
+    let observations = vec![observation(0, 2.0), observation(1, 0.0), observation(2, 1.0)];
+
+    // Hours decay from their end; the current hour is not over yet
+    let now = START + 2 * 3600 + 1800;
+    let expected = 2.0 * (-1.5f64).exp();
+    assert!((washout_index(&observations, now, 3600.0) - expected).abs() < 1e-12);
+
+    // Only hours that have ended count
+    assert_eq!(washout_index(&observations, START + 1800, 3600.0), 0.0);
+    assert!((washout_index(&observations, START + 3 * 3600, 3600.0) - 1.0 - 2.0 * (-2.0f64).exp()).abs() < 1e-12);
+    assert_eq!(washout_index(&[], now, 3600.0), 0.0);
+}
+
+#[tokio::test]
+async fn test_weather_lookup_waits_for_late_weather() {
+    let db = Arc::new(
+        RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+            .await
+            .expect("in-memory database opens"),
+    );
+    let clock = SimulatedClock::new(DateTime::<Utc>::from_timestamp(START + 3600 + 60, 0).unwrap());
+    let mut lookup = WeatherLookup::new(db.clone(), WeatherConfig::default(), Arc::new(clock.clone()));
+    let reading_at = START + 3600 + 30;
+
+    // The previous hour's weather has not been ingested yet
+    assert_eq!(lookup.rain(37.42, 141.03, reading_at).await, None);
+
+    let observation = |hour: i64, precipitation_mm: f64| WeatherObservation {
+        latitude: 37.42,
+        longitude: 141.03,
+        timestamp: START + hour * 3600,
+        source: "open_meteo".to_string(),
+        precipitation_mm,
+    };
+    db.store_weather(&[observation(0, 2.0), observation(1, 5.0)]).await.unwrap();
+
+    // Found once it arrives, and the hour still under way does not count
+    let rain = lookup.rain(37.42, 141.03, reading_at).await.unwrap();
+    assert!((rain - 2.0 * (-30.0f64 / 3600.0).exp()).abs() < 1e-12, "{}", rain);
+}
@@ -0,0 +1,203 @@
+//! Detector and window state carried across restarts, with catch-up from the database.
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use cherenkov_db::{RadiationDatabase, RadiationReading};
+use cherenkov_stream::checkpoint::{ProcessorCheckpoint, CHECKPOINT_VERSION};
+use cherenkov_stream::{Anomaly, SimulatedClock, StreamProcessor};
+use common::{checkpoint_path, database, sorted};
+
+const READINGS: i64 = 240;
+
+fn reading(sensor: u128, timestamp: i64, dose_rate: f64) -> RadiationReading {
+    RadiationReading {
+        // As Safecast hands them over, in day buckets
+        bucket: timestamp / 86_400,
+        ..common::reading(sensor, timestamp, dose_rate)
+    }
+}
+
+/// Two sensors a minute apart over the last day, with spikes on both sides
+/// of the restart
+fn scenario(start: i64) -> Vec<RadiationReading> {
+    let mut readings = Vec::new();
+    for i in 0..READINGS {
+        for sensor in [1u128, 2] {
+            let noise = 0.005 * ((i as f64) * 0.7 + sensor as f64).sin();
+            let spike = match (sensor, i) {
+                (1, 60) | (2, 100) | (1, 150) | (2, 200) => 0.5,
+                _ => 0.0,
+            };
+            readings.push(reading(sensor, start + i * 60, 0.1 + noise + spike));
+        }
+    }
+    readings
+}
+
+struct Running {
+    ingest_tx: tokio::sync::mpsc::Sender<RadiationReading>,
+    anomalies: broadcast::Receiver<Anomaly>,
+    shutdown: CancellationToken,
+    handle: tokio::task::JoinHandle<anyhow::Result<()>>,
+}
+
+fn start(db: Arc<RadiationDatabase>, checkpoint: Option<&Path>) -> Running {
+    let (anomaly_tx, anomalies) = broadcast::channel(1000);
+    let clock = SimulatedClock::new(Utc::now());
+    let mut processor = StreamProcessor::new(db, anomaly_tx)
+        .with_clock(Arc::new(clock))
+        .with_checkpoint_interval(Duration::from_millis(20));
+    if let Some(path) = checkpoint {
+        processor = processor.with_checkpoint(path);
+    }
+    let ingest_tx = processor.get_ingest_tx();
+    let shutdown = CancellationToken::new();
+    let handle = tokio::spawn(processor.run(shutdown.clone()));
+    Running { ingest_tx, anomalies, shutdown, handle }
+}
+
+/// Store readings and deliver them live, as ingest does
+async fn deliver(db: &RadiationDatabase, running: &Running, readings: &[RadiationReading]) {
+    for reading in readings {
+        db.write_reading(reading).await.unwrap();
+        running.ingest_tx.send(reading.clone()).await.unwrap();
+    }
+}
+
+async fn stop(mut running: Running) -> Vec<Anomaly> {
+    running.shutdown.cancel();
+    running.handle.await.unwrap().unwrap();
+    let mut found = Vec::new();
+    while let Ok(anomaly) = running.anomalies.try_recv() {
+        found.push(anomaly);
+    }
+    found
+}
+
+async fn uninterrupted(readings: &[RadiationReading]) -> serde_json::Value {
+    let db = database().await;
+    let running = start(db.clone(), None);
+    deliver(&db, &running, readings).await;
+    sorted(stop(running).await)
+}
+
+#[tokio::test]
+async fn test_restart_gives_identical_anomalies() {
+    let readings = scenario(Utc::now().timestamp() - 86_400);
+    let expected = uninterrupted(&readings).await;
+    assert!(expected.as_array().unwrap().len() >= 4, "{}", expected);
+
+    let db = database().await;
+    let path = checkpoint_path();
+    let (before, after) = readings.split_at(readings.len() / 2);
+
+    let running = start(db.clone(), Some(&path));
+    deliver(&db, &running, before).await;
+    let mut anomalies = stop(running).await;
+
+    // What arrives while the processor is down is only in the database
+    for reading in after {
+        db.write_reading(reading).await.unwrap();
+    }
+    let running = start(db.clone(), Some(&path));
+    anomalies.extend(stop(running).await);
+
+    assert_eq!(sorted(anomalies), expected);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_restart_skips_what_each_sensor_has_processed() {
+    // The second sensor falls silent early, holding the source watermark
+    // hours behind the first
+    let start_at = Utc::now().timestamp() - 86_400;
+    let readings: Vec<_> = scenario(start_at)
+        .into_iter()
+        .filter(|r| r.sensor_id == Uuid::from_u128(1) || r.timestamp < start_at + 30 * 60)
+        .collect();
+
+    let db = database().await;
+    let path = checkpoint_path();
+    let running = start(db.clone(), Some(&path));
+    deliver(&db, &running, &readings).await;
+    let anomalies = stop(running).await;
+    assert!(anomalies.len() >= 2, "{:?}", anomalies);
+
+    let checkpoint = ProcessorCheckpoint::load(&path).await.unwrap().unwrap();
+    assert_eq!(checkpoint.watermarks["safecast"], start_at + 29 * 60);
+    assert_eq!(checkpoint.sensor_watermarks[&Uuid::from_u128(1)], start_at + (READINGS - 1) * 60);
+
+    // Nothing arrived while it was down, so nothing is found again, even
+    // readings older than the windows reach back
+    let running = start(db.clone(), Some(&path));
+    assert!(stop(running).await.is_empty());
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_periodic_checkpoint_survives_a_crash() {
+    let readings = scenario(Utc::now().timestamp() - 86_400);
+    let expected = uninterrupted(&readings).await;
+
+    let db = database().await;
+    let path = checkpoint_path();
+    let (before, after) = readings.split_at(readings.len() / 2);
+
+    let running = start(db.clone(), Some(&path));
+    deliver(&db, &running, before).await;
+
+    // Wait for a periodic checkpoint covering everything released so far,
+    // then stop without the final checkpoint
+    let released_to = before.last().unwrap().timestamp - 300;
+    let mut covered = false;
+    for _ in 0..250 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        if let Ok(Some(checkpoint)) = ProcessorCheckpoint::load(&path).await {
+            if checkpoint.watermarks.get("safecast") == Some(&released_to) {
+                covered = true;
+                break;
+            }
+        }
+    }
+    assert!(covered, "no periodic checkpoint was written");
+    running.handle.abort();
+    let mut anomalies = Vec::new();
+    let mut receiver = running.anomalies;
+    while let Ok(anomaly) = receiver.try_recv() {
+        anomalies.push(anomaly);
+    }
+
+    // Readings still buffered at the crash are replayed with the rest
+    let running = start(db.clone(), Some(&path));
+    deliver(&db, &running, after).await;
+    anomalies.extend(stop(running).await);
+
+    assert_eq!(sorted(anomalies), expected);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_checkpoint_format_is_versioned() {
+    let checkpoint = ProcessorCheckpoint::default();
+    assert_eq!(checkpoint.version, CHECKPOINT_VERSION);
+    let data = serde_json::to_vec(&checkpoint).unwrap();
+    assert!(ProcessorCheckpoint::from_slice(&data).is_ok());
+
+    // The unversioned layout from before is refused rather than misread
+    let legacy = br#"{"detector": {"sensors": {}}, "mobile_detector": {"sensors": {}}, "windows": {}}"#;
+    let error = ProcessorCheckpoint::from_slice(legacy).unwrap_err();
+    assert!(error.to_string().contains("version 1"), "{}", error);
+
+    let mut newer = serde_json::to_value(&checkpoint).unwrap();
+    newer["version"] = serde_json::json!(CHECKPOINT_VERSION + 1);
+    assert!(ProcessorCheckpoint::from_slice(&serde_json::to_vec(&newer).unwrap()).is_err());
+}
@@ -0,0 +1,51 @@
+//! Helpers shared by the integration tests and benchmarks; each uses its own subset.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use cherenkov_db::{DatabaseConfig, QualityFlag, RadiationDatabase, RadiationReading, StorageBackends};
+use cherenkov_stream::Anomaly;
+
+/// A valid Safecast reading from sensor number `sensor`, near Fukushima Daiichi
+pub fn reading(sensor: u128, timestamp: i64, dose_rate: f64) -> RadiationReading {
+    RadiationReading {
+        sensor_id: Uuid::from_u128(sensor),
+        bucket: timestamp / 3600,
+        timestamp,
+        latitude: 37.42,
+        longitude: 141.03,
+        dose_rate_microsieverts: dose_rate,
+        uncertainty: 0.01,
+        quality_flag: QualityFlag::Valid,
+        source: "safecast".to_string(),
+        cell_id: String::new(),
+        track_id: None,
+        altitude_m: None,
+        qc_reasons: Vec::new(),
+        provenance: None,
+    }
+}
+
+pub async fn database() -> Arc<RadiationDatabase> {
+    Arc::new(
+        RadiationDatabase::open(StorageBackends::in_memory(), DatabaseConfig::default())
+            .await
+            .expect("in-memory database opens"),
+    )
+}
+
+/// Anomalies in a fixed order, as JSON so runs compare field by field
+pub fn sorted(mut anomalies: Vec<Anomaly>) -> serde_json::Value {
+    anomalies.sort_by(|a, b| (a.timestamp, &a.sensor_id).cmp(&(b.timestamp, &b.sensor_id)));
+    serde_json::to_value(&anomalies).unwrap()
+}
+
+/// Checkpoint file in a fresh directory of its own
+pub fn checkpoint_path() -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("cherenkov-stream-{}", Uuid::new_v4()))
+        .join("checkpoint.json")
+}
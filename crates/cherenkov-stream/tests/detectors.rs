@@ -0,0 +1,161 @@
+//! Individual anomaly detectors, score combination and per-class ensembles.
+
+use chrono::{DateTime, Utc};
+
+use cherenkov_stream::detector::{
+    CusumConfig, CusumDetector, EwmaConfig, EwmaDetector, GrubbsConfig, GrubbsDetector, IqrConfig, IqrDetector,
+    IsolationForestConfig, IsolationForestDetector, ZScoreConfig, ZScoreDetector,
+};
+use cherenkov_stream::{Algorithm, AnomalyDetector, Combiner, Detector, EnsembleConfig, Reading, Severity};
+
+/// Background dose rate with a little deterministic noise
+fn background(i: usize) -> f64 {
+    let i = i as f64;
+    0.1 + 0.005 * (i * 0.7).sin() + 0.003 * (i * 1.3).cos()
+}
+
+fn learn(detector: &mut dyn Detector, count: usize) {
+    for i in 0..count {
+        detector.update(background(i));
+    }
+}
+
+fn reading(sensor_id: &str, dose_rate: f64) -> Reading {
+    Reading {
+        sensor_id: sensor_id.to_string(),
+        dose_rate,
+        timestamp: DateTime::<Utc>::from_timestamp(1705316400, 0).unwrap(),
+        rain: None,
+    }
+}
+
+#[test]
+fn test_detectors_flag_spikes_but_not_background() {
+    let mut detectors: Vec<Box<dyn Detector>> = vec![
+        Box::new(ZScoreDetector::new(ZScoreConfig::default())),
+        Box::new(EwmaDetector::new(EwmaConfig::default())),
+        Box::new(IqrDetector::new(IqrConfig::default())),
+        Box::new(GrubbsDetector::new(GrubbsConfig::default())),
+    ];
+
+    for detector in &mut detectors {
+        assert!(detector.score(0.5).is_none(), "{:?} scored before learning", detector.algorithm());
+        learn(detector.as_mut(), 200);
+
+        let normal = detector.score(background(200)).unwrap();
+        let spike = detector.score(0.2).unwrap();
+        assert!(normal < 1.0, "{:?} flagged background: {}", detector.algorithm(), normal);
+        assert!(spike > 1.0, "{:?} missed the spike: {}", detector.algorithm(), spike);
+
+        let explanation = detector.explain(0.2).unwrap();
+        assert_eq!(explanation.algorithm, detector.algorithm());
+        assert!((explanation.score - spike).abs() < 1e-12);
+        assert!((explanation.statistic / explanation.threshold - spike).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_cusum_accumulates_a_sustained_shift() {
+    let mut cusum = CusumDetector::new(CusumConfig::default());
+    learn(&mut cusum, 200);
+
+    // A shift of about 1.5 standard deviations is unremarkable on its own
+    let shifted = 0.106;
+    assert!(cusum.score(shifted).unwrap() < 1.0);
+
+    let mut readings = 0;
+    while cusum.score(shifted).unwrap() < 1.0 {
+        cusum.update(shifted);
+        readings += 1;
+        assert!(readings < 20, "CUSUM never alarmed on the shift");
+    }
+    assert!(readings >= 2);
+}
+
+#[test]
+fn test_isolation_forest_is_fitted_and_deterministic() {
+    let config = IsolationForestConfig { subsample: 64, window: 128, refit_every: 64, ..Default::default() };
+    let mut first = IsolationForestDetector::new(config.clone());
+    let mut second = IsolationForestDetector::new(config);
+
+    learn(&mut first, 63);
+    assert!(first.score(0.1).is_none(), "forest scored before it was fitted");
+    learn(&mut first, 200);
+    learn(&mut second, 63);
+    learn(&mut second, 200);
+
+    let spike = first.score(0.3).unwrap();
+    assert!(spike > first.score(background(200)).unwrap());
+    assert!(spike > 1.0, "isolation forest missed the spike: {}", spike);
+    assert_eq!(first.score(0.3), second.score(0.3));
+}
+
+#[test]
+fn test_combiners() {
+    let scores = [(0.4, 1.0), (1.2, 1.0), (0.9, 2.0)];
+    assert_eq!(Combiner::Max.combine(&scores), Some(1.2));
+    assert!((Combiner::Mean.combine(&scores).unwrap() - 0.85).abs() < 1e-12);
+    assert_eq!(Combiner::Vote { votes: 2 }.combine(&scores), Some(0.9));
+    assert_eq!(Combiner::Vote { votes: 4 }.combine(&scores), None);
+    assert_eq!(Combiner::Max.combine(&[]), None);
+}
+
+#[test]
+fn test_classes_pick_detectors_and_severity() {
+    let config: EnsembleConfig = serde_json::from_value(serde_json::json!({
+        "classes": {
+            "epa_radnet": {
+                "detectors": [{"type": "iqr", "fence": 1.5}, {"type": "grubbs", "weight": 2.0}],
+                "combiner": {"method": "vote", "votes": 2},
+                "warning": 0.5,
+                "critical": 2.0
+            }
+        }
+    }))
+    .unwrap();
+    let mut detector = AnomalyDetector::with_config(config);
+
+    let history: Vec<Reading> = (0..200).map(|i| reading("epa", background(i))).collect();
+    assert!(detector.detect_for("epa_radnet", history).is_none());
+    let anomaly = detector.detect_for("epa_radnet", vec![reading("epa", 0.12)]).unwrap();
+    assert_eq!(anomaly.severity, Severity::Warning);
+    assert_eq!(anomaly.algorithm, Algorithm::Ensemble);
+    assert_eq!(anomaly.explanations.len(), 2);
+    assert!(anomaly.explanations[0].score >= anomaly.explanations[1].score);
+    assert!((anomaly.baseline - 0.1).abs() < 0.001);
+    assert!(anomaly.z_score > 3.0);
+
+    // The default ensemble grades the same jump on a sensor of another class
+    let history: Vec<Reading> = (0..200).map(|i| reading("safecast", background(i))).collect();
+    assert!(detector.detect_for("safecast", history).is_none());
+    let anomaly = detector.detect_for("safecast", vec![reading("safecast", 0.2)]).unwrap();
+    assert_eq!(anomaly.severity, Severity::Critical);
+    assert_eq!(anomaly.explanations.len(), 3);
+}
+
+#[test]
+fn test_no_detection_before_min_samples() {
+    let mut detector = AnomalyDetector::new();
+    let readings: Vec<Reading> = (0..5).map(|i| reading("new", background(i))).collect();
+    assert!(detector.detect(readings).is_none());
+    assert!(detector.detect(vec![reading("new", 10.0)]).is_none());
+    assert_eq!(detector.samples("new"), 6);
+}
+
+#[test]
+fn test_state_round_trip_keeps_scoring() {
+    let mut detector = AnomalyDetector::new();
+    let history: Vec<Reading> = (0..100).map(|i| reading("s1", background(i))).collect();
+    detector.detect(history);
+
+    let state = serde_json::to_string(&detector.state()).unwrap();
+    let mut restored = AnomalyDetector::new();
+    restored.restore(serde_json::from_str(&state).unwrap());
+
+    let expected = detector.detect(vec![reading("s1", 0.2)]).unwrap();
+    let actual = restored.detect(vec![reading("s1", 0.2)]).unwrap();
+    assert_eq!(actual.severity, expected.severity);
+    assert_eq!(actual.algorithm, expected.algorithm);
+    assert!((actual.score - expected.score).abs() < 1e-9);
+    assert_eq!(actual.explanations.len(), expected.explanations.len());
+}
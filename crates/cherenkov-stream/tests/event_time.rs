@@ -0,0 +1,217 @@
+//! Event-time buffering, watermarks and late data, driven by a simulated clock.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use cherenkov_db::RadiationReading;
+use cherenkov_stream::window::SlidingWindow;
+use cherenkov_stream::{Anomaly, EventTimeBuffer, SimulatedClock, StreamProcessor, WatermarkConfig};
+
+/// 2024-01-15T08:00:00Z
+const START: i64 = 1705305600;
+
+fn reading(sensor: u128, source: &str, timestamp: i64, dose_rate: f64) -> RadiationReading {
+    RadiationReading {
+        source: source.to_string(),
+        ..common::reading(sensor, timestamp, dose_rate)
+    }
+}
+
+fn clock() -> SimulatedClock {
+    SimulatedClock::new(DateTime::<Utc>::from_timestamp(START, 0).unwrap())
+}
+
+fn buffer(clock: &SimulatedClock) -> EventTimeBuffer {
+    let config = WatermarkConfig {
+        out_of_orderness_secs: 120,
+        allowed_lateness_secs: 600,
+        idle_timeout_secs: 30,
+        ..WatermarkConfig::default()
+    };
+    EventTimeBuffer::new(config, Arc::new(clock.clone()))
+}
+
+fn times(readings: &[RadiationReading]) -> Vec<i64> {
+    readings.iter().map(|r| r.timestamp - START).collect()
+}
+
+#[test]
+fn test_out_of_order_readings_are_released_in_order() {
+    let clock = clock();
+    let mut buffer = buffer(&clock);
+
+    let mut released = Vec::new();
+    for offset in [0, 60, 30, 120, 90, 180, 150, 300] {
+        released.extend(buffer.push(reading(1, "safecast", START + offset, 0.1)));
+    }
+
+    // The watermark trails the newest reading by the out-of-orderness
+    assert_eq!(buffer.watermark(&Uuid::from_u128(1)), Some(START + 180));
+    assert_eq!(times(&released), vec![0, 30, 60, 90, 120, 150, 180]);
+    assert_eq!(buffer.pending(), 1);
+
+    assert_eq!(times(&buffer.flush()), vec![300]);
+    assert_eq!(buffer.stats().in_order, 8);
+}
+
+#[test]
+fn test_late_readings_within_lateness_are_kept() {
+    let clock = clock();
+    clock.advance(chrono::Duration::hours(1));
+    let mut buffer = buffer(&clock);
+    buffer.push(reading(1, "safecast", START + 1000, 0.1));
+    assert_eq!(buffer.watermark(&Uuid::from_u128(1)), Some(START + 880));
+
+    // Behind the watermark but within the allowed lateness: released at once
+    assert_eq!(times(&buffer.push(reading(1, "safecast", START + 500, 0.1))), vec![500]);
+    // Beyond it: dropped
+    assert!(buffer.push(reading(1, "safecast", START + 200, 0.1)).is_empty());
+
+    let stats = buffer.stats();
+    assert_eq!((stats.in_order, stats.late, stats.dropped), (0, 1, 1));
+    assert_eq!(buffer.pending(), 1);
+}
+
+#[test]
+fn test_sensors_have_their_own_watermarks() {
+    let clock = clock();
+    clock.advance(chrono::Duration::days(1));
+    let mut buffer = buffer(&clock);
+    buffer.push(reading(1, "safecast", START + 86_400, 0.1));
+
+    // A device a day behind another of the same source is not late for itself
+    let behind: Vec<RadiationReading> = [0, 60, 300]
+        .into_iter()
+        .flat_map(|offset| buffer.push(reading(2, "safecast", START + offset, 0.1)))
+        .collect();
+    assert_eq!(times(&behind), vec![0, 60]);
+    assert_eq!(buffer.stats().late + buffer.stats().dropped, 0);
+    assert_eq!(buffer.watermark(&Uuid::from_u128(2)), Some(START + 180));
+    assert_eq!(buffer.max_watermark(), Some(START + 86_400 - 120));
+
+    // The source as a whole has released no further than its slowest sensor
+    assert_eq!(buffer.watermarks()["safecast"], START + 180);
+}
+
+#[test]
+fn test_readings_from_the_future_are_dropped() {
+    let clock = clock();
+    let mut buffer = buffer(&clock);
+
+    // A device whose clock runs an hour fast does not move its watermark
+    assert!(buffer.push(reading(1, "safecast", START + 3600, 0.1)).is_empty());
+    assert_eq!(buffer.stats().future, 1);
+    assert_eq!(buffer.watermark(&Uuid::from_u128(1)), None);
+
+    // Its readings on time are put in order as usual
+    buffer.push(reading(1, "safecast", START, 0.1));
+    buffer.push(reading(1, "safecast", START - 60, 0.1));
+    assert_eq!(buffer.watermark(&Uuid::from_u128(1)), Some(START - 120));
+    assert_eq!(buffer.stats().late + buffer.stats().dropped, 0);
+    assert_eq!(buffer.pending(), 2);
+}
+
+#[test]
+fn test_idle_sensors_are_released_by_the_clock() {
+    let clock = clock();
+    let mut buffer = buffer(&clock);
+    buffer.push(reading(1, "safecast", START, 0.1));
+    buffer.push(reading(1, "safecast", START + 60, 0.1));
+
+    clock.advance(chrono::Duration::seconds(29));
+    assert!(buffer.poll().is_empty());
+    clock.advance(chrono::Duration::seconds(1));
+    assert_eq!(times(&buffer.poll()), vec![0, 60]);
+
+    // The flushed watermark holds once the sensor resumes
+    assert_eq!(buffer.watermark(&Uuid::from_u128(1)), Some(START + 60));
+    assert!(buffer.push(reading(1, "safecast", START + 90, 0.1)).is_empty());
+    assert_eq!(times(&buffer.push(reading(1, "safecast", START + 30, 0.1))), vec![30]);
+    assert_eq!(buffer.stats().late, 1);
+}
+
+#[test]
+fn test_windows_prune_by_reading_time() {
+    let mut window = SlidingWindow::new(Duration::from_secs(3600), Duration::from_secs(60));
+    let sensor = Uuid::from_u128(1).to_string();
+
+    // Readings from long ago are kept for as long as they are within the window
+    for offset in [0, 1800, 600, 3600, 5400] {
+        window.add(reading(1, "safecast", START + offset, 0.1));
+    }
+    let kept: Vec<i64> = window.get_window(&sensor).iter().map(|r| r.timestamp.timestamp() - START).collect();
+    assert_eq!(kept, vec![1800, 3600, 5400]);
+
+    let now = DateTime::<Utc>::from_timestamp(START + 5400, 0).unwrap();
+    assert!(!window.is_stale(now, 3600));
+    assert!(window.is_stale(now + chrono::Duration::hours(2), 3600));
+    window.cleanup(now + chrono::Duration::hours(2));
+    assert!(window.sensor_ids().is_empty());
+}
+
+/// Readings from two sensors with a spike each, a minute apart
+fn scenario() -> Vec<RadiationReading> {
+    let mut readings = Vec::new();
+    for i in 0..200i64 {
+        for sensor in [1u128, 2] {
+            let noise = 0.005 * ((i as f64) * 0.7 + sensor as f64).sin();
+            let spike = if (sensor == 1 && i == 150) || (sensor == 2 && i == 170) { 0.5 } else { 0.0 };
+            readings.push(reading(sensor, "safecast", START + i * 60, 0.1 + noise + spike));
+        }
+    }
+    readings
+}
+
+async fn replay(readings: Vec<RadiationReading>) -> Vec<Anomaly> {
+    let db = common::database().await;
+    let (anomaly_tx, mut anomalies) = broadcast::channel(1000);
+    // The clock has caught up with the last reading
+    let clock = clock();
+    clock.advance(chrono::Duration::days(1));
+    let processor = StreamProcessor::new(db, anomaly_tx).with_clock(Arc::new(clock));
+    let ingest_tx = processor.get_ingest_tx();
+    let shutdown = CancellationToken::new();
+    let running = tokio::spawn(processor.run(shutdown.clone()));
+
+    for reading in readings {
+        ingest_tx.send(reading).await.unwrap();
+    }
+    shutdown.cancel();
+    running.await.unwrap().unwrap();
+
+    let mut found = Vec::new();
+    while let Ok(anomaly) = anomalies.try_recv() {
+        found.push(anomaly);
+    }
+    found.sort_by(|a, b| (a.timestamp, &a.sensor_id).cmp(&(b.timestamp, &b.sensor_id)));
+    found
+}
+
+#[tokio::test]
+async fn test_replay_is_deterministic_under_reordering() {
+    let in_order = replay(scenario()).await;
+
+    // Anomalies are stamped with the time of the reading, not of processing
+    let spike = START + 150 * 60;
+    assert!(in_order.iter().any(|a| a.timestamp.timestamp() == spike && a.sensor_id == Uuid::from_u128(1).to_string()));
+    assert!(in_order.iter().all(|a| a.timestamp.timestamp() < START + 200 * 60));
+
+    // Delivering readings out of order within the allowed out-of-orderness
+    // gives exactly the same anomalies
+    let mut shuffled = scenario();
+    for chunk in shuffled.chunks_mut(6) {
+        chunk.reverse();
+    }
+    let reordered = replay(shuffled).await;
+    assert_eq!(
+        serde_json::to_value(&in_order).unwrap(),
+        serde_json::to_value(&reordered).unwrap()
+    );
+}
@@ -0,0 +1,118 @@
+//! Readings partitioned by sensor across detection workers.
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::Utc;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use cherenkov_db::{RadiationDatabase, RadiationReading};
+use cherenkov_stream::checkpoint::{ProcessorCheckpoint, SensorCheckpoint};
+use cherenkov_stream::{Anomaly, SimulatedClock, StreamProcessor};
+use common::{checkpoint_path, database, reading, sorted};
+
+const SENSORS: u128 = 12;
+const READINGS: i64 = 120;
+
+/// A dozen sensors a minute apart, each with a spike of its own
+fn scenario(start: i64) -> Vec<RadiationReading> {
+    let mut readings = Vec::new();
+    for i in 0..READINGS {
+        for sensor in 1..=SENSORS {
+            let noise = 0.005 * ((i as f64) * 0.7 + sensor as f64).sin();
+            let spike = if i == 40 + 5 * sensor as i64 { 0.5 } else { 0.0 };
+            readings.push(reading(sensor, start + i * 60, 0.1 + noise + spike));
+        }
+    }
+    readings
+}
+
+/// Run `readings` through a processor with `shards` workers, storing them
+/// first as ingest does
+async fn run(db: Arc<RadiationDatabase>, shards: usize, checkpoint: Option<&Path>, readings: &[RadiationReading]) -> Vec<Anomaly> {
+    let (anomaly_tx, mut anomalies) = broadcast::channel(1000);
+    let mut processor = StreamProcessor::new(db.clone(), anomaly_tx)
+        .with_clock(Arc::new(SimulatedClock::new(Utc::now())))
+        .with_shards(shards);
+    if let Some(path) = checkpoint {
+        processor = processor.with_checkpoint(path);
+    }
+    let ingest_tx = processor.get_ingest_tx();
+    let shutdown = CancellationToken::new();
+    let running = tokio::spawn(processor.run(shutdown.clone()));
+
+    for reading in readings {
+        db.write_reading(reading).await.unwrap();
+        ingest_tx.send(reading.clone()).await.unwrap();
+    }
+    shutdown.cancel();
+    running.await.unwrap().unwrap();
+
+    let mut found = Vec::new();
+    while let Ok(anomaly) = anomalies.try_recv() {
+        found.push(anomaly);
+    }
+    found
+}
+
+#[tokio::test]
+async fn test_shards_find_the_same_anomalies_as_one_worker() {
+    let readings = scenario(Utc::now().timestamp() - 86_400);
+    let serial = sorted(run(database().await, 1, None, &readings).await);
+    assert!(serial.as_array().unwrap().len() as u128 >= SENSORS);
+
+    for shards in [2, 4, 7] {
+        let sharded = sorted(run(database().await, shards, None, &readings).await);
+        assert_eq!(sharded, serial, "{} shards", shards);
+    }
+}
+
+#[tokio::test]
+async fn test_checkpoint_is_repartitioned_for_a_new_shard_count() {
+    let readings = scenario(Utc::now().timestamp() - 86_400);
+    let expected = sorted(run(database().await, 1, None, &readings).await);
+
+    let db = database().await;
+    let path = checkpoint_path();
+    let (before, after) = readings.split_at(readings.len() / 2);
+    let mut anomalies = run(db.clone(), 4, Some(&path), before).await;
+
+    // Every sensor is in the one merged checkpoint
+    let checkpoint = ProcessorCheckpoint::load(&path).await.unwrap().unwrap();
+    assert_eq!(checkpoint.sensors.len() as u128, SENSORS);
+
+    anomalies.extend(run(db, 3, Some(&path), after).await);
+    assert_eq!(sorted(anomalies), expected);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_merged_checkpoint_keeps_each_sensor_watermark() {
+    let (a, b, mobile) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+    let part = |sensor: Uuid, watermarks: &[(&str, i64)], sensor_watermarks: &[(Uuid, i64)]| ProcessorCheckpoint {
+        watermarks: watermarks.iter().map(|(s, w)| (s.to_string(), *w)).collect(),
+        sensor_watermarks: sensor_watermarks.iter().copied().collect(),
+        sensors: BTreeMap::from([(sensor.to_string(), SensorCheckpoint::default())]),
+        ..ProcessorCheckpoint::default()
+    };
+    let merged = ProcessorCheckpoint::merge([
+        part(a, &[("safecast", 1000), ("epa_radnet", 500)], &[(a, 1000), (mobile, 1200)]),
+        part(b, &[("safecast", 900)], &[(b, 900), (mobile, 1100)]),
+    ]);
+
+    // Sources replay from the oldest watermark, sensors skip up to their own
+    assert_eq!(merged.watermarks, BTreeMap::from([("epa_radnet".to_string(), 500), ("safecast".to_string(), 900)]));
+    assert_eq!(merged.sensor_watermarks, BTreeMap::from([(a, 1000), (b, 900), (mobile, 1100)]));
+    assert_eq!(merged.sensors.len(), 2);
+
+    // Splitting hands each shard its sensors and every watermark
+    let parts = merged.split(2, |key| usize::from(key == b.to_string()));
+    assert_eq!(parts[0].sensors.keys().collect::<Vec<_>>(), [&a.to_string()]);
+    assert_eq!(parts[1].sensors.keys().collect::<Vec<_>>(), [&b.to_string()]);
+    assert!(parts.iter().all(|p| p.watermarks.len() == 2 && p.sensor_watermarks.len() == 3));
+}
@@ -20,6 +20,7 @@ async fn test_eventbus_publish_subscribe() {
         uncertainty: 0.02,
         source: "test".to_string(),
         quality_flag: cherenkov_core::QualityFlag::Valid,
+        track_id: None,
     };
     
     let event = CherenkovEvent::NewReading(reading.clone());
@@ -118,6 +119,7 @@ async fn test_multiple_subscribers() {
         uncertainty: 0.01,
         source: "test".to_string(),
         quality_flag: cherenkov_core::QualityFlag::Valid,
+        track_id: None,
     };
     
     let event = CherenkovEvent::NewReading(reading);
@@ -148,6 +150,7 @@ async fn test_eventbus_lag() {
             uncertainty: 0.01,
             source: "test".to_string(),
             quality_flag: cherenkov_core::QualityFlag::Valid,
+            track_id: None,
         };
         let event = CherenkovEvent::NewReading(reading);
         event_bus.publish(event).await.unwrap();
@@ -172,6 +175,9 @@ fn test_radiation_reading_serialization() {
         quality_flag: DbQualityFlag::Valid,
         source: "safecast".to_string(),
         cell_id: "u09".to_string(),
+        track_id: None,
+        altitude_m: None,
+        qc_reasons: Vec::new(),
     };
     
     let json = serde_json::to_string(&reading).unwrap();